@@ -0,0 +1,15 @@
+// Empty an input or textarea the way typing would, so the Neos UI notices the change.
+// The inspector's fields are React components that keep their own copy of the value, so the
+// value is set through the native setter and announced with input and change events.
+// arguments[0]: the field element
+const [field] = arguments;
+const prototype = Object.getPrototypeOf(field);
+const setter = Object.getOwnPropertyDescriptor(prototype, "value")?.set;
+if (setter) {
+  setter.call(field, "");
+} else {
+  field.value = "";
+}
+field.dispatchEvent(new Event("input", { bubbles: true }));
+field.dispatchEvent(new Event("change", { bubbles: true }));
+return field.value;
@@ -0,0 +1,105 @@
+// Read the ExternalLinks, YouTube and Tutorial entries of the loaded node page in one call.
+// Mirrors extract_external_links, extract_youtube_content and extract_tutorial_content,
+// returning the entries in the same order. `matched` is false for containers in which none of
+// the expected fields were found. `container_index` is 1-based and null for tutorials.
+// arguments[0]: CSS selector of the content region to read, or null for the whole page
+// arguments[1]: the [content] selector candidates
+// arguments[2]: the content types to read (e.g. ["ExternalLink", "Tutorial"]), or null for all
+const [region, selectors, types] = arguments;
+const root = (region && document.querySelector(region)) || document;
+const wanted = (kind) => !types || types.includes(kind);
+
+// All elements of the first candidate that matches anything (like selectors::find_all_first)
+function findAllFirst(root, candidates) {
+  for (const candidate of candidates) {
+    const found = root.querySelectorAll(candidate);
+    if (found.length > 0) return Array.from(found);
+  }
+  return [];
+}
+
+// The first element matched by one of the candidates (like selectors::probe_first)
+function findFirst(root, candidates) {
+  for (const candidate of candidates) {
+    const found = root.querySelector(candidate);
+    if (found) return found;
+  }
+  return null;
+}
+
+// Trimmed text of the first match of the candidates, or null if nothing matches
+function text(root, candidates) {
+  const element = findFirst(root, candidates);
+  return element ? element.innerText.trim() : null;
+}
+
+// Mark an embedded player, so its rendering can still be checked from WebDriver
+let players = 0;
+function player(root) {
+  const element = findFirst(root, selectors.player);
+  if (!element) return null;
+  const id = String(players++);
+  element.setAttribute("data-tag-spider-player", id);
+  return { id, src: (element.getAttribute("src") || "").trim() };
+}
+
+// Raw fusion path of a content element, to find it again in the Neos UI
+function fusionPath(element) {
+  return element.getAttribute("data-__neos-fusion-path") || "";
+}
+
+// Containers hold only links and videos, so they are not looked up without them
+const containers =
+  wanted("ExternalLink") || wanted("YouTube") ? findAllFirst(root, selectors.container) : [];
+
+const entries = [];
+containers.forEach((container, index) => {
+  const container_index = index + 1;
+  const links = wanted("ExternalLink") ? findAllFirst(container, selectors.external_link) : [];
+  for (const item of links) {
+    const fields = {
+      url: text(item, selectors.url),
+      title: text(item, selectors.title),
+      author: text(item, selectors.author),
+      file_type: text(item, selectors.type),
+      size: text(item, selectors.size),
+    };
+    const matched = Object.values(fields).some((value) => value !== null);
+    for (const name in fields) fields[name] = fields[name] ?? "";
+    entries.push({
+      kind: "ExternalLink",
+      ...fields,
+      container_index,
+      fusion_path: fusionPath(item),
+      player: null,
+      matched,
+    });
+  }
+  const videos = wanted("YouTube") ? findAllFirst(container, selectors.youtube) : [];
+  for (const item of videos) {
+    const embed = player(item);
+    entries.push({
+      kind: "YouTube",
+      url: embed ? embed.src : "",
+      container_index,
+      fusion_path: fusionPath(item),
+      player: embed ? embed.id : null,
+      matched: embed !== null,
+    });
+  }
+});
+
+const tutorials = wanted("Tutorial") ? findAllFirst(root, selectors.tutorial) : [];
+for (const article of tutorials) {
+  const embed = player(article);
+  const url = text(article, selectors.video_url);
+  entries.push({
+    kind: "Tutorial",
+    url: url ?? "",
+    container_index: null,
+    fusion_path: fusionPath(article),
+    player: embed ? embed.id : null,
+    matched: url !== null || embed !== null,
+  });
+}
+return entries;
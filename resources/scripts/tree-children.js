@@ -0,0 +1,40 @@
+// Read the child tree items of a tree item in one call.
+// arguments[0]: the parent tree item element, arguments[1]: the [tree] selector candidates
+const [parent, selectors] = arguments;
+
+// First element matched by any candidate, in candidate order (like selectors::find_first)
+function findFirst(root, candidates) {
+  for (const candidate of candidates) {
+    const found = root.querySelector(candidate);
+    if (found) return found;
+  }
+  return null;
+}
+
+// All elements of the first candidate that matches anything (like selectors::find_all_first)
+function findAllFirst(root, candidates) {
+  for (const candidate of candidates) {
+    const found = root.querySelectorAll(candidate);
+    if (found.length > 0) return Array.from(found);
+  }
+  return [];
+}
+
+const items = [];
+for (const contents of findAllFirst(parent, selectors.contents)) {
+  for (const item of findAllFirst(contents, selectors.child_item)) {
+    const header = findFirst(item, selectors.header);
+    const icon = header ? findFirst(header, selectors.icon) : null;
+    items.push({
+      id: item.getAttribute("aria-labelledby"),
+      level: item.getAttribute("aria-level"),
+      expanded: item.getAttribute("aria-expanded") === "true",
+      expandable: findFirst(item, selectors.chevron) !== null,
+      label: header ? header.innerText.trim() : "",
+      header_classes: header ? header.getAttribute("class") || "" : "",
+      icon_data: icon ? icon.getAttribute("data-icon") : null,
+      icon_classes: icon ? icon.getAttribute("class") || "" : "",
+    });
+  }
+}
+return items;
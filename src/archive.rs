@@ -0,0 +1,267 @@
+use crate::config::{ArchiveConfig, Compression};
+use anyhow::{Context, Result};
+use chrono::{Local, NaiveDateTime, TimeZone};
+use flate2::{read::GzDecoder, write::GzEncoder};
+use std::{
+    fs,
+    io::{Read, Write},
+    path::{Path, PathBuf},
+};
+
+/// Extensions of the files the output sinks and a bulk extraction write to its results
+/// directory.
+const RESULT_EXTENSIONS: &[&str] = &["csv", "json", "jsonl", "xlsx", "html", "parquet"];
+
+/// Endings of the files next to the results that later runs read, which are never deleted:
+/// checkpoints to resume a run from and timing reports to estimate runs with.
+const KEPT_SUFFIXES: &[&str] = &[".checkpoint.jsonl", "-timing.json"];
+
+/// What applying the retention policy did.
+#[derive(Debug, Default)]
+pub struct RetentionSummary {
+    /// Snapshot files of older sessions or snapshot objects compressed.
+    pub compressed: usize,
+    /// Sessions (recording files and snapshot directories), snapshot runs or result files
+    /// deleted.
+    pub deleted: usize,
+}
+
+/// Write `data` to `path` with `compression`, adding the format's extension to the file name.
+/// Returns the path written.
+pub fn write(path: &Path, data: &[u8], compression: Compression) -> Result<PathBuf> {
+    let path = compressed_path(path, compression);
+    let encoded = match compression {
+        Compression::None => data.to_vec(),
+        Compression::Gzip => {
+            let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(data)?;
+            encoder.finish()?
+        }
+        // Level 0 picks zstd's default
+        Compression::Zstd => zstd::encode_all(data, 0)?,
+    };
+    fs::write(&path, encoded).with_context(|| format!("Could not write {}", path.display()))?;
+    Ok(path)
+}
+
+/// Read a file written by `write`, decompressing it according to its extension.
+pub fn read(path: &Path) -> Result<Vec<u8>> {
+    let data = fs::read(path).with_context(|| format!("Could not read {}", path.display()))?;
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("gz") => {
+            let mut decoded = Vec::new();
+            GzDecoder::new(data.as_slice())
+                .read_to_end(&mut decoded)
+                .with_context(|| format!("Could not decompress {}", path.display()))?;
+            Ok(decoded)
+        }
+        Some("zst") => zstd::decode_all(data.as_slice())
+            .with_context(|| format!("Could not decompress {}", path.display())),
+        _ => Ok(data),
+    }
+}
+
+/// `path`, or its compressed variant if the file was archived since it was recorded.
+pub fn locate(path: &Path) -> Option<PathBuf> {
+    [Compression::None, Compression::Gzip, Compression::Zstd]
+        .into_iter()
+        .map(|compression| compressed_path(path, compression))
+        .find(|candidate| candidate.exists())
+}
+
+/// An uncompressed copy of `path` a browser can open: the file itself if it is not compressed,
+/// otherwise a decompressed copy in the temp directory.
+pub fn plain_file(path: &Path) -> Result<PathBuf> {
+    let compressed = matches!(
+        path.extension().and_then(|ext| ext.to_str()),
+        Some("gz" | "zst")
+    );
+    if !compressed {
+        return fs::canonicalize(path)
+            .with_context(|| format!("Could not find {}", path.display()));
+    }
+
+    let dir = std::env::temp_dir().join("tag-spider-snapshots");
+    fs::create_dir_all(&dir).context("Could not create the snapshot temp directory")?;
+    let plain = dir.join(path.file_stem().context("Snapshot without a file name")?);
+    fs::write(&plain, read(path)?).context("Could not write the decompressed snapshot")?;
+    Ok(plain)
+}
+
+/// Apply the retention policy to the sessions recorded in `dir`: sessions older than
+/// `delete_after_days` are deleted, and the page snapshots of sessions older than
+/// `compress_after_days` are compressed.
+pub fn apply_retention(dir: &Path, config: &ArchiveConfig) -> Result<RetentionSummary> {
+    let mut summary = RetentionSummary::default();
+    if !dir.is_dir() {
+        return Ok(summary);
+    }
+
+    for entry in fs::read_dir(dir).context("Could not read the recordings directory")? {
+        let path = entry?.path();
+        // Only session files and snapshot directories, aged by the time in their name
+        let Some(age) = session_age(&path) else {
+            continue;
+        };
+
+        if config.delete_after_days > 0 && age > config.delete_after() {
+            let removed = if path.is_dir() {
+                fs::remove_dir_all(&path)
+            } else {
+                fs::remove_file(&path)
+            };
+            removed.with_context(|| format!("Could not delete {}", path.display()))?;
+            summary.deleted += 1;
+        } else if path.is_dir()
+            && config.compression != Compression::None
+            && config.compress_after_days > 0
+            && age > config.compress_after()
+        {
+            summary.compressed += compress_snapshots(&path, config.compression)?;
+        }
+    }
+    Ok(summary)
+}
+
+/// Apply the deletion part of the retention policy to the results of bulk extractions in
+/// `dir`: result files not written for `delete_after_days` are deleted, except the files in
+/// `keep` (e.g. the SQLite history), checkpoints and timing reports. Results stay
+/// uncompressed, since the reports read them.
+pub fn apply_results_retention(
+    dir: &Path,
+    keep: &[&Path],
+    config: &ArchiveConfig,
+) -> Result<RetentionSummary> {
+    let mut summary = RetentionSummary::default();
+    if config.delete_after_days == 0 || !dir.is_dir() {
+        return Ok(summary);
+    }
+
+    for entry in fs::read_dir(dir).context("Could not read the results directory")? {
+        let path = entry?.path();
+        let is_result = path.is_file()
+            && path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| RESULT_EXTENSIONS.contains(&ext));
+        let is_kept = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| KEPT_SUFFIXES.iter().any(|suffix| name.ends_with(suffix)));
+        if !is_result || is_kept || keep.iter().any(|kept| same_file(kept, &path)) {
+            continue;
+        }
+        if modified_age(&path).is_some_and(|age| age > config.delete_after()) {
+            fs::remove_file(&path)
+                .with_context(|| format!("Could not delete {}", path.display()))?;
+            summary.deleted += 1;
+        }
+    }
+    Ok(summary)
+}
+
+/// Compress `path` in place, replacing it with the compressed file.
+pub fn compress_file(path: &Path, compression: Compression) -> Result<()> {
+    write(path, &fs::read(path)?, compression)?;
+    fs::remove_file(path).with_context(|| format!("Could not remove {}", path.display()))
+}
+
+/// Time since `path` was last written.
+pub fn modified_age(path: &Path) -> Option<std::time::Duration> {
+    fs::metadata(path).ok()?.modified().ok()?.elapsed().ok()
+}
+
+/// Age of a snapshot run or recorded session, from the `%Y%m%d-%H%M%S` time it was started.
+pub fn timestamp_age(timestamp: &str) -> Option<std::time::Duration> {
+    let started = NaiveDateTime::parse_from_str(timestamp, "%Y%m%d-%H%M%S").ok()?;
+    let started = Local.from_local_datetime(&started).earliest()?;
+    (Local::now() - started).to_std().ok()
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// Compress the uncompressed page snapshots in a session's snapshot directory.
+fn compress_snapshots(dir: &Path, compression: Compression) -> Result<usize> {
+    let mut compressed = 0;
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "html") {
+            compress_file(&path, compression)?;
+            compressed += 1;
+        }
+    }
+    Ok(compressed)
+}
+
+/// Age of a `session-<timestamp>` recording or snapshot directory.
+fn session_age(path: &Path) -> Option<std::time::Duration> {
+    let stem = path.file_stem()?.to_str()?;
+    timestamp_age(stem.strip_prefix("session-")?)
+}
+
+fn compressed_path(path: &Path, compression: Compression) -> PathBuf {
+    match compression.extension() {
+        Some(extension) => {
+            let mut name = path.as_os_str().to_owned();
+            name.push(format!(".{extension}"));
+            PathBuf::from(name)
+        }
+        None => path.to_path_buf(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, SystemTime};
+
+    #[test]
+    fn results_retention_keeps_checkpoints_and_timings() {
+        let dir = std::env::temp_dir().join(format!("tag-spider-retention-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let names = [
+            "folder.csv",
+            "folder.jsonl",
+            "folder.checkpoint.jsonl",
+            "folder-timing.json",
+            "history.sqlite",
+            "notes.txt",
+        ];
+        let old = SystemTime::now() - Duration::from_secs(40 * 24 * 3600);
+        for name in names {
+            let file = fs::File::create(dir.join(name)).unwrap();
+            file.set_modified(old).unwrap();
+        }
+        let kept_csv = dir.join("kept.csv");
+        fs::File::create(&kept_csv).unwrap().set_modified(old).unwrap();
+
+        let config = ArchiveConfig {
+            delete_after_days: 30,
+            ..ArchiveConfig::default()
+        };
+        let summary = apply_results_retention(&dir, &[&kept_csv], &config).unwrap();
+        let mut left: Vec<String> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        left.sort();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(summary.deleted, 2);
+        assert_eq!(
+            left,
+            [
+                "folder-timing.json",
+                "folder.checkpoint.jsonl",
+                "history.sqlite",
+                "kept.csv",
+                "notes.txt"
+            ]
+        );
+    }
+}
@@ -0,0 +1,49 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::{fs::OpenOptions, io::Write, path::Path};
+
+/// A change the spider made in the CMS.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditRecord {
+    pub at: DateTime<Local>,
+    pub node_id: String,
+    /// What was changed, e.g. `replace_link`.
+    pub action: String,
+    pub old_value: String,
+    pub new_value: String,
+    pub ok: bool,
+    /// Error message if the change failed.
+    pub error: Option<String>,
+}
+
+impl AuditRecord {
+    pub fn new(node_id: &str, action: &str, old_value: &str, new_value: &str) -> Self {
+        Self {
+            at: Local::now(),
+            node_id: node_id.to_string(),
+            action: action.to_string(),
+            old_value: old_value.to_string(),
+            new_value: new_value.to_string(),
+            ok: true,
+            error: None,
+        }
+    }
+
+    pub fn failed(mut self, error: &anyhow::Error) -> Self {
+        self.ok = false;
+        self.error = Some(format!("{error:#}"));
+        self
+    }
+}
+
+/// Append a record to the audit log, one JSON object per line. The log is never rewritten.
+pub fn append<P: AsRef<Path>>(path: P, record: &AuditRecord) -> Result<()> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path.as_ref())
+        .context("Could not open audit log")?;
+    writeln!(file, "{}", serde_json::to_string(record)?).context("Could not write audit log")?;
+    Ok(())
+}
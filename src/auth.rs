@@ -0,0 +1,140 @@
+use crate::selectors::{self, Selectors};
+use crate::wait::{self, Timeouts};
+use anyhow::{Context, Result};
+use futures::{future::BoxFuture, FutureExt};
+use std::time::Duration;
+use thirtyfour::{prelude::ElementQueryable, WebDriver};
+
+/// Looks up the backend credentials as (username, password) whenever a strategy needs them.
+pub type CredentialSource = fn() -> Result<(String, String)>;
+
+/// How the spider gets into the backend, and back in once its session expired.
+///
+/// The futures are boxed so the strategy can be chosen at runtime.
+pub trait AuthStrategy: Send + Sync {
+    /// Log in on a freshly opened backend.
+    fn login<'a>(&'a self, driver: &'a WebDriver) -> BoxFuture<'a, Result<()>>;
+
+    /// Whether the session expired and has to be renewed with `relogin`.
+    fn detect_expired<'a>(&'a self, driver: &'a WebDriver) -> BoxFuture<'a, bool>;
+
+    /// Renew an expired session. Returns whether the backend is usable again.
+    fn relogin<'a>(&'a self, driver: &'a WebDriver) -> BoxFuture<'a, Result<bool>>;
+
+    /// Start a new session while the current one is still valid, leaving the backend on its
+    /// start page. Called between nodes, shortly before the session is expected to expire.
+    fn reauthenticate<'a>(&'a self, driver: &'a WebDriver) -> BoxFuture<'a, Result<()>>;
+}
+
+/// The Neos login form, and the relogin dialog the backend shows once the session expired.
+pub struct NeosFormLogin {
+    credentials: CredentialSource,
+    /// Login page, opened again to start a new session.
+    login_url: String,
+    /// How long the form may take to become usable, and to go away after submitting it.
+    form_wait: Duration,
+}
+
+impl NeosFormLogin {
+    pub fn new(credentials: CredentialSource, login_url: &str) -> Self {
+        Self {
+            credentials,
+            login_url: login_url.to_string(),
+            form_wait: Timeouts::default().element,
+        }
+    }
+
+    /// Wait up to `form_wait` for the form, e.g. the `[timeouts]` element timeout.
+    pub fn with_form_wait(mut self, form_wait: Duration) -> Self {
+        self.form_wait = form_wait;
+        self
+    }
+}
+
+impl AuthStrategy for NeosFormLogin {
+    fn login<'a>(&'a self, driver: &'a WebDriver) -> BoxFuture<'a, Result<()>> {
+        async move {
+            let credentials = (self.credentials)()?;
+            submit_login_form(&driver.handle, &credentials, self.form_wait).await?;
+            // A form that stays means rejected credentials, which the session check reports
+            let form = &Selectors::get().login;
+            wait::wait_until_gone(&driver.handle, &form.username, self.form_wait).await;
+            Ok(())
+        }
+        .boxed()
+    }
+
+    fn detect_expired<'a>(&'a self, driver: &'a WebDriver) -> BoxFuture<'a, bool> {
+        async move {
+            let form = &Selectors::get().login;
+            selectors::probe_first(&driver.handle, "login.relogin_dialog", &form.relogin_dialog)
+                .await
+                .is_ok()
+        }
+        .boxed()
+    }
+
+    fn relogin<'a>(&'a self, driver: &'a WebDriver) -> BoxFuture<'a, Result<bool>> {
+        async move {
+            let credentials = (self.credentials)()?;
+
+            // Only look for the form fields inside the dialog
+            let form = &Selectors::get().login;
+            let dialog =
+                selectors::find_first(&driver.handle, "login.relogin_dialog", &form.relogin_dialog).await?;
+            submit_login_form(&dialog, &credentials, self.form_wait)
+                .await
+                .context("Could not fill in relogin dialog")?;
+
+            // The dialog closes once the login completed
+            let login_successful =
+                wait::wait_until_gone(&driver.handle, &form.relogin_dialog, self.form_wait).await;
+            if login_successful {
+                println!("Relogin successful!");
+            } else {
+                println!("Relogin may have failed - dialog still present");
+            }
+            Ok(login_successful)
+        }
+        .boxed()
+    }
+
+    fn reauthenticate<'a>(&'a self, driver: &'a WebDriver) -> BoxFuture<'a, Result<()>> {
+        async move {
+            // Without the session cookie the backend shows the login form again
+            driver.delete_all_cookies().await?;
+            driver.goto(&self.login_url).await?;
+            self.login(driver).await
+        }
+        .boxed()
+    }
+}
+
+/// Fill in and submit the login form found under `root`, waiting up to `timeout` for it
+async fn submit_login_form<R: ElementQueryable + Sync>(
+    root: &R,
+    credentials: &(String, String),
+    timeout: Duration,
+) -> Result<()> {
+    let form = &Selectors::get().login;
+
+    let username_field = wait::wait_for_element(root, "login.username", &form.username, timeout)
+        .await
+        .context("Could not find username field!")?;
+    let password_field = selectors::find_first(root, "login.password", &form.password)
+        .await
+        .context("Could not find a password field!")?;
+    let login_button = wait::wait_for_clickable(root, "login.submit", &form.submit, timeout)
+        .await
+        .context("Could not find login button!")?;
+
+    // Clear existing values and enter credentials
+    username_field.clear().await?;
+    username_field.send_keys(&credentials.0).await?;
+
+    password_field.clear().await?;
+    password_field.send_keys(&credentials.1).await?;
+
+    login_button.click().await?;
+    Ok(())
+}
@@ -0,0 +1,254 @@
+use chrono::{DateTime, Local};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::{
+    collections::VecDeque,
+    fmt,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+static RELOGIN_BREAKER: Lazy<Mutex<Option<ReloginBreaker>>> = Lazy::new(|| Mutex::new(None));
+
+/// Smallest delay an overload signal backs off to, so a base delay of zero still grows.
+const MIN_OVERLOAD_DELAY: Duration = Duration::from_secs(1);
+
+/// Signs that the CMS is struggling and we should slow down.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OverloadSignal {
+    /// The page took longer than the configured threshold to load.
+    SlowResponse(Duration),
+    /// The backend answered with a gateway/service error page (502/503/504).
+    GatewayError(u16),
+    /// Neos showed its server error overlay or an error flash message.
+    ServerErrorOverlay,
+}
+
+impl fmt::Display for OverloadSignal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OverloadSignal::SlowResponse(elapsed) => {
+                write!(f, "slow response ({:.1}s)", elapsed.as_secs_f32())
+            }
+            OverloadSignal::GatewayError(status) => write!(f, "HTTP {status} from the CMS"),
+            OverloadSignal::ServerErrorOverlay => write!(f, "Neos server error overlay"),
+        }
+    }
+}
+
+/// Recognises gateway error pages by their title or body text.
+pub fn gateway_error_status(text: &str) -> Option<u16> {
+    let lower = text.to_lowercase();
+    if lower.contains("502 bad gateway") {
+        Some(502)
+    } else if lower.contains("503 service") || lower.contains("service temporarily unavailable") {
+        Some(503)
+    } else if lower.contains("504 gateway") {
+        Some(504)
+    } else {
+        None
+    }
+}
+
+/// Exponential backoff for the delay between nodes.
+///
+/// Each overload signal doubles the delay, to at least one second and at most `max`; every
+/// healthy node halves it again until it is back at `base`. After `pause_after` consecutive
+/// signals the caller should pause for `pause` before continuing.
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    base: Duration,
+    max: Duration,
+    current: Duration,
+    consecutive: u32,
+    pause_after: u32,
+    pause: Duration,
+}
+
+impl Backoff {
+    pub fn new(base: Duration, max: Duration, pause_after: u32, pause: Duration) -> Self {
+        Self {
+            base,
+            max,
+            current: base,
+            consecutive: 0,
+            pause_after,
+            pause,
+        }
+    }
+
+    /// Current delay to wait before the next node.
+    pub fn delay(&self) -> Duration {
+        self.current
+    }
+
+    /// Register an overload signal. Returns how long to wait before continuing.
+    pub fn on_overload(&mut self) -> Duration {
+        self.consecutive += 1;
+        self.current = (self.current * 2).max(MIN_OVERLOAD_DELAY).min(self.max);
+
+        if self.pause_after > 0 && self.consecutive >= self.pause_after {
+            self.consecutive = 0;
+            self.pause.max(self.current)
+        } else {
+            self.current
+        }
+    }
+
+    /// Register a healthy response, easing the delay back towards the base.
+    pub fn on_success(&mut self) {
+        self.consecutive = 0;
+        self.current = (self.current / 2).max(self.base);
+    }
+}
+
+/// Raised when relogins happen so often that the session or credentials must be broken.
+#[derive(Debug, Clone, Serialize, thiserror::Error)]
+#[error(
+    "Relogin was needed {count} times within {window_mins} minutes, which points to a \
+     credentials or session problem; check the password and the CMS session lifetime"
+)]
+pub struct ReloginStorm {
+    pub count: usize,
+    pub window_mins: u64,
+    pub at: DateTime<Local>,
+}
+
+/// Circuit breaker that trips when more than `max` relogins happen within `window`.
+/// Once tripped it stays open, so every later relogin fails right away.
+#[derive(Debug, Clone)]
+pub struct ReloginBreaker {
+    max: usize,
+    window: Duration,
+    relogins: VecDeque<Instant>,
+    tripped: Option<ReloginStorm>,
+}
+
+impl ReloginBreaker {
+    /// A breaker allowing `max` relogins per `window`; `max = 0` never trips.
+    pub fn new(max: usize, window: Duration) -> Self {
+        Self {
+            max,
+            window,
+            relogins: VecDeque::new(),
+            tripped: None,
+        }
+    }
+
+    /// Register a relogin at `now`. Fails if this trips the breaker or it already tripped.
+    pub fn on_relogin(&mut self, now: Instant) -> Result<(), ReloginStorm> {
+        if let Some(storm) = &self.tripped {
+            return Err(storm.clone());
+        }
+        if self.max == 0 {
+            return Ok(());
+        }
+
+        self.relogins.push_back(now);
+        while self
+            .relogins
+            .front()
+            .is_some_and(|first| now.duration_since(*first) > self.window)
+        {
+            self.relogins.pop_front();
+        }
+
+        if self.relogins.len() > self.max {
+            let storm = ReloginStorm {
+                count: self.relogins.len(),
+                window_mins: self.window.as_secs() / 60,
+                at: Local::now(),
+            };
+            self.tripped = Some(storm.clone());
+            return Err(storm);
+        }
+        Ok(())
+    }
+}
+
+/// Use `breaker` for all relogins of this run.
+pub fn install_relogin_breaker(breaker: ReloginBreaker) {
+    *RELOGIN_BREAKER.lock().unwrap() = Some(breaker);
+}
+
+/// Register a relogin with the installed breaker. Without a breaker this always succeeds.
+pub fn note_relogin() -> Result<(), ReloginStorm> {
+    match RELOGIN_BREAKER.lock().unwrap().as_mut() {
+        Some(breaker) => breaker.on_relogin(Instant::now()),
+        None => Ok(()),
+    }
+}
+
+/// The event that tripped the installed breaker, if it tripped.
+pub fn relogin_storm() -> Option<ReloginStorm> {
+    RELOGIN_BREAKER
+        .lock()
+        .unwrap()
+        .as_ref()
+        .and_then(|breaker| breaker.tripped.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEC: Duration = Duration::from_secs(1);
+
+    #[test]
+    fn backoff_doubles_on_overload_and_eases_back() {
+        let mut backoff = Backoff::new(2 * SEC, 10 * SEC, 0, 60 * SEC);
+        assert_eq!(backoff.delay(), 2 * SEC);
+        assert_eq!(backoff.on_overload(), 4 * SEC);
+        assert_eq!(backoff.on_overload(), 8 * SEC);
+        assert_eq!(backoff.on_overload(), 10 * SEC);
+        backoff.on_success();
+        assert_eq!(backoff.delay(), 5 * SEC);
+        backoff.on_success();
+        backoff.on_success();
+        assert_eq!(backoff.delay(), 2 * SEC);
+    }
+
+    #[test]
+    fn backoff_grows_from_a_zero_base() {
+        let mut backoff = Backoff::new(Duration::ZERO, 10 * SEC, 0, 60 * SEC);
+        assert_eq!(backoff.on_overload(), SEC);
+        assert_eq!(backoff.on_overload(), 2 * SEC);
+    }
+
+    #[test]
+    fn backoff_pauses_after_consecutive_signals() {
+        let mut backoff = Backoff::new(SEC, 10 * SEC, 2, 60 * SEC);
+        assert_eq!(backoff.on_overload(), 2 * SEC);
+        assert_eq!(backoff.on_overload(), 60 * SEC);
+        // The count starts over after a pause and after a healthy node
+        assert_eq!(backoff.on_overload(), 8 * SEC);
+        backoff.on_success();
+        assert_eq!(backoff.on_overload(), 8 * SEC);
+    }
+
+    #[test]
+    fn breaker_trips_on_too_many_relogins_in_the_window() {
+        let mut breaker = ReloginBreaker::new(2, 600 * SEC);
+        let start = Instant::now();
+        assert!(breaker.on_relogin(start).is_ok());
+        assert!(breaker.on_relogin(start + 100 * SEC).is_ok());
+        let storm = breaker.on_relogin(start + 200 * SEC).unwrap_err();
+        assert_eq!(storm.count, 3);
+        assert_eq!(storm.window_mins, 10);
+        // Once tripped it stays open
+        assert!(breaker.on_relogin(start + 3600 * SEC).is_err());
+    }
+
+    #[test]
+    fn breaker_forgets_relogins_outside_the_window() {
+        let mut breaker = ReloginBreaker::new(2, 600 * SEC);
+        let start = Instant::now();
+        for minutes in [0, 5, 11, 16, 22, 27] {
+            assert!(breaker.on_relogin(start + minutes * 60 * SEC).is_ok());
+        }
+        let mut disabled = ReloginBreaker::new(0, 600 * SEC);
+        for _ in 0..10 {
+            assert!(disabled.on_relogin(start).is_ok());
+        }
+    }
+}
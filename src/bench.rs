@@ -0,0 +1,142 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path, time::Duration};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    task::JoinHandle,
+};
+
+/// Longest request the mock server reads before answering.
+const MAX_REQUEST: usize = 64 * 1024;
+
+/// Pseudo-random numbers from a seed (SplitMix64), so a seed always builds the same benchmark
+/// run on every machine.
+pub struct SeededRng(u64);
+
+impl SeededRng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// A number below `n`, which must not be 0.
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+}
+
+/// How fast one pipeline stage handled the entries of a benchmark run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StageResult {
+    pub stage: String,
+    pub entries: usize,
+    pub secs: f64,
+}
+
+impl StageResult {
+    pub fn new(stage: &str, entries: usize, elapsed: Duration) -> Self {
+        Self {
+            stage: stage.to_string(),
+            entries,
+            secs: elapsed.as_secs_f64(),
+        }
+    }
+
+    pub fn per_sec(&self) -> f64 {
+        if self.secs > 0.0 {
+            self.entries as f64 / self.secs
+        } else {
+            f64::INFINITY
+        }
+    }
+}
+
+/// Results of `fixtures bench`, saved to compare later runs against.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchReport {
+    pub seed: u64,
+    pub nodes: usize,
+    /// The fastest round of each stage.
+    pub stages: Vec<StageResult>,
+}
+
+impl BenchReport {
+    pub fn load(path: &Path) -> Result<Self> {
+        let json = fs::read_to_string(path)
+            .with_context(|| format!("Could not read benchmark results {}", path.display()))?;
+        serde_json::from_str(&json)
+            .with_context(|| format!("Invalid benchmark results {}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Could not write benchmark results {}", path.display()))
+    }
+
+    /// Keep the faster result of each stage, for the best of several rounds.
+    pub fn keep_fastest(&mut self, round: Vec<StageResult>) {
+        for result in round {
+            match self.stages.iter_mut().find(|known| known.stage == result.stage) {
+                Some(known) if known.secs <= result.secs => {}
+                Some(known) => *known = result,
+                None => self.stages.push(result),
+            }
+        }
+    }
+
+    /// Stages at least `max_slowdown` (0.2 for 20 %) slower than in `baseline`, with how much
+    /// slower they are. Stages the baseline does not have are not compared.
+    pub fn regressions(&self, baseline: &BenchReport, max_slowdown: f64) -> Vec<(String, f64)> {
+        self.stages
+            .iter()
+            .filter_map(|result| {
+                let base = baseline.stages.iter().find(|base| base.stage == result.stage)?;
+                let slowdown = 1.0 - result.per_sec() / base.per_sec();
+                (slowdown >= max_slowdown).then(|| (result.stage.clone(), slowdown))
+            })
+            .collect()
+    }
+}
+
+/// Start a local HTTP server answering every request with an empty 200, so a benchmark run
+/// validates its URLs without the network. Returns its base URL and the task serving it.
+pub async fn mock_server() -> Result<(String, JoinHandle<()>)> {
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .await
+        .context("Could not start the mock server")?;
+    let url = format!("http://{}", listener.local_addr()?);
+    let server = tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            tokio::spawn(answer(stream));
+        }
+    });
+    Ok((url, server))
+}
+
+/// `url` on the mock server at `base`, keeping its host and path so distinct URLs stay distinct.
+pub fn mock_url(base: &str, url: &str) -> String {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    format!("{base}/{}", rest.trim_start_matches('/'))
+}
+
+async fn answer(mut stream: TcpStream) {
+    let mut request = Vec::new();
+    let mut buf = [0; 4096];
+    while !request.windows(4).any(|end| end == b"\r\n\r\n") && request.len() < MAX_REQUEST {
+        match stream.read(&mut buf).await {
+            Ok(0) | Err(_) => return,
+            Ok(read) => request.extend_from_slice(&buf[..read]),
+        }
+    }
+    let response = b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+    let _ = stream.write_all(response).await;
+    let _ = stream.shutdown().await;
+}
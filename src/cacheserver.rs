@@ -0,0 +1,293 @@
+use crate::validator::Cached;
+use anyhow::{Context, Result};
+use chrono::Local;
+use reqwest::{header::CONTENT_TYPE, Client};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+    time::{timeout_at, Instant},
+};
+
+/// Largest request body the server accepts, far above any team's link count.
+const MAX_BODY: usize = 64 * 1024 * 1024;
+
+/// Longest request line or header the server reads.
+const MAX_LINE: u64 = 8 * 1024;
+
+/// Most headers the server reads from one request.
+const MAX_HEADERS: usize = 100;
+
+/// Time a client has to send its whole request.
+const READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Path of the checks on the cache server.
+const CHECKS_PATH: &str = "/checks";
+
+/// Header carrying the token shared by the cache server and its clients.
+const TOKEN_HEADER: &str = "X-Cache-Token";
+
+/// The shared token from the environment variable `env` (`[checks] cache_token_env`).
+pub fn token(env: &str) -> Result<String> {
+    std::env::var(env)
+        .ok()
+        .filter(|token| !token.is_empty())
+        .with_context(|| format!("Set {env} to the token shared with the validation cache"))
+}
+
+/// All checks the cache server at `url` knows, by [`crate::validator::cache_key`].
+pub async fn fetch(client: &Client, url: &str, token: &str) -> Result<HashMap<String, Cached>> {
+    let endpoint = format!("{}{CHECKS_PATH}", url.trim_end_matches('/'));
+    let response = client
+        .get(&endpoint)
+        .header(TOKEN_HEADER, token)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .with_context(|| format!("Could not reach the validation cache at {endpoint}"))?;
+    serde_json::from_str(&response.text().await?)
+        .with_context(|| format!("The validation cache at {endpoint} sent invalid JSON"))
+}
+
+/// Add `checks` to the cache server at `url`, which keeps the newer check of each URL.
+pub async fn push(
+    client: &Client,
+    url: &str,
+    token: &str,
+    checks: &HashMap<String, Cached>,
+) -> Result<()> {
+    let endpoint = format!("{}{CHECKS_PATH}", url.trim_end_matches('/'));
+    client
+        .post(&endpoint)
+        .header(TOKEN_HEADER, token)
+        .header(CONTENT_TYPE, "application/json")
+        .body(serde_json::to_string(checks)?)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .with_context(|| format!("Could not update the validation cache at {endpoint}"))?;
+    Ok(())
+}
+
+/// Checks shared by the cache server, kept in `path` if there is one.
+struct Store {
+    checks: HashMap<String, Cached>,
+    path: Option<PathBuf>,
+    max_age: Duration,
+    /// Counts the merges, so an older state is never written over a newer one.
+    version: u64,
+}
+
+/// The state of the store to write to disk after a merge.
+struct Snapshot {
+    path: PathBuf,
+    json: String,
+    version: u64,
+}
+
+impl Store {
+    /// Keep the newer of each URL's checks and drop the expired ones. Returns what to write to
+    /// disk, if the store is kept in a file.
+    fn merge(&mut self, checks: HashMap<String, Cached>) -> Result<Option<Snapshot>> {
+        let now = Local::now();
+        for (url, mut check) in checks {
+            // A client with a wrong clock must not pin its checks in the cache
+            check.checked_at = check.checked_at.min(now);
+            match self.checks.get(&url) {
+                Some(known) if known.checked_at >= check.checked_at => {}
+                _ => {
+                    self.checks.insert(url, check);
+                }
+            }
+        }
+        self.checks.retain(|_, check| check.is_fresh(self.max_age));
+        self.version += 1;
+        let Some(path) = &self.path else {
+            return Ok(None);
+        };
+        Ok(Some(Snapshot {
+            path: path.clone(),
+            json: serde_json::to_string(&self.checks)?,
+            version: self.version,
+        }))
+    }
+}
+
+/// What the connections of the cache server share.
+struct Server {
+    store: Mutex<Store>,
+    /// Version of the store last written to disk, held while writing.
+    written: Mutex<u64>,
+    token: String,
+}
+
+impl Server {
+    /// Write `snapshot` to disk unless a newer one was written meanwhile. Blocks.
+    fn persist(&self, snapshot: Snapshot) -> Result<()> {
+        let mut written = self.written.lock().unwrap();
+        if *written >= snapshot.version {
+            return Ok(());
+        }
+        let path = &snapshot.path;
+        // Written next to the file and renamed, so a crash never leaves half a cache
+        let partial = path.with_extension("json.partial");
+        fs::write(&partial, snapshot.json)
+            .with_context(|| format!("Could not write {}", partial.display()))?;
+        fs::rename(&partial, path).with_context(|| format!("Could not write {}", path.display()))?;
+        *written = snapshot.version;
+        Ok(())
+    }
+
+    /// Whether a request sent `token`. Compares in constant time.
+    fn authorized(&self, token: &str) -> bool {
+        let expected = self.token.as_bytes();
+        token.len() == expected.len()
+            && token
+                .bytes()
+                .zip(expected)
+                .fold(0, |diff, (a, b)| diff | (a ^ b))
+                == 0
+    }
+}
+
+/// Serve the validation cache on `listen`, e.g. `127.0.0.1:7878`, until the process is stopped.
+/// Checks are kept in `path` if given, and dropped after `max_age`. Clients get all checks with
+/// `GET /checks` and add theirs with `POST /checks`, both sending `token` in the
+/// `X-Cache-Token` header; requests are handled concurrently, and the newer check of a URL
+/// wins.
+pub async fn serve(
+    listen: &str,
+    path: Option<&Path>,
+    max_age: Duration,
+    token: String,
+) -> Result<()> {
+    let checks: HashMap<String, Cached> = match path {
+        Some(path) if path.exists() => serde_json::from_str(&fs::read_to_string(path)?)
+            .with_context(|| format!("Could not read the validation cache {}", path.display()))?,
+        _ => HashMap::new(),
+    };
+    if let Some(dir) = path.and_then(Path::parent).filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir).context("Could not create the validation cache directory")?;
+    }
+    let server = Arc::new(Server {
+        store: Mutex::new(Store {
+            checks,
+            path: path.map(Path::to_path_buf),
+            max_age,
+            version: 0,
+        }),
+        written: Mutex::new(0),
+        token,
+    });
+
+    let listener = TcpListener::bind(listen)
+        .await
+        .with_context(|| format!("Could not listen on {listen}"))?;
+    println!(
+        "✓ Serving the validation cache on http://{}{CHECKS_PATH} ({} URLs)",
+        listener.local_addr()?,
+        server.store.lock().unwrap().checks.len()
+    );
+    loop {
+        let (stream, peer) = listener.accept().await?;
+        let server = server.clone();
+        tokio::spawn(async move {
+            if let Err(e) = handle(stream, server).await {
+                println!("⚠ {peer}: {e:#}");
+            }
+        });
+    }
+}
+
+/// Read one line of the request into `line`, failing when it is longer than `MAX_LINE` or does
+/// not arrive before `deadline`. Returns the bytes read, 0 at the end of the stream.
+async fn read_line(
+    reader: &mut BufReader<TcpStream>,
+    line: &mut String,
+    deadline: Instant,
+) -> Result<usize> {
+    let read = timeout_at(deadline, (&mut *reader).take(MAX_LINE).read_line(line))
+        .await
+        .context("Timed out reading the request")??;
+    if read as u64 == MAX_LINE && !line.ends_with('\n') {
+        anyhow::bail!("Request line or header longer than {MAX_LINE} bytes");
+    }
+    Ok(read)
+}
+
+/// Answer one HTTP request and close the connection.
+async fn handle(stream: TcpStream, server: Arc<Server>) -> Result<()> {
+    let deadline = Instant::now() + READ_TIMEOUT;
+    let mut reader = BufReader::new(stream);
+    let mut request_line = String::new();
+    read_line(&mut reader, &mut request_line, deadline).await?;
+    let mut parts = request_line.split_whitespace();
+    let (method, target) = (parts.next().unwrap_or_default(), parts.next().unwrap_or_default());
+
+    let mut content_length = 0;
+    let mut token = String::new();
+    for _ in 0..MAX_HEADERS {
+        let mut header = String::new();
+        if read_line(&mut reader, &mut header, deadline).await? == 0 || header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().context("Invalid Content-Length")?;
+            } else if name.trim().eq_ignore_ascii_case(TOKEN_HEADER) {
+                token = value.trim().to_string();
+            }
+        }
+    }
+
+    let (status, body) = match (method, target) {
+        (_, target) if target != CHECKS_PATH => ("404 Not Found", String::new()),
+        _ if !server.authorized(&token) => ("401 Unauthorized", String::new()),
+        ("GET", _) => ("200 OK", serde_json::to_string(&server.store.lock().unwrap().checks)?),
+        ("POST", _) if content_length > MAX_BODY => ("413 Payload Too Large", String::new()),
+        ("POST", _) => {
+            let mut body = vec![0; content_length];
+            timeout_at(deadline, reader.read_exact(&mut body))
+                .await
+                .context("Timed out reading the request")??;
+            match serde_json::from_slice(&body) {
+                Ok(checks) => match merge(&server, checks).await {
+                    Ok(()) => ("204 No Content", String::new()),
+                    Err(e) => {
+                        println!("⚠ {e:#}");
+                        ("500 Internal Server Error", String::new())
+                    }
+                },
+                Err(e) => ("400 Bad Request", serde_json::to_string(&e.to_string())?),
+            }
+        }
+        _ => ("405 Method Not Allowed", String::new()),
+    };
+
+    let stream = reader.get_mut();
+    let head = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+         Connection: close\r\n\r\n",
+        body.len()
+    );
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(body.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+/// Merge `checks` into the store and write it to disk off the async workers.
+async fn merge(server: &Arc<Server>, checks: HashMap<String, Cached>) -> Result<()> {
+    let snapshot = server.store.lock().unwrap().merge(checks)?;
+    let Some(snapshot) = snapshot else {
+        return Ok(());
+    };
+    let server = Arc::clone(server);
+    tokio::task::spawn_blocking(move || server.persist(snapshot)).await?
+}
@@ -0,0 +1,177 @@
+use crate::config::CalendarConfig;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local, NaiveDateTime, Utc};
+use rusqlite::Connection;
+use std::{fmt::Write as _, fs, path::Path};
+
+/// A run recorded in the SQLite history by the `sqlite` sink.
+#[derive(Debug, Clone)]
+pub struct CompletedRun {
+    pub id: i64,
+    pub job: String,
+    pub started_at: DateTime<Local>,
+    /// `None` for a run that ended before writing its summary, e.g. in a crash.
+    pub finished_at: Option<DateTime<Local>>,
+    pub entries: i64,
+    pub outdated: i64,
+}
+
+/// The runs recorded in the SQLite history at `path`, oldest first. Empty if there is no
+/// history yet.
+pub fn completed_runs(path: &Path) -> Result<Vec<CompletedRun>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let connection =
+        Connection::open(path).with_context(|| format!("Could not open {}", path.display()))?;
+    let mut statement = connection
+        .prepare(
+            "SELECT id, job, started_at, finished_at, entries, outdated FROM runs ORDER BY id",
+        )
+        .context("Could not read the run history")?;
+    let rows = statement.query_map([], |row| {
+        Ok((
+            row.get::<_, i64>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, String>(2)?,
+            row.get::<_, Option<String>>(3)?,
+            row.get::<_, Option<i64>>(4)?,
+            row.get::<_, Option<i64>>(5)?,
+        ))
+    })?;
+
+    let mut runs = Vec::new();
+    for row in rows {
+        let (id, job, started_at, finished_at, entries, outdated) = row?;
+        // Timestamps are written as RFC 3339 by the sink; anything else is skipped
+        let Ok(started_at) = DateTime::parse_from_rfc3339(&started_at) else {
+            continue;
+        };
+        let finished_at = finished_at
+            .and_then(|at| DateTime::parse_from_rfc3339(&at).ok())
+            .map(|at| at.with_timezone(&Local));
+        runs.push(CompletedRun {
+            id,
+            job,
+            started_at: started_at.with_timezone(&Local),
+            finished_at,
+            entries: entries.unwrap_or_default(),
+            outdated: outdated.unwrap_or_default(),
+        });
+    }
+    Ok(runs)
+}
+
+/// iCalendar feed with the scheduled audit runs, as one recurring event, and an event for
+/// every completed run.
+pub fn feed(config: &CalendarConfig, runs: &[CompletedRun]) -> String {
+    let stamp = ics_time(&Utc::now());
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//tag-spider-rs//audit runs//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+        "X-WR-CALNAME:Content audits".to_string(),
+    ];
+
+    if let Some(first_run) = config.first_run {
+        let end = first_run + chrono::Duration::minutes(config.duration_mins as i64);
+        lines.extend([
+            "BEGIN:VEVENT".to_string(),
+            format!("UID:scheduled-{}@tag-spider-rs", floating_time(&first_run)),
+            format!("DTSTAMP:{stamp}"),
+            // Floating times, so the runs stay at the same wall-clock time across DST changes
+            format!("DTSTART:{}", floating_time(&first_run)),
+            format!("DTEND:{}", floating_time(&end)),
+            format!("RRULE:FREQ=DAILY;INTERVAL={}", config.every_days.max(1)),
+            format!("SUMMARY:{}", escape("Content audit (scheduled)")),
+            "TRANSP:TRANSPARENT".to_string(),
+            "END:VEVENT".to_string(),
+        ]);
+    }
+
+    for run in runs {
+        let (summary, description) = match run.finished_at {
+            Some(_) => (
+                format!(
+                    "Content audit {}: {} entries, {} outdated",
+                    run.job, run.entries, run.outdated
+                ),
+                format!(
+                    "Run {} extracted {} entries, {} of them flagged as outdated.",
+                    run.id, run.entries, run.outdated
+                ),
+            ),
+            None => (
+                format!("Content audit {}: did not finish", run.job),
+                format!("Run {} ended before writing its summary.", run.id),
+            ),
+        };
+        lines.extend([
+            "BEGIN:VEVENT".to_string(),
+            format!("UID:run-{}@tag-spider-rs", run.id),
+            format!("DTSTAMP:{stamp}"),
+            format!("DTSTART:{}", ics_time(&run.started_at.with_timezone(&Utc))),
+        ]);
+        if let Some(finished_at) = run.finished_at {
+            lines.push(format!("DTEND:{}", ics_time(&finished_at.with_timezone(&Utc))));
+        }
+        lines.extend([
+            format!("SUMMARY:{}", escape(&summary)),
+            format!("DESCRIPTION:{}", escape(&description)),
+            "TRANSP:TRANSPARENT".to_string(),
+            "END:VEVENT".to_string(),
+        ]);
+    }
+    lines.push("END:VCALENDAR".to_string());
+
+    let mut ics = String::new();
+    for line in &lines {
+        let _ = write!(ics, "{}\r\n", fold(line));
+    }
+    ics
+}
+
+/// Write the feed for the runs in the SQLite history at `database` to `path`.
+/// Returns the number of completed runs in it.
+pub fn write(config: &CalendarConfig, database: &Path, path: &Path) -> Result<usize> {
+    let runs = completed_runs(database)?;
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir).context("Could not create the calendar directory")?;
+    }
+    fs::write(path, feed(config, &runs))
+        .with_context(|| format!("Could not write {}", path.display()))?;
+    Ok(runs.len())
+}
+
+fn ics_time(time: &DateTime<Utc>) -> String {
+    time.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+fn floating_time(time: &NaiveDateTime) -> String {
+    time.format("%Y%m%dT%H%M%S").to_string()
+}
+
+/// Escape the characters with a meaning in iCalendar text values.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Fold a content line into lines of at most 75 octets, as RFC 5545 requires.
+fn fold(line: &str) -> String {
+    let mut folded = String::new();
+    let mut length = 0;
+    for c in line.chars() {
+        if length + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            // The leading space counts towards the continuation line
+            length = 1;
+        }
+        folded.push(c);
+        length += c.len_utf8();
+    }
+    folded
+}
@@ -0,0 +1,79 @@
+use crate::dimensions::VariantCoverage;
+use crate::report::ReportEntry;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+};
+
+/// What a bulk extraction got from one node, as a line of its checkpoint file.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NodeRecord {
+    pub node: String,
+    /// The node's entries before validation, variants included.
+    pub entries: Vec<ReportEntry>,
+    #[serde(default)]
+    pub coverage: Vec<VariantCoverage>,
+}
+
+/// Append-only record of the nodes a bulk extraction finished, so a run that dies halfway can be
+/// resumed without extracting them again.
+pub struct Checkpoint {
+    path: PathBuf,
+    file: File,
+}
+
+/// Checkpoint file of the bulk extraction of `job` writing its results to `dir`.
+pub fn path(dir: &Path, job: &str) -> PathBuf {
+    dir.join(format!("{job}.checkpoint.jsonl"))
+}
+
+/// Nodes recorded by an earlier run, in the order they were finished. Empty if there is no
+/// checkpoint.
+pub fn load(path: &Path) -> Result<Vec<NodeRecord>> {
+    let Ok(file) = File::open(path) else {
+        return Ok(Vec::new());
+    };
+    let mut records = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = line.with_context(|| format!("Could not read {}", path.display()))?;
+        // A run killed mid-write can leave half a line at the end
+        if let Ok(record) = serde_json::from_str(&line) {
+            records.push(record);
+        }
+    }
+    Ok(records)
+}
+
+impl Checkpoint {
+    /// Open the checkpoint at `path`, keeping its records if `resume` is set and starting it
+    /// over otherwise.
+    pub fn open(path: &Path, resume: bool) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(resume)
+            .write(true)
+            .truncate(!resume)
+            .open(path)
+            .with_context(|| format!("Could not open checkpoint {}", path.display()))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+        })
+    }
+
+    /// Record a finished node. Synced to disk right away, since the point is surviving a crash.
+    pub fn record(&mut self, record: &NodeRecord) -> Result<()> {
+        writeln!(self.file, "{}", serde_json::to_string(record)?)
+            .context("Could not write checkpoint")?;
+        self.file.sync_data().context("Could not sync checkpoint")
+    }
+
+    /// Delete the checkpoint after the run completed.
+    pub fn finish(self) -> Result<()> {
+        fs::remove_file(&self.path)
+            .with_context(|| format!("Could not remove checkpoint {}", self.path.display()))
+    }
+}
@@ -0,0 +1,232 @@
+use crate::lexer::Lexer;
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::io::Cursor;
+
+/// Visual state of an embedded iframe or video element.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmbedStatus {
+    Ok,
+    ZeroSize,
+    ErrorThumbnail,
+    Unknown,
+}
+
+impl EmbedStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EmbedStatus::Ok => "OK",
+            EmbedStatus::ZeroSize => "Broken (zero size)",
+            EmbedStatus::ErrorThumbnail => "Broken (error thumbnail)",
+            EmbedStatus::Unknown => "Unknown",
+        }
+    }
+
+    /// Whether the embed should be reported as visually broken.
+    pub fn is_broken(&self) -> bool {
+        matches!(self, EmbedStatus::ZeroSize | EmbedStatus::ErrorThumbnail)
+    }
+}
+
+/// Elements narrower or flatter than a single pixel are not visible to readers.
+pub fn has_zero_size(width: f64, height: f64) -> bool {
+    width < 1.0 || height < 1.0
+}
+
+/// Number of sample points per axis when inspecting a screenshot.
+const SAMPLE_GRID: u32 = 16;
+
+/// Samples a PNG screenshot of an embed on a grid and reports whether it looks like
+/// a provider error thumbnail ("Video unavailable") or an empty frame.
+///
+/// Healthy embeds show a colourful video still; error states are almost entirely
+/// dark or a single flat colour with some grey text.
+pub fn looks_like_error_thumbnail(png_data: &[u8]) -> Result<bool> {
+    let mut decoder = png::Decoder::new(Cursor::new(png_data));
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().context("Could not read PNG header")?;
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let frame = reader
+        .next_frame(&mut buf)
+        .context("Could not decode PNG frame")?;
+
+    let channels = match frame.color_type {
+        png::ColorType::Grayscale => 1,
+        png::ColorType::GrayscaleAlpha => 2,
+        png::ColorType::Rgb => 3,
+        png::ColorType::Rgba => 4,
+        png::ColorType::Indexed => return Ok(false),
+    };
+
+    if frame.width == 0 || frame.height == 0 {
+        return Ok(true);
+    }
+
+    let mut samples = Vec::new();
+    for gy in 0..SAMPLE_GRID {
+        for gx in 0..SAMPLE_GRID {
+            let x = (gx * 2 + 1) * frame.width / (SAMPLE_GRID * 2);
+            let y = (gy * 2 + 1) * frame.height / (SAMPLE_GRID * 2);
+            let offset = y as usize * frame.line_size + x as usize * channels;
+            let pixel = &buf[offset..offset + channels];
+            let rgb = if channels < 3 {
+                [pixel[0]; 3]
+            } else {
+                [pixel[0], pixel[1], pixel[2]]
+            };
+            samples.push(rgb);
+        }
+    }
+
+    let total = samples.len() as f32;
+    let neutral = samples
+        .iter()
+        .filter(|p| p.iter().max().unwrap() - p.iter().min().unwrap() < 24)
+        .count() as f32;
+    let dark = samples
+        .iter()
+        .filter(|p| p.iter().map(|&c| c as u32).sum::<u32>() / 3 < 40)
+        .count() as f32;
+
+    // Share of samples close to the first sampled (background) colour
+    let background = samples[0];
+    let uniform = samples
+        .iter()
+        .filter(|p| {
+            p.iter()
+                .zip(background.iter())
+                .all(|(&a, &b)| a.abs_diff(b) < 16)
+        })
+        .count() as f32;
+
+    Ok(neutral / total > 0.95 && (dark / total > 0.85 || uniform / total > 0.95))
+}
+
+/// Common words that carry no meaning when comparing a link title with its target.
+const STOP_WORDS: &[&str] = &[
+    "the", "and", "for", "with", "from", "www", "com", "html", "htm", "php", "und", "der",
+    "die", "das", "mit", "von", "für", "ein", "eine", "zum", "zur", "den", "dem", "des",
+];
+
+/// Extracts the contents of the first `<title>` element from an HTML document.
+pub fn extract_html_title(html: &str) -> Option<String> {
+    let lower = html.to_ascii_lowercase();
+    let open = lower.find("<title")?;
+    let content_start = open + lower[open..].find('>')? + 1;
+    let content_end = content_start + lower[content_start..].find("</title")?;
+    let title = html
+        .get(content_start..content_end)?
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    if title.is_empty() {
+        None
+    } else {
+        Some(title)
+    }
+}
+
+/// Stemmed, lowercase words of `text` that are long enough to be meaningful.
+fn significant_terms(text: &str) -> HashSet<String> {
+    Lexer::new(text)
+        .filter(|token| token.chars().count() >= 3 && token.chars().all(char::is_alphanumeric))
+        .filter(|token| !token.chars().all(|c| c.is_ascii_digit()))
+        .filter(|token| !STOP_WORDS.contains(&token.as_str()))
+        .collect()
+}
+
+/// Compares the visible title of a link with the target's domain and page title.
+///
+/// Returns `None` when there is too little text to judge, otherwise whether the
+/// title shares at least one meaningful term with the target.
+pub fn title_matches_target(title: &str, url: &str, page_title: Option<&str>) -> Option<bool> {
+    let title_terms = significant_terms(title);
+    if title_terms.len() < 2 {
+        return None;
+    }
+
+    let host = url
+        .split("://")
+        .nth(1)
+        .unwrap_or(url)
+        .split(['/', '?', '#'])
+        .next()
+        .unwrap_or_default()
+        .replace(['.', '-'], " ");
+
+    let mut target_terms = significant_terms(&host);
+    if let Some(page_title) = page_title {
+        target_terms.extend(significant_terms(page_title));
+    }
+
+    if target_terms.is_empty() {
+        return None;
+    }
+
+    Some(!title_terms.is_disjoint(&target_terms))
+}
+
+/// Finds plausible publication years (1900 up to next year) in `text`.
+///
+/// Only standalone four-digit runs count, so IDs like `120175` are ignored.
+pub fn find_years(text: &str, current_year: i32) -> Vec<i32> {
+    let mut years = Vec::new();
+    let mut digits = String::new();
+
+    for ch in text.chars().chain(std::iter::once(' ')) {
+        if ch.is_ascii_digit() {
+            digits.push(ch);
+            continue;
+        }
+        if digits.len() == 4 {
+            if let Ok(year) = digits.parse::<i32>() {
+                if (1900..=current_year + 1).contains(&year) {
+                    years.push(year);
+                }
+            }
+        }
+        digits.clear();
+    }
+
+    years
+}
+
+/// Returns an age flag like `Outdated (2017)` when the title or URL mentions a year
+/// more than `max_age_years` before `current_year`.
+pub fn age_flag(title: &str, url: &str, current_year: i32, max_age_years: i32) -> Option<String> {
+    let mut years = find_years(title, current_year);
+    years.extend(find_years(url, current_year));
+
+    // A newer year anywhere in the entry means it was already refreshed
+    let newest = years.into_iter().max()?;
+    if newest < current_year - max_age_years {
+        Some(format!("Outdated ({newest})"))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_standalone_plausible_years() {
+        assert_eq!(find_years("Katalog 2017/2018", 2026), vec![2017, 2018]);
+        assert_eq!(find_years("/files/120175/doc-1899-2027.pdf", 2026), vec![2027]);
+        assert!(find_years("no years here", 2026).is_empty());
+    }
+
+    #[test]
+    fn flags_entries_by_their_newest_year() {
+        assert_eq!(
+            age_flag("Preisliste 2015", "https://example.com/a.pdf", 2026, 5),
+            Some("Outdated (2015)".to_string())
+        );
+        // The URL mentions a refresh
+        assert_eq!(age_flag("Preisliste 2015", "https://example.com/2024/a.pdf", 2026, 5), None);
+        assert_eq!(age_flag("Preisliste 2021", "", 2026, 5), None);
+        assert_eq!(age_flag("Preisliste", "https://example.com", 2026, 5), None);
+    }
+}
@@ -0,0 +1,37 @@
+use crate::dimensions;
+use anyhow::{Context, Result};
+
+/// A node copied from the browser.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NodeRef {
+    /// A tree item ID, e.g. `treeitem-c6643bf0-label`.
+    TreeItem(String),
+    /// A backend URL showing the node, e.g. `https://cms.example.com/neos/content?node=...`.
+    BackendUrl(String),
+}
+
+impl NodeRef {
+    /// Recognize a tree item ID (with or without the `-label` suffix) or a backend URL.
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        if text.starts_with("treeitem-") && !text.contains(char::is_whitespace) {
+            let id = if text.ends_with("-label") {
+                text.to_string()
+            } else {
+                format!("{text}-label")
+            };
+            return Some(NodeRef::TreeItem(id));
+        }
+        dimensions::node_path(text).map(|_| NodeRef::BackendUrl(text.to_string()))
+    }
+}
+
+/// Read a node reference from the system clipboard.
+pub fn read_node_ref() -> Result<NodeRef> {
+    let text = arboard::Clipboard::new()
+        .and_then(|mut clipboard| clipboard.get_text())
+        .context("Could not read text from the clipboard")?;
+    NodeRef::parse(&text).with_context(|| {
+        format!("The clipboard does not contain a treeitem ID or backend URL: '{}'", text.trim())
+    })
+}
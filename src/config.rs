@@ -0,0 +1,1191 @@
+use crate::backoff::{Backoff, ReloginBreaker};
+use crate::wait::Timeouts;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fmt, fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// Runtime settings loaded from `spider.toml`.
+///
+/// Every field has a default, so the file (and any section in it) is optional.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub spider: SpiderConfig,
+    pub driver: DriverConfig,
+    pub timeouts: TimeoutsConfig,
+    pub checks: ChecksConfig,
+    pub output: OutputConfig,
+    pub backoff: BackoffConfig,
+    pub recorder: RecorderConfig,
+    pub tree: TreeConfig,
+    /// Sites to crawl in one run. Empty means the folder is asked for interactively.
+    pub sites: Vec<SiteConfig>,
+    pub bulk: BulkConfig,
+    pub dimensions: DimensionsConfig,
+    pub report: ReportConfig,
+    pub issues: IssuesConfig,
+    pub notify: NotifyConfig,
+    pub calendar: CalendarConfig,
+    pub state: StateConfig,
+    pub secrets: SecretsConfig,
+    pub permissions: PermissionsConfig,
+    pub lock: LockConfig,
+    pub heartbeat: HeartbeatConfig,
+    pub recovery: RecoveryConfig,
+    pub session: SessionConfig,
+    pub extraction: ExtractionConfig,
+    pub tagging: TaggingConfig,
+    pub archive: ArchiveConfig,
+    pub snapshots: SnapshotConfig,
+}
+
+/// Where the spider connects to and how long it waits for the login.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SpiderConfig {
+    /// Login page of the Neos backend.
+    pub login_url: String,
+    /// WebDriver server (geckodriver, chromedriver or msedgedriver) controlling the browser.
+    pub webdriver_url: String,
+    /// Browser the WebDriver server drives.
+    pub browser: Browser,
+    /// Extra capabilities sent when a session is opened, e.g. `platformName` or `se:...` keys
+    /// picking a Selenium Grid node. Objects are merged into the browser's own, like
+    /// `moz:firefoxOptions`.
+    pub capabilities: BTreeMap<String, serde_json::Value>,
+    /// Seconds to wait for a manual login in a visible browser, otherwise at most for the
+    /// backend to show the document tree after logging in.
+    pub login_wait_secs: u64,
+}
+
+impl SpiderConfig {
+    pub fn login_wait(&self) -> Duration {
+        Duration::from_secs(self.login_wait_secs)
+    }
+}
+
+impl Default for SpiderConfig {
+    fn default() -> Self {
+        Self {
+            login_url: "https://cms.schrackforstudents.com/neos/login".to_string(),
+            webdriver_url: "http://localhost:4444".to_string(),
+            browser: Browser::Firefox,
+            capabilities: BTreeMap::new(),
+            login_wait_secs: 10,
+        }
+    }
+}
+
+/// How geckodriver is started when nothing answers at the local `webdriver_url`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DriverConfig {
+    /// Start geckodriver for Firefox runs, and stop it when the run ends.
+    pub manage: bool,
+    /// geckodriver binary to use. Without it, `geckodriver` is looked up on the PATH and then
+    /// in `dir`, and downloaded there if missing.
+    pub path: Option<PathBuf>,
+    /// geckodriver release downloaded when there is none.
+    pub version: String,
+    /// Where downloaded drivers are kept.
+    pub dir: PathBuf,
+    /// Seconds to wait for a started driver to answer.
+    pub startup_timeout_secs: u64,
+}
+
+impl DriverConfig {
+    pub fn startup_timeout(&self) -> Duration {
+        Duration::from_secs(self.startup_timeout_secs)
+    }
+}
+
+impl Default for DriverConfig {
+    fn default() -> Self {
+        Self {
+            manage: true,
+            path: None,
+            version: "0.36.0".to_string(),
+            dir: PathBuf::from(".drivers"),
+            startup_timeout_secs: 10,
+        }
+    }
+}
+
+/// How long to wait for the backend. How long linked servers get is `[checks] url_timeout_secs`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TimeoutsConfig {
+    /// Seconds a node page, the document tree or the backend may take to load.
+    pub page_load_secs: u64,
+    /// Seconds a single element, e.g. a login form field, may take to appear or go away.
+    pub element_secs: u64,
+    /// Seconds the children of a just expanded folder may take to show up.
+    pub expand_secs: u64,
+    /// Pause before retrying a failed operation, in milliseconds. Also the time the backend gets
+    /// to save inspector changes.
+    pub retry_delay_ms: u64,
+}
+
+impl Default for TimeoutsConfig {
+    fn default() -> Self {
+        Self {
+            page_load_secs: 30,
+            element_secs: 10,
+            expand_secs: 5,
+            retry_delay_ms: 2000,
+        }
+    }
+}
+
+/// Settings for the content quality heuristics.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChecksConfig {
+    /// Entries mentioning a year older than this many years are flagged as outdated.
+    pub max_content_age_years: i32,
+    /// URLs validated at the same time, while the browser moves on to the next nodes.
+    pub validation_concurrency: usize,
+    /// Seconds to wait for a linked server before its URL counts as invalid.
+    pub url_timeout_secs: u64,
+    /// Redirects followed from a linked URL before it is reported as "Redirect".
+    pub max_redirects: usize,
+    /// JSON file keeping reachable URLs between extractions, so they are not checked again.
+    /// Also where `cache serve` keeps the shared cache.
+    pub cache_path: Option<PathBuf>,
+    /// Validation cache server shared by the team (`cache serve`), used instead of the file.
+    pub cache_url: Option<String>,
+    /// Environment variable holding the token the cache server and its clients share.
+    pub cache_token_env: String,
+    /// Hours a cached URL counts as checked.
+    pub cache_max_age_hours: u64,
+    /// Look up an archived copy of dead links in the Wayback Machine.
+    pub wayback: bool,
+}
+
+impl ChecksConfig {
+    pub fn url_timeout(&self) -> Duration {
+        Duration::from_secs(self.url_timeout_secs)
+    }
+
+    pub fn cache_max_age(&self) -> Duration {
+        Duration::from_secs(self.cache_max_age_hours * 3600)
+    }
+}
+
+impl Default for ChecksConfig {
+    fn default() -> Self {
+        Self {
+            max_content_age_years: 5,
+            validation_concurrency: 8,
+            url_timeout_secs: 10,
+            max_redirects: 10,
+            cache_path: None,
+            cache_url: None,
+            cache_token_env: "SPIDER_CACHE_TOKEN".to_string(),
+            cache_max_age_hours: 24,
+            wayback: true,
+        }
+    }
+}
+
+/// Durability settings for the result writers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OutputConfig {
+    /// Flush buffered rows to the file after this many entries.
+    pub flush_every_entries: usize,
+    /// Flush at least this often (in seconds) while entries are coming in.
+    pub flush_interval_secs: u64,
+    /// Also fsync the file after every flush, so rows survive power loss.
+    pub fsync: bool,
+    /// Where the entries of a run are written; all listed sinks are written at once.
+    pub sinks: Vec<SinkKind>,
+    /// SQLite database collecting the entries of every run, used by the `sqlite` sink.
+    pub sqlite_path: PathBuf,
+    /// Row format of the `stdout` sink.
+    pub stdout_format: StdoutFormat,
+    /// Write the rows sorted by breadcrumb path once the run is done, instead of as they are
+    /// extracted. Diffs between runs stay small, but a crashed run writes nothing.
+    pub sort_rows: bool,
+    /// Entries held in memory for sorting and the whole-file sinks (`json`, `html`, `xlsx`)
+    /// beyond this many MiB are moved to a temporary SQLite file; 0 keeps all of them in memory.
+    pub memory_limit_mb: usize,
+}
+
+impl Default for OutputConfig {
+    fn default() -> Self {
+        Self {
+            flush_every_entries: 20,
+            flush_interval_secs: 10,
+            fsync: false,
+            sinks: vec![SinkKind::Csv],
+            sqlite_path: PathBuf::from("embedded_content/history.sqlite"),
+            stdout_format: StdoutFormat::Jsonl,
+            sort_rows: false,
+            memory_limit_mb: 256,
+        }
+    }
+}
+
+/// Browsers the spider can drive, each through its own WebDriver server.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Browser {
+    /// Firefox through geckodriver.
+    #[default]
+    Firefox,
+    /// Chrome or Chromium through chromedriver.
+    Chrome,
+    /// Microsoft Edge through msedgedriver.
+    Edge,
+}
+
+impl std::str::FromStr for Browser {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "firefox" => Ok(Browser::Firefox),
+            "chrome" | "chromium" => Ok(Browser::Chrome),
+            "edge" => Ok(Browser::Edge),
+            _ => Err(format!("unknown browser '{name}' (expected firefox, chrome or edge)")),
+        }
+    }
+}
+
+/// Formats the entries of a run can be written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SinkKind {
+    /// `{job}.csv` in the results directory.
+    Csv,
+    /// `{job}.jsonl` in the results directory, one JSON object per entry.
+    Jsonl,
+    /// `{job}.json` in the results directory, one pretty-printed document with the run summary
+    /// and all entries.
+    Json,
+    /// One row per entry in the `sqlite_path` database, tagged with the run.
+    Sqlite,
+    /// `{job}.xlsx` in the results directory, with a sheet per content type and a summary sheet.
+    Xlsx,
+    /// `{job}.html` in the results directory, a self-contained report to share.
+    Html,
+    /// `{job}.parquet` in the results directory, for analytics tools. Needs the `parquet`
+    /// feature.
+    Parquet,
+    /// Streamed to stdout as they are extracted, with all progress output on stderr.
+    Stdout,
+}
+
+impl std::str::FromStr for SinkKind {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "csv" => Ok(SinkKind::Csv),
+            "jsonl" => Ok(SinkKind::Jsonl),
+            "json" => Ok(SinkKind::Json),
+            "sqlite" => Ok(SinkKind::Sqlite),
+            "xlsx" => Ok(SinkKind::Xlsx),
+            "html" => Ok(SinkKind::Html),
+            "parquet" => Ok(SinkKind::Parquet),
+            "stdout" => Ok(SinkKind::Stdout),
+            _ => Err(format!(
+                "unknown output format '{name}' (expected csv, jsonl, json, sqlite, xlsx, html, \
+                 parquet or stdout)"
+            )),
+        }
+    }
+}
+
+/// Row formats of the `stdout` sink.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StdoutFormat {
+    /// One JSON object per entry, keyed by the CSV column names.
+    Jsonl,
+    /// CSV rows after a header line.
+    Csv,
+}
+
+impl OutputConfig {
+    pub fn flush_interval(&self) -> Duration {
+        Duration::from_secs(self.flush_interval_secs)
+    }
+
+    /// `memory_limit_mb` in bytes.
+    pub fn memory_limit(&self) -> usize {
+        self.memory_limit_mb * 1024 * 1024
+    }
+}
+
+/// How to slow down when the CMS shows signs of overload.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BackoffConfig {
+    /// Normal delay between nodes, in milliseconds.
+    pub base_delay_ms: u64,
+    /// Upper bound for the delay between nodes, in seconds.
+    pub max_delay_secs: u64,
+    /// A node taking longer than this (in seconds) counts as an overload signal.
+    pub slow_response_secs: u64,
+    /// Pause completely after this many consecutive overload signals (0 disables pausing).
+    pub pause_after: u32,
+    /// Length of that pause, in seconds.
+    pub pause_secs: u64,
+    /// Abort when more than this many relogins are needed within `relogin_window_mins`
+    /// (0 disables the check).
+    pub max_relogins: usize,
+    pub relogin_window_mins: u64,
+}
+
+impl Default for BackoffConfig {
+    fn default() -> Self {
+        Self {
+            base_delay_ms: 800,
+            max_delay_secs: 60,
+            slow_response_secs: 45,
+            pause_after: 3,
+            pause_secs: 300,
+            max_relogins: 5,
+            relogin_window_mins: 15,
+        }
+    }
+}
+
+impl BackoffConfig {
+    pub fn to_backoff(&self) -> Backoff {
+        Backoff::new(
+            Duration::from_millis(self.base_delay_ms),
+            Duration::from_secs(self.max_delay_secs),
+            self.pause_after,
+            Duration::from_secs(self.pause_secs),
+        )
+    }
+
+    pub fn slow_response(&self) -> Duration {
+        Duration::from_secs(self.slow_response_secs)
+    }
+
+    pub fn to_relogin_breaker(&self) -> ReloginBreaker {
+        ReloginBreaker::new(
+            self.max_relogins,
+            Duration::from_secs(self.relogin_window_mins * 60),
+        )
+    }
+}
+
+/// One site of a multi-site Neos instance, selected by its root node, its domain, or both.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SiteConfig {
+    /// Name recorded in the `Site` column of every entry.
+    pub name: String,
+    /// Treeitem ID of the node to start from. Defaults to the site's root node.
+    pub root: Option<String>,
+    /// Domain whose backend shows this site, e.g. `cms.example.com`.
+    pub domain: Option<String>,
+}
+
+/// Content dimension values (e.g. languages) to extract every node in.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DimensionsConfig {
+    /// Dimension values as shown in the dimension switcher. The first one is the
+    /// dimension the tree is crawled in; the others are checked for each node.
+    pub values: Vec<String>,
+}
+
+impl DimensionsConfig {
+    /// Whether there is more than one dimension to crawl.
+    pub fn is_matrix(&self) -> bool {
+        self.values.len() > 1
+    }
+}
+
+/// How document tree nodes are interpreted during traversal.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TreeConfig {
+    /// Node type icons that mark shortcut (redirect) nodes.
+    pub shortcut_icons: Vec<String>,
+}
+
+impl Default for TreeConfig {
+    fn default() -> Self {
+        Self {
+            shortcut_icons: vec!["share".to_string()],
+        }
+    }
+}
+
+/// Settings for the generated reports.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReportConfig {
+    /// Breadcrumb segment (0 = the site root) that groups entries into chapters.
+    pub chapter_level: usize,
+}
+
+impl Default for ReportConfig {
+    fn default() -> Self {
+        Self { chapter_level: 1 }
+    }
+}
+
+/// Optional issue tracker that gets one issue per chapter with broken links.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IssuesConfig {
+    /// Unset disables the integration.
+    pub provider: Option<IssueProvider>,
+    /// API root, e.g. `https://gitlab.example.com/api/v4` for a self-hosted GitLab.
+    /// Unset uses the provider's public API.
+    pub api_url: Option<String>,
+    /// `owner/repo` on GitHub, the project path (`group/project`) or ID on GitLab.
+    pub project: String,
+    /// Environment variable holding the access token.
+    pub token_env: String,
+    /// Label marking the issues the spider manages.
+    pub label: String,
+}
+
+impl Default for IssuesConfig {
+    fn default() -> Self {
+        Self {
+            provider: None,
+            api_url: None,
+            project: String::new(),
+            token_env: "ISSUES_TOKEN".to_string(),
+            label: "broken-links".to_string(),
+        }
+    }
+}
+
+/// Chat rooms that get the summary of every bulk extraction and its critical failures.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotifyConfig {
+    pub matrix: MatrixConfig,
+}
+
+/// Room on a Matrix homeserver the notifications are posted to as notices.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MatrixConfig {
+    /// e.g. `https://matrix.example.org`. Unset disables the backend.
+    pub homeserver: Option<String>,
+    /// Room ID (`!abc123:example.org`), not an alias; the account must have joined the room.
+    pub room: String,
+    /// Environment variable holding the access token of the posting account.
+    pub token_env: String,
+}
+
+impl Default for MatrixConfig {
+    fn default() -> Self {
+        Self {
+            homeserver: None,
+            room: String::new(),
+            token_env: "MATRIX_TOKEN".to_string(),
+        }
+    }
+}
+
+/// iCalendar feed of the audit schedule and the completed runs, for the team's shared calendar.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CalendarConfig {
+    /// Local start of the first scheduled run, e.g. `"2026-01-05T02:00:00"`. Unset leaves the
+    /// schedule out of the feed.
+    pub first_run: Option<chrono::NaiveDateTime>,
+    /// Days between scheduled runs.
+    pub every_days: u32,
+    /// Expected length of a run, the length of the scheduled events.
+    pub duration_mins: u64,
+    /// Feed rewritten after every bulk extraction. Unset only writes it with `report calendar`.
+    pub path: Option<PathBuf>,
+}
+
+impl Default for CalendarConfig {
+    fn default() -> Self {
+        Self {
+            first_run: None,
+            every_days: 7,
+            duration_mins: 120,
+            path: None,
+        }
+    }
+}
+
+/// Issue trackers findings can be reported to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueProvider {
+    Github,
+    Gitlab,
+}
+
+impl IssueProvider {
+    pub fn default_api_url(self) -> &'static str {
+        match self {
+            IssueProvider::Github => "https://api.github.com",
+            IssueProvider::Gitlab => "https://gitlab.com/api/v4",
+        }
+    }
+}
+
+/// Where state that outlives a run (review decisions) is kept.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StateConfig {
+    pub path: PathBuf,
+    /// Append-only log of every change made in the CMS.
+    pub audit_log: PathBuf,
+    /// Transaction logs of interrupted bulk jobs that change the CMS, to resume them.
+    pub jobs_dir: PathBuf,
+}
+
+impl Default for StateConfig {
+    fn default() -> Self {
+        Self {
+            path: PathBuf::from("state.json"),
+            audit_log: PathBuf::from("audit.jsonl"),
+            jobs_dir: PathBuf::from("jobs"),
+        }
+    }
+}
+
+/// Optional secrets backend the CMS credentials are fetched from instead of a file on disk,
+/// e.g. HashiCorp Vault or any HTTP endpoint returning JSON.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SecretsConfig {
+    /// URL of the secret, e.g. `https://vault.example.com/v1/secret/data/cms`.
+    /// Without it the credentials files are used.
+    pub url: Option<String>,
+    /// Environment variable holding the access token.
+    pub token_env: String,
+    /// Header the token is sent in.
+    pub token_header: String,
+    /// Dotted paths to the username and password in the JSON response.
+    pub username_field: String,
+    pub password_field: String,
+}
+
+impl Default for SecretsConfig {
+    fn default() -> Self {
+        Self {
+            url: None,
+            token_env: "VAULT_TOKEN".to_string(),
+            token_header: "X-Vault-Token".to_string(),
+            username_field: "data.data.username".to_string(),
+            password_field: "data.data.password".to_string(),
+        }
+    }
+}
+
+/// Kinds of operations a profile can be allowed to perform.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Operation {
+    /// Reading content from the CMS (bulk extraction, single nodes).
+    Extract,
+    /// Reports and merges built from the result files.
+    Report,
+    /// Importing and viewing review decisions.
+    Review,
+    /// Changing question tags in the CMS.
+    WriteTags,
+    /// Changing links in the CMS (replacements, metadata).
+    WriteLinks,
+    Replay,
+    Fixtures,
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Operation::Extract => "extract",
+            Operation::Report => "report",
+            Operation::Review => "review",
+            Operation::WriteTags => "write_tags",
+            Operation::WriteLinks => "write_links",
+            Operation::Replay => "replay",
+            Operation::Fixtures => "fixtures",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Which operations this profile may perform, so read-only accounts can share the tool.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PermissionsConfig {
+    /// Allowed operations. Without the setting every operation is allowed.
+    pub allowed: Option<Vec<Operation>>,
+}
+
+impl PermissionsConfig {
+    /// Fail if `operation` is not allowed for this profile.
+    pub fn check(&self, operation: Operation) -> Result<()> {
+        match &self.allowed {
+            Some(allowed) if !allowed.contains(&operation) => {
+                bail!("The '{operation}' operation is not allowed by [permissions] in spider.toml")
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Run lock that keeps two operators from changing the same CMS at the same time.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LockConfig {
+    /// Directory of the lock files, one per CMS host. Put it on a shared drive to
+    /// coordinate operators on different machines.
+    pub dir: PathBuf,
+    /// A lock older than this many hours is considered left over from a crashed run.
+    pub stale_after_hours: u64,
+}
+
+impl Default for LockConfig {
+    fn default() -> Self {
+        Self {
+            dir: PathBuf::from("locks"),
+            stale_after_hours: 12,
+        }
+    }
+}
+
+impl LockConfig {
+    pub fn stale_after(&self) -> Duration {
+        Duration::from_secs(self.stale_after_hours * 3600)
+    }
+}
+
+/// Heartbeat file that external monitoring can watch to detect hung runs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HeartbeatConfig {
+    pub enabled: bool,
+    pub path: PathBuf,
+    /// How often the file is rewritten while a bulk extraction runs, in seconds.
+    pub interval_secs: u64,
+}
+
+impl Default for HeartbeatConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            path: PathBuf::from("heartbeat.json"),
+            interval_secs: 30,
+        }
+    }
+}
+
+impl HeartbeatConfig {
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval_secs)
+    }
+}
+
+/// How the entries of a node page are read.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExtractionConfig {
+    /// Read all fields with one injected script per page instead of a lookup per field.
+    /// The field-by-field lookup is still used if the script fails.
+    pub script: bool,
+    /// CSS selector of the page region entries and snapshots are read from, leaving out
+    /// navigation and footers. Empty reads the whole page.
+    pub region: String,
+    /// Region selectors by node type, keyed by the node's tree icon (e.g. `file`), overriding
+    /// `region`.
+    pub regions: BTreeMap<String, String>,
+    /// Save the page source of nodes that fail or show no content containers, for debugging
+    /// selector drift without crawling again.
+    pub dump_failed_pages: bool,
+    /// Where those page sources are saved.
+    pub page_dumps_dir: PathBuf,
+    /// Content types to extract. The others are skipped along with their element lookups.
+    pub types: Vec<ContentType>,
+}
+
+impl Default for ExtractionConfig {
+    fn default() -> Self {
+        Self {
+            script: true,
+            region: String::new(),
+            regions: BTreeMap::new(),
+            dump_failed_pages: true,
+            page_dumps_dir: PathBuf::from("embedded_content/page-dumps"),
+            types: vec![ContentType::ExternalLink, ContentType::YouTube, ContentType::Tutorial],
+        }
+    }
+}
+
+impl ExtractionConfig {
+    /// The region selector for a node with the tree icon `icon`, or `None` for the whole page.
+    pub fn region_for(&self, icon: Option<&str>) -> Option<&str> {
+        let region = icon
+            .and_then(|icon| self.regions.get(icon))
+            .unwrap_or(&self.region)
+            .trim();
+        (!region.is_empty()).then_some(region)
+    }
+
+    pub fn extracts(&self, kind: ContentType) -> bool {
+        self.types.contains(&kind)
+    }
+}
+
+/// Kinds of content entries a node page can have. Parsed with [`ContentType::from_str`] in the
+/// configuration too, so both reject unsupported types with the same message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase", try_from = "String")]
+pub enum ContentType {
+    /// ExternalLinks elements in the dynamic content containers.
+    ExternalLink,
+    /// YouTube elements in the dynamic content containers.
+    YouTube,
+    /// Tutorial articles with an embedded video, outside the containers.
+    Tutorial,
+}
+
+impl ContentType {
+    /// The content type as recorded in the entries.
+    pub fn name(self) -> &'static str {
+        match self {
+            ContentType::ExternalLink => "ExternalLink",
+            ContentType::YouTube => "YouTube",
+            ContentType::Tutorial => "Tutorial",
+        }
+    }
+}
+
+impl std::str::FromStr for ContentType {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.to_lowercase().as_str() {
+            "externallink" | "externallinks" => Ok(ContentType::ExternalLink),
+            "youtube" => Ok(ContentType::YouTube),
+            "tutorial" | "tutorials" => Ok(ContentType::Tutorial),
+            // Assets (downloads from the media browser) are not read from the node pages
+            "asset" | "assets" => Err(format!(
+                "content type '{name}' is not supported yet (supported: externallink, youtube, \
+                 tutorial)"
+            )),
+            _ => Err(format!(
+                "unknown content type '{name}' (expected externallink, youtube or tutorial)"
+            )),
+        }
+    }
+}
+
+impl TryFrom<String> for ContentType {
+    type Error = String;
+
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        name.parse()
+    }
+}
+
+/// Recovery from a backend that stops responding while a node loads.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RecoveryConfig {
+    /// How long the loading overlay may stay up before the page counts as stuck, in seconds.
+    pub spinner_timeout_secs: u64,
+    /// Page refreshes attempted for one node before it is given up.
+    pub max_refreshes: u32,
+    /// Consecutive nodes failing with missing or stale elements before the page is reloaded and
+    /// the tree expanded again. 0 disables it.
+    pub max_dom_errors: u32,
+}
+
+impl Default for RecoveryConfig {
+    fn default() -> Self {
+        Self {
+            spinner_timeout_secs: 30,
+            max_refreshes: 2,
+            max_dom_errors: 3,
+        }
+    }
+}
+
+impl RecoveryConfig {
+    pub fn spinner_timeout(&self) -> Duration {
+        Duration::from_secs(self.spinner_timeout_secs)
+    }
+}
+
+/// Renewing the backend session before it expires.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionConfig {
+    /// Log in again between nodes shortly before the session is expected to expire, instead of
+    /// waiting for the relogin dialog.
+    pub proactive_reauth: bool,
+    /// Known session lifetime in minutes, used until one was observed. 0 learns it from the
+    /// first relogin dialog.
+    pub lifetime_mins: u64,
+    /// How long before the expected expiry to log in again, in seconds.
+    pub margin_secs: u64,
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        Self {
+            proactive_reauth: true,
+            lifetime_mins: 0,
+            margin_secs: 120,
+        }
+    }
+}
+
+impl SessionConfig {
+    pub fn lifetime(&self) -> Option<Duration> {
+        (self.lifetime_mins > 0).then(|| Duration::from_secs(self.lifetime_mins * 60))
+    }
+
+    pub fn margin(&self) -> Duration {
+        Duration::from_secs(self.margin_secs)
+    }
+}
+
+/// Session recording for reproducing failures.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RecorderConfig {
+    /// Record every high-level action of a session.
+    pub enabled: bool,
+    /// Directory the session files are written to.
+    pub dir: PathBuf,
+    /// Also save the page source after each action, so the session can be replayed offline.
+    pub snapshots: bool,
+}
+
+impl Default for RecorderConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            dir: PathBuf::from("recordings"),
+            snapshots: false,
+        }
+    }
+}
+
+/// Content store of node snapshots, for diffing what changed between audits.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SnapshotConfig {
+    /// Store a snapshot of every extracted node page.
+    pub enabled: bool,
+    /// Directory of the store.
+    pub dir: PathBuf,
+    /// Whether the visible text or the full HTML of a node page is stored.
+    pub format: SnapshotFormat,
+}
+
+impl Default for SnapshotConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            dir: PathBuf::from("snapshots"),
+            format: SnapshotFormat::Text,
+        }
+    }
+}
+
+/// What a node snapshot holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SnapshotFormat {
+    Text,
+    Html,
+}
+
+/// Defaults of a bulk extraction, so `extract` and batch runs need no prompts or flags.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BulkConfig {
+    /// Treeitem ID of the folder to extract. Unset means all `[[sites]]`, or the default folder.
+    pub folder: Option<String>,
+    /// Levels below the folder to traverse.
+    pub max_depth: usize,
+    pub validate_urls: bool,
+    /// Also extract the other dimension variants of every node.
+    pub all_dimensions: bool,
+    /// Directory the results and reports are written to.
+    pub out_dir: PathBuf,
+    /// Browser sessions extracting the nodes in parallel. Above 1, extra sessions are opened
+    /// and logged in next to the one that traverses the tree.
+    pub sessions: usize,
+    /// Also write `{job}.html`, a self-contained report to share, whatever the [output] sinks.
+    pub html_report: bool,
+}
+
+impl Default for BulkConfig {
+    fn default() -> Self {
+        Self {
+            folder: None,
+            max_depth: 5,
+            validate_urls: true,
+            all_dimensions: false,
+            out_dir: PathBuf::from("embedded_content"),
+            sessions: 1,
+            html_report: true,
+        }
+    }
+}
+
+/// Settings for adding tags to questions.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TaggingConfig {
+    /// CSV of question IDs and their tags, or a glob like `resources/tags/*.csv` naming several
+    /// that are merged.
+    pub tags_path: PathBuf,
+    /// Pause after applying the tags of a question, in milliseconds.
+    pub apply_delay_ms: u64,
+    /// Regex finding the question ID in a question's title, with the ID in the `id` group.
+    pub question_id_pattern: String,
+    /// What to do with a question ID listed more than once in the tags file.
+    pub duplicates: DuplicatePolicy,
+}
+
+/// What to do with a question ID listed more than once with different tags, in one tags file
+/// or across several.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicatePolicy {
+    /// Refuse the tags until the duplicate is removed.
+    #[default]
+    Error,
+    /// Use the tags of the first line with the ID, in file name order.
+    First,
+    /// Use the tags of the last line with the ID.
+    Last,
+}
+
+impl TaggingConfig {
+    pub fn apply_delay(&self) -> Duration {
+        Duration::from_millis(self.apply_delay_ms)
+    }
+}
+
+impl Default for TaggingConfig {
+    fn default() -> Self {
+        Self {
+            tags_path: PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/resources/tags.csv")),
+            apply_delay_ms: 1000,
+            question_id_pattern: r"^\s*(?P<id>\d+(?:\.\d+)*)\.?(?:\s|$)".to_string(),
+            duplicates: DuplicatePolicy::Error,
+        }
+    }
+}
+
+/// Compression and retention of page snapshots, old recorded sessions and old results.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ArchiveConfig {
+    /// How page snapshots are compressed, when saved and when archived.
+    pub compression: Compression,
+    /// Compress the snapshots of sessions and the store objects older than this many days.
+    /// 0 disables archiving.
+    pub compress_after_days: u64,
+    /// Delete sessions, snapshot runs and result files older than this many days. 0 keeps them
+    /// forever.
+    pub delete_after_days: u64,
+}
+
+impl Default for ArchiveConfig {
+    fn default() -> Self {
+        Self {
+            compression: Compression::None,
+            compress_after_days: 7,
+            delete_after_days: 0,
+        }
+    }
+}
+
+impl ArchiveConfig {
+    pub fn compress_after(&self) -> Duration {
+        Duration::from_secs(self.compress_after_days * 24 * 60 * 60)
+    }
+
+    pub fn delete_after(&self) -> Duration {
+        Duration::from_secs(self.delete_after_days * 24 * 60 * 60)
+    }
+}
+
+/// Compression formats for archived files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    /// File extension added to compressed files, without the dot.
+    pub fn extension(self) -> Option<&'static str> {
+        match self {
+            Compression::None => None,
+            Compression::Gzip => Some("gz"),
+            Compression::Zstd => Some("zst"),
+        }
+    }
+}
+
+/// Where an effective setting comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    Default,
+    File,
+    Env,
+    Cli,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Source::Default => "default",
+            Source::File => "file",
+            Source::Env => "env",
+            Source::Cli => "CLI",
+        };
+        // pad() so the source lines up in `config show`
+        f.pad(name)
+    }
+}
+
+/// One effective setting, as shown by `config show`.
+#[derive(Debug, Clone)]
+pub struct Setting {
+    /// Dotted key, e.g. `backoff.pause_secs` or `sites[0].name`.
+    pub key: String,
+    pub value: String,
+    pub source: Source,
+}
+
+impl Setting {
+    /// A setting with its value redacted if it may contain a secret.
+    pub fn new(key: String, value: String, source: Source) -> Self {
+        let value = redact(&key, value);
+        Self { key, value, source }
+    }
+}
+
+/// Hide values of secret-looking keys and passwords embedded in URLs.
+fn redact(key: &str, value: String) -> String {
+    let name = key.rsplit('.').next().unwrap_or(key);
+    if ["password", "token", "secret", "api_key"].contains(&name) {
+        return "***".to_string();
+    }
+    match reqwest::Url::parse(value.trim_matches('"')) {
+        Ok(mut url) if url.password().is_some() => {
+            let _ = url.set_password(Some("***"));
+            format!("\"{url}\"")
+        }
+        _ => value,
+    }
+}
+
+/// Flatten a TOML value into dotted keys and rendered values.
+fn flatten(prefix: &str, value: &toml::Value, out: &mut Vec<(String, String)>) {
+    let is_table_array = |items: &[toml::Value]| !items.is_empty() && items.iter().all(toml::Value::is_table);
+    match value {
+        toml::Value::Table(table) => {
+            for (key, value) in table {
+                let key = if prefix.is_empty() { key.clone() } else { format!("{prefix}.{key}") };
+                flatten(&key, value, out);
+            }
+        }
+        toml::Value::Array(items) if is_table_array(items) => {
+            for (index, item) in items.iter().enumerate() {
+                flatten(&format!("{prefix}[{index}]"), item, out);
+            }
+        }
+        value => out.push((prefix.to_string(), value.to_string())),
+    }
+}
+
+impl Config {
+    /// The effective settings as dotted keys, marking those set in the file at `path`.
+    pub fn settings<P: AsRef<Path>>(&self, path: P) -> Result<Vec<Setting>> {
+        let path = path.as_ref();
+        let mut from_file = Vec::new();
+        if path.exists() {
+            let data = fs::read_to_string(path)
+                .with_context(|| format!("Could not read config file {}", path.display()))?;
+            let file: toml::Value = toml::from_str(&data)
+                .with_context(|| format!("Invalid config file {}", path.display()))?;
+            flatten("", &file, &mut from_file);
+        }
+
+        let mut effective = Vec::new();
+        flatten("", &toml::Value::try_from(self)?, &mut effective);
+        Ok(effective
+            .into_iter()
+            .map(|(key, value)| {
+                let source = if from_file.iter().any(|(file_key, _)| *file_key == key) {
+                    Source::File
+                } else {
+                    Source::Default
+                };
+                Setting::new(key, value, source)
+            })
+            .collect())
+    }
+
+    /// The `[timeouts]`, together with the `[checks]` timeout for linked servers.
+    pub fn timeouts(&self) -> Timeouts {
+        Timeouts {
+            page_load: Duration::from_secs(self.timeouts.page_load_secs),
+            element: Duration::from_secs(self.timeouts.element_secs),
+            expand: Duration::from_secs(self.timeouts.expand_secs),
+            validation: self.checks.url_timeout(),
+            retry: Duration::from_millis(self.timeouts.retry_delay_ms),
+        }
+    }
+
+    /// Load the configuration from `path`, falling back to defaults if the file does not exist.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let data = fs::read_to_string(path)
+            .with_context(|| format!("Could not read config file {}", path.display()))?;
+        toml::from_str(&data).with_context(|| format!("Invalid config file {}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn content_types_parse_from_the_cli_and_the_config() {
+        assert_eq!("externallinks".parse(), Ok(ContentType::ExternalLink));
+        assert_eq!("YouTube".parse(), Ok(ContentType::YouTube));
+        let extraction: ExtractionConfig = toml::from_str("types = [\"tutorial\"]").unwrap();
+        assert_eq!(extraction.types, [ContentType::Tutorial]);
+    }
+
+    #[test]
+    fn asset_is_rejected_with_the_supported_types() {
+        let error = "asset".parse::<ContentType>().unwrap_err();
+        assert!(error.contains("not supported") && error.contains("externallink"));
+        let error = toml::from_str::<ExtractionConfig>("types = [\"asset\"]").unwrap_err();
+        assert!(error.to_string().contains("not supported"), "{error}");
+    }
+}
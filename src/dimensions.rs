@@ -0,0 +1,50 @@
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Whether a node exists in one content dimension (e.g. language).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VariantCoverage {
+    pub node_id: String,
+    pub label: String,
+    pub dimension: String,
+    pub present: bool,
+}
+
+/// The node path of the document shown in the backend, without the workspace and
+/// dimension part of the context path, e.g. `/sites/foo/node-abc` for
+/// `/neos/content?node=/sites/foo/node-abc@user-admin;language=en`.
+///
+/// It stays the same when switching to a dimension the node exists in.
+pub fn node_path(backend_url: &str) -> Option<String> {
+    let url = Url::parse(backend_url).ok()?;
+    let (_, context_path) = url.query_pairs().find(|(key, _)| key == "node")?;
+    let path = context_path.split('@').next()?;
+    (!path.is_empty()).then(|| path.to_string())
+}
+
+/// A link to the node shown at `backend_url` that works for every editor: the context path
+/// with the workspace set to `live` (Neos opens it in the editor's own workspace), without
+/// any other query parameters.
+pub fn backend_link(backend_url: &str) -> Option<String> {
+    let mut url = Url::parse(backend_url).ok()?;
+    let (_, context_path) = url.query_pairs().find(|(key, _)| key == "node")?;
+    let (path, context) = context_path.split_once('@')?;
+    let dimensions = context.split_once(';').map(|(_, dimensions)| dimensions);
+    let context_path = match dimensions {
+        Some(dimensions) => format!("{path}@live;{dimensions}"),
+        None => format!("{path}@live"),
+    };
+    url.query_pairs_mut().clear().append_pair("node", &context_path);
+    url.set_fragment(None);
+    Some(url.to_string())
+}
+
+/// Number of nodes missing per dimension.
+pub fn missing_per_dimension(coverage: &[VariantCoverage]) -> BTreeMap<&str, usize> {
+    let mut missing = BTreeMap::new();
+    for variant in coverage.iter().filter(|v| !v.present) {
+        *missing.entry(variant.dimension.as_str()).or_insert(0) += 1;
+    }
+    missing
+}
@@ -0,0 +1,181 @@
+use crate::config::{Browser, DriverConfig, SpiderConfig};
+use anyhow::{bail, Context, Result};
+use reqwest::{Client, Url};
+use std::{
+    env, fs,
+    net::TcpListener,
+    path::{Path, PathBuf},
+    process::Stdio,
+    time::Duration,
+};
+use tokio::{
+    process::{Child, Command},
+    time::{sleep, Instant},
+};
+
+/// Where the geckodriver release archives are downloaded from.
+const RELEASES_URL: &str = "https://github.com/mozilla/geckodriver/releases/download";
+
+/// Pause between checks whether a started driver answers.
+const STARTUP_POLL: Duration = Duration::from_millis(200);
+
+/// A geckodriver started for this run. It is killed when dropped, i.e. when the run ends.
+pub struct ManagedDriver {
+    child: Child,
+    url: String,
+}
+
+impl ManagedDriver {
+    /// WebDriver URL the driver listens on.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+}
+
+impl Drop for ManagedDriver {
+    fn drop(&mut self) {
+        let _ = self.child.start_kill();
+    }
+}
+
+/// Start geckodriver on a free port if the run drives Firefox through a local `webdriver_url`
+/// that nothing answers at, and `[driver] manage` is on. `None` if a driver is running already,
+/// or another browser or a remote server is used.
+pub async fn ensure(spider: &SpiderConfig, config: &DriverConfig) -> Result<Option<ManagedDriver>> {
+    if !config.manage || spider.browser != Browser::Firefox || !is_local(&spider.webdriver_url) {
+        return Ok(None);
+    }
+    let client = Client::builder().timeout(Duration::from_secs(2)).build()?;
+    if answers(&client, &spider.webdriver_url).await {
+        return Ok(None);
+    }
+
+    let binary = locate(config).await?;
+    let port = free_port()?;
+    let child = Command::new(&binary)
+        .arg("--port")
+        .arg(port.to_string())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .with_context(|| format!("Could not start {}", binary.display()))?;
+    let mut driver = ManagedDriver {
+        child,
+        url: format!("http://127.0.0.1:{port}"),
+    };
+
+    let deadline = Instant::now() + config.startup_timeout();
+    while !answers(&client, &driver.url).await {
+        if let Some(status) = driver.child.try_wait()? {
+            bail!("{} exited right away ({status})", binary.display());
+        }
+        if Instant::now() > deadline {
+            bail!(
+                "{} did not answer within {}s",
+                binary.display(),
+                config.startup_timeout_secs
+            );
+        }
+        sleep(STARTUP_POLL).await;
+    }
+    Ok(Some(driver))
+}
+
+/// The geckodriver binary: `[driver] path`, else the one on the PATH, else the one in
+/// `[driver] dir`, which is downloaded if missing.
+async fn locate(config: &DriverConfig) -> Result<PathBuf> {
+    if let Some(path) = &config.path {
+        return Ok(path.clone());
+    }
+    let name = if cfg!(windows) { "geckodriver.exe" } else { "geckodriver" };
+    let on_path = env::var_os("PATH").and_then(|paths| {
+        env::split_paths(&paths)
+            .map(|dir| dir.join(name))
+            .find(|path| path.is_file())
+    });
+    if let Some(path) = on_path {
+        return Ok(path);
+    }
+    let dir = config.dir.join(format!("geckodriver-{}", config.version));
+    let path = dir.join(name);
+    if !path.is_file() {
+        download(&config.version, &dir).await?;
+    }
+    Ok(path)
+}
+
+/// Download geckodriver `version` for this platform and unpack it into `dir`. The archive is
+/// unpacked with `tar`, which also handles the zip files of the Windows releases.
+async fn download(version: &str, dir: &Path) -> Result<()> {
+    let asset = asset_name(version)?;
+    let url = format!("{RELEASES_URL}/v{version}/{asset}");
+    println!("Downloading geckodriver {version} from {url}");
+    let archive = reqwest::get(&url)
+        .await
+        .and_then(|response| response.error_for_status())
+        .with_context(|| format!("Could not download {url}"))?
+        .bytes()
+        .await
+        .with_context(|| format!("Could not download {url}"))?;
+
+    fs::create_dir_all(dir)
+        .with_context(|| format!("Could not create the driver directory {}", dir.display()))?;
+    let archive_path = dir.join(&asset);
+    fs::write(&archive_path, &archive)
+        .with_context(|| format!("Could not write {}", archive_path.display()))?;
+    let status = Command::new("tar")
+        .arg("-xf")
+        .arg(&archive_path)
+        .arg("-C")
+        .arg(dir)
+        .status()
+        .await
+        .context("Could not run tar to unpack geckodriver")?;
+    let _ = fs::remove_file(&archive_path);
+    if !status.success() {
+        bail!("Could not unpack {asset} ({status})");
+    }
+    println!("✓ geckodriver {version} saved in {}", dir.display());
+    Ok(())
+}
+
+/// File name of the geckodriver release for this platform.
+fn asset_name(version: &str) -> Result<String> {
+    let platform = match (env::consts::OS, env::consts::ARCH) {
+        ("linux", "x86_64") => "linux64",
+        ("linux", "x86") => "linux32",
+        ("linux", "aarch64") => "linux-aarch64",
+        ("macos", "x86_64") => "macos",
+        ("macos", "aarch64") => "macos-aarch64",
+        ("windows", "x86_64") => "win64",
+        ("windows", "x86") => "win32",
+        ("windows", "aarch64") => "win-aarch64",
+        (os, arch) => bail!("No geckodriver release for {os} on {arch}, set [driver] path"),
+    };
+    let extension = if cfg!(windows) { "zip" } else { "tar.gz" };
+    Ok(format!("geckodriver-v{version}-{platform}.{extension}"))
+}
+
+/// Whether a WebDriver server answers at `url`.
+async fn answers(client: &Client, url: &str) -> bool {
+    let status = format!("{}/status", url.trim_end_matches('/'));
+    client
+        .get(status)
+        .send()
+        .await
+        .is_ok_and(|response| response.status().is_success())
+}
+
+/// Whether `url` points at this machine, where a driver can be started.
+fn is_local(url: &str) -> bool {
+    Url::parse(url).is_ok_and(|url| {
+        matches!(url.host_str(), Some("localhost" | "127.0.0.1" | "[::1]"))
+    })
+}
+
+/// A port nothing listens on right now.
+fn free_port() -> Result<u16> {
+    let listener = TcpListener::bind("127.0.0.1:0").context("Could not find a free port")?;
+    Ok(listener.local_addr()?.port())
+}
@@ -0,0 +1,347 @@
+use crate::config::ContentType;
+use crate::html::{Document, Element};
+use crate::selectors::ContentSelectors;
+use crate::spider::ScriptEntry;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path};
+
+/// Placeholder for values removed from captured pages.
+const REDACTED: &str = "REDACTED";
+
+/// Metadata stored next to each captured fixture.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FixtureManifest {
+    pub node_id: String,
+    pub breadcrumb_path: String,
+    pub neos_version: Option<String>,
+    pub captured_at: DateTime<Local>,
+    /// Whether the content was found inside the content iframe.
+    pub in_iframe: bool,
+    /// Number of entries the extractors found when the fixture was captured.
+    pub entries: usize,
+}
+
+impl FixtureManifest {
+    pub fn from_json_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let json = fs::read_to_string(path)
+            .with_context(|| format!("Could not read fixture manifest {}", path.display()))?;
+        serde_json::from_str(&json)
+            .with_context(|| format!("Invalid fixture manifest {}", path.display()))
+    }
+
+    pub fn to_json_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(path, json).context("Could not write fixture manifest")?;
+        Ok(())
+    }
+}
+
+/// The entries the content script (`resources/scripts/content-entries.js`) reads from a
+/// captured page, found without a browser. Mirrors the script field for field, so a fixture
+/// gives the same entries offline as on the live page; `player` holds the index of the
+/// embedded player in the page.
+pub fn page_entries(
+    page: &Document,
+    region: Option<&str>,
+    selectors: &ContentSelectors,
+    types: &[ContentType],
+) -> Vec<ScriptEntry> {
+    let root = region
+        .and_then(|region| page.select_first(region))
+        .unwrap_or_else(|| page.root());
+    let wanted = |kind| types.contains(&kind);
+    let mut players = 0;
+    let mut player = |item: &Element| {
+        let element = find_first(item, &selectors.player)?;
+        players += 1;
+        Some((players - 1, element.attr("src").unwrap_or_default().trim().to_string()))
+    };
+
+    let containers = if wanted(ContentType::ExternalLink) || wanted(ContentType::YouTube) {
+        find_all_first(&root, &selectors.container)
+    } else {
+        Vec::new()
+    };
+    let mut entries = Vec::new();
+    for (index, container) in containers.iter().enumerate() {
+        let container_index = Some(index + 1);
+        let links = if wanted(ContentType::ExternalLink) {
+            find_all_first(container, &selectors.external_link)
+        } else {
+            Vec::new()
+        };
+        for item in links {
+            let fields = [
+                &selectors.url,
+                &selectors.title,
+                &selectors.author,
+                &selectors.file_type,
+                &selectors.size,
+            ]
+            .map(|candidates| text(&item, candidates));
+            let matched = fields.iter().any(Option::is_some);
+            let [url, title, author, file_type, size] = fields.map(Option::unwrap_or_default);
+            entries.push(ScriptEntry {
+                kind: ContentType::ExternalLink.name().to_string(),
+                url,
+                title,
+                author,
+                file_type,
+                size,
+                container_index,
+                fusion_path: fusion_path(&item),
+                player: None,
+                matched,
+            });
+        }
+        let videos = if wanted(ContentType::YouTube) {
+            find_all_first(container, &selectors.youtube)
+        } else {
+            Vec::new()
+        };
+        for item in videos {
+            let embed = player(&item);
+            entries.push(ScriptEntry {
+                kind: ContentType::YouTube.name().to_string(),
+                url: embed.as_ref().map(|(_, src)| src.clone()).unwrap_or_default(),
+                title: String::new(),
+                author: String::new(),
+                file_type: String::new(),
+                size: String::new(),
+                container_index,
+                fusion_path: fusion_path(&item),
+                matched: embed.is_some(),
+                player: embed.map(|(id, _)| id.to_string()),
+            });
+        }
+    }
+
+    let tutorials = if wanted(ContentType::Tutorial) {
+        find_all_first(&root, &selectors.tutorial)
+    } else {
+        Vec::new()
+    };
+    for article in tutorials {
+        let embed = player(&article);
+        let url = text(&article, &selectors.video_url);
+        entries.push(ScriptEntry {
+            kind: ContentType::Tutorial.name().to_string(),
+            matched: url.is_some() || embed.is_some(),
+            url: url.unwrap_or_default(),
+            title: String::new(),
+            author: String::new(),
+            file_type: String::new(),
+            size: String::new(),
+            container_index: None,
+            fusion_path: fusion_path(&article),
+            player: embed.map(|(id, _)| id.to_string()),
+        });
+    }
+    entries
+}
+
+/// Node IDs of the tree items in a captured tree, found with the `[tree] child_item` candidates.
+pub fn tree_node_ids(tree: &Document, child_item: &[String]) -> Vec<String> {
+    find_all_first(&tree.root(), child_item)
+        .iter()
+        .filter_map(|item| item.attr("aria-labelledby"))
+        .filter(|id| !id.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// All elements of the first candidate that matches anything, like `selectors::find_all_first`.
+fn find_all_first<'a>(root: &Element<'a>, candidates: &[String]) -> Vec<Element<'a>> {
+    candidates
+        .iter()
+        .map(|candidate| root.select(candidate))
+        .find(|found| !found.is_empty())
+        .unwrap_or_default()
+}
+
+/// The first element matched by one of the candidates, like `selectors::probe_first`.
+fn find_first<'a>(root: &Element<'a>, candidates: &[String]) -> Option<Element<'a>> {
+    candidates
+        .iter()
+        .find_map(|candidate| root.select_first(candidate))
+}
+
+/// Trimmed text of the first match of the candidates.
+fn text(root: &Element, candidates: &[String]) -> Option<String> {
+    find_first(root, candidates).map(|element| element.text().trim().to_string())
+}
+
+fn fusion_path(element: &Element) -> String {
+    element
+        .attr("data-__neos-fusion-path")
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Remove session-specific and personal data from a captured page.
+///
+/// Inline scripts (which carry the backend configuration and user data) are emptied,
+/// CSRF tokens and e-mail addresses are redacted and every string in `secrets`
+/// (e.g. the username) is replaced.
+pub fn sanitize_html(html: &str, secrets: &[&str]) -> String {
+    let mut html = redact_emails(&redact_csrf_tags(&strip_script_bodies(html)));
+    for secret in secrets.iter().filter(|s| !s.is_empty()) {
+        html = html.replace(secret, REDACTED);
+    }
+    html
+}
+
+/// Keep `<script>` tags but drop their inline contents.
+fn strip_script_bodies(html: &str) -> String {
+    let lower = html.to_ascii_lowercase();
+    let mut out = String::with_capacity(html.len());
+    let mut pos = 0;
+
+    while let Some(start) = lower[pos..].find("<script") {
+        let start = pos + start;
+        let Some(open_len) = lower[start..].find('>') else {
+            break;
+        };
+        let body_start = start + open_len + 1;
+        let Some(body_len) = lower[body_start..].find("</script") else {
+            break;
+        };
+        out.push_str(&html[pos..body_start]);
+        pos = body_start + body_len;
+    }
+
+    out.push_str(&html[pos..]);
+    out
+}
+
+/// Redact the `value`/`content` of every tag that mentions a CSRF token.
+fn redact_csrf_tags(html: &str) -> String {
+    let mut out = String::with_capacity(html.len());
+    let mut rest = html;
+
+    while let Some(start) = rest.find('<') {
+        let Some(len) = rest[start..].find('>') else {
+            break;
+        };
+        let end = start + len + 1;
+        let tag = &rest[start..end];
+
+        out.push_str(&rest[..start]);
+        if tag.to_ascii_lowercase().contains("csrf") {
+            out.push_str(&redact_attribute(&redact_attribute(tag, "value"), "content"));
+        } else {
+            out.push_str(tag);
+        }
+        rest = &rest[end..];
+    }
+
+    out.push_str(rest);
+    out
+}
+
+fn redact_attribute(tag: &str, attribute: &str) -> String {
+    let needle = format!("{attribute}=\"");
+    let Some(start) = tag.find(&needle) else {
+        return tag.to_string();
+    };
+    let value_start = start + needle.len();
+    let Some(len) = tag[value_start..].find('"') else {
+        return tag.to_string();
+    };
+    format!("{}{REDACTED}{}", &tag[..value_start], &tag[value_start + len..])
+}
+
+/// Replace anything that looks like an e-mail address with `user@example.com`.
+fn redact_emails(text: &str) -> String {
+    let is_part = |c: char| c.is_alphanumeric() || "._%+-".contains(c);
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(at) = rest.find('@') {
+        let local_start = rest[..at]
+            .char_indices()
+            .rev()
+            .find(|(_, c)| !is_part(*c))
+            .map_or(0, |(i, c)| i + c.len_utf8());
+        let domain = &rest[at + 1..];
+        let domain_len = domain.find(|c: char| !is_part(c)).unwrap_or(domain.len());
+        let domain = domain[..domain_len].trim_end_matches('.');
+        let end = at + 1 + domain.len();
+
+        if local_start < at && domain.contains('.') && !domain.starts_with('.') {
+            out.push_str(&rest[..local_start]);
+            out.push_str("user@example.com");
+        } else {
+            out.push_str(&rest[..end]);
+        }
+        rest = &rest[end..];
+    }
+
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sanitize_empties_inline_scripts() {
+        let html = r#"<script>var user = {"name": "editor"};</script><SCRIPT src="/main.js"></SCRIPT>"#;
+        assert_eq!(
+            sanitize_html(html, &[]),
+            r#"<script></script><SCRIPT src="/main.js"></SCRIPT>"#
+        );
+    }
+
+    #[test]
+    fn sanitize_redacts_csrf_tokens_emails_and_secrets() {
+        let html = concat!(
+            r#"<meta name="neos-csrf-token" content="a1b2c3">"#,
+            r#"<input type="hidden" name="__csrfToken" value="d4e5f6">"#,
+            r#"<p>Kontakt: max.muster@schrack.at, editor1</p>"#,
+            r#"<input name="q" value="keep">"#,
+        );
+        assert_eq!(
+            sanitize_html(html, &["editor1", ""]),
+            concat!(
+                r#"<meta name="neos-csrf-token" content="REDACTED">"#,
+                r#"<input type="hidden" name="__csrfToken" value="REDACTED">"#,
+                r#"<p>Kontakt: user@example.com, REDACTED</p>"#,
+                r#"<input name="q" value="keep">"#,
+            )
+        );
+    }
+
+    #[test]
+    fn sanitize_keeps_at_signs_that_are_no_addresses() {
+        let html = "<p>@media and user@localhost</p>";
+        assert_eq!(sanitize_html(html, &[]), html);
+    }
+
+    #[test]
+    fn page_entries_respect_the_region() {
+        let page = Document::parse(concat!(
+            r#"<div id="main"><div class="c"><div class="link"><p class="url">a</p></div></div></div>"#,
+            r#"<div class="c"><div class="link"><p class="url">b</p></div></div>"#,
+        ));
+        let selectors = ContentSelectors {
+            container: vec![".c".to_string()],
+            external_link: vec![".link".to_string()],
+            url: vec![".url".to_string()],
+            ..ContentSelectors::default()
+        };
+        let types = [ContentType::ExternalLink];
+        let urls = |region| -> Vec<String> {
+            page_entries(&page, region, &selectors, &types)
+                .into_iter()
+                .map(|entry| entry.url)
+                .collect()
+        };
+        assert_eq!(urls(None), ["a", "b"]);
+        assert_eq!(urls(Some("#main")), ["a"]);
+    }
+}
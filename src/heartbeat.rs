@@ -0,0 +1,124 @@
+use crate::config::HeartbeatConfig;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::{fs, path::Path, sync::Mutex};
+use tokio::task::JoinHandle;
+
+static HEARTBEAT: Lazy<Mutex<Option<Heartbeat>>> = Lazy::new(|| Mutex::new(None));
+
+/// Contents of the heartbeat file.
+///
+/// `updated_at` shows the process is alive; `last_progress_at` not moving while it is
+/// points to a hang.
+#[derive(Debug, Clone, Serialize)]
+pub struct Heartbeat {
+    pub pid: u32,
+    /// What the job works on, e.g. the start folder of a bulk extraction.
+    pub job: String,
+    /// `running`, `finished`, `aborted` or `stopped` (ended by an error).
+    pub status: String,
+    pub started_at: DateTime<Local>,
+    pub updated_at: DateTime<Local>,
+    pub last_progress_at: DateTime<Local>,
+    pub current_node: Option<String>,
+    pub done: usize,
+    pub total: usize,
+    pub progress_percent: f64,
+}
+
+/// Keeps the heartbeat file updated until it is dropped.
+pub struct HeartbeatTask {
+    handle: JoinHandle<()>,
+    config: HeartbeatConfig,
+}
+
+impl Drop for HeartbeatTask {
+    fn drop(&mut self) {
+        self.handle.abort();
+        if let Some(heartbeat) = HEARTBEAT.lock().unwrap().as_mut() {
+            // Still running means the job ended early through an error
+            if heartbeat.status == "running" {
+                heartbeat.status = "stopped".to_string();
+            }
+        }
+        if let Err(e) = write(&self.config.path) {
+            eprintln!("⚠ Could not write heartbeat: {e:#}");
+        }
+    }
+}
+
+/// Start writing the heartbeat for `job` with `total` nodes every configured interval.
+/// Returns `None` if heartbeats are disabled.
+pub fn spawn(config: &HeartbeatConfig, job: &str, total: usize) -> Option<HeartbeatTask> {
+    if !config.enabled {
+        return None;
+    }
+
+    let now = Local::now();
+    *HEARTBEAT.lock().unwrap() = Some(Heartbeat {
+        pid: std::process::id(),
+        job: job.to_string(),
+        status: "running".to_string(),
+        started_at: now,
+        updated_at: now,
+        last_progress_at: now,
+        current_node: None,
+        done: 0,
+        total,
+        progress_percent: 0.0,
+    });
+
+    let path = config.path.clone();
+    let interval = config.interval();
+    let handle = tokio::spawn(async move {
+        loop {
+            if let Err(e) = write(&path) {
+                eprintln!("⚠ Could not write heartbeat: {e:#}");
+            }
+            tokio::time::sleep(interval).await;
+        }
+    });
+    Some(HeartbeatTask {
+        handle,
+        config: config.clone(),
+    })
+}
+
+/// Record that `node` is being processed after `done` nodes finished.
+pub fn progress(node: &str, done: usize) {
+    if let Some(heartbeat) = HEARTBEAT.lock().unwrap().as_mut() {
+        heartbeat.current_node = Some(node.to_string());
+        heartbeat.done = done;
+        heartbeat.last_progress_at = Local::now();
+        if heartbeat.total > 0 {
+            heartbeat.progress_percent = done as f64 * 100.0 / heartbeat.total as f64;
+        }
+    }
+}
+
+/// Set the final status, written when the heartbeat task is dropped.
+pub fn finish(status: &str) {
+    if let Some(heartbeat) = HEARTBEAT.lock().unwrap().as_mut() {
+        heartbeat.status = status.to_string();
+        if status == "finished" {
+            heartbeat.done = heartbeat.total;
+            heartbeat.progress_percent = 100.0;
+        }
+    }
+}
+
+/// Write the current heartbeat, replacing the file only once it is complete so monitoring
+/// never reads half a file.
+fn write(path: &Path) -> Result<()> {
+    let Some(mut heartbeat) = HEARTBEAT.lock().unwrap().clone() else {
+        return Ok(());
+    };
+    heartbeat.updated_at = Local::now();
+
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, serde_json::to_string_pretty(&heartbeat)?).context("Could not write heartbeat")?;
+    fs::rename(&tmp, path).context("Could not replace heartbeat file")?;
+    Ok(())
+}
@@ -0,0 +1,610 @@
+//! A small HTML parser with CSS selector matching, to read captured fixture pages without a
+//! browser. It supports the selector syntax the bundled profiles use: type, `*`, `#id`,
+//! `.class` and attribute selectors (`=`, `*=`, `^=`, `$=`, `~=`, `|=`), descendant and child
+//! combinators and selector lists. Other selectors (pseudo-classes, sibling combinators)
+//! match nothing.
+
+/// Elements that never have children.
+const VOID: [&str; 14] = [
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source",
+    "track", "wbr",
+];
+
+/// Elements whose contents are text up to their end tag.
+const RAW_TEXT: [&str; 4] = ["script", "style", "textarea", "title"];
+
+/// Elements whose start tag closes an open `<p>`.
+const CLOSES_P: [&str; 13] = [
+    "address", "article", "aside", "div", "footer", "form", "h1", "h2", "h3", "header", "p",
+    "section", "ul",
+];
+
+/// A parsed HTML document.
+#[derive(Debug)]
+pub struct Document {
+    /// All nodes, parents before their children; the document itself is node 0.
+    nodes: Vec<Node>,
+}
+
+#[derive(Debug)]
+struct Node {
+    parent: Option<usize>,
+    children: Vec<usize>,
+    kind: Kind,
+}
+
+#[derive(Debug)]
+enum Kind {
+    Element {
+        tag: String,
+        attrs: Vec<(String, String)>,
+    },
+    Text(String),
+}
+
+/// An element of a [`Document`].
+#[derive(Debug, Clone, Copy)]
+pub struct Element<'a> {
+    doc: &'a Document,
+    index: usize,
+}
+
+impl Document {
+    /// Parse `html` leniently, like a browser would for the markup Neos renders: unclosed
+    /// elements are closed by the end tag of an ancestor and stray end tags are ignored.
+    pub fn parse(html: &str) -> Self {
+        let mut doc = Self {
+            nodes: vec![Node {
+                parent: None,
+                children: Vec::new(),
+                kind: Kind::Element {
+                    tag: String::new(),
+                    attrs: Vec::new(),
+                },
+            }],
+        };
+        let mut open = vec![0];
+        let mut rest = html;
+
+        while !rest.is_empty() {
+            if let Some(comment) = rest.strip_prefix("<!--") {
+                rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+            } else if let Some(end_tag) = rest.strip_prefix("</") {
+                let name_len = end_tag
+                    .find(['>', ' ', '\t', '\n', '\r'])
+                    .unwrap_or(end_tag.len());
+                let name = end_tag[..name_len].to_ascii_lowercase();
+                rest = end_tag.find('>').map_or("", |end| &end_tag[end + 1..]);
+                if let Some(depth) = open.iter().rposition(|&index| doc.tag(index) == name) {
+                    open.truncate(depth.max(1));
+                }
+            } else if rest.starts_with("<!") || rest.starts_with("<?") {
+                rest = rest.find('>').map_or("", |end| &rest[end + 1..]);
+            } else if rest.starts_with('<')
+                && rest[1..].starts_with(|c: char| c.is_ascii_alphabetic())
+            {
+                let (tag, attrs, self_closing, after) = parse_tag(&rest[1..]);
+                rest = after;
+                let current = open[open.len() - 1];
+                if (doc.tag(current) == "p" && CLOSES_P.contains(&tag.as_str()))
+                    || (doc.tag(current) == "li" && tag == "li")
+                {
+                    open.pop();
+                }
+                let parent = open[open.len() - 1];
+                let raw_text = RAW_TEXT.contains(&tag.as_str());
+                let void = self_closing || VOID.contains(&tag.as_str());
+                let index = doc.push(parent, Kind::Element { tag, attrs });
+                if raw_text {
+                    let end = find_end_tag(rest, doc.tag(index));
+                    if end > 0 {
+                        doc.push(index, Kind::Text(rest[..end].to_string()));
+                    }
+                    rest = &rest[end..];
+                    open.push(index);
+                } else if !void {
+                    open.push(index);
+                }
+            } else {
+                let end = rest[1..].find('<').map_or(rest.len(), |end| end + 1);
+                doc.push(
+                    open[open.len() - 1],
+                    Kind::Text(decode_entities(&rest[..end])),
+                );
+                rest = &rest[end..];
+            }
+        }
+        doc
+    }
+
+    /// The document itself, to select from.
+    pub fn root(&self) -> Element<'_> {
+        Element {
+            doc: self,
+            index: 0,
+        }
+    }
+
+    /// All elements matching `selector`, in document order.
+    pub fn select(&self, selector: &str) -> Vec<Element<'_>> {
+        self.root().select(selector)
+    }
+
+    /// The first element matching `selector`.
+    pub fn select_first(&self, selector: &str) -> Option<Element<'_>> {
+        self.root().select_first(selector)
+    }
+
+    fn push(&mut self, parent: usize, kind: Kind) -> usize {
+        let index = self.nodes.len();
+        self.nodes.push(Node {
+            parent: Some(parent),
+            children: Vec::new(),
+            kind,
+        });
+        self.nodes[parent].children.push(index);
+        index
+    }
+
+    fn tag(&self, index: usize) -> &str {
+        match &self.nodes[index].kind {
+            Kind::Element { tag, .. } => tag,
+            Kind::Text(_) => "",
+        }
+    }
+
+    fn attr(&self, index: usize, name: &str) -> Option<&str> {
+        match &self.nodes[index].kind {
+            Kind::Element { attrs, .. } => attrs
+                .iter()
+                .find(|(attr, _)| attr == name)
+                .map(|(_, value)| value.as_str()),
+            Kind::Text(_) => None,
+        }
+    }
+
+    /// The elements below `index`, in document order.
+    fn descendants(&self, index: usize) -> Vec<usize> {
+        let mut found = Vec::new();
+        let mut pending: Vec<usize> = self.nodes[index].children.iter().rev().copied().collect();
+        while let Some(next) = pending.pop() {
+            if let Kind::Element { .. } = self.nodes[next].kind {
+                found.push(next);
+                pending.extend(self.nodes[next].children.iter().rev());
+            }
+        }
+        found
+    }
+}
+
+impl<'a> Element<'a> {
+    /// Lowercase tag name, empty for the document.
+    pub fn tag(&self) -> &'a str {
+        self.doc.tag(self.index)
+    }
+
+    /// Value of the attribute `name` (lowercase), with entities decoded.
+    pub fn attr(&self, name: &str) -> Option<&'a str> {
+        self.doc.attr(self.index, name)
+    }
+
+    /// The text of the element and its descendants.
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+        let mut pending = vec![self.index];
+        while let Some(next) = pending.pop() {
+            match &self.doc.nodes[next].kind {
+                Kind::Text(part) => text.push_str(part),
+                Kind::Element { .. } => {
+                    pending.extend(self.doc.nodes[next].children.iter().rev());
+                }
+            }
+        }
+        text
+    }
+
+    /// All elements below this one matching `selector`, in document order, like
+    /// `querySelectorAll`. An unsupported selector matches nothing.
+    pub fn select(&self, selector: &str) -> Vec<Element<'a>> {
+        let Some(list) = parse_selector_list(selector) else {
+            return Vec::new();
+        };
+        self.doc
+            .descendants(self.index)
+            .into_iter()
+            .filter(|&index| list.iter().any(|complex| self.matches(index, complex)))
+            .map(|index| Element {
+                doc: self.doc,
+                index,
+            })
+            .collect()
+    }
+
+    /// The first element below this one matching `selector`, like `querySelector`.
+    pub fn select_first(&self, selector: &str) -> Option<Element<'a>> {
+        self.select(selector).into_iter().next()
+    }
+
+    fn matches(&self, index: usize, parts: &[(Combinator, Compound)]) -> bool {
+        let Some(((combinator, last), rest)) = parts.split_last() else {
+            return true;
+        };
+        if index == 0 || !last.matches(self.doc, index) {
+            return false;
+        }
+        if rest.is_empty() {
+            return true;
+        }
+        let mut parent = self.doc.nodes[index].parent;
+        while let Some(ancestor) = parent {
+            if self.matches(ancestor, rest) {
+                return true;
+            }
+            if *combinator == Combinator::Child {
+                return false;
+            }
+            parent = self.doc.nodes[ancestor].parent;
+        }
+        false
+    }
+}
+
+/// Whether `selector` only uses syntax [`Element::select`] supports.
+pub fn is_supported(selector: &str) -> bool {
+    parse_selector_list(selector).is_some()
+}
+
+/// The tag name, attributes and self-closing flag of the start tag at the beginning of `s`
+/// (after the `<`), and the text after it.
+fn parse_tag(s: &str) -> (String, Vec<(String, String)>, bool, &str) {
+    let name_len = s
+        .find(|c: char| c.is_whitespace() || c == '/' || c == '>')
+        .unwrap_or(s.len());
+    let tag = s[..name_len].to_ascii_lowercase();
+    let mut attrs = Vec::new();
+    let mut rest = &s[name_len..];
+
+    loop {
+        rest = rest.trim_start();
+        if let Some(after) = rest.strip_prefix("/>") {
+            return (tag, attrs, true, after);
+        }
+        if let Some(after) = rest.strip_prefix('>') {
+            return (tag, attrs, false, after);
+        }
+        if rest.is_empty() {
+            return (tag, attrs, false, rest);
+        }
+        let name_len = rest
+            .find(|c: char| c.is_whitespace() || "/>=".contains(c))
+            .unwrap_or(rest.len());
+        if name_len == 0 {
+            // A stray `/` or `=`
+            rest = &rest[1..];
+            continue;
+        }
+        let name = rest[..name_len].to_ascii_lowercase();
+        rest = rest[name_len..].trim_start();
+
+        let mut value = String::new();
+        if let Some(after) = rest.strip_prefix('=') {
+            let after = after.trim_start();
+            let (raw, remaining) = match after.chars().next() {
+                Some(quote @ ('"' | '\'')) => {
+                    let body = &after[1..];
+                    match body.find(quote) {
+                        Some(end) => (&body[..end], &body[end + 1..]),
+                        None => (body, ""),
+                    }
+                }
+                _ => {
+                    let end = after
+                        .find(|c: char| c.is_whitespace() || c == '>')
+                        .unwrap_or(after.len());
+                    (&after[..end], &after[end..])
+                }
+            };
+            value = decode_entities(raw);
+            rest = remaining;
+        }
+        attrs.push((name, value));
+    }
+}
+
+/// Offset of the end tag of `tag` in `s`, or the length of `s` if it is not closed.
+fn find_end_tag(s: &str, tag: &str) -> usize {
+    let mut from = 0;
+    while let Some(found) = s[from..].find("</") {
+        let start = from + found;
+        let name = s.as_bytes().get(start + 2..start + 2 + tag.len());
+        if name.is_some_and(|name| name.eq_ignore_ascii_case(tag.as_bytes())) {
+            return start;
+        }
+        from = start + 2;
+    }
+    s.len()
+}
+
+/// `text` with character references (`&amp;`, `&#39;`, `&#x2F;`, ...) replaced.
+fn decode_entities(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let decoded = rest[1..]
+            .find(';')
+            .filter(|&end| end <= 10)
+            .and_then(|end| Some((entity(&rest[1..end + 1])?, end + 2)));
+        match decoded {
+            Some((c, len)) => {
+                out.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn entity(name: &str) -> Option<char> {
+    match name {
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        "nbsp" => Some('\u{a0}'),
+        _ => {
+            let number = name.strip_prefix('#')?;
+            let code = match number.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => number.parse().ok()?,
+            };
+            char::from_u32(code)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Combinator {
+    Descendant,
+    Child,
+}
+
+/// A compound selector like `div.item[role='treeitem']`.
+#[derive(Debug, Default)]
+struct Compound {
+    tag: Option<String>,
+    conditions: Vec<Condition>,
+}
+
+#[derive(Debug)]
+enum Condition {
+    Id(String),
+    Class(String),
+    /// An attribute, with the operator (`=`, `*`, `^`, `$`, `~` or `|`) and value to match.
+    Attr(String, Option<(char, String)>),
+}
+
+impl Compound {
+    fn matches(&self, doc: &Document, index: usize) -> bool {
+        if let Kind::Text(_) = doc.nodes[index].kind {
+            return false;
+        }
+        if self.tag.as_ref().is_some_and(|tag| tag != doc.tag(index)) {
+            return false;
+        }
+        self.conditions.iter().all(|condition| match condition {
+            Condition::Id(id) => doc.attr(index, "id") == Some(id),
+            Condition::Class(class) => doc
+                .attr(index, "class")
+                .is_some_and(|classes| classes.split_whitespace().any(|c| c == class)),
+            Condition::Attr(name, test) => {
+                let Some(value) = doc.attr(index, name) else {
+                    return false;
+                };
+                match test {
+                    None => true,
+                    Some(('=', wanted)) => value == wanted,
+                    Some(('*', wanted)) => !wanted.is_empty() && value.contains(wanted.as_str()),
+                    Some(('^', wanted)) => !wanted.is_empty() && value.starts_with(wanted.as_str()),
+                    Some(('$', wanted)) => !wanted.is_empty() && value.ends_with(wanted.as_str()),
+                    Some(('~', wanted)) => value.split_whitespace().any(|word| word == wanted),
+                    Some((_, wanted)) => {
+                        value == wanted
+                            || value
+                                .strip_prefix(wanted.as_str())
+                                .is_some_and(|r| r.starts_with('-'))
+                    }
+                }
+            }
+        })
+    }
+}
+
+/// The complex selectors of a selector list, `None` if it uses unsupported syntax.
+fn parse_selector_list(selector: &str) -> Option<Vec<Vec<(Combinator, Compound)>>> {
+    let mut list = Vec::new();
+    let mut rest = selector;
+    loop {
+        let (complex, after) = parse_complex(rest)?;
+        list.push(complex);
+        let after = after.trim_start();
+        if after.is_empty() {
+            return Some(list);
+        }
+        rest = after.strip_prefix(',')?;
+    }
+}
+
+fn parse_complex(s: &str) -> Option<(Vec<(Combinator, Compound)>, &str)> {
+    let mut parts = Vec::new();
+    let mut combinator = Combinator::Descendant;
+    let mut rest = s.trim_start();
+    loop {
+        let (compound, after) = parse_compound(rest)?;
+        parts.push((combinator, compound));
+        let trimmed = after.trim_start();
+        if let Some(child) = trimmed.strip_prefix('>') {
+            combinator = Combinator::Child;
+            rest = child.trim_start();
+        } else if trimmed.is_empty() || trimmed.starts_with(',') {
+            return Some((parts, trimmed));
+        } else if trimmed.len() < after.len() {
+            combinator = Combinator::Descendant;
+            rest = trimmed;
+        } else {
+            return None;
+        }
+    }
+}
+
+fn parse_compound(s: &str) -> Option<(Compound, &str)> {
+    let ident_len = |s: &str| {
+        s.find(|c: char| !(c.is_alphanumeric() || c == '-' || c == '_'))
+            .unwrap_or(s.len())
+    };
+    let mut compound = Compound::default();
+    let mut rest = s;
+    let universal = rest.starts_with('*');
+    if universal {
+        rest = &rest[1..];
+    } else {
+        let len = ident_len(rest);
+        if len > 0 {
+            compound.tag = Some(rest[..len].to_ascii_lowercase());
+            rest = &rest[len..];
+        }
+    }
+
+    loop {
+        if let Some(after) = rest.strip_prefix(['#', '.']) {
+            let len = ident_len(after);
+            if len == 0 {
+                return None;
+            }
+            let name = after[..len].to_string();
+            compound.conditions.push(if rest.starts_with('#') {
+                Condition::Id(name)
+            } else {
+                Condition::Class(name)
+            });
+            rest = &after[len..];
+        } else if let Some(after) = rest.strip_prefix('[') {
+            let end = after.find(']')?;
+            compound.conditions.push(parse_attribute(&after[..end])?);
+            rest = &after[end + 1..];
+        } else {
+            break;
+        }
+    }
+
+    (universal || compound.tag.is_some() || !compound.conditions.is_empty())
+        .then_some((compound, rest))
+}
+
+/// The condition of an attribute selector, given what is inside the brackets.
+fn parse_attribute(inner: &str) -> Option<Condition> {
+    let Some(op_at) = inner.find(['=', '*', '^', '$', '~', '|']) else {
+        let name = inner.trim();
+        return (!name.is_empty()).then(|| Condition::Attr(name.to_ascii_lowercase(), None));
+    };
+    let name = inner[..op_at].trim().to_ascii_lowercase();
+    let (op, value) = match inner[op_at..].strip_prefix('=') {
+        Some(value) => ('=', value),
+        None => {
+            let op = inner[op_at..].chars().next()?;
+            (op, inner[op_at + 1..].strip_prefix('=')?)
+        }
+    };
+    let value = value.trim();
+    let value = ['"', '\'']
+        .iter()
+        .find_map(|&quote| value.strip_prefix(quote)?.strip_suffix(quote))
+        .unwrap_or(value);
+    (!name.is_empty()).then(|| Condition::Attr(name, Some((op, value.to_string()))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = r#"<!DOCTYPE html>
+<html><body class="neos-backend">
+  <!-- <div class="comment"></div> -->
+  <div class="dynamicContent dynamic-content-container-1" data-__neos-fusion-path="page/ExternalLinks">
+    <p property="typo3:url">https://example.com/a?x=1&amp;y=2</p>
+    <p property='typo3:title'>Broschüre&nbsp;2019</p>
+    <img src=/logo.png alt="logo">
+    <p>unclosed
+    <section id="inner"><span class=note>text</span></section>
+  </div>
+  <script>if (a < b) { document.write("<div class='fake'></div>"); }</script>
+</body></html>"#;
+
+    #[test]
+    fn selects_by_attribute_class_and_id() {
+        let doc = Document::parse(PAGE);
+        assert_eq!(
+            doc.select("div[data-__neos-fusion-path*='ExternalLinks']")
+                .len(),
+            1
+        );
+        assert_eq!(
+            doc.select(".dynamicContent.dynamic-content-container-1")
+                .len(),
+            1
+        );
+        assert_eq!(doc.select("p[property^='typo3:']").len(), 2);
+        assert_eq!(doc.select("[property$=title]").len(), 1);
+        assert_eq!(doc.select("#inner > span.note").len(), 1);
+        assert_eq!(doc.select("body > span").len(), 0);
+        assert_eq!(doc.select("body span, img").len(), 2);
+        assert!(doc.select("div.fake").is_empty());
+        assert!(doc.select("div.comment").is_empty());
+    }
+
+    #[test]
+    fn decodes_text_and_attributes() {
+        let doc = Document::parse(PAGE);
+        let url = doc.select_first("p[property='typo3:url']").unwrap();
+        assert_eq!(url.text(), "https://example.com/a?x=1&y=2");
+        let title = doc.select_first("[property='typo3:title']").unwrap();
+        assert_eq!(title.text(), "Broschüre\u{a0}2019");
+        assert_eq!(
+            doc.select_first("img").unwrap().attr("src"),
+            Some("/logo.png")
+        );
+    }
+
+    #[test]
+    fn selects_within_an_element() {
+        let doc = Document::parse(PAGE);
+        let container = doc.select_first("div").unwrap();
+        assert_eq!(container.select("p").len(), 3);
+        assert!(container.select("div").is_empty());
+        // An unclosed <p> is closed by the next block element
+        assert_eq!(doc.select("p > section").len(), 0);
+    }
+
+    #[test]
+    fn unsupported_selectors_match_nothing() {
+        let doc = Document::parse(PAGE);
+        assert!(!is_supported(
+            "[role='treeitem']:has(> [class*='isFocused'])"
+        ));
+        assert!(doc.select("p + p").is_empty());
+        assert!(is_supported(
+            "a.node__header__chevron___zXVME.reset__reset___2e25U"
+        ));
+        assert!(is_supported("iframe, video"));
+    }
+}
@@ -0,0 +1,142 @@
+use crate::selectors::{self, Selectors};
+use anyhow::{bail, Context, Result};
+use std::time::Duration;
+use thirtyfour::{prelude::*, support};
+
+static CLEAR_FIELD_SCRIPT: &str = include_str!("../resources/scripts/clear-field.js");
+
+/// Times an edit is made before it counts as not registered by the Neos UI.
+const EDIT_ATTEMPTS: usize = 3;
+
+/// Pause before checking whether the Neos UI registered an edit.
+const REGISTER_WAIT: Duration = Duration::from_millis(300);
+
+/// The editor of one node property in the Neos inspector, e.g. `Tags` or `url`, found with the
+/// `[inspector] field` selectors with `{property}` replaced by the property name.
+pub struct InspectorField<'a> {
+    driver: &'a WebDriver,
+    property: String,
+    element: WebElement,
+}
+
+impl<'a> InspectorField<'a> {
+    /// Find the field of `property` in the open inspector.
+    pub async fn find(driver: &'a WebDriver, property: &str) -> Result<Self> {
+        let candidates = selectors::with_property(&Selectors::get().inspector.field, property);
+        let element = selectors::find_first(&driver.handle, "inspector.field", &candidates)
+            .await
+            .with_context(|| format!("Could not find the {property} field in the inspector"))?;
+        Ok(Self {
+            driver,
+            property: property.to_string(),
+            element,
+        })
+    }
+
+    /// The value the field shows.
+    pub async fn read(&self) -> Result<String> {
+        Ok(self.element.prop("value").await?.unwrap_or_default())
+    }
+
+    /// Empty the field. WebDriver's clear comes first; fields it leaves filled are reset from
+    /// JavaScript, and select-all and backspace are the last resort, as they depend on the
+    /// platform and keyboard layout. Fails if the field still is not empty.
+    pub async fn clear(&self) -> Result<()> {
+        let _ = self.element.clear().await;
+        if self.read().await?.is_empty() {
+            return Ok(());
+        }
+        let _ = self
+            .driver
+            .execute(CLEAR_FIELD_SCRIPT, vec![self.element.to_json()?])
+            .await;
+        if self.read().await?.is_empty() {
+            return Ok(());
+        }
+        self.driver
+            .action_chain()
+            .click_element(&self.element)
+            .key_down(Key::Control)
+            .send_keys("a")
+            .key_up(Key::Control)
+            .send_keys(Key::Backspace)
+            .perform()
+            .await?;
+        let left = self.read().await?;
+        if !left.is_empty() {
+            bail!("The {} field still shows '{left}' after clearing", self.property);
+        }
+        Ok(())
+    }
+
+    /// Replace the value of the field with `value`, without applying it. A field that already
+    /// shows `value` is left alone. The Neos UI enables the apply button once it registered an
+    /// edit; if the button stays disabled, the edit is made again, and fails after
+    /// `EDIT_ATTEMPTS` tries.
+    pub async fn set(&self, value: &str) -> Result<()> {
+        if self.read().await? == value {
+            return Ok(());
+        }
+        for attempt in 1..=EDIT_ATTEMPTS {
+            self.clear()
+                .await
+                .with_context(|| format!("Could not clear the {} field", self.property))?;
+            // Focus the field again on retries, in case typing went elsewhere
+            if attempt > 1 {
+                self.element.click().await?;
+            }
+            self.element.send_keys(value).await?;
+            if self.registered().await? {
+                return Ok(());
+            }
+            println!(
+                "  ⚠ Edit of {} not registered by the Neos UI (attempt {attempt}/{EDIT_ATTEMPTS})",
+                self.property
+            );
+        }
+        bail!("The Neos UI did not register the edit of the {} field", self.property)
+    }
+
+    /// Apply the changes made in the inspector, see [`apply`].
+    pub async fn apply(&self) -> Result<bool> {
+        apply(self.driver).await
+    }
+
+    /// Whether the Neos UI noticed the last edit, told by its apply button becoming enabled.
+    async fn registered(&self) -> Result<bool> {
+        support::sleep(REGISTER_WAIT).await;
+        has_pending_changes(self.driver).await
+    }
+
+    /// Check that the field shows `expected`, e.g. after applying. `same` compares the shown
+    /// value with the expected one.
+    pub async fn verify(&self, expected: &str, same: impl Fn(&str, &str) -> bool) -> Result<()> {
+        let shown = self.read().await?;
+        if !same(&shown, expected) {
+            bail!("The {} field shows '{shown}' instead of '{expected}'", self.property);
+        }
+        Ok(())
+    }
+}
+
+/// Click the inspector's apply button, saving the changes of all its fields. The button is only
+/// clicked if it is enabled, i.e. there are pending changes. Returns whether it was clicked.
+pub async fn apply(driver: &WebDriver) -> Result<bool> {
+    let button = apply_button(driver).await?;
+    if !button.is_enabled().await? {
+        return Ok(false);
+    }
+    button.click().await?;
+    Ok(true)
+}
+
+/// Whether the inspector has changes to apply, i.e. its apply button is enabled.
+pub async fn has_pending_changes(driver: &WebDriver) -> Result<bool> {
+    Ok(apply_button(driver).await?.is_enabled().await?)
+}
+
+async fn apply_button(driver: &WebDriver) -> Result<WebElement> {
+    selectors::find_first(&driver.handle, "inspector.apply", &Selectors::get().inspector.apply)
+        .await
+        .context("Could not find the inspector's apply button")
+}
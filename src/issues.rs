@@ -0,0 +1,287 @@
+use crate::config::{IssueProvider, IssuesConfig};
+use crate::report::{markdown_cell, ReportEntry};
+use anyhow::{Context, Result};
+use reqwest::{Method, RequestBuilder, Url};
+use serde_json::{json, Value};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write as _,
+};
+
+/// What syncing the issues with a run's findings did.
+#[derive(Debug, Default)]
+pub struct IssueSummary {
+    pub opened: usize,
+    pub updated: usize,
+    /// Issues of chapters without broken links anymore.
+    pub closed: usize,
+}
+
+/// An open issue created by an earlier sync.
+struct Issue {
+    /// Issue number on GitHub, `iid` on GitLab.
+    number: u64,
+    title: String,
+    body: String,
+}
+
+/// Open an issue for every chapter with broken links, update the issues whose list of broken
+/// links changed, and close the issues of chapters whose links were all fixed. Issues are
+/// recognized by the configured label and their title. Chapters that are not part of `entries`
+/// were not checked, so their issues stay open.
+pub async fn sync(
+    config: &IssuesConfig,
+    entries: &[ReportEntry],
+    chapter_level: usize,
+) -> Result<IssueSummary> {
+    let Some(provider) = config.provider else {
+        anyhow::bail!("No issue tracker configured in [issues] provider");
+    };
+    let tracker = Tracker::new(provider, config)?;
+
+    let checked: BTreeSet<String> = entries
+        .iter()
+        .map(|entry| issue_title(entry.chapter(chapter_level)))
+        .collect();
+    let mut broken: BTreeMap<&str, Vec<&ReportEntry>> = BTreeMap::new();
+    for entry in entries.iter().filter(|entry| entry.has_broken_url()) {
+        broken.entry(entry.chapter(chapter_level)).or_default().push(entry);
+    }
+
+    let mut open: BTreeMap<String, Issue> = tracker
+        .open_issues()
+        .await?
+        .into_iter()
+        .map(|issue| (issue.title.clone(), issue))
+        .collect();
+
+    let mut summary = IssueSummary::default();
+    for (chapter, entries) in &broken {
+        let title = issue_title(chapter);
+        let body = issue_body(entries);
+        match open.remove(&title) {
+            Some(issue) if issue.body.trim() == body.trim() => {}
+            Some(issue) => {
+                tracker.update(issue.number, &body).await?;
+                summary.updated += 1;
+            }
+            None => {
+                tracker.create(&title, &body).await?;
+                summary.opened += 1;
+            }
+        }
+    }
+
+    // Whatever is left of the checked chapters had broken links in an earlier run but none in
+    // this one
+    for issue in open.into_values() {
+        if issue.title.starts_with(TITLE_PREFIX) && checked.contains(&issue.title) {
+            tracker.close(issue.number).await?;
+            summary.closed += 1;
+        }
+    }
+    Ok(summary)
+}
+
+const TITLE_PREFIX: &str = "Broken links: ";
+
+fn issue_title(chapter: &str) -> String {
+    format!("{TITLE_PREFIX}{chapter}")
+}
+
+/// Markdown table of the broken links of one chapter, with links back into the backend.
+fn issue_body(entries: &[&ReportEntry]) -> String {
+    let mut body = String::from(
+        "Broken links found by tag-spider-rs. This issue is updated by later runs and closed \
+         once all links are fixed.\n\n| Page | Title | URL | Status | Backend | Archived copy |\n\
+         |---|---|---|---|---|---|\n",
+    );
+    for entry in entries {
+        let backend = if entry.backend_url.is_empty() {
+            String::new()
+        } else {
+            format!("[open]({})", entry.backend_url)
+        };
+        let archived = if entry.wayback_url.is_empty() {
+            String::new()
+        } else {
+            format!("[archived]({})", entry.wayback_url)
+        };
+        let _ = writeln!(
+            body,
+            "| {} | {} | {} | {} | {backend} | {archived} |",
+            markdown_cell(&entry.breadcrumb_path),
+            markdown_cell(&entry.title),
+            markdown_cell(&entry.url),
+            markdown_cell(&entry.url_valid),
+        );
+    }
+    body
+}
+
+/// REST client for the issues of one GitHub repository or GitLab project.
+struct Tracker {
+    client: reqwest::Client,
+    provider: IssueProvider,
+    /// The issues collection, e.g. `https://api.github.com/repos/owner/repo/issues`.
+    issues_url: Url,
+    token: String,
+    label: String,
+}
+
+impl Tracker {
+    fn new(provider: IssueProvider, config: &IssuesConfig) -> Result<Self> {
+        let token = std::env::var(&config.token_env).with_context(|| {
+            format!("Set {} to the access token for the issue tracker", config.token_env)
+        })?;
+        let api_url = config
+            .api_url
+            .clone()
+            .unwrap_or_else(|| provider.default_api_url().to_string());
+        let mut issues_url = Url::parse(&api_url).context("[issues] api_url is not a valid URL")?;
+        {
+            let mut segments = issues_url
+                .path_segments_mut()
+                .map_err(|_| anyhow::anyhow!("[issues] api_url cannot have a path"))?;
+            segments.pop_if_empty();
+            match provider {
+                IssueProvider::Github => {
+                    segments.push("repos").extend(config.project.split('/'));
+                }
+                // GitLab takes the project path as one escaped segment
+                IssueProvider::Gitlab => {
+                    segments.push("projects").push(&config.project);
+                }
+            }
+            segments.push("issues");
+        }
+
+        Ok(Self {
+            client: reqwest::Client::new(),
+            provider,
+            issues_url,
+            token,
+            label: config.label.clone(),
+        })
+    }
+
+    fn request(&self, method: Method, url: Url) -> RequestBuilder {
+        let request = self.client.request(method, url);
+        match self.provider {
+            // GitHub rejects requests without a user agent
+            IssueProvider::Github => request
+                .bearer_auth(&self.token)
+                .header("User-Agent", "tag-spider-rs")
+                .header("Accept", "application/vnd.github+json"),
+            IssueProvider::Gitlab => request.header("PRIVATE-TOKEN", &self.token),
+        }
+    }
+
+    fn issue_url(&self, number: u64) -> Url {
+        let mut url = self.issues_url.clone();
+        if let Ok(mut segments) = url.path_segments_mut() {
+            segments.push(&number.to_string());
+        }
+        url
+    }
+
+    async fn send(&self, request: RequestBuilder) -> Result<Value> {
+        let response = request
+            .send()
+            .await
+            .context("Could not reach the issue tracker")?
+            .error_for_status()
+            .context("The issue tracker refused the request")?;
+        let body = response.text().await.context("Could not read the issue tracker's answer")?;
+        serde_json::from_str(&body).context("The issue tracker did not return JSON")
+    }
+
+    /// Open issues with the configured label, across all pages.
+    async fn open_issues(&self) -> Result<Vec<Issue>> {
+        let state = match self.provider {
+            IssueProvider::Github => "open",
+            IssueProvider::Gitlab => "opened",
+        };
+        let mut issues = Vec::new();
+        for page in 1.. {
+            let mut url = self.issues_url.clone();
+            url.query_pairs_mut()
+                .append_pair("state", state)
+                .append_pair("labels", &self.label)
+                .append_pair("per_page", "100")
+                .append_pair("page", &page.to_string());
+            let found = self.send(self.request(Method::GET, url)).await?;
+            let found = found.as_array().context("Expected a list of issues")?;
+            issues.extend(found.iter().filter_map(|issue| self.parse_issue(issue)));
+            if found.len() < 100 {
+                break;
+            }
+        }
+        Ok(issues)
+    }
+
+    fn parse_issue(&self, issue: &Value) -> Option<Issue> {
+        let (number, body) = match self.provider {
+            IssueProvider::Github => {
+                // The issues endpoint lists pull requests too
+                if issue.get("pull_request").is_some() {
+                    return None;
+                }
+                (issue.get("number"), issue.get("body"))
+            }
+            IssueProvider::Gitlab => (issue.get("iid"), issue.get("description")),
+        };
+        Some(Issue {
+            number: number?.as_u64()?,
+            title: issue.get("title")?.as_str()?.to_string(),
+            body: body.and_then(Value::as_str).unwrap_or_default().to_string(),
+        })
+    }
+
+    async fn create(&self, title: &str, body: &str) -> Result<()> {
+        let payload = match self.provider {
+            IssueProvider::Github => json!({ "title": title, "body": body, "labels": [self.label] }),
+            IssueProvider::Gitlab => {
+                json!({ "title": title, "description": body, "labels": self.label })
+            }
+        };
+        let request = self
+            .request(Method::POST, self.issues_url.clone())
+            .header("Content-Type", "application/json")
+            .body(payload.to_string());
+        self.send(request)
+            .await
+            .with_context(|| format!("Could not open issue '{title}'"))?;
+        Ok(())
+    }
+
+    async fn update(&self, number: u64, body: &str) -> Result<()> {
+        let (method, payload) = match self.provider {
+            IssueProvider::Github => (Method::PATCH, json!({ "body": body })),
+            IssueProvider::Gitlab => (Method::PUT, json!({ "description": body })),
+        };
+        let request = self
+            .request(method, self.issue_url(number))
+            .header("Content-Type", "application/json")
+            .body(payload.to_string());
+        self.send(request)
+            .await
+            .with_context(|| format!("Could not update issue #{number}"))?;
+        Ok(())
+    }
+
+    async fn close(&self, number: u64) -> Result<()> {
+        let (method, payload) = match self.provider {
+            IssueProvider::Github => (Method::PATCH, json!({ "state": "closed" })),
+            IssueProvider::Gitlab => (Method::PUT, json!({ "state_event": "close" })),
+        };
+        let request = self
+            .request(method, self.issue_url(number))
+            .header("Content-Type", "application/json")
+            .body(payload.to_string());
+        self.send(request)
+            .await
+            .with_context(|| format!("Could not close issue #{number}"))?;
+        Ok(())
+    }
+}
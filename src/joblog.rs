@@ -0,0 +1,94 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashSet,
+    fs::{self, File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+};
+
+/// One item a bulk job applied, as a line of its log.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Applied {
+    item: String,
+    applied_at: DateTime<Local>,
+}
+
+/// Append-only transaction log of a bulk job that changes the CMS. Every item is recorded once
+/// it was applied, so a run that dies halfway resumes at the next item and never applies a
+/// finished one again. Items are told apart by a key the job chooses, e.g. a treeitem ID.
+pub struct JobLog {
+    path: PathBuf,
+    file: File,
+    applied: HashSet<String>,
+}
+
+/// Log file of the bulk job `job` in `dir`. Characters that do not belong in a file name are
+/// replaced.
+pub fn path(dir: &Path, job: &str) -> PathBuf {
+    let name: String = job
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    dir.join(format!("{name}.log.jsonl"))
+}
+
+impl JobLog {
+    /// Open the log at `path`, keeping the items an earlier run applied.
+    pub fn open(path: &Path) -> Result<Self> {
+        let mut applied = HashSet::new();
+        if let Ok(file) = File::open(path) {
+            for line in BufReader::new(file).lines() {
+                let line = line.with_context(|| format!("Could not read {}", path.display()))?;
+                // A run killed mid-write can leave half a line at the end
+                if let Ok(record) = serde_json::from_str::<Applied>(&line) {
+                    applied.insert(record.item);
+                }
+            }
+        }
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir).context("Could not create the job log directory")?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Could not open job log {}", path.display()))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+            applied,
+        })
+    }
+
+    /// Whether an earlier run already applied `item`.
+    pub fn is_applied(&self, item: &str) -> bool {
+        self.applied.contains(item)
+    }
+
+    /// Index of the first of `items` not applied yet, where a resumed run continues.
+    pub fn cursor<'a>(&self, items: impl IntoIterator<Item = &'a str>) -> usize {
+        items.into_iter().take_while(|item| self.is_applied(item)).count()
+    }
+
+    /// Record that `item` was applied. Synced to disk right away, since the point is surviving
+    /// a crash.
+    pub fn record(&mut self, item: &str) -> Result<()> {
+        let record = Applied {
+            item: item.to_string(),
+            applied_at: Local::now(),
+        };
+        writeln!(self.file, "{}", serde_json::to_string(&record)?)
+            .context("Could not write job log")?;
+        self.file.sync_data().context("Could not sync job log")?;
+        self.applied.insert(item.to_string());
+        Ok(())
+    }
+
+    /// Delete the log after every item was applied.
+    pub fn finish(self) -> Result<()> {
+        fs::remove_file(&self.path)
+            .with_context(|| format!("Could not remove job log {}", self.path.display()))
+    }
+}
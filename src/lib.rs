@@ -1,6 +1,46 @@
 // src/lib.rs
+pub mod archive;
+pub mod audit;
+pub mod auth;
+pub mod backoff;
+pub mod bench;
+pub mod cacheserver;
+pub mod calendar;
+pub mod checkpoint;
+pub mod checks;
+pub mod clipboard;
+pub mod config;
+pub mod dimensions;
+pub mod driver;
 pub mod filenode;
+pub mod fixtures;
+pub mod heartbeat;
+pub mod html;
+pub mod inspector;
+pub mod issues;
+pub mod joblog;
 pub mod lexer;
+pub mod lock;
+pub mod merge;
+pub mod metrics;
 pub mod model;
+pub mod notify;
+pub mod porcelain;
+pub mod recorder;
+pub mod report;
+pub mod secrets;
+pub mod selectors;
+pub mod session;
+pub mod sink;
+pub mod snapshot;
 pub mod spider;
+pub mod spill;
+pub mod state;
+pub mod tagging;
+pub mod text;
 pub mod tree;
+pub mod treeitem;
+pub mod validator;
+pub mod wait;
+pub mod wayback;
+pub mod youtube;
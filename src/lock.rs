@@ -0,0 +1,268 @@
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, ErrorKind, Write},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+/// How long an unreadable lock file is taken to be still being written by another run.
+const WRITING_GRACE: Duration = Duration::from_secs(2);
+
+/// Who holds a run lock, stored as JSON in the lock file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LockInfo {
+    pub operation: String,
+    pub user: String,
+    pub host: String,
+    pub pid: u32,
+    pub started_at: DateTime<Local>,
+}
+
+impl LockInfo {
+    fn current(operation: &str) -> Self {
+        Self {
+            operation: operation.to_string(),
+            user: std::env::var("USER").unwrap_or_else(|_| "unknown".to_string()),
+            host: hostname(),
+            pid: std::process::id(),
+            started_at: Local::now(),
+        }
+    }
+
+    /// Whether the run holding the lock is gone: it is older than `stale_after`, or it ran on
+    /// this machine and its process no longer exists. The process is only checked on Linux,
+    /// through `/proc`; elsewhere only the age counts.
+    fn is_stale(&self, stale_after: Duration) -> bool {
+        let age = (Local::now() - self.started_at).to_std().unwrap_or_default();
+        let process_gone = cfg!(target_os = "linux")
+            && self.host == hostname()
+            && !Path::new(&format!("/proc/{}", self.pid)).exists();
+        age > stale_after || process_gone
+    }
+
+    fn same_run(&self, other: &LockInfo) -> bool {
+        self.pid == other.pid && self.started_at == other.started_at
+    }
+}
+
+/// A held run lock. The lock file is removed when this is dropped, unless another run
+/// took it over with `--force` in the meantime.
+#[derive(Debug)]
+pub struct RunLock {
+    path: PathBuf,
+    info: LockInfo,
+}
+
+impl Drop for RunLock {
+    fn drop(&mut self) {
+        if read_holder(&self.path).is_some_and(|holder| holder.same_run(&self.info)) {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// Take the lock for `environment` (e.g. the CMS host) in `dir`, so no other operator can run a
+/// conflicting job against the same CMS at the same time. Stale locks are replaced; `force`
+/// replaces any existing lock.
+pub fn acquire(
+    dir: &Path,
+    environment: &str,
+    operation: &str,
+    force: bool,
+    stale_after: Duration,
+) -> Result<RunLock> {
+    fs::create_dir_all(dir).context("Could not create the lock directory")?;
+    let path = dir.join(format!("{environment}.lock"));
+    let info = LockInfo::current(operation);
+
+    // Write the holder to a file of our own first and link it into place, so no other run can
+    // see the lock file before its content is complete
+    let pending = dir.join(format!("{environment}.lock.{}", info.pid));
+    fs::write(&pending, serde_json::to_string_pretty(&info)?)
+        .context("Could not write the lock file")?;
+    let result = link_lock(&pending, &path, environment, force, stale_after);
+    let _ = fs::remove_file(&pending);
+    result.map(|_| RunLock { path, info })
+}
+
+/// Hard-link the complete lock file `pending` to `path`, replacing a stale or forced lock. Where
+/// the lock directory does not support hard links (some SMB and NFS shares), the lock file is
+/// created exclusively and written in place instead.
+fn link_lock(
+    pending: &Path,
+    path: &Path,
+    environment: &str,
+    force: bool,
+    stale_after: Duration,
+) -> Result<()> {
+    let mut hard_links = true;
+    loop {
+        let created = if hard_links {
+            match fs::hard_link(pending, path) {
+                Err(e) if e.kind() != ErrorKind::AlreadyExists => {
+                    println!(
+                        "⚠ Could not hard-link the lock file ({e}), creating it directly instead"
+                    );
+                    hard_links = false;
+                    create_lock(pending, path)
+                }
+                result => result,
+            }
+        } else {
+            create_lock(pending, path)
+        };
+
+        match created {
+            Ok(()) => return Ok(()),
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                let holder = read_holder(path);
+                match &holder {
+                    Some(holder) if !force && !holder.is_stale(stale_after) => bail!(
+                        "{} is locked by a '{}' run of {} on {} (pid {}) since {}; \
+                         use --force if that run is gone",
+                        environment,
+                        holder.operation,
+                        holder.user,
+                        holder.host,
+                        holder.pid,
+                        holder.started_at.format("%Y-%m-%d %H:%M")
+                    ),
+                    Some(holder) => println!(
+                        "⚠ Replacing the lock of {}'s '{}' run from {}",
+                        holder.user,
+                        holder.operation,
+                        holder.started_at.format("%Y-%m-%d %H:%M")
+                    ),
+                    // Without hard links, another run may still be writing its lock file
+                    None if recently_modified(path) => {
+                        std::thread::sleep(Duration::from_millis(200));
+                        continue;
+                    }
+                    // An unreadable lock file cannot belong to a running job
+                    None => println!("⚠ Replacing an unreadable lock file {}", path.display()),
+                }
+                remove_lock(path, holder.as_ref())?;
+            }
+            Err(e) => return Err(e).context("Could not create the lock file"),
+        }
+    }
+}
+
+/// Create `path` with the content of `pending`, failing if it exists.
+fn create_lock(pending: &Path, path: &Path) -> io::Result<()> {
+    let content = fs::read(pending)?;
+    let mut file = OpenOptions::new().write(true).create_new(true).open(path)?;
+    file.write_all(&content)
+}
+
+/// Remove the lock file at `path` if it still is the one of `replaced`. The file is renamed
+/// aside first, so when another run replaced the same stale lock in the meantime, the new lock
+/// is put back instead of being removed.
+fn remove_lock(path: &Path, replaced: Option<&LockInfo>) -> Result<()> {
+    let aside = path.with_extension(format!("lock.old.{}", std::process::id()));
+    match fs::rename(path, &aside) {
+        Ok(()) => {}
+        // Another run removed it first
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e).context("Could not remove the old lock file"),
+    }
+    let same = match (replaced, read_holder(&aside)) {
+        (Some(replaced), Some(moved)) => replaced.same_run(&moved),
+        (None, None) => true,
+        _ => false,
+    };
+    if !same {
+        // If yet another run has taken the lock since, the restored lock is already moot
+        let _ = fs::hard_link(&aside, path).or_else(|_| create_lock(&aside, path));
+    }
+    let _ = fs::remove_file(&aside);
+    Ok(())
+}
+
+fn recently_modified(path: &Path) -> bool {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .is_some_and(|age| age < WRITING_GRACE)
+}
+
+fn read_holder(path: &Path) -> Option<LockInfo> {
+    let data = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&data).ok()
+}
+
+fn hostname() -> String {
+    fs::read_to_string("/etc/hostname")
+        .map(|name| name.trim().to_string())
+        .or_else(|_| std::env::var("HOSTNAME"))
+        .unwrap_or_else(|_| "unknown".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lock_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("tag-spider-lock-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn write_holder(path: &Path, pid: u32, age: Duration) -> LockInfo {
+        let mut info = LockInfo::current("tags");
+        info.pid = pid;
+        info.started_at -= chrono::Duration::from_std(age).unwrap();
+        fs::write(path, serde_json::to_string(&info).unwrap()).unwrap();
+        info
+    }
+
+    #[test]
+    fn a_stale_lock_is_replaced_and_a_fresh_one_kept() {
+        let dir = lock_dir("stale");
+        let stale_after = Duration::from_secs(3600);
+        fs::create_dir_all(&dir).unwrap();
+        write_holder(&dir.join("cms.lock"), 1, stale_after * 2);
+
+        let lock = acquire(&dir, "cms", "extract", false, stale_after).unwrap();
+        assert!(acquire(&dir, "cms", "extract", false, stale_after).is_err());
+        drop(lock);
+        assert!(!dir.join("cms.lock").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn a_lock_taken_since_it_was_judged_stale_is_put_back() {
+        let dir = lock_dir("race");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("cms.lock");
+        let stale = write_holder(&path, 1, Duration::from_secs(7200));
+        // Another run replaced the stale lock between our read and our removal
+        let fresh = write_holder(&path, 2, Duration::ZERO);
+
+        remove_lock(&path, Some(&stale)).unwrap();
+        assert!(read_holder(&path).is_some_and(|holder| holder.same_run(&fresh)));
+        remove_lock(&path, Some(&fresh)).unwrap();
+        assert!(!path.exists());
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn the_direct_lock_file_is_created_exclusively() {
+        let dir = lock_dir("direct");
+        fs::create_dir_all(&dir).unwrap();
+        let (pending, path) = (dir.join("pending"), dir.join("cms.lock"));
+        fs::write(&pending, "{}").unwrap();
+
+        create_lock(&pending, &path).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{}");
+        let error = create_lock(&pending, &path).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::AlreadyExists);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
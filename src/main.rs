@@ -1,19 +1,442 @@
 // src/main.rs
 use anyhow::{Context, Result};
-use async_recursion::async_recursion;
+use clap::{builder::PossibleValuesParser, Args, CommandFactory, Parser, Subcommand};
+use clap_complete::Shell;
 use crossterm::event::{Event, KeyCode};
-use csv::{Reader, Writer};
-use futures::stream::{self, StreamExt};
-use std::io::{self, Write};
-use std::path::PathBuf;
-use std::{collections::HashMap, fs, time::Duration};
-use tag_spider_rs::spider::Spider;
+use csv::Writer;
+use futures::{stream, StreamExt};
+use once_cell::sync::OnceCell;
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::{collections::{BTreeSet, HashMap, HashSet, VecDeque}, fs, sync::Mutex, time::Duration};
+use chrono::{Datelike, Local};
+use tag_spider_rs::archive;
+use tag_spider_rs::audit::{self, AuditRecord};
+use tag_spider_rs::auth::{AuthStrategy, NeosFormLogin};
+use tag_spider_rs::backoff::{self, Backoff, OverloadSignal, ReloginStorm};
+use tag_spider_rs::bench::{self, BenchReport, SeededRng, StageResult};
+use tag_spider_rs::cacheserver;
+use tag_spider_rs::calendar;
+use tag_spider_rs::checkpoint::{self, Checkpoint, NodeRecord};
+use tag_spider_rs::checks::{self, EmbedStatus};
+use tag_spider_rs::clipboard::{self, NodeRef};
+use tag_spider_rs::config::{
+    BackoffConfig, Browser, ChecksConfig, Config, ContentType, DimensionsConfig, ExtractionConfig,
+    Operation, OutputConfig, RecoveryConfig, Setting, SinkKind, SiteConfig, SnapshotFormat, Source,
+    SpiderConfig, StdoutFormat, TaggingConfig,
+};
+use tag_spider_rs::dimensions::{self, VariantCoverage};
+use tag_spider_rs::driver;
+use tag_spider_rs::fixtures::{self, FixtureManifest};
+use tag_spider_rs::heartbeat;
+use tag_spider_rs::html;
+use tag_spider_rs::inspector::{self, InspectorField};
+use tag_spider_rs::issues;
+use tag_spider_rs::joblog::{self, JobLog};
+use tag_spider_rs::lock::{self, RunLock};
+use tag_spider_rs::merge;
+use tag_spider_rs::metrics::{self, Estimate, TimingHistory, TimingReport};
+use tag_spider_rs::notify;
+use tag_spider_rs::porcelain;
+use tag_spider_rs::recorder::{self, Action};
+use tag_spider_rs::report::{self, ReportEntry};
+use tag_spider_rs::secrets;
+use tag_spider_rs::selectors::{self, NoMatch, Selectors};
+use tag_spider_rs::session;
+use tag_spider_rs::sink::{self, MultiSink, OutputSink, RunSummary};
+use tag_spider_rs::snapshot;
+use tag_spider_rs::spider::{self, DriverPool, ScriptEntry, Spider, Traversal, CONTENT_SCRIPT};
+use tag_spider_rs::spill::EntryBuffer;
+use tag_spider_rs::state::{Decision, Review, State};
+use tag_spider_rs::tagging::{tag_list, QuestionIds, TagPreview, TagsFile};
+use tag_spider_rs::text::{self, Encoding};
 use tag_spider_rs::tree::FileTree;
-use thirtyfour::{prelude::*, support, By, WebDriver};
+use tag_spider_rs::treeitem::TreeItem;
+use tag_spider_rs::validator::{UrlCheck, Validator};
+use tag_spider_rs::wait::{self, Timeouts};
+use tag_spider_rs::youtube;
+use thirtyfour::{
+    error::WebDriverErrorInner, prelude::*, support, By, Capabilities, WebDriver,
+};
+use tokio::sync::mpsc;
 use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
+
+static CONFIGPATH: &str = "spider.toml";
+static SELECTORPATH: &str = "selectors.toml";
+static TREEPATH: &str = "resources/tree.json";
+/// Folder a bulk extraction starts from when none is given and no sites are configured
+static DEFAULT_FOLDER: &str = "treeitem-c6643bf0-label";
+static CREDENTIAL_PATHS: &[&str] = &[
+    "/run/secrets/cms-pswd",
+    "./credentials.json",
+    "./config/credentials.json",
+];
+
+/// Environment variables holding the backend credentials, used before any other source
+static USERNAME_ENV: &str = "SPIDER_USERNAME";
+static PASSWORD_ENV: &str = "SPIDER_PASSWORD";
+
+/// Credentials fetched from the secrets backend at startup, if one is configured
+static BACKEND_CREDENTIALS: OnceCell<(String, String)> = OnceCell::new();
+
+/// How sessions log in, set at startup
+static AUTH: OnceCell<Box<dyn AuthStrategy>> = OnceCell::new();
+
+/// Extraction settings from `spider.toml`, set at startup
+static EXTRACTION: OnceCell<ExtractionConfig> = OnceCell::new();
+
+/// How long to wait for the backend, from `spider.toml` and the command line, set at startup
+static TIMEOUTS: OnceCell<Timeouts> = OnceCell::new();
+
+/// Tagging settings from `spider.toml`, set at startup
+static TAGGING: OnceCell<TaggingConfig> = OnceCell::new();
+
+/// Whether the browser runs headless, so extra sessions are opened the same way
+static HEADLESS: OnceCell<bool> = OnceCell::new();
+
+#[derive(Parser)]
+#[command(about = "Crawls the Neos CMS backend to tag content and audit embedded media")]
+#[command(args_conflicts_with_subcommands = true)]
+struct Cli {
+    /// Extract only the node whose treeitem ID or backend URL is on the clipboard
+    #[arg(long)]
+    from_clipboard: bool,
+    /// Take over the run lock of another job changing the same CMS
+    #[arg(long, global = true)]
+    force: bool,
+    /// Print extracted entries to stdout as JSON lines and all progress output to stderr
+    #[arg(long, global = true)]
+    porcelain: bool,
+    /// Run the browser without a window, like HEADLESS=true
+    #[arg(long, global = true)]
+    headless: bool,
+    /// Settings file
+    #[arg(long, global = true, default_value = CONFIGPATH)]
+    config: PathBuf,
+    /// Login page of the Neos backend, overriding [spider] login_url
+    #[arg(long, global = true)]
+    login_url: Option<String>,
+    /// WebDriver server controlling the browser, overriding [spider] webdriver_url
+    #[arg(long, global = true)]
+    webdriver_url: Option<String>,
+    /// Browser to drive (firefox, chrome, edge), overriding [spider] browser
+    #[arg(long, global = true)]
+    browser: Option<Browser>,
+    /// CSV of question IDs and their tags, or a glob like 'resources/tags/*.csv' merging
+    /// several, overriding [tagging] tags_path
+    #[arg(long, global = true)]
+    tags: Option<PathBuf>,
+    /// Formats the results are written in (csv, jsonl, json, sqlite, xlsx, html, parquet,
+    /// stdout), comma separated, overriding [output] sinks
+    #[arg(long, global = true, value_delimiter = ',')]
+    format: Vec<SinkKind>,
+    /// Content types to extract (externallink, youtube, tutorial), comma separated, overriding
+    /// [extraction] types
+    #[arg(long, global = true, value_delimiter = ',')]
+    types: Vec<ContentType>,
+    /// Seconds a page or the document tree may take to load, overriding [timeouts]
+    /// page_load_secs
+    #[arg(long, global = true)]
+    page_load_timeout: Option<u64>,
+    /// Seconds a single element may take to appear, overriding [timeouts] element_secs
+    #[arg(long, global = true)]
+    element_timeout: Option<u64>,
+    /// Seconds a linked server may take to answer, overriding [checks] url_timeout_secs
+    #[arg(long, global = true)]
+    url_timeout: Option<u64>,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Extract all nodes below a folder and check their entries, like the `d` key. Unset flags
+    /// come from the [bulk] settings
+    Extract {
+        /// Treeitem ID of the folder (default: all configured sites, or treeitem-c6643bf0-label)
+        #[arg(long)]
+        folder: Option<String>,
+        /// How many levels below the folder to traverse (default: 5)
+        #[arg(long)]
+        depth: Option<usize>,
+        /// Directory the results are written to (default: embedded_content)
+        #[arg(long)]
+        out: Option<PathBuf>,
+        /// Skip the URL validation
+        #[arg(long)]
+        no_validate: bool,
+        /// Also extract the other configured dimension variants of every node
+        #[arg(long)]
+        all_dimensions: bool,
+        /// Continue an interrupted run, skipping the nodes in its checkpoint
+        #[arg(long)]
+        resume: bool,
+        /// Stop taking new nodes after this long (e.g. 2h, 90m, 1h30m), finish the current one
+        /// and save a checkpoint for --resume
+        #[arg(long, value_parser = parse_duration)]
+        max_duration: Option<Duration>,
+        /// Only traverse the tree and print how long the extraction is expected to take, from the
+        /// timings of earlier runs
+        #[arg(long)]
+        estimate: bool,
+    },
+    /// Add the tags from the tags file to the questions of a node, like the `a` key
+    Tag {
+        /// Treeitem ID or backend URL of the node containing the questions
+        #[arg(long)]
+        node: String,
+        /// Also tag every page below the node (needs a treeitem ID), after a preview
+        #[arg(long)]
+        subtree: bool,
+        /// Apply a --subtree job without asking for confirmation
+        #[arg(long, short = 'y')]
+        yes: bool,
+    },
+    /// Remove the tags of a node's questions, like the `c` key
+    ClearTags {
+        /// Treeitem ID or backend URL of the node containing the questions
+        #[arg(long)]
+        node: String,
+        /// Also clear every page below the node (needs a treeitem ID), after a preview
+        #[arg(long)]
+        subtree: bool,
+        /// Apply a --subtree job without asking for confirmation
+        #[arg(long, short = 'y')]
+        yes: bool,
+    },
+    /// Validate the URLs of earlier results again, without a browser; exits non-zero on broken URLs
+    Validate {
+        /// Result CSVs to validate (default: all results in ./embedded_content)
+        inputs: Vec<PathBuf>,
+        /// Result CSV with the new URL status
+        #[arg(long, default_value = "validated.csv")]
+        out: PathBuf,
+    },
+    /// Replay a recorded session to reproduce a failure
+    Replay {
+        /// Session file written by the recorder (recordings/session-*.jsonl)
+        session: PathBuf,
+        /// Replay against the saved page snapshots instead of the live CMS
+        #[arg(long)]
+        offline: bool,
+        /// Apply recorded tagging to the live CMS; without it tagging actions are skipped
+        #[arg(long, conflicts_with = "offline")]
+        apply_tags: bool,
+    },
+    /// Extract a single node and print its entries instead of writing files
+    ExtractOne {
+        /// Treeitem ID or backend URL of the node
+        node: String,
+        /// Print the entries as JSON instead of a table
+        #[arg(long)]
+        json: bool,
+    },
+    /// Generate reports from the extraction results
+    Report {
+        #[command(subcommand)]
+        command: ReportCommand,
+    },
+    /// Combine the results of partial runs into one result CSV, keeping the latest row per finding
+    Merge {
+        /// Result CSVs of the partial runs
+        #[arg(required = true)]
+        inputs: Vec<PathBuf>,
+        /// Merged result CSV
+        #[arg(long, default_value = "merged.csv")]
+        out: PathBuf,
+    },
+    /// Compare the stored node snapshots of different runs
+    Snapshot {
+        #[command(subcommand)]
+        command: SnapshotCommand,
+    },
+    /// Import and track editors' review decisions
+    Review {
+        #[command(subcommand)]
+        command: ReviewCommand,
+    },
+    /// Edit the tags of question-answer content
+    Tags {
+        #[command(subcommand)]
+        command: TagsCommand,
+    },
+    /// Bulk changes to ExternalLinks elements in the CMS
+    Links {
+        #[command(subcommand)]
+        command: LinksCommand,
+    },
+    /// Developer tools for the offline parser and selector tests
+    Fixtures {
+        #[command(subcommand)]
+        command: FixturesCommand,
+    },
+    /// Inspect the configuration
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+    /// Share URL checks across the team
+    Cache {
+        #[command(subcommand)]
+        command: CacheCommand,
+    },
+    /// Print a shell completion script, e.g. `tag-spider-rs completions bash > ~/.local/share/bash-completion/completions/tag-spider-rs`
+    Completions { shell: Shell },
+}
+
+#[derive(Subcommand)]
+enum ConfigCommand {
+    /// Print the effective settings (secrets redacted) and where each one comes from
+    Show,
+    /// Check files, selectors, URLs and credentials before a run; exits non-zero on problems
+    Check,
+}
+
+#[derive(Subcommand)]
+enum ReportCommand {
+    /// Build a static HTML site with per-chapter pages and a search over titles and URLs
+    Site {
+        /// Result CSVs to include (default: all results in ./embedded_content)
+        inputs: Vec<PathBuf>,
+        /// Directory the site is written to
+        #[arg(long, default_value = "report-site")]
+        out: PathBuf,
+    },
+    /// Open or update an issue per chapter with broken links in the [issues] tracker, and close
+    /// the issues of chapters whose links were fixed
+    Issues {
+        /// Result CSVs to report (default: all results in ./embedded_content)
+        inputs: Vec<PathBuf>,
+    },
+    /// Write a Markdown list of the broken links, grouped by page, to paste into an issue
+    Broken {
+        /// Result CSVs to report (default: all results in ./embedded_content)
+        inputs: Vec<PathBuf>,
+        /// Markdown file
+        #[arg(long, default_value = "broken-links.md")]
+        out: PathBuf,
+    },
+    /// Write an iCalendar feed of the [calendar] schedule and the runs in the SQLite history
+    Calendar {
+        /// Feed file (default: [calendar] path, or audits.ics)
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum SnapshotCommand {
+    /// List the runs that stored a snapshot of a node
+    List {
+        /// Treeitem ID of the node
+        node: String,
+    },
+    /// Print a unified diff of a node's snapshots from two runs
+    Diff {
+        /// Treeitem ID of the node
+        node: String,
+        /// Earlier run ID, as shown by `snapshot list`
+        run_a: String,
+        /// Later run ID
+        run_b: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum ReviewCommand {
+    /// Import a reviewed result CSV with a Decision column (fixed, ignore, replace with <url>)
+    Import { csv: PathBuf },
+    /// Show the fix progress against the latest results
+    Status {
+        /// Result CSVs to compare against (default: all results in ./embedded_content)
+        inputs: Vec<PathBuf>,
+    },
+    /// Replace the URLs of rows marked "replace with <url>" in the CMS (dry run unless --apply)
+    ApplyReplacements {
+        /// Actually change the links; without it the planned replacements are only listed
+        #[arg(long)]
+        apply: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum TagsCommand {
+    /// Replace the tags of a single question
+    Set {
+        /// Question ID as shown at the start of the question title, e.g. 1.1.3
+        question: String,
+        /// Comma-separated tags, e.g. "Ladung, Stromstärke"
+        tags: String,
+        /// Treeitem ID or backend URL of the node containing the question
+        #[arg(long)]
+        node: String,
+    },
+    /// Check the tags files for encoding, CSV and duplicate ID problems, with line numbers
+    Lint {
+        /// Tags file or glob to check instead of `[tagging] tags_path`
+        path: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum LinksCommand {
+    /// Update title, author, type and size of links from a corrected result CSV
+    ApplyMetadata { csv: PathBuf },
+}
+
+#[derive(Subcommand)]
+enum CacheCommand {
+    /// Serve the validation cache to other machines, which point `[checks] cache_url` at it.
+    /// Checks are kept in `[checks] cache_path`
+    Serve {
+        /// Address to listen on, e.g. 0.0.0.0:7878 to serve other machines
+        #[arg(long, default_value = "127.0.0.1:7878")]
+        listen: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum FixturesCommand {
+    /// Save sanitized page source, tree HTML and a screenshot of representative nodes
+    Capture {
+        /// Treeitem IDs of the nodes to capture
+        #[arg(required = true)]
+        nodes: Vec<String>,
+        /// Directory the fixtures are written to
+        #[arg(long, default_value = "tests/fixtures")]
+        out: PathBuf,
+    },
+    /// Replay the captured pages through parsing, the content checks and the output sinks,
+    /// without a browser or network, and print the entries per second of each stage
+    Bench(BenchArgs),
+}
 
-static URL: &str = "https://cms.schrackforstudents.com/neos/login";
-static TAGPATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/resources/tags.csv");
+/// Arguments of `fixtures bench`
+#[derive(Args)]
+struct BenchArgs {
+    /// Directory of captured fixtures
+    #[arg(long, default_value = "tests/fixtures")]
+    fixtures: PathBuf,
+    /// Nodes in the run, each showing one of the captured pages
+    #[arg(long, default_value_t = 1000)]
+    nodes: usize,
+    /// Seed picking the page of each node, so runs with the same seed are the same
+    #[arg(long, default_value_t = 42)]
+    seed: u64,
+    /// Rounds to run; the fastest of each stage counts
+    #[arg(long, default_value_t = 3)]
+    rounds: usize,
+    /// Earlier results to compare with; exits non-zero if a stage got slower
+    #[arg(long)]
+    baseline: Option<PathBuf>,
+    /// Percent a stage may be slower than in the baseline
+    #[arg(long, default_value_t = 20.0)]
+    max_slowdown: f64,
+    /// Save the results as JSON, e.g. as the baseline of later runs
+    #[arg(long)]
+    save: Option<PathBuf>,
+}
 
 #[derive(serde::Deserialize)]
 struct Credentials {
@@ -21,7 +444,8 @@ struct Credentials {
     password: String,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
 struct ContentEntry {
     source_node: String,
     breadcrumb_path: String,
@@ -32,127 +456,247 @@ struct ContentEntry {
     file_type: String,
     size: String,
     url_valid: String,
+    visual_check: String,
+    title_check: String,
+    age_flag: String,
+    visibility: String,
+    site: String,
+    dimension: String,
+    /// 1-based index of the dynamic container the entry was found in; empty for tutorials.
+    container_index: String,
+    /// `data-__neos-fusion-path` of the entry's element, to find it again in the Neos UI.
+    fusion_path: String,
+    /// Backend URL opening the source node, for editors to jump straight to a finding.
+    backend_url: String,
+    /// Request method that answered the URL check, "HEAD" or "GET".
+    url_method: String,
+    /// Where the URL redirects to, empty if it does not, to update stale links in the CMS.
+    final_url: String,
+    redirect_hops: String,
+    wayback_url: String,
 }
 
-/// Check if relogin dialog is present
-async fn is_relogin_dialog_present(driver: &WebDriver) -> bool {
-    driver
-        .find(By::Id("neos-ReloginDialog"))
-        .await
-        .is_ok()
+impl From<ReportEntry> for ContentEntry {
+    fn from(entry: ReportEntry) -> Self {
+        Self {
+            source_node: entry.source_node,
+            breadcrumb_path: entry.breadcrumb_path,
+            content_type: entry.content_type,
+            url: entry.url,
+            title: entry.title,
+            author: entry.author,
+            file_type: entry.file_type,
+            size: entry.size,
+            url_valid: entry.url_valid,
+            visual_check: entry.visual_check,
+            title_check: entry.title_check,
+            age_flag: entry.age_flag,
+            visibility: entry.visibility,
+            site: entry.site,
+            dimension: entry.dimension,
+            container_index: entry.container_index,
+            fusion_path: entry.fusion_path,
+            backend_url: entry.backend_url,
+            url_method: entry.url_method,
+            final_url: entry.final_url,
+            redirect_hops: entry.redirect_hops,
+            wayback_url: entry.wayback_url,
+        }
+    }
+}
+
+impl ContentEntry {
+    /// Normalize the scraped text fields, see [`text::normalize`]
+    fn normalize(&mut self) {
+        for field in [
+            &mut self.breadcrumb_path,
+            &mut self.title,
+            &mut self.author,
+            &mut self.file_type,
+            &mut self.size,
+        ] {
+            *field = text::normalize(field);
+        }
+        self.url = text::normalize_url(&self.url);
+    }
+}
+
+impl From<&ContentEntry> for ReportEntry {
+    fn from(entry: &ContentEntry) -> Self {
+        Self {
+            source_node: entry.source_node.clone(),
+            breadcrumb_path: entry.breadcrumb_path.clone(),
+            content_type: entry.content_type.clone(),
+            url: entry.url.clone(),
+            title: entry.title.clone(),
+            author: entry.author.clone(),
+            file_type: entry.file_type.clone(),
+            size: entry.size.clone(),
+            url_valid: entry.url_valid.clone(),
+            visual_check: entry.visual_check.clone(),
+            title_check: entry.title_check.clone(),
+            age_flag: entry.age_flag.clone(),
+            visibility: entry.visibility.clone(),
+            site: entry.site.clone(),
+            dimension: entry.dimension.clone(),
+            container_index: entry.container_index.clone(),
+            fusion_path: entry.fusion_path.clone(),
+            backend_url: entry.backend_url.clone(),
+            url_method: entry.url_method.clone(),
+            final_url: entry.final_url.clone(),
+            redirect_hops: entry.redirect_hops.clone(),
+            wayback_url: entry.wayback_url.clone(),
+        }
+    }
 }
 
 /// Handle relogin dialog if present
 async fn handle_relogin_dialog(driver: &WebDriver) -> Result<bool> {
-    if !is_relogin_dialog_present(driver).await {
+    if !auth().detect_expired(driver).await {
         return Ok(false);
     }
 
     println!("Relogin dialog detected! Attempting to login again...");
+    if let Some(lifetime) = session::note_expired(&session_key(driver)) {
+        println!("Session expired after {} min", lifetime.as_secs() / 60);
+    }
+    backoff::note_relogin()?;
+    let started = Instant::now();
+    let result = auth().relogin(driver).await;
+    if matches!(result, Ok(true)) {
+        session::note_login(&session_key(driver));
+    }
+    record_action(driver, Action::Relogin, started, &result, |ok| relogin_summary(*ok).to_string()).await;
+    result
+}
 
-    // Get credentials
-    let credentials = get_credentials()?;
-
-    // Find username field in relogin dialog
-    let username_field = driver
-        .find(By::Name("__authentication[Neos][Flow][Security][Authentication][Token][UsernamePassword][username]"))
-        .await
-        .context("Could not find username field in relogin dialog!")?;
-
-    // Find password field in relogin dialog
-    let password_field = driver
-        .find(By::Name("__authentication[Neos][Flow][Security][Authentication][Token][UsernamePassword][password]"))
-        .await
-        .context("Could not find password field in relogin dialog!")?;
-
-    // Find login button in relogin dialog
-    let login_button = driver
-        .find(By::Css("button.style__btn___3rhzP.style__btn--brand___1ZsvX.style__loginButton___1nLYF"))
-        .await
-        .context("Could not find login button in relogin dialog!")?;
-
-    // Clear existing values and enter credentials
-    username_field.clear().await?;
-    username_field.send_keys(&credentials.0).await?;
-
-    password_field.clear().await?;
-    password_field.send_keys(&credentials.1).await?;
-
-    // Click login button
-    login_button.click().await?;
-
-    // Wait for login to complete
-    support::sleep(Duration::from_secs(3)).await;
-
-    // Check if dialog is gone
-    let login_successful = !is_relogin_dialog_present(driver).await;
+/// Key of the session clock of the browser session of `driver`
+fn session_key(driver: &WebDriver) -> String {
+    driver.session_id().to_string()
+}
 
-    if login_successful {
-        println!("Relogin successful!");
+fn relogin_summary(successful: bool) -> &'static str {
+    if successful {
+        "relogin successful"
     } else {
-        println!("Relogin may have failed - dialog still present");
+        "relogin failed"
     }
-
-    Ok(login_successful)
 }
 
-/// Get credentials from files
+/// Get credentials from `SPIDER_USERNAME`/`SPIDER_PASSWORD`, else from the secrets backend, else
+/// from the first credentials file that exists
 fn get_credentials() -> Result<(String, String)> {
-    let credential_paths = [
-        PathBuf::from("/run/secrets/cms-pswd"),
-        PathBuf::from("./credentials.json"),
-        PathBuf::from("./config/credentials.json"),
-    ];
+    if let Some(credentials) = env_credentials()? {
+        return Ok(credentials);
+    }
+    if let Some(credentials) = BACKEND_CREDENTIALS.get() {
+        return Ok(credentials.clone());
+    }
+
+    read_credentials_file().map(|(_, credentials)| credentials)
+}
+
+/// The credentials from `SPIDER_USERNAME` and `SPIDER_PASSWORD`, if they are set, so CI or a
+/// container can inject them without a credentials file
+fn env_credentials() -> Result<Option<(String, String)>> {
+    let var = |name| std::env::var(name).ok().filter(|value: &String| !value.is_empty());
+    match (var(USERNAME_ENV), var(PASSWORD_ENV)) {
+        (Some(username), Some(password)) => Ok(Some((username, password))),
+        (None, None) => Ok(None),
+        (Some(_), None) => anyhow::bail!("{USERNAME_ENV} is set but {PASSWORD_ENV} is not"),
+        (None, Some(_)) => anyhow::bail!("{PASSWORD_ENV} is set but {USERNAME_ENV} is not"),
+    }
+}
 
-    for path in &credential_paths {
+/// Read the credentials from the first credentials file that exists, returning its path too
+fn read_credentials_file() -> Result<(&'static str, (String, String))> {
+    for path in CREDENTIAL_PATHS {
         match fs::read_to_string(path) {
             Ok(content) => {
-                let creds: Credentials = serde_json::from_str(&content).context(
-                    "Credentials are not valid JSON with fields 'password' and 'username'",
-                )?;
-                return Ok((creds.username, creds.password));
+                let creds: Credentials = serde_json::from_str(&content).with_context(|| {
+                    format!("{path} is not valid JSON with fields 'password' and 'username'")
+                })?;
+                return Ok((path, (creds.username, creds.password)));
             }
             Err(_) => continue,
         }
     }
 
-    Err(anyhow::anyhow!("No credentials file found"))
+    Err(anyhow::anyhow!(
+        "No credentials file found (looked for {}) and {USERNAME_ENV}/{PASSWORD_ENV} are not set",
+        CREDENTIAL_PATHS.join(", ")
+    ))
+}
+
+/// The authentication strategy used for all sessions: the Neos login form
+fn auth() -> &'static dyn AuthStrategy {
+    AUTH.get_or_init(|| {
+        Box::new(NeosFormLogin::new(get_credentials, &SpiderConfig::default().login_url))
+    })
+    .as_ref()
 }
 
 /// Log in using the provided WebDriver.
 pub async fn login(driver: &WebDriver) -> Result<()> {
-    let credentials = get_credentials()?;
-
-    // Find the login elements
-    let username_field = driver
-        .find(By::Id("username"))
-        .await
-        .context("Could not find username field!")?;
+    auth().login(driver).await?;
+    session::note_login(&session_key(driver));
+    Ok(())
+}
 
-    let password_field = driver
-        .find(By::Id("password"))
+/// Log in for a subcommand and wait up to `[spider] login_wait_secs` for the document tree
+async fn login_and_wait(driver: &WebDriver, config: &Config) -> Result<()> {
+    login(driver).await?;
+    let tree = &Selectors::get().tree;
+    wait::wait_for_element(&driver.handle, "tree.root", &tree.root, config.spider.login_wait())
         .await
-        .context("Could not find a password field!")?;
+        .context("The backend did not show the document tree after logging in")?;
+    Ok(())
+}
 
-    let login_button = driver
-        .find(By::ClassName("neos-login-btn"))
-        .await
-        .context("Could not find login button!")?;
-
-    // Perform the login action
-    driver
-        .action_chain()
-        .click_element(&username_field)
-        .send_keys(&credentials.0)
-        .click_element(&password_field)
-        .send_keys(&credentials.1)
-        .click_element(&login_button)
-        .perform()
-        .await?;
+/// Start a new session if the current one is about to expire, so the relogin dialog does not
+/// interrupt the next node
+async fn renew_session_if_due(driver: &WebDriver) -> Result<bool> {
+    let key = session_key(driver);
+    if !session::reauth_due(&key) {
+        return Ok(false);
+    }
+    let lifetime = session::expected_lifetime(&key).unwrap_or_default();
+    println!(
+        "Session expected to expire soon (lifetime ~{} min), logging in again...",
+        lifetime.as_secs() / 60
+    );
+    auth().reauthenticate(driver).await?;
+    session::note_login(&key);
+    // The reloaded backend starts with a collapsed tree
+    spider::forget_expanded();
+    check_session(driver, timeouts().page_load).await?;
+    println!("✓ New session started");
+    Ok(true)
+}
 
-    support::sleep(Duration::from_secs(2)).await;
+/// Make sure the backend is usable before a long job starts, so rejected or expired credentials
+/// are reported within seconds instead of at the first relogin dialog an hour later
+async fn check_session(driver: &WebDriver, timeout: Duration) -> Result<()> {
+    let started = Instant::now();
+    loop {
+        if auth().detect_expired(driver).await && !handle_relogin_dialog(driver).await? {
+            anyhow::bail!("The relogin dialog rejected the credentials; check whether the password has expired");
+        }
 
-    Ok(())
+        let selectors = Selectors::get();
+        if selectors::probe_first(&driver.handle, "tree.root", &selectors.tree.root).await.is_ok() {
+            return Ok(());
+        }
+        if started.elapsed() > timeout {
+            if selectors::probe_first(&driver.handle, "login.username", &selectors.login.username).await.is_ok() {
+                anyhow::bail!(
+                    "Still on the login page: the CMS rejected the credentials or the password has expired"
+                );
+            }
+            anyhow::bail!("The backend did not show the document tree within {}s", timeout.as_secs());
+        }
+        support::sleep(timeouts().retry / 2).await;
+    }
 }
 
 /// Retry wrapper that handles relogin dialogs automatically
@@ -169,17 +713,18 @@ where
 
     for attempt in 0..=max_retries {
         // Check for relogin dialog before attempting operation
-        if is_relogin_dialog_present(driver).await {
+        if auth().detect_expired(driver).await {
             println!("Relogin dialog detected before operation attempt {}", attempt + 1);
             match handle_relogin_dialog(driver).await {
                 Ok(true) => {
                     println!("Relogin successful, continuing with operation...");
                     // Give some time for the page to settle after relogin
-                    support::sleep(Duration::from_secs(2)).await;
+                    support::sleep(timeouts().retry).await;
                 },
                 Ok(false) => {
                     return Err(anyhow::anyhow!("Relogin dialog present but login failed"));
                 },
+                Err(e) if e.is::<ReloginStorm>() => return Err(e),
                 Err(e) => {
                     return Err(anyhow::anyhow!("Failed to handle relogin dialog: {}", e));
                 }
@@ -204,28 +749,38 @@ where
                         match handle_relogin_dialog(driver).await {
                             Ok(true) => {
                                 println!("Relogin successful, retrying operation...");
-                                support::sleep(Duration::from_secs(2)).await;
+                                support::sleep(timeouts().retry).await;
                                 continue;
                             },
                             Ok(false) => {
                                 println!("Relogin failed, but will retry operation anyway");
-                                support::sleep(Duration::from_secs(1)).await;
+                                support::sleep(timeouts().retry / 2).await;
                                 continue;
                             },
+                            Err(relogin_err) if relogin_err.is::<ReloginStorm>() => {
+                                return Err(relogin_err);
+                            },
                             Err(relogin_err) => {
                                 println!("Failed to handle relogin: {}", relogin_err);
-                                support::sleep(Duration::from_secs(1)).await;
+                                support::sleep(timeouts().retry / 2).await;
                                 continue;
                             }
                         }
                     }
                 }
 
+                if attempt < max_retries {
+                    record_selector_retry(&e);
+                }
                 last_error = Some(e);
 
                 if attempt < max_retries {
-                    println!("Operation failed (attempt {}), retrying in 2 seconds...", attempt + 1);
-                    support::sleep(Duration::from_secs(2)).await;
+                    println!(
+                        "Operation failed (attempt {}), retrying in {:.1}s...",
+                        attempt + 1,
+                        timeouts().retry.as_secs_f64()
+                    );
+                    support::sleep(timeouts().retry).await;
                 }
             }
         }
@@ -241,108 +796,150 @@ async fn safe_click_element(driver: &WebDriver, element: &thirtyfour::WebElement
     }, 3).await
 }
 
+/// Find a tree item by its aria-labelledby ID, recording the lookup latency
+async fn find_treeitem_element(driver: &WebDriver, folder_id: &str) -> Result<WebElement> {
+    let candidates = selectors::with_id(&Selectors::get().tree.treeitem, folder_id);
+    metrics::timed(
+        "find",
+        "tree.treeitem",
+        selectors::find_first(&driver.handle, "tree.treeitem", &candidates),
+    )
+    .await
+}
+
 async fn find_and_click_folder(driver: &WebDriver, folder_id: &str) -> Result<()> {
-    let selector = format!("div[aria-labelledby='{folder_id}']");
-    let folder_element = driver
-        .find(By::Css(&selector))
+    let folder_element = find_treeitem_element(driver, folder_id)
         .await
         .context(format!("Could not find folder with ID: {folder_id}"))?;
 
     folder_element.scroll_into_view().await?;
 
-    let folder_header = folder_element
-        .find(By::ClassName("node__header__labelWrapper___dJ7OH"))
-        .await
-        .context("Could not find folder header!")?;
+    let tree = &Selectors::get().tree;
+    let folder_header = metrics::timed(
+        "find",
+        "tree.header",
+        selectors::find_first(&folder_element, "tree.header", &tree.header),
+    )
+    .await
+    .context("Could not find folder header!")?;
 
     folder_header.click().await?;
     Ok(())
 }
 
 async fn expand_folder_if_needed(driver: &WebDriver, folder_id: &str) -> Result<()> {
+    if spider::is_expanded(folder_id) {
+        return Ok(());
+    }
+
     retry_with_relogin(driver, || async {
-        let selector = format!("div[aria-labelledby='{folder_id}']");
-        let folder_element = driver.find(By::Css(&selector)).await.context(format!(
+        let folder_element = find_treeitem_element(driver, folder_id).await.context(format!(
             "Could not find folder element '{folder_id}'. Make sure you're on the correct page and logged in."))?;
 
         let expanded = folder_element.attr("aria-expanded").await?;
         if expanded != Some("true".to_string()) {
-            let toggle_button = folder_element
-                .find(By::Css(
-                    "a.node__header__chevron___zXVME.reset__reset___2e25U",
-                ))
-                .await
-                .context("Could not find toggle button!")?;
+            let chevron = &Selectors::get().tree.chevron;
+            let toggle_button = metrics::timed(
+                "find",
+                "tree.chevron",
+                selectors::find_first(&folder_element, "tree.chevron", chevron),
+            )
+            .await
+            .context("Could not find toggle button!")?;
 
             toggle_button.click().await?;
-            support::sleep(Duration::from_secs(1)).await;
+            let contents = &Selectors::get().tree.contents;
+            wait::wait_for_element(&folder_element, "tree.contents", contents, timeouts().expand)
+                .await
+                .context("The folder did not show its children")?;
         }
+        spider::mark_expanded(folder_id);
         Ok(())
     }, 3).await
 }
 
-async fn is_folder_expandable(driver: &WebDriver, folder_id: &str) -> Result<bool> {
-    let selector = format!("div[aria-labelledby='{folder_id}']");
-    let folder_element = driver.find(By::Css(&selector)).await.context(format!(
+/// Read the current state of a tree item
+async fn read_tree_item(driver: &WebDriver, folder_id: &str) -> Result<TreeItem> {
+    let folder_element = find_treeitem_element(driver, folder_id).await.context(format!(
         "Could not find folder element '{folder_id}'"))?;
 
-    // Check if the folder has a chevron button (indicates it's expandable)
-    let chevron_exists = folder_element
-        .find(By::Css("a.node__header__chevron___zXVME"))
-        .await
-        .is_ok();
-
-    Ok(chevron_exists)
+    metrics::timed("read", "tree.item", TreeItem::from_element(&folder_element)).await
 }
 
-async fn get_folder_children(driver: &WebDriver, folder_id: &str) -> Result<Vec<String>> {
+async fn get_folder_children(driver: &WebDriver, folder_id: &str) -> Result<Vec<TreeItem>> {
     println!("Getting children for folder: {folder_id}");
 
-    // First check if the folder is expandable
-    if !is_folder_expandable(driver, folder_id).await? {
+    // First check if the folder is expandable (has a chevron)
+    if !read_tree_item(driver, folder_id).await?.expandable {
         println!("Folder {folder_id} is not expandable (no chevron found)");
         return Ok(Vec::new());
     }
 
     expand_folder_if_needed(driver, folder_id).await?;
-    // Reduced from 2000ms to 1000ms
-    support::sleep(Duration::from_millis(1000)).await;
 
-    let parent_selector = format!("div[aria-labelledby='{folder_id}']");
-    let parent_element = driver
-        .find(By::Css(&parent_selector))
+    let parent_element = find_treeitem_element(driver, folder_id)
         .await
         .context("Could not find parent folder element")?;
 
+    // One script call reads all children; the element-by-element lookup below is the fallback
+    match metrics::timed(
+        "execute",
+        "tree.children_script",
+        TreeItem::read_children(driver, &parent_element),
+    )
+    .await
+    {
+        Ok(children) => {
+            println!("Total children found: {}", children.len());
+            return Ok(children);
+        }
+        Err(e) => println!("Script read failed ({e:#}), looking up the tree items one by one..."),
+    }
+
     println!("Found parent element, now looking for node__contents...");
 
-    let contents_divs = parent_element
-        .find_all(By::Css("div.node__contents___GgwYX"))
-        .await?;
+    let tree = &Selectors::get().tree;
+    let contents_divs = metrics::timed(
+        "find_all",
+        "tree.contents",
+        selectors::find_all_first(&parent_element, "tree.contents", &tree.contents),
+    )
+    .await?;
 
-    let mut child_ids = Vec::new();
+    let mut children = Vec::new();
 
     for contents_div in contents_divs {
         println!("Found contents div, looking for child treeitems...");
 
-        let child_treeitems = contents_div
-            .find_all(By::Css("div[role='treeitem']"))
-            .await?;
+        let child_treeitems = metrics::timed(
+            "find_all",
+            "tree.child_item",
+            selectors::find_all_first(&contents_div, "tree.child_item", &tree.child_item),
+        )
+        .await?;
 
         println!("Found {} potential child treeitems", child_treeitems.len());
 
         for child in child_treeitems {
-            if let Some(id) = child.attr("aria-labelledby").await? {
-                child_ids.push(id.clone());
-                println!("Found child: {id}");
+            match TreeItem::from_element(&child).await {
+                Ok(item) => {
+                    println!("Found child: {} ({})", item.label, item.id);
+                    children.push(item);
+                }
+                Err(e) => println!("Skipping unreadable tree item: {e}"),
             }
         }
     }
 
-    if child_ids.is_empty() {
+    if children.is_empty() {
         println!("No children found in contents div. Trying fallback method...");
 
-        let all_items = driver.find_all(By::Css("div[role='treeitem']")).await?;
+        let all_items = metrics::timed(
+            "find_all",
+            "tree.child_item",
+            selectors::find_all_first(&driver.handle, "tree.child_item", &tree.child_item),
+        )
+        .await?;
         let mut found_parent = false;
         let mut parent_level: Option<i32> = None;
 
@@ -361,8 +958,9 @@ async fn get_folder_children(driver: &WebDriver, folder_id: &str) -> Result<Vec<
                         if let Some(current_level_str) = item.attr("aria-level").await? {
                             if let Ok(current_level) = current_level_str.parse::<i32>() {
                                 if current_level == parent_lvl + 1 {
-                                    child_ids.push(id.clone());
-                                    println!("Found child via fallback: {id}");
+                                    let child = TreeItem::from_element(&item).await?;
+                                    println!("Found child via fallback: {} ({id})", child.label);
+                                    children.push(child);
                                 } else if current_level <= parent_lvl {
                                     break;
                                 }
@@ -374,149 +972,80 @@ async fn get_folder_children(driver: &WebDriver, folder_id: &str) -> Result<Vec<
         }
     }
 
-    println!("Total children found: {}", child_ids.len());
-    Ok(child_ids)
+    println!("Total children found: {}", children.len());
+    Ok(children)
+}
+
+/// `get_folder_children`, recorded as a session action
+async fn recorded_get_children(driver: &WebDriver, folder_id: &str) -> Result<Vec<TreeItem>> {
+    let action = Action::GetChildren {
+        folder_id: folder_id.to_string(),
+    };
+    let started = Instant::now();
+    let result = get_folder_children(driver, folder_id).await;
+    record_action(driver, action, started, &result, |children| children_summary(children)).await;
+    result
 }
 
-#[async_recursion]
+/// Collect the descendants of `folder_id` up to `max_depth` levels with a [`Traversal`],
+/// recording their parents in `tree`. Stops early with the descendants found so far if the
+/// spider is cancelled.
 async fn get_all_descendants(
-    driver: &WebDriver,
+    spider: &Spider,
+    tree: &mut FileTree,
     folder_id: &str,
     max_depth: usize,
-    current_depth: usize,
-) -> Result<Vec<String>> {
-    let mut all_descendants = Vec::new();
-
-    if current_depth >= max_depth {
-        println!("  Reached maximum depth {max_depth} for folder: {folder_id}");
-        return Ok(all_descendants);
-    }
-
-    println!("  Traversing folder at depth {current_depth}: {folder_id}");
-
-    let children = get_folder_children(driver, folder_id).await?;
-
-    for child_id in children {
-        all_descendants.push(child_id.clone());
-        println!("    Added child: {child_id}");
-
-        // Check if child is expandable before trying to get its children
-        match is_folder_expandable(driver, &child_id).await {
-            Ok(true) => {
-                // Child is expandable, get its children
-                match get_folder_children(driver, &child_id).await {
-                    Ok(grandchildren) => {
-                        if !grandchildren.is_empty() {
-                            println!(
-                                "    Child {} has {} grandchildren, recursing...",
-                                child_id,
-                                grandchildren.len()
-                            );
-                            let descendants =
-                                get_all_descendants(driver, &child_id, max_depth, current_depth + 1)
-                                    .await?;
-                            all_descendants.extend(descendants);
-                        } else {
-                            println!("    Child {child_id} is expandable but has no children");
-                        }
-                    }
-                    Err(e) => {
-                        println!("    Failed to get children for {child_id}: {e}");
-                    }
-                }
-            }
-            Ok(false) => {
-                println!("    Child {child_id} is a leaf node (no chevron indicator)");
-            }
-            Err(e) => {
-                println!("    Could not check if {child_id} is expandable: {e}");
-            }
-        }
-
-        // Reduced from 500ms to 300ms
-        support::sleep(Duration::from_millis(300)).await;
-    }
-
-    println!(
-        "  Found {} total descendants for folder: {}",
-        all_descendants.len(),
-        folder_id
-    );
-    Ok(all_descendants)
-}
-
-async fn extract_breadcrumb_path(driver: &WebDriver) -> Result<String> {
-    let breadcrumbs = driver
-        .find_all(By::Css(
-            ".neos-breadcrumb a, .breadcrumb a, [class*='breadcrumb'] a",
-        ))
-        .await?;
-
-    if !breadcrumbs.is_empty() {
-        let mut path_parts = Vec::new();
-        for breadcrumb in breadcrumbs {
-            if let Ok(text) = breadcrumb.text().await {
-                let trimmed = text.trim();
-                if !trimmed.is_empty() {
-                    path_parts.push(trimmed.to_string());
-                }
-            }
-        }
-        if !path_parts.is_empty() {
-            return Ok(path_parts.join(" > "));
-        }
+) -> Result<Vec<TreeItem>> {
+    println!("  Traversing folder {folder_id} down to depth {max_depth}");
+    let driver = &spider.driver;
+    let mut traversal = Traversal::new(spider, folder_id)
+        .with_depth(max_depth)
+        .with_children(move |id| async move { recorded_get_children(driver, &id).await });
+
+    let mut descendants = Vec::new();
+    while let Some(child) = traversal.next().await {
+        println!("    Added child: {}", child.id);
+        descendants.push(child);
     }
-
-    if let Ok(title) = driver.title().await {
-        return Ok(title);
+    if spider.cancel.is_cancelled() {
+        println!("  Traversal cancelled in folder: {folder_id}");
     }
 
-    Ok("Unknown Path".to_string())
-}
-
-fn extract_youtube_video_id(url: &str) -> Option<String> {
-    // Extract video ID from YouTube embed URL like: https://www.youtube.com/embed/H7WzSiZOauA?wmode=transparent&hl=de&rel=0
-    if let Some(start) = url.find("/embed/") {
-        let after_embed = &url[start + 7..];
-        if let Some(end) = after_embed.find('?') {
-            Some(after_embed[..end].to_string())
-        } else {
-            Some(after_embed.to_string())
+    for (id, e) in traversal.failures() {
+        if id == folder_id {
+            anyhow::bail!("Could not read the children of {folder_id}: {e}");
         }
+        println!("    Failed to get children for {id}: {e}");
     }
-    // Extract video ID from YouTube watch URL like: https://www.youtube.com/watch?v=HGk7PYSJEsM
-    else if let Some(start) = url.find("watch?v=") {
-        let after_v = &url[start + 8..];
-        if let Some(end) = after_v.find('&') {
-            Some(after_v[..end].to_string())
-        } else {
-            Some(after_v.to_string())
+    for (id, node) in traversal.file_tree().nodes {
+        if let Some(parent) = &node.parent {
+            tree.insert_child(parent, &id);
         }
-    } else {
-        None
     }
+
+    println!(
+        "  Found {} total descendants for folder: {}",
+        descendants.len(),
+        folder_id
+    );
+    Ok(descendants)
 }
 
 async fn navigate_to_node(driver: &WebDriver, node_id: &str) -> Result<()> {
     println!("  Clicking treeitem to load content...");
     find_and_click_folder(driver, node_id).await?;
     println!("  Waiting for page to load after click...");
-    support::sleep(Duration::from_secs(5)).await;
+    require_page_load(driver).await?;
     Ok(())
 }
 
 async fn find_content_context(driver: &WebDriver) -> Result<bool> {
     println!("  Looking for content in main context...");
+    let content = &Selectors::get().content;
 
     // Try to find content in main context first
-    let main_containers = driver
-        .find_all(By::Css(".dynamicContent.dynamic-content-container-1"))
-        .await?;
-    let main_articles = driver
-        .find_all(By::Css(
-            "article[data-__neos-fusion-path*='Plan2net.Schrack4students:Tutorials']",
-        ))
-        .await?;
+    let main_containers = selectors::probe_all_first(&driver.handle, "content.container", &content.container).await?;
+    let main_articles = selectors::probe_all_first(&driver.handle, "content.tutorial", &content.tutorial).await?;
 
     if !main_containers.is_empty() || !main_articles.is_empty() {
         println!("  Found content in main context");
@@ -534,14 +1063,8 @@ async fn find_content_context(driver: &WebDriver) -> Result<bool> {
                 println!("  Successfully entered iframe {}", i);
 
                 // Check if content is in this iframe
-                let iframe_containers = driver
-                    .find_all(By::Css(".dynamicContent.dynamic-content-container-1"))
-                    .await?;
-                let iframe_articles = driver
-                    .find_all(By::Css(
-                        "article[data-__neos-fusion-path*='Plan2net.Schrack4students:Tutorials']",
-                    ))
-                    .await?;
+                let iframe_containers = selectors::probe_all_first(&driver.handle, "content.container", &content.container).await?;
+                let iframe_articles = selectors::probe_all_first(&driver.handle, "content.tutorial", &content.tutorial).await?;
 
                 if !iframe_containers.is_empty() || !iframe_articles.is_empty() {
                     println!("  Found content in iframe {}", i);
@@ -564,15 +1087,20 @@ async fn find_content_context(driver: &WebDriver) -> Result<bool> {
 
 async fn extract_external_links(
     container: &WebElement,
+    container_index: usize,
     node_id: &str,
     breadcrumb_path: &str,
 ) -> Result<Vec<ContentEntry>> {
     let mut entries = Vec::new();
 
     println!("    Looking for divs containing ExternalLinks paragraphs...");
-    let link_container_divs = container
-        .find_all(By::Css("div[data-__neos-fusion-path*='ExternalLinks']"))
-        .await?;
+    let links = &Selectors::get().content.external_link;
+    let link_container_divs = metrics::timed(
+        "find_all",
+        "content.external_link",
+        selectors::probe_all_first(container, "content.external_link", links),
+    )
+    .await?;
 
     println!(
         "    Found {} divs with ExternalLinks in fusion path",
@@ -589,83 +1117,35 @@ async fn extract_external_links(
             source_node: node_id.to_string(),
             breadcrumb_path: breadcrumb_path.to_string(),
             content_type: "ExternalLink".to_string(),
-            url: String::new(),
-            title: String::new(),
-            author: String::new(),
-            file_type: String::new(),
-            size: String::new(),
-            url_valid: String::new(),
+            container_index: container_index.to_string(),
+            fusion_path: fusion_path(item).await,
+            ..Default::default()
         };
 
-        // Extract URL
-        println!("      Looking for URL...");
-        if let Ok(url_element) = item.query(By::Css("p[property='typo3:url']")).first().await {
-            if let Ok(url) = url_element.text().await {
-                entry.url = url.trim().to_string();
-                println!("      Found URL: {}", entry.url);
-            }
-        } else {
-            println!("      No URL element found");
-        }
-
-        // Extract Title
-        println!("      Looking for Title...");
-        if let Ok(title_element) = item
-            .query(By::Css("p[property='typo3:title']"))
-            .first()
-            .await
-        {
-            if let Ok(title) = title_element.text().await {
-                entry.title = title.trim().to_string();
-                println!("      Found Title: {}", entry.title);
-            }
-        } else {
-            println!("      No Title element found");
-        }
-
-        // Extract Author
-        println!("      Looking for Author...");
-        if let Ok(author_element) = item
-            .query(By::Css("p[property='typo3:author']"))
-            .first()
-            .await
-        {
-            if let Ok(author) = author_element.text().await {
-                entry.author = author.trim().to_string();
-                println!("      Found Author: {}", entry.author);
-            }
-        } else {
-            println!("      No Author element found");
-        }
-
-        // Extract Type
-        println!("      Looking for Type...");
-        if let Ok(type_element) = item
-            .query(By::Css("p[property='typo3:type']"))
-            .first()
-            .await
-        {
-            if let Ok(file_type) = type_element.text().await {
-                entry.file_type = file_type.trim().to_string();
-                println!("      Found Type: {}", entry.file_type);
+        // Whether any of the expected fields exists at all, empty or not
+        let mut matched = false;
+        let content = &Selectors::get().content;
+        let fields = [
+            ("URL", "content.url", &content.url, &mut entry.url),
+            ("Title", "content.title", &content.title, &mut entry.title),
+            ("Author", "content.author", &content.author, &mut entry.author),
+            ("Type", "content.type", &content.file_type, &mut entry.file_type),
+            ("Size", "content.size", &content.size, &mut entry.size),
+        ];
+        for (label, name, candidates, value) in fields {
+            println!("      Looking for {label}...");
+            match field_text(item, name, candidates).await {
+                Some(text) => {
+                    matched = true;
+                    *value = text;
+                    println!("      Found {label}: {value}");
+                }
+                None => println!("      No {label} element found"),
             }
-        } else {
-            println!("      No Type element found");
         }
 
-        // Extract Size
-        println!("      Looking for Size...");
-        if let Ok(size_element) = item
-            .query(By::Css("p[property='typo3:size']"))
-            .first()
-            .await
-        {
-            if let Ok(size) = size_element.text().await {
-                entry.size = size.trim().to_string();
-                println!("      Found Size: {}", entry.size);
-            }
-        } else {
-            println!("      No Size element found");
+        if !matched {
+            selectors::record_empty_container(node_id, "ExternalLinks");
         }
 
         // URL validation will happen later in batch
@@ -682,15 +1162,20 @@ async fn extract_external_links(
 
 async fn extract_youtube_content(
     container: &WebElement,
+    container_index: usize,
     node_id: &str,
     breadcrumb_path: &str,
 ) -> Result<Vec<ContentEntry>> {
     let mut entries = Vec::new();
 
     println!("    Looking for YouTube content...");
-    let youtube_container_divs = container
-        .find_all(By::Css("div[data-__neos-fusion-path*='YouTube']"))
-        .await?;
+    let youtube = &Selectors::get().content.youtube;
+    let youtube_container_divs = metrics::timed(
+        "find_all",
+        "content.youtube",
+        selectors::probe_all_first(container, "content.youtube", youtube),
+    )
+    .await?;
 
     println!(
         "    Found {} divs with YouTube in fusion path",
@@ -707,28 +1192,31 @@ async fn extract_youtube_content(
             source_node: node_id.to_string(),
             breadcrumb_path: breadcrumb_path.to_string(),
             content_type: "YouTube".to_string(),
-            url: String::new(),
-            title: String::new(),
-            author: String::new(),
             file_type: "video".to_string(),
-            size: String::new(),
-            url_valid: String::new(),
+            container_index: container_index.to_string(),
+            fusion_path: fusion_path(item).await,
+            ..Default::default()
         };
 
         // Extract YouTube URL from iframe src
         println!("      Looking for YouTube iframe...");
-        if let Ok(iframe_element) = item.query(By::Css("iframe")).first().await {
+        let player = &Selectors::get().content.player;
+        if let Ok(iframe_element) = selectors::probe_first(item, "content.player", player).await {
             if let Ok(Some(url)) = iframe_element.attr("src").await {
                 entry.url = url.trim().to_string();
                 println!("      Found YouTube URL: {}", entry.url);
 
                 // Extract video ID from YouTube URL for title
-                if let Some(video_id) = extract_youtube_video_id(&entry.url) {
+                if let Some(video_id) = youtube::video_id(&entry.url) {
                     entry.title = format!("YouTube Video ({})", video_id);
                 }
             }
+
+            let status = check_embed_visual(&iframe_element).await;
+            entry.visual_check = status.as_str().to_string();
         } else {
             println!("      No YouTube iframe found");
+            selectors::record_empty_container(node_id, "YouTube");
         }
 
         // URL validation will happen later in batch
@@ -743,19 +1231,33 @@ async fn extract_youtube_content(
     Ok(entries)
 }
 
+/// Trimmed text of the element below `item` matched by the logical selector `name`, or `None`
+/// if nothing matches.
+async fn field_text(item: &WebElement, name: &str, candidates: &[String]) -> Option<String> {
+    let element = selectors::probe_first(item, name, candidates).await.ok()?;
+    Some(element.text().await.ok()?.trim().to_string())
+}
+
+/// The raw `data-__neos-fusion-path` of a content element, or empty if it has none.
+async fn fusion_path(element: &WebElement) -> String {
+    element
+        .attr("data-__neos-fusion-path")
+        .await
+        .ok()
+        .flatten()
+        .unwrap_or_default()
+}
+
 async fn extract_tutorial_content(
-    driver: &WebDriver,
+    scope: &WebElement,
     node_id: &str,
     breadcrumb_path: &str,
 ) -> Result<Vec<ContentEntry>> {
     let mut entries = Vec::new();
 
     println!("    Looking for Tutorial content...");
-    let tutorial_articles = driver
-        .find_all(By::Css(
-            "article[data-__neos-fusion-path*='Plan2net.Schrack4students:Tutorials']",
-        ))
-        .await?;
+    let tutorials = &Selectors::get().content.tutorial;
+    let tutorial_articles = selectors::probe_all_first(scope, "content.tutorial", tutorials).await?;
 
     println!("    Found {} tutorial articles", tutorial_articles.len());
 
@@ -769,21 +1271,17 @@ async fn extract_tutorial_content(
             source_node: node_id.to_string(),
             breadcrumb_path: breadcrumb_path.to_string(),
             content_type: "Tutorial".to_string(),
-            url: String::new(),
-            title: String::new(),
-            author: String::new(),
             file_type: "video".to_string(),
-            size: String::new(),
-            url_valid: String::new(),
+            fusion_path: fusion_path(article).await,
+            ..Default::default()
         };
 
-        // Extract YouTube URL from div[property='typo3:videoUrl']
+        // Extract YouTube URL from the tutorial's video URL property
         println!("      Looking for video URL...");
-        if let Ok(url_div) = article
-            .query(By::Css("div[property='typo3:videoUrl']"))
-            .first()
-            .await
-        {
+        let content = &Selectors::get().content;
+        let mut matched = false;
+        if let Ok(url_div) = selectors::probe_first(article, "content.video_url", &content.video_url).await {
+            matched = true;
             // First try to get text directly from the div
             if let Ok(url_text) = url_div.text().await {
                 let url = url_text.trim().to_string();
@@ -792,7 +1290,7 @@ async fn extract_tutorial_content(
                     println!("      Found URL: {}", entry.url);
 
                     // Extract video ID from YouTube URL for title
-                    if let Some(video_id) = extract_youtube_video_id(&entry.url) {
+                    if let Some(video_id) = youtube::video_id(&entry.url) {
                         entry.title = format!("YouTube Tutorial ({})", video_id);
                     }
                 }
@@ -801,6 +1299,16 @@ async fn extract_tutorial_content(
             println!("      No video URL element found");
         }
 
+        // Check the rendered player, if the tutorial embeds one
+        if let Ok(player) = selectors::probe_first(article, "content.player", &content.player).await {
+            matched = true;
+            let status = check_embed_visual(&player).await;
+            entry.visual_check = status.as_str().to_string();
+        }
+        if !matched {
+            selectors::record_empty_container(node_id, "Tutorial");
+        }
+
         // URL validation will happen later in batch
         if !entry.url.is_empty() {
             entry.url_valid = String::new();
@@ -811,121 +1319,683 @@ async fn extract_tutorial_content(
     Ok(entries)
 }
 
-async fn validate_url(url: &str) -> String {
-    if url.is_empty() {
-        return "N/A".to_string();
+/// Check whether an embedded iframe or video is actually visible to readers
+async fn check_embed_visual(element: &WebElement) -> EmbedStatus {
+    let rect = match element.rect().await {
+        Ok(rect) => rect,
+        Err(_) => return EmbedStatus::Unknown,
+    };
+
+    if checks::has_zero_size(rect.width, rect.height) {
+        println!(
+            "      ⚠ Embed rendered with zero size ({}x{})",
+            rect.width, rect.height
+        );
+        return EmbedStatus::ZeroSize;
     }
 
-    let client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(10))
-        .build()
-        .unwrap_or_else(|_| reqwest::Client::new());
+    if element.scroll_into_view().await.is_err() {
+        return EmbedStatus::Unknown;
+    }
 
-    match client.head(url).send().await {
-        Ok(response) => {
-            let status = response.status().as_u16();
-            if response.status().is_success() {
-                "Valid".to_string()
-            } else if response.status().is_redirection() {
-                "Redirect".to_string()
-            } else {
-                format!("Error {status}")
-            }
-        }
-        Err(_) => {
-            "Invalid".to_string()
+    let status = match element.screenshot_as_png().await {
+        Ok(png) => match checks::looks_like_error_thumbnail(&png) {
+            Ok(true) => EmbedStatus::ErrorThumbnail,
+            Ok(false) => EmbedStatus::Ok,
+            Err(_) => EmbedStatus::Unknown,
+        },
+        Err(_) => EmbedStatus::Unknown,
+    };
+
+    if status.is_broken() {
+        println!("      ⚠ Embed shows the provider's error thumbnail");
+    }
+
+    status
+}
+
+/// The wait and retry timeouts of this run
+fn timeouts() -> Timeouts {
+    TIMEOUTS.get().copied().unwrap_or_default()
+}
+
+/// Compare an ExternalLink's visible title against the target domain and the title of the page
+/// at `target`, where `url` leads
+async fn check_link_title(title: &str, url: &str, target: &str, validator: &Validator) -> String {
+    let page_title = validator.page_title(target).await;
+
+    match checks::title_matches_target(title, url, page_title.as_deref()) {
+        Some(true) => "Match".to_string(),
+        Some(false) => {
+            println!(
+                "  ⚠ Title mismatch: '{}' -> {} ({})",
+                title,
+                url,
+                page_title.as_deref().unwrap_or("no page title")
+            );
+            "Mismatch".to_string()
         }
+        None => "Unclear".to_string(),
+    }
+}
+
+/// Validate an entry's URL and, for ExternalLinks, compare its title with the target
+async fn validate_entry(entry: &mut ContentEntry, validator: &Validator) {
+    let check = validator.check(&entry.url).await;
+    let target = if check.final_url.is_empty() { entry.url.clone() } else { check.final_url.clone() };
+    entry.url_valid = check.status;
+    entry.url_method = check.method;
+    entry.final_url = check.final_url;
+    entry.redirect_hops = check.redirect_hops.to_string();
+    entry.wayback_url = check.wayback_url;
+
+    // Only ExternalLinks carry an editor-written title worth comparing
+    let reachable = entry.url_valid == "Valid" || entry.url_valid == "Redirect";
+    if entry.content_type == "ExternalLink" && reachable {
+        entry.title_check = check_link_title(&entry.title, &entry.url, &target, validator).await;
     }
 }
 
-/// Validate URLs concurrently in batches
-async fn validate_urls_concurrent(entries: &mut [ContentEntry], concurrency: usize) {
-    let total_urls = entries.len();
-    println!("Validating {} URLs with concurrency {}...", total_urls, concurrency);
+/// Run the content checks and, given a validator, URL validation on one entry.
+/// Returns whether the entry was flagged as outdated.
+async fn check_entry(
+    entry: &mut ContentEntry,
+    validator: Option<&Validator>,
+    current_year: i32,
+    max_content_age_years: i32,
+) -> bool {
+    // Non-breaking spaces and soft hyphens from the editor would make equal entries differ
+    entry.normalize();
+
+    // Flag entries that reference outdated years for the annual refresh
+    let flag = checks::age_flag(&entry.title, &entry.url, current_year, max_content_age_years);
+    let outdated = flag.is_some();
+    if let Some(flag) = flag {
+        entry.age_flag = flag;
+    }
 
-    let urls_with_indices: Vec<(usize, String)> = entries
-        .iter()
-        .enumerate()
-        .map(|(i, e)| (i, e.url.clone()))
-        .collect();
+    if let Some(validator) = validator {
+        validate_entry(entry, validator).await;
+    } else {
+        entry.url_valid = "Skipped".to_string();
+    }
+    outdated
+}
 
-    let validated: Vec<(usize, String)> = stream::iter(urls_with_indices)
-        .map(|(idx, url)| async move {
-            let result = validate_url(&url).await;
-            if (idx + 1) % 10 == 0 {
-                println!("  Validated {}/{} URLs", idx + 1, total_urls);
+/// Pipeline stage: run content checks and URL validation on each extracted entry.
+/// Up to `validation_concurrency` entries are validated at once, so the network waits overlap
+/// with each other and with the browser working on the next nodes; entries keep their order.
+/// A URL linked from several entries is only requested once.
+/// Once `cancel` is cancelled, the remaining entries are passed on without URL validation.
+/// Returns the number of entries flagged as outdated.
+async fn check_entries(
+    entries: mpsc::Receiver<ContentEntry>,
+    checked: mpsc::Sender<ContentEntry>,
+    validate_urls: bool,
+    config: ChecksConfig,
+    cancel: CancellationToken,
+) -> Result<usize> {
+    let current_year = Local::now().year();
+    let mut outdated = 0;
+    let mut validated = 0;
+
+    let received = Box::pin(stream::unfold(entries, |mut entries| async move {
+        entries.recv().await.map(|entry| (entry, entries))
+    }));
+    let max_age = config.max_content_age_years;
+    let validator = Validator::with_cache(&config).await;
+    let mut results = received
+        .map(|mut entry| {
+            let validator = (validate_urls && !cancel.is_cancelled()).then_some(&validator);
+            async move {
+                let flagged = check_entry(&mut entry, validator, current_year, max_age).await;
+                (entry, flagged)
             }
-            (idx, result)
         })
-        .buffer_unordered(concurrency)
-        .collect()
-        .await;
+        .buffered(config.validation_concurrency.max(1));
+
+    while let Some((entry, flagged)) = results.next().await {
+        if flagged {
+            outdated += 1;
+        }
+        if validate_urls {
+            validated += 1;
+            if validated % 10 == 0 {
+                println!("  Validated {validated} URLs");
+            }
+        }
 
-    // Update entries with validation results
-    for (idx, validation_result) in validated {
-        entries[idx].url_valid = validation_result;
+        checked
+            .send(entry)
+            .await
+            .context("Output writer stopped unexpectedly")?;
     }
 
-    println!("URL validation complete!");
+    if let Err(e) = validator.save().await {
+        println!("⚠ {e:#}");
+    }
+    Ok(outdated)
 }
 
-/// Wait for page content to load (matches Spider::wait_content_load)
-async fn wait_for_page_load(driver: &WebDriver, timeout: Duration) -> Result<()> {
-    let start = Instant::now();
+/// Pipeline stage: hand each checked entry to the output sinks as it arrives.
+/// The sinks are flushed according to `policy` so a crash loses at most the last few rows.
+/// With `sort_rows`, the entries are collected and written in sorted order at the end instead.
+/// Returns the number of entries written and the sinks, which still get the run summary.
+async fn write_entries(
+    mut entries: mpsc::Receiver<ContentEntry>,
+    mut sink: MultiSink,
+    policy: OutputConfig,
+) -> Result<(usize, MultiSink)> {
+    let mut written = 0;
+    let mut unflushed = 0;
+    let mut last_flush = Instant::now();
+
+    if policy.sort_rows {
+        let mut sorted = EntryBuffer::new(policy.memory_limit());
+        while let Some(entry) = entries.recv().await {
+            sorted.push(ReportEntry::from(&entry))?;
+        }
+        sorted.sort();
+        sorted.for_each(|entry| sink.write_entry(entry))?;
+        sink.flush()?;
+        return Ok((sorted.len(), sink));
+    }
 
-    loop {
-        // Check for loading indicators (same as Spider::wait_content_load)
-        let loading_bars = driver
-            .find_all(By::Css(".style__loadingIndicator__container___1yhsy"))
-            .await?;
+    while let Some(entry) = entries.recv().await {
+        sink.write_entry(&ReportEntry::from(&entry))?;
+        written += 1;
+        unflushed += 1;
 
-        if loading_bars.is_empty() {
-            return Ok(());
+        if unflushed >= policy.flush_every_entries || last_flush.elapsed() >= policy.flush_interval() {
+            sink.flush()?;
+            unflushed = 0;
+            last_flush = Instant::now();
         }
+    }
 
-        if start.elapsed() > timeout {
-            // Timeout is OK - just proceed
-            return Ok(());
+    sink.flush()?;
+    Ok((written, sink))
+}
+
+/// Look for signs that the CMS is overloaded after processing a node
+async fn detect_overload(
+    driver: &WebDriver,
+    elapsed: Duration,
+    config: &BackoffConfig,
+) -> Option<OverloadSignal> {
+    if let Ok(title) = driver.title().await {
+        if let Some(status) = backoff::gateway_error_status(&title) {
+            return Some(OverloadSignal::GatewayError(status));
         }
+    }
+
+    let error_screen = &Selectors::get().backend.error_screen;
+    let overlay = selectors::probe_first(&driver.handle, "backend.error_screen", error_screen).await;
+    if overlay.is_ok() {
+        return Some(OverloadSignal::ServerErrorOverlay);
+    }
 
-        support::sleep(Duration::from_millis(500)).await;
+    if elapsed > config.slow_response() {
+        return Some(OverloadSignal::SlowResponse(elapsed));
     }
+
+    None
 }
 
-async fn extract_content_from_page(
-    driver: &WebDriver,
-    node_id: &str,
-) -> Result<Vec<ContentEntry>> {
-    println!("  Extracting content from treeitem: {node_id}");
+/// Adjust the backoff after a node and wait before the next one
+async fn pace_next_node(driver: &WebDriver, backoff: &mut Backoff, elapsed: Duration, config: &BackoffConfig) {
+    match detect_overload(driver, elapsed, config).await {
+        Some(signal) => {
+            let delay = backoff.on_overload();
+            println!(
+                "⚠ CMS overload detected ({signal}), backing off for {:.1}s",
+                delay.as_secs_f32()
+            );
+            support::sleep(delay).await;
+        }
+        None => {
+            backoff.on_success();
+            support::sleep(backoff.delay()).await;
+        }
+    }
+}
 
-    println!("  Clicking treeitem to load content...");
+/// A shortcut node and the target it redirects to
+struct Shortcut {
+    node_id: String,
+    label: String,
+    target_url: String,
+    target_label: String,
+}
 
-    // Use retry wrapper to handle relogin dialogs
+/// Open a shortcut node and read its target from the placeholder page
+async fn resolve_shortcut(driver: &WebDriver, item: &TreeItem) -> Result<Shortcut> {
     retry_with_relogin(driver, || async {
-        find_and_click_folder(driver, node_id).await
+        find_and_click_folder(driver, &item.id).await
     }, 3).await?;
+    require_page_load(driver).await?;
+
+    let in_iframe = enter_content_frame(driver).await?;
+    let candidates = &Selectors::get().content.shortcut_target;
+    let target = selectors::find_first(&driver.handle, "content.shortcut_target", candidates).await;
+    let result = match target {
+        Ok(link) => Ok(Shortcut {
+            node_id: item.id.clone(),
+            label: item.label.clone(),
+            target_url: link.attr("href").await?.unwrap_or_default(),
+            target_label: link.text().await?.trim().to_string(),
+        }),
+        Err(e) => Err(e.context("Shortcut page shows no target link")),
+    };
 
-    println!("  Waiting for page to load...");
-    // Wait for loading indicators to disappear (no hardcoded delays)
-    wait_for_page_load(driver, Duration::from_secs(30)).await?;
+    if in_iframe {
+        let _ = driver.enter_default_frame().await;
+    }
+    result
+}
 
-    // Find content context (main page or iframe)
-    let in_iframe = find_content_context(driver).await?;
+/// Switch the backend to another content dimension (e.g. language)
+async fn switch_dimension(driver: &WebDriver, value: &str) -> Result<()> {
+    let dimensions = &Selectors::get().dimensions;
 
-    // Extract breadcrumb path
-    let breadcrumb_path = extract_breadcrumb_path(driver)
+    let switcher = selectors::find_first(&driver.handle, "dimensions.switcher", &dimensions.switcher)
         .await
-        .unwrap_or_else(|_| "Unknown Path".to_string());
-    println!("  Breadcrumb path: {breadcrumb_path}");
+        .context("Could not find the dimension switcher")?;
+    switcher.click().await?;
+
+    let candidates = selectors::with_value(&dimensions.option, value);
+    let option = selectors::find_first(&driver.handle, "dimensions.option", &candidates)
+        .await
+        .with_context(|| format!("The dimension switcher does not offer {value}"))?;
+    option.click().await?;
+
+    if let Ok(apply) = selectors::probe_first(&driver.handle, "dimensions.apply", &dimensions.apply).await {
+        apply.click().await?;
+    }
+
+    // The loading indicator only shows once the backend starts switching
+    support::sleep(timeouts().retry / 2).await;
+    require_page_load(driver).await?;
+    Ok(())
+}
+
+/// Extract the other dimension variants of the open node, then switch back to the first dimension.
+/// Returns the variant entries and whether the node exists in each dimension.
+async fn extract_variants(
+    driver: &WebDriver,
+    node_id: &str,
+    label: &str,
+    dimensions: &DimensionsConfig,
+) -> Result<(Vec<ContentEntry>, Vec<VariantCoverage>)> {
+    let result = collect_variants(driver, node_id, label, dimensions).await;
+    switch_dimension(driver, &dimensions.values[0])
+        .await
+        .context("Could not switch back to the first dimension")?;
+    result
+}
+
+async fn collect_variants(
+    driver: &WebDriver,
+    node_id: &str,
+    label: &str,
+    dimensions: &DimensionsConfig,
+) -> Result<(Vec<ContentEntry>, Vec<VariantCoverage>)> {
+    let base_path = dimensions::node_path(driver.current_url().await?.as_str())
+        .context("Could not read the node path from the backend URL")?;
 
     let mut entries = Vec::new();
+    let mut coverage = Vec::new();
+    for value in dimensions.values.iter().skip(1) {
+        switch_dimension(driver, value).await?;
+
+        // Neos falls back to another document if the node does not exist in this dimension
+        let path = dimensions::node_path(driver.current_url().await?.as_str());
+        let present = path.as_deref() == Some(base_path.as_str());
+        if present {
+            let mut variant = extract_loaded_page(driver, node_id).await?;
+            for entry in &mut variant {
+                entry.dimension = value.clone();
+            }
+            println!("  Found {} entries in the {value} variant", variant.len());
+            entries.extend(variant);
+        } else {
+            println!("  ⚠ {label} has no {value} variant");
+        }
 
-    // Look for dynamic content containers
-    let dynamic_containers = driver
-        .find_all(By::Css(".dynamicContent.dynamic-content-container-1"))
-        .await?;
+        coverage.push(VariantCoverage {
+            node_id: node_id.to_string(),
+            label: label.to_string(),
+            dimension: value.clone(),
+            present,
+        });
+    }
+
+    Ok((entries, coverage))
+}
+
+fn write_coverage(path: &Path, coverage: &[VariantCoverage]) -> Result<()> {
+    let mut writer = Writer::from_path(path).context("Failed to create translation coverage file")?;
+    writer.write_record(["Node", "Label", "Dimension", "Status"])?;
+    for variant in coverage {
+        let status = if variant.present { "Present" } else { "Missing" };
+        writer.write_record([&variant.node_id, &variant.label, &variant.dimension, status])?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Enter the Neos content iframe if the page has one
+async fn enter_content_frame(driver: &WebDriver) -> Result<bool> {
+    // Not every page has one, so no match is not a miss
+    let frame = &Selectors::get().content.frame;
+    match selectors::probe_all_first(&driver.handle, "content.frame", frame).await?.into_iter().next() {
+        Some(iframe) => {
+            iframe.enter_frame().await?;
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+fn write_shortcuts(path: &Path, shortcuts: &[Shortcut]) -> Result<()> {
+    let mut writer = Writer::from_path(path).context("Failed to create shortcut CSV file")?;
+    writer.write_record(["Shortcut Node", "Label", "Target URL", "Target Label"])?;
+    for shortcut in shortcuts {
+        writer.write_record([
+            &shortcut.node_id,
+            &shortcut.label,
+            &shortcut.target_url,
+            &shortcut.target_label,
+        ])?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Wait for the loading indicators to disappear. Returns whether they did within `timeout`.
+async fn wait_for_page_load(driver: &WebDriver, timeout: Duration) -> Result<bool> {
+    let loading = &Selectors::get().tree.loading_indicator;
+    Ok(wait::wait_until_gone(&driver.handle, loading, timeout).await)
+}
+
+/// Wait for the page to load before it is read. Fails if it is still loading after the page
+/// load timeout, so a half-loaded page is not taken for the node's content.
+async fn require_page_load(driver: &WebDriver) -> Result<()> {
+    let timeout = timeouts().page_load;
+    if !wait_for_page_load(driver, timeout).await? {
+        anyhow::bail!("The page was still loading after {}s", timeout.as_secs());
+    }
+    Ok(())
+}
+
+async fn extract_content_from_page(
+    driver: &WebDriver,
+    node_id: &str,
+    recovery: &RecoveryConfig,
+) -> Result<Vec<ContentEntry>> {
+    println!("  Extracting content from treeitem: {node_id}");
+
+    println!("  Clicking treeitem to load content...");
+
+    // Use retry wrapper to handle relogin dialogs
+    retry_with_relogin(driver, || async {
+        find_and_click_folder(driver, node_id).await
+    }, 3).await?;
+
+    println!("  Waiting for page to load...");
+    // Wait for loading indicators to disappear (no hardcoded delays)
+    wait_for_node_load(driver, recovery, || reopen_node(driver, node_id)).await?;
+
+    extract_loaded_page(driver, node_id).await
+}
+
+/// Wait for the page of the opened node to load. The backend sometimes keeps its loading overlay
+/// up for good; once it stays longer than the spinner timeout, `reopen` reloads the page and
+/// opens the node again and the wait goes on, up to `max_refreshes` times.
+async fn wait_for_node_load<F, Fut>(
+    driver: &WebDriver,
+    recovery: &RecoveryConfig,
+    mut reopen: F,
+) -> Result<()>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = Result<()>>,
+{
+    let mut refreshes = 0;
+    while !wait_for_page_load(driver, recovery.spinner_timeout()).await? {
+        if refreshes == recovery.max_refreshes {
+            anyhow::bail!("The loading overlay did not clear after {refreshes} page refreshes");
+        }
+        refreshes += 1;
+        println!(
+            "⚠ Loading overlay stuck for over {}s, refreshing the page ({refreshes}/{})...",
+            recovery.spinner_timeout_secs, recovery.max_refreshes
+        );
+        reopen().await?;
+    }
+
+    if refreshes > 0 {
+        println!("✓ Recovered from the stuck loading overlay");
+    }
+    Ok(())
+}
+
+/// Reload the backend page and click `node_id` in the document tree again
+async fn reopen_node(driver: &WebDriver, node_id: &str) -> Result<()> {
+    reload_backend(driver).await?;
+    retry_with_relogin(driver, || async {
+        find_and_click_folder(driver, node_id).await
+    }, 3).await
+}
+
+/// Reload the backend page and wait until it is usable again
+async fn reload_backend(driver: &WebDriver) -> Result<()> {
+    driver.refresh().await?;
+    spider::forget_expanded();
+    // The whole backend, tree included, is rendered again after the reload
+    wait_for_backend(driver).await
+}
+
+/// Wait until the backend shows the document tree and no loading indicator
+async fn wait_for_backend(driver: &WebDriver) -> Result<()> {
+    let tree = &Selectors::get().tree;
+    wait::wait_for_element(&driver.handle, "tree.root", &tree.root, timeouts().page_load).await?;
+    // The tree is usable; the page of each node opened next is waited for on its own
+    if !wait_for_page_load(driver, timeouts().page_load).await? {
+        println!("  ⚠ The backend was still loading after {}s", timeouts().page_load.as_secs());
+    }
+    Ok(())
+}
+
+/// Reload the backend page and expand the document tree down to `node_id` again, along the
+/// ancestry recorded in `tree`
+async fn restore_tree_path(driver: &WebDriver, tree: &FileTree, node_id: &str) -> Result<()> {
+    reload_backend(driver).await?;
+    for ancestor in tree.ancestors(node_id) {
+        expand_folder_if_needed(driver, &ancestor).await?;
+    }
+    Ok(())
+}
+
+/// Whether an error comes from elements that vanished or went stale, i.e. the tree or page was
+/// re-rendered under us
+fn is_dom_drift(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        cause.is::<NoMatch>()
+            || matches!(
+                cause.downcast_ref::<WebDriverError>().map(|error| &**error),
+                Some(
+                    WebDriverErrorInner::NoSuchElement(..)
+                        | WebDriverErrorInner::StaleElementReference(..)
+                )
+            )
+    })
+}
+
+/// Store the loaded node page, or its content region, in the snapshot store to diff it against
+/// later audits
+async fn save_snapshot(driver: &WebDriver, node_id: &str, format: SnapshotFormat, region: Option<&str>) {
+    let property = match format {
+        SnapshotFormat::Html if region.is_none() => None,
+        SnapshotFormat::Html => Some("outerHTML"),
+        SnapshotFormat::Text => Some("innerText"),
+    };
+    let content = match property {
+        None => driver.source().await,
+        Some(property) => {
+            let script = format!(
+                "const root = arguments[0] ? document.querySelector(arguments[0]) : document.body;\n\
+                 return root.{property};"
+            );
+            match driver.execute(&script, vec![serde_json::json!(region)]).await {
+                Ok(ret) => ret.convert::<String>(),
+                Err(e) => Err(e),
+            }
+        }
+    };
+    let saved = content
+        .map_err(anyhow::Error::from)
+        .and_then(|content| snapshot::save(node_id, &content));
+    if let Err(e) = saved {
+        println!("  ⚠ Could not store a snapshot of {node_id}: {e:#}");
+    }
+}
+
+/// Save the source of the document the driver is in, when extracting `node_id` failed or found
+/// no content, to `[extraction] page_dumps_dir`. The reason heads the file as a comment.
+async fn dump_page_source(driver: &WebDriver, node_id: &str, reason: &str) {
+    let extraction = EXTRACTION.get().cloned().unwrap_or_default();
+    if !extraction.dump_failed_pages {
+        return;
+    }
+    let dir = &extraction.page_dumps_dir;
+    let path = dir.join(format!("{node_id}-{}.html", Local::now().format("%Y%m%d-%H%M%S")));
+    let url = driver.current_url().await.map(|url| url.to_string()).unwrap_or_default();
+    let dumped = match driver.source().await {
+        Ok(source) => {
+            // "--" would end the comment early
+            let comment = format!("{node_id} at {url}: {reason}").replace("--", "- -");
+            fs::create_dir_all(dir)
+                .and_then(|()| fs::write(&path, format!("<!-- {comment} -->\n{source}")))
+                .map_err(anyhow::Error::from)
+        }
+        Err(e) => Err(e.into()),
+    };
+    match dumped {
+        Ok(()) => println!("  Saved the page source of {node_id} to {}", path.display()),
+        Err(e) => println!("  ⚠ Could not save the page source of {node_id}: {e:#}"),
+    }
+}
+
+/// The CSS scope extraction is restricted to on the page of `node_id`, by its node type
+async fn content_region(driver: &WebDriver, node_id: &str) -> Option<String> {
+    let extraction = EXTRACTION.get()?;
+    // Reading the tree item costs a few round trips, so only when node types differ
+    let icon = if extraction.regions.is_empty() {
+        None
+    } else {
+        match find_treeitem_element(driver, node_id).await {
+            Ok(element) => TreeItem::from_element(&element).await.ok().and_then(|item| item.icon),
+            Err(_) => None,
+        }
+    };
+    extraction.region_for(icon.as_deref()).map(str::to_string)
+}
+
+/// Count a retry against the logical selector that matched nothing in `error`, if any
+fn record_selector_retry(error: &anyhow::Error) {
+    if let Some(no_match) = error.chain().find_map(|cause| cause.downcast_ref::<NoMatch>()) {
+        selectors::record_retry(&no_match.name);
+    }
+}
+
+/// Extract the entries of the node page that is currently loaded
+async fn extract_loaded_page(driver: &WebDriver, node_id: &str) -> Result<Vec<ContentEntry>> {
+    // Read before entering a content iframe, while the URL still shows the node
+    let backend_url = match driver.current_url().await {
+        Ok(url) => dimensions::backend_link(url.as_str()).unwrap_or_default(),
+        Err(_) => String::new(),
+    };
+
+    let mut entries = match read_loaded_page(driver, node_id).await {
+        Ok(entries) => entries,
+        Err(e) => {
+            dump_page_source(driver, node_id, &format!("{e:#}")).await;
+            return Err(e);
+        }
+    };
+    for entry in &mut entries {
+        entry.backend_url = backend_url.clone();
+    }
+    Ok(entries)
+}
+
+async fn read_loaded_page(driver: &WebDriver, node_id: &str) -> Result<Vec<ContentEntry>> {
+    // The node type is read from the tree, so before entering a content iframe
+    let region = content_region(driver, node_id).await;
+
+    // Find content context (main page or iframe)
+    let in_iframe = find_content_context(driver).await?;
+
+    let region = match region {
+        Some(region) => {
+            let found = driver.find_all(By::Css(&region)).await.is_ok_and(|found| !found.is_empty());
+            if found {
+                println!("  Reading content region {region}");
+            } else {
+                println!("  ⚠ Content region {region} not found on {node_id}, reading the whole page");
+            }
+            found.then_some(region)
+        }
+        None => None,
+    };
+    if let Some(format) = snapshot::wanted() {
+        save_snapshot(driver, node_id, format, region.as_deref()).await;
+    }
+
+    // Extract breadcrumb path
+    let breadcrumb_path = spider::breadcrumb_path(driver)
+        .await
+        .unwrap_or_else(|_| "Unknown Path".to_string());
+    println!("  Breadcrumb path: {breadcrumb_path}");
+
+    let extraction = EXTRACTION.get().cloned().unwrap_or_default();
+    if extraction.script {
+        let types = &extraction.types;
+        match extract_with_script(driver, node_id, &breadcrumb_path, region.as_deref(), types).await {
+            Ok(entries) => {
+                if in_iframe {
+                    let _ = driver.enter_default_frame().await;
+                }
+                println!("  Extracted {} entries from {} with one script call", entries.len(), node_id);
+                return Ok(entries);
+            }
+            Err(e) => println!("  Script extraction failed ({e:#}), reading the fields one by one..."),
+        }
+    }
 
-    println!("  Found {} dynamic containers", dynamic_containers.len());
+    let mut entries = Vec::new();
+    let scope = driver.find(By::Css(region.as_deref().unwrap_or(":root"))).await?;
+
+    // Look for dynamic content containers, unless none of their content types is wanted
+    let links = extraction.extracts(ContentType::ExternalLink);
+    let videos = extraction.extracts(ContentType::YouTube);
+    let dynamic_containers = if links || videos {
+        let containers = &Selectors::get().content.container;
+        let found = metrics::timed(
+            "find_all",
+            "content.container",
+            selectors::probe_all_first(&scope, "content.container", containers),
+        )
+        .await?;
+        println!("  Found {} dynamic containers", found.len());
+        if found.is_empty() {
+            dump_page_source(driver, node_id, "no content containers").await;
+        }
+        found
+    } else {
+        Vec::new()
+    };
 
     // Extract content from dynamic containers
     for (i, container) in dynamic_containers.iter().enumerate() {
@@ -935,30 +2005,36 @@ async fn extract_content_from_page(
             dynamic_containers.len()
         );
         container.scroll_into_view().await?;
-        // Reduced from 500ms to 300ms
-        support::sleep(Duration::from_millis(300)).await;
 
         // Extract ExternalLinks - but mark URLs as Pending for batch validation
-        let mut external_links = extract_external_links(container, node_id, &breadcrumb_path).await?;
-        for entry in &mut external_links {
-            entry.url_valid = "Pending".to_string();
+        if links {
+            let mut external_links =
+                extract_external_links(container, i + 1, node_id, &breadcrumb_path).await?;
+            for entry in &mut external_links {
+                entry.url_valid = "Pending".to_string();
+            }
+            entries.extend(external_links);
         }
-        entries.extend(external_links);
 
         // Extract YouTube content - but mark URLs as Pending for batch validation
-        let mut youtube_content = extract_youtube_content(container, node_id, &breadcrumb_path).await?;
-        for entry in &mut youtube_content {
-            entry.url_valid = "Pending".to_string();
+        if videos {
+            let mut youtube_content =
+                extract_youtube_content(container, i + 1, node_id, &breadcrumb_path).await?;
+            for entry in &mut youtube_content {
+                entry.url_valid = "Pending".to_string();
+            }
+            entries.extend(youtube_content);
         }
-        entries.extend(youtube_content);
     }
 
     // Extract Tutorial content (not in dynamic containers) - mark URLs as Pending for batch validation
-    let mut tutorial_content = extract_tutorial_content(driver, node_id, &breadcrumb_path).await?;
-    for entry in &mut tutorial_content {
-        entry.url_valid = "Pending".to_string();
+    if extraction.extracts(ContentType::Tutorial) {
+        let mut tutorial_content = extract_tutorial_content(&scope, node_id, &breadcrumb_path).await?;
+        for entry in &mut tutorial_content {
+            entry.url_valid = "Pending".to_string();
+        }
+        entries.extend(tutorial_content);
     }
-    entries.extend(tutorial_content);
 
     // Exit iframe if we entered one
     if in_iframe {
@@ -969,125 +2045,2153 @@ async fn extract_content_from_page(
     Ok(entries)
 }
 
-/// Load CSV data for tags.
-fn load_csv_data(path: &str) -> Result<HashMap<String, String>> {
-    let mut tags: HashMap<String, String> = HashMap::new();
-    let mut reader = Reader::from_path(path)?;
+/// Read the entries of `types` on the loaded page with one injected script instead of a
+/// WebDriver round trip per field. Only embedded players are still checked one by one.
+async fn extract_with_script(
+    driver: &WebDriver,
+    node_id: &str,
+    breadcrumb_path: &str,
+    region: Option<&str>,
+    types: &[ContentType],
+) -> Result<Vec<ContentEntry>> {
+    let types: Vec<&str> = types.iter().map(|kind| kind.name()).collect();
+    let result = metrics::timed(
+        "execute",
+        "content.entries_script",
+        driver.execute(
+            CONTENT_SCRIPT,
+            vec![
+                serde_json::json!(region),
+                serde_json::json!(Selectors::get().content),
+                serde_json::json!(types),
+            ],
+        ),
+    )
+    .await
+    .context("Could not run the content script")?;
+    let found: Vec<ScriptEntry> = result
+        .convert()
+        .context("Unexpected result from the content script")?;
+    if found.is_empty() {
+        dump_page_source(driver, node_id, "no content found by the content script").await;
+    }
+
+    let mut entries = Vec::new();
+    for found in found {
+        if !found.matched {
+            let kind = if found.kind == "ExternalLink" { "ExternalLinks" } else { found.kind.as_str() };
+            selectors::record_empty_container(node_id, kind);
+        }
+        let marker = found.player.clone();
+        let Some(mut entry) = script_content_entry(found, node_id, breadcrumb_path) else {
+            continue;
+        };
+        if let Some(marker) = marker {
+            let selector = format!("[data-tag-spider-player='{marker}']");
+            if let Ok(player) = driver.query(By::Css(&selector)).nowait().first().await {
+                entry.visual_check = check_embed_visual(&player).await.as_str().to_string();
+            }
+        }
+        entries.push(entry);
+    }
+
+    Ok(entries)
+}
+
+/// The entry for an entry found by the content script on `node_id`, with the title and file
+/// type of videos filled in. `None` if it would be dropped by the field-by-field lookup too:
+/// links need a URL or title, videos a URL.
+fn script_content_entry(
+    found: ScriptEntry,
+    node_id: &str,
+    breadcrumb_path: &str,
+) -> Option<ContentEntry> {
+    let mut entry = ContentEntry {
+        source_node: node_id.to_string(),
+        breadcrumb_path: breadcrumb_path.to_string(),
+        content_type: found.kind,
+        url: found.url,
+        title: found.title,
+        author: found.author,
+        file_type: found.file_type,
+        size: found.size,
+        url_valid: "Pending".to_string(),
+        container_index: found.container_index.map(|i| i.to_string()).unwrap_or_default(),
+        fusion_path: found.fusion_path,
+        ..Default::default()
+    };
 
-    for line in reader.records() {
-        let record = line?;
-        tags.insert(record[0].to_string(), record[1].to_string());
+    if entry.content_type != "ExternalLink" {
+        entry.file_type = "video".to_string();
+        if let Some(video_id) = youtube::video_id(&entry.url) {
+            let kind = if entry.content_type == "YouTube" { "Video" } else { "Tutorial" };
+            entry.title = format!("YouTube {kind} ({video_id})");
+        }
     }
 
-    Ok(tags)
+    let keep = if entry.content_type == "ExternalLink" {
+        !entry.url.is_empty() || !entry.title.is_empty()
+    } else {
+        !entry.url.is_empty()
+    };
+    keep.then_some(entry)
 }
 
-/// Example function to add tags.
-async fn add_tags(clear: bool, driver: &WebDriver) -> Result<()> {
-    let tags = load_csv_data(TAGPATH).unwrap();
-    let iframe = driver
-        .query(By::Css(r#"iframe[name="neos-content-main"]"#))
-        .first()
-        .await?;
-    iframe.clone().enter_frame().await?;
+/// `extract_content_from_page`, recorded as a session action
+async fn recorded_extract(
+    driver: &WebDriver,
+    node_id: &str,
+    recovery: &RecoveryConfig,
+) -> Result<Vec<ContentEntry>> {
+    let action = Action::ExtractNode {
+        node_id: node_id.to_string(),
+    };
+    let started = Instant::now();
+    let result = extract_content_from_page(driver, node_id, recovery).await;
+    record_action(driver, action, started, &result, |entries| entries_summary(entries)).await;
+    result
+}
 
-    let content_collection = driver
-        .query(By::Css(
-            "html body.neos-backend div.container div.neos-contentcollection",
-        ))
-        .first()
-        .await?;
-    let questions = content_collection
-        .find_all(By::Css("p.neos-inline-editable.questionTitle"))
-        .await?;
+fn children_summary(children: &[TreeItem]) -> String {
+    format!("{} children", children.len())
+}
 
-    for question in questions {
-        question.scroll_into_view().await?;
-        let text = question.text().await?;
-        let id = text.split(' ').next().unwrap();
-        let value = tags.get(id).unwrap();
+fn entries_summary(entries: &[ContentEntry]) -> String {
+    format!("{} entries", entries.len())
+}
 
-        question.click().await?;
-        driver.enter_default_frame().await?;
+/// Record a finished action, with a page snapshot if the recorder asks for one
+async fn record_action<T>(
+    driver: &WebDriver,
+    action: Action,
+    started: Instant,
+    result: &Result<T>,
+    summary: impl FnOnce(&T) -> String,
+) {
+    let elapsed = started.elapsed();
+    let page_source = if recorder::wants_snapshot() {
+        page_snapshot(driver, &action).await
+    } else {
+        None
+    };
+    let outcome = match result {
+        Ok(value) => Ok(summary(value)),
+        Err(e) => Err(format!("{e:#}")),
+    };
+
+    if let Err(e) = recorder::record(action, elapsed, outcome, page_source.as_deref()) {
+        println!("⚠ Could not record action: {e}");
+    }
+}
+
+/// Page source for a snapshot. Extraction snapshots capture the frame holding the content.
+async fn page_snapshot(driver: &WebDriver, action: &Action) -> Option<String> {
+    if !matches!(action, Action::ExtractNode { .. }) {
+        return driver.source().await.ok();
+    }
+
+    let in_iframe = find_content_context(driver).await.ok()?;
+    let source = driver.source().await.ok();
+    if in_iframe {
+        let _ = driver.enter_default_frame().await;
+    }
+    source
+}
+
+/// Re-run one recorded action and describe its outcome the same way the recorder did
+async fn replay_action(
+    driver: &WebDriver,
+    action: &Action,
+    options: &ReplayOptions,
+    recovery: &RecoveryConfig,
+    cancel: &CancellationToken,
+) -> Result<String> {
+    let offline = options.offline;
+    match action {
+        Action::Login | Action::AddTags { .. } if offline => Ok("skipped offline".to_string()),
+        Action::Login => login(driver).await.map(|_| "logged in".to_string()),
+        Action::Relogin => {
+            if !auth().detect_expired(driver).await {
+                return Ok("no relogin dialog".to_string());
+            }
+            auth().relogin(driver).await.map(|ok| relogin_summary(ok).to_string())
+        }
+        Action::ExpandFolder { folder_id } => expand_folder_if_needed(driver, folder_id)
+            .await
+            .map(|_| "expanded".to_string()),
+        Action::GetChildren { folder_id } => get_folder_children(driver, folder_id)
+            .await
+            .map(|children| children_summary(&children)),
+        Action::ExtractNode { node_id } if offline => extract_loaded_page(driver, node_id)
+            .await
+            .map(|entries| entries_summary(&entries)),
+        Action::ExtractNode { node_id } => extract_content_from_page(driver, node_id, recovery)
+            .await
+            .map(|entries| entries_summary(&entries)),
+        Action::AddTags { .. } if !options.apply_tags => {
+            Ok("skipped, pass --apply-tags to write the tags".to_string())
+        }
+        Action::AddTags { clear } => add_tags(*clear, driver, cancel)
+            .await
+            .map(|_| "tags applied".to_string()),
+    }
+}
+
+/// How a recorded session is replayed.
+struct ReplayOptions {
+    /// Against the saved page snapshots instead of the live CMS.
+    offline: bool,
+    /// Write recorded tagging to the live CMS instead of skipping it.
+    apply_tags: bool,
+}
+
+/// Replay a recorded session against the CMS, or with `offline` against its saved page snapshots
+async fn replay_session(
+    driver: &WebDriver,
+    path: &Path,
+    options: &ReplayOptions,
+    recovery: &RecoveryConfig,
+    cancel: &CancellationToken,
+) -> Result<()> {
+    let actions = recorder::load_session(path)?;
+    println!("Replaying {} actions from {}", actions.len(), path.display());
+
+    let mut mismatches = 0;
+    for recorded in &actions {
+        if cancel.is_cancelled() {
+            println!("⚠ Cancelled, skipping the remaining actions");
+            break;
+        }
+        if options.offline {
+            let Some(snapshot) = &recorded.snapshot else {
+                println!("  #{} {}: no snapshot, skipped", recorded.seq, recorded.action);
+                continue;
+            };
+            let snapshot = archive::locate(snapshot)
+                .with_context(|| format!("Could not find snapshot {}", snapshot.display()))?;
+            let snapshot = archive::plain_file(&snapshot)?;
+            driver.get(format!("file://{}", snapshot.display())).await?;
+            spider::forget_expanded();
+        }
+
+        let (ok, outcome) =
+            match replay_action(driver, &recorded.action, options, recovery, cancel).await {
+                Ok(summary) => (true, summary),
+                Err(e) => (false, format!("{e:#}")),
+            };
+
+        if ok == recorded.ok && outcome == recorded.outcome {
+            println!("  ✓ #{} {}: {outcome}", recorded.seq, recorded.action);
+        } else {
+            mismatches += 1;
+            println!("  ✗ #{} {}", recorded.seq, recorded.action);
+            println!("      recorded: {}", recorded.outcome);
+            println!("      replayed: {outcome}");
+        }
+    }
+
+    println!("\nReplay finished: {mismatches} of {} actions behaved differently", actions.len());
+    Ok(())
+}
+
+/// Save a sanitized page source, the tree HTML, a screenshot and the extracted entries
+/// of each node into its own directory under `out`
+async fn capture_fixtures(
+    driver: &WebDriver,
+    nodes: &[String],
+    out: &Path,
+    neos_version: Option<&str>,
+) -> Result<()> {
+    let credentials = get_credentials()?;
+    let secrets = [credentials.0.as_str(), credentials.1.as_str()];
+
+    for node_id in nodes {
+        println!("Capturing fixture for {node_id}...");
+        retry_with_relogin(driver, || async {
+            find_and_click_folder(driver, node_id).await
+        }, 3).await?;
+        require_page_load(driver).await?;
+
+        let dir = out.join(node_id);
+        fs::create_dir_all(&dir).context("Could not create fixture directory")?;
+
+        let tree_html = match selectors::find_first(&driver.handle, "tree.root", &Selectors::get().tree.root).await {
+            Ok(tree) => tree.outer_html().await?,
+            Err(e) => {
+                println!("  ⚠ Could not capture the tree: {e}");
+                String::new()
+            }
+        };
+        let screenshot = driver.screenshot_as_png().await?;
+        let breadcrumb_path = spider::breadcrumb_path(driver)
+            .await
+            .unwrap_or_else(|_| "Unknown Path".to_string());
+        let entries = extract_loaded_page(driver, node_id).await?;
+
+        let in_iframe = find_content_context(driver).await?;
+        let page_source = driver.source().await?;
+        if in_iframe {
+            driver.enter_default_frame().await?;
+        }
+
+        fs::write(dir.join("page.html"), fixtures::sanitize_html(&page_source, &secrets))?;
+        fs::write(dir.join("tree.html"), fixtures::sanitize_html(&tree_html, &secrets))?;
+        fs::write(dir.join("screenshot.png"), screenshot)?;
+        fs::write(dir.join("expected.json"), serde_json::to_string_pretty(&entries)?)?;
+        FixtureManifest {
+            node_id: node_id.clone(),
+            breadcrumb_path,
+            neos_version: neos_version.map(str::to_string),
+            captured_at: Local::now(),
+            in_iframe,
+            entries: entries.len(),
+        }
+        .to_json_file(dir.join("manifest.json"))?;
+
+        println!("  ✓ Saved {} ({} entries)", dir.display(), entries.len());
+    }
+
+    println!("Review the screenshots for personal data before committing the fixtures.");
+    Ok(())
+}
+
+/// Benchmark the extraction pipeline on the captured fixtures, see `fixtures bench`. The nodes
+/// are those of the captured trees; a node with a fixture of its own shows its page, the others
+/// one picked with the seed. Each round reads the entries from the page HTML like the content
+/// script, validates their URLs against a local mock server and writes them to the configured
+/// sinks in a temporary directory.
+async fn run_bench(args: &BenchArgs, config: &Config) -> Result<()> {
+    let BenchArgs {
+        fixtures,
+        nodes,
+        seed,
+        rounds,
+        baseline,
+        max_slowdown,
+        save,
+    } = args;
+
+    let mut dirs: Vec<PathBuf> = fs::read_dir(fixtures)
+        .with_context(|| format!("Could not read fixtures in {}", fixtures.display()))?
+        .filter_map(|dir| Some(dir.ok()?.path()))
+        .filter(|dir| dir.join("manifest.json").is_file())
+        .collect();
+    dirs.sort();
+    let mut pages = Vec::new();
+    for dir in &dirs {
+        let manifest = FixtureManifest::from_json_file(dir.join("manifest.json"))?;
+        let path = dir.join("page.html");
+        let page = fs::read_to_string(&path)
+            .with_context(|| format!("Could not read {}", path.display()))?;
+        pages.push((manifest, page));
+    }
+    let Some((first, _)) = pages.first() else {
+        anyhow::bail!(
+            "No fixtures in {}, capture some with `fixtures capture`",
+            fixtures.display()
+        );
+    };
+    // The selectors the fixtures were captured with
+    Selectors::resolve(SELECTORPATH, first.neos_version.as_deref())
+        .context("Could not load selectors")?
+        .install();
+
+    let mut node_ids: BTreeSet<String> =
+        pages.iter().map(|(manifest, _)| manifest.node_id.clone()).collect();
+    for dir in &dirs {
+        if let Ok(tree) = fs::read_to_string(dir.join("tree.html")) {
+            let tree = html::Document::parse(&tree);
+            node_ids.extend(fixtures::tree_node_ids(&tree, &Selectors::get().tree.child_item));
+        }
+    }
+    let node_ids: Vec<String> = node_ids.into_iter().collect();
+    let mut rng = SeededRng::new(*seed);
+    let run: Vec<(String, usize)> = (0..*nodes)
+        .map(|i| {
+            let node = &node_ids[i % node_ids.len()];
+            let page = pages
+                .iter()
+                .position(|(manifest, _)| manifest.node_id == *node)
+                .unwrap_or_else(|| rng.below(pages.len()));
+            (node.clone(), page)
+        })
+        .collect();
+
+    // Only file sinks, in a directory of their own, so the benchmark adds nothing to the history
+    let dir = std::env::temp_dir().join(format!("tag-spider-bench-{}", std::process::id()));
+    fs::create_dir_all(&dir).context("Could not create the benchmark directory")?;
+    let mut output = config.output.clone();
+    output.sinks.retain(|kind| *kind != SinkKind::Stdout);
+    output.sqlite_path = dir.join("history.sqlite");
+
+    println!(
+        "Benchmarking {nodes} nodes ({} in the captured trees) showing {} captured pages \
+         (seed {seed}), best of {rounds} rounds",
+        node_ids.len(),
+        pages.len()
+    );
+    let mut report = BenchReport {
+        seed: *seed,
+        nodes: *nodes,
+        stages: Vec::new(),
+    };
+    for _ in 0..(*rounds).max(1) {
+        let round = bench_round(&run, &pages, config, &output, &dir).await;
+        report.keep_fastest(round?);
+    }
+    let _ = fs::remove_dir_all(&dir);
+
+    for result in &report.stages {
+        println!(
+            "  {:<8} {:>8} entries  {:>8.3}s  {:>10.0} entries/s",
+            result.stage,
+            result.entries,
+            result.secs,
+            result.per_sec()
+        );
+    }
+    if let Some(path) = save {
+        report.save(path)?;
+        println!("✓ Results saved to {}", path.display());
+    }
+    if let Some(path) = baseline {
+        let regressions = report.regressions(&BenchReport::load(path)?, max_slowdown / 100.0);
+        for (stage, slowdown) in &regressions {
+            println!("✗ {stage} is {:.0}% slower than in {}", slowdown * 100.0, path.display());
+        }
+        if !regressions.is_empty() {
+            anyhow::bail!("{} stages got slower than the baseline allows", regressions.len());
+        }
+        println!("✓ No stage is more than {max_slowdown}% slower than in {}", path.display());
+    }
+    Ok(())
+}
+
+/// One round of `fixtures bench`: read the entries of each node of `run` from its page in
+/// `pages`, check and validate them and write them to the sinks of `output` in `dir`
+async fn bench_round(
+    run: &[(String, usize)],
+    pages: &[(FixtureManifest, String)],
+    config: &Config,
+    output: &OutputConfig,
+    dir: &Path,
+) -> Result<Vec<StageResult>> {
+    let started = Instant::now();
+    let region = config.extraction.region_for(None);
+    let mut entries = Vec::new();
+    for (node, page) in run {
+        let (manifest, html) = &pages[*page];
+        let page = html::Document::parse(html);
+        let found = fixtures::page_entries(
+            &page,
+            region,
+            &Selectors::get().content,
+            &config.extraction.types,
+        );
+        entries.extend(
+            found
+                .into_iter()
+                .filter_map(|found| script_content_entry(found, node, &manifest.breadcrumb_path)),
+        );
+    }
+    let parsed = StageResult::new("parse", entries.len(), started.elapsed());
+
+    let started = Instant::now();
+    let (server, mock) = bench::mock_server().await?;
+    let checks = ChecksConfig {
+        wayback: false,
+        ..config.checks.clone()
+    };
+    let validator = Validator::new(&checks);
+    let current_year = Local::now().year();
+    let max_age = checks.max_content_age_years;
+    let flags: Vec<bool> = stream::iter(&mut entries)
+        .map(|entry| {
+            let (validator, server) = (&validator, &server);
+            async move {
+                // Keep the entry's own URL, only the request goes to the mock server
+                entry.normalize();
+                let url = entry.url.clone();
+                if !url.is_empty() {
+                    entry.url = bench::mock_url(server, &url);
+                }
+                let flagged = check_entry(entry, Some(validator), current_year, max_age).await;
+                entry.url = url;
+                flagged
+            }
+        })
+        .buffered(checks.validation_concurrency.max(1))
+        .collect()
+        .await;
+    mock.abort();
+    let outdated = flags.into_iter().filter(|&flagged| flagged).count();
+    let checked = StageResult::new("check", entries.len(), started.elapsed());
+
+    let started = Instant::now();
+    let started_at = Local::now();
+    let mut sink = sink::from_config(output, dir)?;
+    sink.open("bench")?;
+    for entry in &entries {
+        sink.write_entry(&ReportEntry::from(entry))?;
+    }
+    sink.write_summary(&RunSummary {
+        job: "bench".to_string(),
+        started_at,
+        finished_at: Local::now(),
+        entries: entries.len(),
+        outdated,
+        relogin_storm: None,
+    })?;
+    sink.close()?;
+    let written = StageResult::new("write", entries.len(), started.elapsed());
+
+    Ok(vec![parsed, checked, written])
+}
+
+async fn run_report(command: &ReportCommand, config: &Config) -> Result<()> {
+    match command {
+        ReportCommand::Site { inputs, out } => {
+            let entries = report::load_results(&result_files(inputs)?)?;
+            let state = State::load(&config.state.path)?;
+            let chapters = report::write_site(&entries, &state, out, config.report.chapter_level)?;
+            println!(
+                "Report site with {} entries in {chapters} chapters written to {}",
+                entries.len(),
+                out.join("index.html").display()
+            );
+        }
+        ReportCommand::Issues { inputs } => {
+            let entries = report::load_results(&result_files(inputs)?)?;
+            println!("Syncing issues for {} entries...", entries.len());
+            let summary =
+                issues::sync(&config.issues, &entries, config.report.chapter_level).await?;
+            println!(
+                "✓ Issues: {} opened, {} updated, {} closed",
+                summary.opened, summary.updated, summary.closed
+            );
+        }
+        ReportCommand::Broken { inputs, out } => {
+            let entries = report::load_results(&result_files(inputs)?)?;
+            let broken = entries.iter().filter(|entry| entry.has_broken_url()).count();
+            fs::write(out, report::broken_links_markdown(&entries))
+                .with_context(|| format!("Could not write {}", out.display()))?;
+            println!("✓ {broken} broken links written to {}", out.display());
+        }
+        ReportCommand::Calendar { out } => {
+            let out = out
+                .clone()
+                .or_else(|| config.calendar.path.clone())
+                .unwrap_or_else(|| PathBuf::from("audits.ics"));
+            let runs = calendar::write(&config.calendar, &config.output.sqlite_path, &out)?;
+            println!("✓ Calendar with {runs} completed runs written to {}", out.display());
+        }
+    }
+    Ok(())
+}
+
+/// The operation a command performs for the [permissions] check, or `None` for local tooling
+/// that needs no permission
+fn command_operation(command: &Command) -> Option<Operation> {
+    let operation = match command {
+        // Replaying recorded tagging changes the CMS like `tag` does
+        Command::Replay {
+            offline: false,
+            apply_tags: true,
+            ..
+        } => Operation::WriteTags,
+        Command::Replay { .. } => Operation::Replay,
+        Command::Extract { .. } | Command::ExtractOne { .. } => Operation::Extract,
+        Command::Report { .. }
+        | Command::Merge { .. }
+        | Command::Snapshot { .. }
+        | Command::Validate { .. } => Operation::Report,
+        Command::Tag { .. } | Command::ClearTags { .. } => Operation::WriteTags,
+        Command::Review {
+            command: ReviewCommand::ApplyReplacements { apply: true },
+        } => Operation::WriteLinks,
+        Command::Review { .. } => Operation::Review,
+        Command::Tags {
+            command: TagsCommand::Lint { .. },
+        } => return None,
+        Command::Tags { .. } => Operation::WriteTags,
+        Command::Links { .. } => Operation::WriteLinks,
+        Command::Fixtures { .. } => Operation::Fixtures,
+        Command::Config { .. } | Command::Cache { .. } | Command::Completions { .. } => {
+            return None
+        }
+    };
+    Some(operation)
+}
+
+/// Print the effective settings from the defaults, `spider.toml`, the environment and the
+/// command line
+fn show_config(config: &Config, cli: &Cli) -> Result<()> {
+    let source = |set: bool, origin: Source| if set { origin } else { Source::Default };
+
+    let mut settings = vec![Setting::new(
+        "selectors.file".to_string(),
+        format!("\"{SELECTORPATH}\""),
+        source(Path::new(SELECTORPATH).exists(), Source::File),
+    )];
+    let overridden = [
+        ("spider.login_url", cli.login_url.is_some()),
+        ("spider.webdriver_url", cli.webdriver_url.is_some()),
+        ("spider.browser", cli.browser.is_some()),
+        ("tagging.tags_path", cli.tags.is_some()),
+        ("output.sinks", !cli.format.is_empty()),
+        ("extraction.types", !cli.types.is_empty()),
+        ("timeouts.page_load_secs", cli.page_load_timeout.is_some()),
+        ("timeouts.element_secs", cli.element_timeout.is_some()),
+        ("checks.url_timeout_secs", cli.url_timeout.is_some()),
+    ];
+    for mut setting in config.settings(&cli.config)? {
+        if overridden.contains(&(setting.key.as_str(), true)) {
+            setting.source = Source::Cli;
+        }
+        settings.push(setting);
+    }
+
+    let headless = std::env::var("HEADLESS").ok();
+    settings.push(Setting::new(
+        "HEADLESS".to_string(),
+        headless.as_deref().is_some_and(|value| value.eq_ignore_ascii_case("true")).to_string(),
+        source(headless.is_some(), Source::Env),
+    ));
+    let username = std::env::var(USERNAME_ENV).ok();
+    settings.push(Setting::new(
+        USERNAME_ENV.to_string(),
+        username.clone().unwrap_or_else(|| "(not set)".to_string()),
+        source(username.is_some(), Source::Env),
+    ));
+    let password_set = std::env::var(PASSWORD_ENV).is_ok();
+    settings.push(Setting::new(
+        PASSWORD_ENV.to_string(),
+        if password_set { "***" } else { "(not set)" }.to_string(),
+        source(password_set, Source::Env),
+    ));
+    let token_set = std::env::var(&config.secrets.token_env).is_ok();
+    settings.push(Setting::new(
+        config.secrets.token_env.clone(),
+        if token_set { "***" } else { "(not set)" }.to_string(),
+        source(token_set, Source::Env),
+    ));
+    if config.issues.provider.is_some() {
+        let token_set = std::env::var(&config.issues.token_env).is_ok();
+        settings.push(Setting::new(
+            config.issues.token_env.clone(),
+            if token_set { "***" } else { "(not set)" }.to_string(),
+            source(token_set, Source::Env),
+        ));
+    }
+    if config.notify.matrix.homeserver.is_some() {
+        let token_set = std::env::var(&config.notify.matrix.token_env).is_ok();
+        settings.push(Setting::new(
+            config.notify.matrix.token_env.clone(),
+            if token_set { "***" } else { "(not set)" }.to_string(),
+            source(token_set, Source::Env),
+        ));
+    }
+    let flags = [
+        ("--force", cli.force),
+        ("--porcelain", cli.porcelain),
+        ("--headless", cli.headless),
+    ];
+    for (flag, set) in flags {
+        settings.push(Setting::new(flag.to_string(), set.to_string(), source(set, Source::Cli)));
+    }
+
+    let found = if cli.config.exists() { "" } else { " (not found, using defaults)" };
+    println!("Configuration file: {}{found}", cli.config.display());
+    let width = settings.iter().map(|s| s.key.len()).max().unwrap_or_default();
+    for setting in &settings {
+        println!("{:<width$}  {:<8}  {}", setting.key, setting.source, setting.value);
+    }
+    Ok(())
+}
+
+/// Pre-flight check of the configuration, files, selectors, URLs and credentials a run needs.
+/// Every problem is listed before failing, so one run of a scheduled job shows all of them.
+async fn check_config(path: &Path) -> Result<()> {
+    let mut problems = 0;
+    let mut report = |what: &str, result: Result<String>| match result {
+        Ok(detail) => println!("✓ {what}: {detail}"),
+        Err(e) => {
+            problems += 1;
+            println!("✗ {what}: {e:#}");
+        }
+    };
+
+    let found = |path: &Path, missing: &str| {
+        if path.exists() { "valid" } else { missing }.to_string()
+    };
+
+    let config_file = path.display().to_string();
+    let config = match Config::load(path) {
+        Ok(config) => {
+            report(&config_file, Ok(found(path, "not found, using defaults")));
+            config
+        }
+        Err(e) => {
+            report(&config_file, Err(e));
+            Config::default()
+        }
+    };
+
+    let selectors = Selectors::load(SELECTORPATH)
+        .map(|_| found(Path::new(SELECTORPATH), "not found, using the bundled profiles"));
+    report(SELECTORPATH, selectors);
+    for version in Selectors::profile_versions() {
+        let profile = Selectors::profile(version).map(|_| "valid".to_string());
+        report(&format!("bundled selector profile for Neos {version}"), profile);
+    }
+
+    let tree = FileTree::from_json_file(TREEPATH)
+        .with_context(|| format!("Could not read {TREEPATH}"))
+        .map(|tree| format!("{} nodes", tree.nodes.len()));
+    report(TREEPATH, tree);
+    let tags = load_tags(&config.tagging).map(|tags| format!("{} questions", tags.len()));
+    report("tags", tags);
+    let pattern = &config.tagging.question_id_pattern;
+    report("question ID pattern", QuestionIds::new(pattern).map(|_| pattern.to_string()));
+
+    // Files written during a run need an existing directory
+    let mut outputs = vec![
+        ("state file", &config.state.path),
+        ("audit log", &config.state.audit_log),
+    ];
+    if config.heartbeat.enabled {
+        outputs.push(("heartbeat file", &config.heartbeat.path));
+    }
+    for (what, path) in outputs {
+        let dir = path.parent().filter(|dir| !dir.as_os_str().is_empty());
+        let result = match dir {
+            Some(dir) if !dir.is_dir() => {
+                Err(anyhow::anyhow!("directory {} does not exist", dir.display()))
+            }
+            _ => Ok(path.display().to_string()),
+        };
+        report(what, result);
+    }
+
+    let login_url = &config.spider.login_url;
+    let backend = reqwest::Url::parse(login_url).map(|_| login_url.clone());
+    report("backend URL", backend.context("Not a valid URL"));
+    for site in &config.sites {
+        if let Some(domain) = &site.domain {
+            let url = reqwest::Url::parse(&format!("https://{domain}/neos/content"));
+            let result = match url {
+                Ok(url) if url.host_str() == Some(domain.as_str()) => Ok(domain.clone()),
+                _ => Err(anyhow::anyhow!(
+                    "'{domain}' is not a plain host name like cms.example.com"
+                )),
+            };
+            report(&format!("domain of site {}", site.name), result);
+        }
+    }
+
+    let credentials = match (env_credentials(), &config.secrets.url) {
+        (Ok(Some(_)), _) => Ok(format!("read from {USERNAME_ENV} and {PASSWORD_ENV}")),
+        (Err(e), _) => Err(e),
+        (Ok(None), Some(url)) => match reqwest::Url::parse(url) {
+            Ok(_) => secrets::fetch_credentials(&config.secrets, config.timeouts().validation)
+                .await
+                .map(|_| "fetched from the secrets backend".to_string()),
+            Err(e) => Err(anyhow::anyhow!("secrets.url is not a valid URL: {e}")),
+        },
+        (Ok(None), None) => read_credentials_file().map(|(path, _)| format!("read from {path}")),
+    };
+    report("credentials", credentials);
+
+    if problems > 0 {
+        anyhow::bail!("Found {problems} configuration problems");
+    }
+    println!("\nConfiguration OK");
+    Ok(())
+}
+
+/// Print a completion script for `shell`, offering the treeitem IDs from the file tree
+/// wherever a node is expected
+fn print_completions(shell: Shell) {
+    let mut command = Cli::command();
+    if let Ok(tree) = FileTree::from_json_file(TREEPATH) {
+        let mut ids: Vec<String> = tree.nodes.into_keys().collect();
+        ids.sort();
+        // Only used to generate the script, so parsing still accepts backend URLs
+        let nodes = PossibleValuesParser::new(ids);
+        command = command
+            .mut_subcommand("extract", |c| c.mut_arg("folder", |a| a.value_parser(nodes.clone())))
+            .mut_subcommand("extract-one", |c| c.mut_arg("node", |a| a.value_parser(nodes.clone())))
+            .mut_subcommand("tag", |c| c.mut_arg("node", |a| a.value_parser(nodes.clone())))
+            .mut_subcommand("clear-tags", |c| c.mut_arg("node", |a| a.value_parser(nodes.clone())))
+            .mut_subcommand("tags", |c| {
+                c.mut_subcommand("set", |c| c.mut_arg("node", |a| a.value_parser(nodes.clone())))
+            })
+            .mut_subcommand("snapshot", |c| {
+                c.mut_subcommand("list", |c| c.mut_arg("node", |a| a.value_parser(nodes.clone())))
+                    .mut_subcommand("diff", |c| c.mut_arg("node", |a| a.value_parser(nodes.clone())))
+            })
+            .mut_subcommand("fixtures", |c| {
+                c.mut_subcommand("capture", |c| c.mut_arg("nodes", |a| a.value_parser(nodes)))
+            });
+    }
+
+    let name = command.get_name().to_string();
+    clap_complete::generate(shell, &mut command, name, &mut io::stdout());
+}
+
+/// Whether `operation` changes content in the CMS and so needs the run lock. Replays that apply
+/// recorded tagging count as [`Operation::WriteTags`].
+fn is_write(operation: Operation) -> bool {
+    matches!(operation, Operation::WriteTags | Operation::WriteLinks)
+}
+
+/// Take the run lock for the CMS this spider works on
+fn lock_cms(config: &Config, operation: Operation, force: bool) -> Result<RunLock> {
+    let environment = reqwest::Url::parse(&config.spider.login_url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+        .unwrap_or_else(|| "cms".to_string());
+    lock::acquire(
+        &config.lock.dir,
+        &environment,
+        &operation.to_string(),
+        force,
+        config.lock.stale_after(),
+    )
+}
+
+fn run_snapshot(command: &SnapshotCommand, config: &Config) -> Result<()> {
+    let dir = &config.snapshots.dir;
+    match command {
+        SnapshotCommand::List { node } => {
+            let runs = snapshot::runs_with(dir, node)?;
+            if runs.is_empty() {
+                println!("No snapshots of {node} in {}", dir.display());
+            }
+            let mut previous = None;
+            for (run, hash) in &runs {
+                let change = match previous {
+                    None => "first snapshot",
+                    Some(previous) if previous == hash => "unchanged",
+                    Some(_) => "changed",
+                };
+                println!("{run}  {}  {change}", &hash[..12]);
+                previous = Some(hash);
+            }
+        }
+        SnapshotCommand::Diff { node, run_a, run_b } => {
+            let diff = snapshot::diff(dir, node, run_a, run_b)?;
+            if diff.is_empty() {
+                println!("{node} is unchanged between {run_a} and {run_b}");
+            } else {
+                print!("{diff}");
+            }
+        }
+    }
+    Ok(())
+}
+
+fn run_merge(inputs: &[PathBuf], out: &Path) -> Result<()> {
+    let (entries, summary) = merge::merge_results(inputs)?;
+    if entries.is_empty() {
+        println!("The inputs contain no entries, nothing written.");
+        return Ok(());
+    }
+    merge::write_results(out, &entries)?;
+
+    println!("Merged {} rows from {} files:", summary.rows, summary.files);
+    println!("  Duplicates replaced by a more recent run: {}", summary.duplicates);
+    println!("  Of which with a different URL status:     {}", summary.conflicts);
+    println!("{} entries saved to: {}", entries.len(), out.display());
+    Ok(())
+}
+
+/// Validate the URLs of earlier results again and save them with the new status to `out`
+async fn run_validate(inputs: &[PathBuf], out: &Path, config: &Config) -> Result<()> {
+    let mut entries = report::load_results(&result_files(inputs)?)?;
+    if entries.is_empty() {
+        println!("The inputs contain no entries, nothing validated.");
+        return Ok(());
+    }
+    println!("Validating {} URLs...", entries.len());
+
+    let cancel = CancellationToken::new();
+    cancel_on_ctrl_c(cancel.clone());
+    let validator = Validator::new(&config.checks);
+    let checks: HashMap<String, UrlCheck> = validator
+        .checks(entries.iter().map(|entry| entry.url.as_str()))
+        .take_until(cancel.cancelled())
+        .collect()
+        .await;
+    let unchecked = entries.iter().filter(|entry| !checks.contains_key(&entry.url)).count();
+    if unchecked > 0 {
+        println!("⚠ Cancelled, {unchecked} entries keep their previous URL status");
+    }
+
+    let mut changed = 0;
+    let mut broken = 0;
+    for entry in &mut entries {
+        let Some(check) = checks.get(&entry.url).cloned() else {
+            continue;
+        };
+        let status = check.status;
+        if entry.url_valid != status {
+            changed += 1;
+            println!("  {} {} -> {status}", entry.url, entry.url_valid);
+        }
+        entry.url_valid = status;
+        entry.url_method = check.method;
+        entry.final_url = check.final_url;
+        entry.redirect_hops = check.redirect_hops.to_string();
+        entry.wayback_url = check.wayback_url;
+        if entry.has_broken_url() {
+            broken += 1;
+        }
+    }
+    merge::write_results(out, &entries)?;
+
+    println!("URL status changed for {changed} entries");
+    println!("{} entries saved to: {}", entries.len(), out.display());
+    if broken > 0 {
+        anyhow::bail!("{broken} URLs are broken");
+    }
+    println!("✓ All URLs valid");
+    Ok(())
+}
+
+/// Result CSVs given on the command line, or all results in ./embedded_content
+fn result_files(inputs: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    if inputs.is_empty() {
+        report::find_result_files("./embedded_content")
+    } else {
+        Ok(inputs.to_vec())
+    }
+}
+
+/// `review import`: store the decisions of a reviewed CSV in the state file
+fn import_review(csv: &Path, config: &Config) -> Result<()> {
+    let mut state = State::load(&config.state.path)?;
+    let summary = state.import_csv(csv)?;
+    state.save(&config.state.path)?;
+    println!(
+        "Imported {} decisions into {}",
+        summary.imported,
+        config.state.path.display()
+    );
+    for error in &summary.errors {
+        println!("  ⚠ Skipped {error}");
+    }
+    Ok(())
+}
+
+/// `review status`: print the review progress over the result files
+fn review_status(inputs: &[PathBuf], config: &Config) -> Result<()> {
+    let state = State::load(&config.state.path)?;
+    let entries = report::load_results(&result_files(inputs)?)?;
+    println!("Review progress over {} entries:", entries.len());
+    state.progress(&entries).print();
+    Ok(())
+}
+
+/// Dry run of `review apply-replacements`
+fn list_replacements(config: &Config) -> Result<()> {
+    let state = State::load(&config.state.path)?;
+    let pending = state.pending_replacements();
+    println!("{} pending replacements:", pending.len());
+    for (_, review) in &pending {
+        if let Decision::Replace { url } = &review.decision {
+            println!("  {}: {} -> {url}", review.source_node, review.url);
+        }
+    }
+    if !pending.is_empty() {
+        println!("Run again with --apply to change these links in the CMS.");
+    }
+    Ok(())
+}
+
+/// Carry out all pending replacements, recording each attempt in the audit log
+async fn apply_replacements(driver: &WebDriver, config: &Config) -> Result<()> {
+    let mut state = State::load(&config.state.path)?;
+    let pending: Vec<(String, Review)> = state
+        .pending_replacements()
+        .into_iter()
+        .map(|(key, review)| (key.clone(), review.clone()))
+        .collect();
+
+    if pending.is_empty() {
+        println!("No pending replacements.");
+        return Ok(());
+    }
+    if !ask_yes_no(&format!("Replace {} links in the CMS?", pending.len())) {
+        return Ok(());
+    }
+
+    let mut replaced = 0;
+    for (key, review) in &pending {
+        let Decision::Replace { url: new_url } = &review.decision else {
+            continue;
+        };
+        println!("Replacing {} in {}...", review.url, review.source_node);
+
+        let record = AuditRecord::new(&review.source_node, "replace_link", &review.url, new_url);
+        let record = match replace_link(driver, review, new_url).await {
+            Ok(()) => {
+                println!("  ✓ Now links to {new_url}");
+                state.mark_applied(key);
+                replaced += 1;
+                record
+            }
+            Err(e) => {
+                eprintln!("  ✗ Could not replace link: {e:#}");
+                record.failed(&e)
+            }
+        };
+        audit::append(&config.state.audit_log, &record)?;
+        // Save after every change, so an interrupted run does not replace links twice
+        state.save(&config.state.path)?;
+    }
+
+    println!("Replaced {replaced} of {} links.", pending.len());
+    println!("The changes are in your workspace; review and publish them in Neos.");
+    Ok(())
+}
+
+/// Find the ExternalLinks element showing `url` in the content of the open node
+async fn find_external_link(driver: &WebDriver, url: &str) -> Result<Option<WebElement>> {
+    let candidates = &Selectors::get().content.external_link;
+    let links = selectors::find_all_first(&driver.handle, "content.external_link", candidates).await?;
+    for link in links {
+        if link_property(&link, "url").await? == url {
+            return Ok(Some(link));
+        }
+    }
+    Ok(None)
+}
+
+/// Text of the `property` field of an ExternalLinks element, empty if it has none
+async fn link_property(link: &WebElement, property: &str) -> Result<String> {
+    let candidates = Selectors::get()
+        .content
+        .link_field(property)
+        .with_context(|| format!("ExternalLinks elements have no field '{property}'"))?;
+    let name = format!("content.{property}");
+    Ok(field_text(link, &name, candidates).await.unwrap_or_default())
+}
+
+/// Read `properties` of the ExternalLinks element showing `url`, or `None` if it is not on the page
+async fn read_external_link(
+    driver: &WebDriver,
+    url: &str,
+    properties: &[&str],
+) -> Result<Option<Vec<String>>> {
+    let in_iframe = enter_content_frame(driver).await?;
+    let mut values = None;
+    if let Some(link) = find_external_link(driver, url).await? {
+        let mut read = Vec::new();
+        for property in properties {
+            read.push(link_property(&link, property).await?);
+        }
+        values = Some(read);
+    }
+    if in_iframe {
+        driver.enter_default_frame().await?;
+    }
+    Ok(values)
+}
+
+/// Open `node_id` and select the ExternalLinks element showing `url`, so its properties are
+/// shown in the inspector. Returns the current values of `properties`.
+async fn select_external_link(
+    driver: &WebDriver,
+    node_id: &str,
+    url: &str,
+    properties: &[&str],
+) -> Result<Vec<String>> {
+    retry_with_relogin(driver, || async {
+        find_and_click_folder(driver, node_id).await
+    }, 3).await?;
+    require_page_load(driver).await?;
+
+    let in_iframe = enter_content_frame(driver).await?;
+    let link = find_external_link(driver, url)
+        .await?
+        .context("The link is no longer on the page")?;
+    let mut values = Vec::new();
+    for property in properties {
+        values.push(link_property(&link, property).await?);
+    }
+    link.scroll_into_view().await?;
+    // Selecting the element opens its properties in the inspector
+    link.click().await?;
+    if in_iframe {
+        driver.enter_default_frame().await?;
+    }
+    Ok(values)
+}
+
+/// Apply the inspector's pending changes and give the Neos UI time to save them
+async fn apply_inspector(driver: &WebDriver) -> Result<()> {
+    if inspector::apply(driver).await? {
+        support::sleep(timeouts().retry).await;
+    }
+    Ok(())
+}
+
+/// Open the node of `review`, select its link and change the URL in the inspector
+async fn replace_link(driver: &WebDriver, review: &Review, new_url: &str) -> Result<()> {
+    select_external_link(driver, &review.source_node, &review.url, &[]).await?;
+    InspectorField::find(driver, "url").await?.set(new_url).await?;
+    apply_inspector(driver).await?;
+
+    // Check that the content now shows the new URL
+    read_external_link(driver, new_url, &[])
+        .await?
+        .context("The page does not show the new URL after applying")?;
+    Ok(())
+}
+
+/// ExternalLinks properties that `links apply-metadata` can change, with the CSV value for each
+fn metadata_values(entry: &ReportEntry) -> [(&'static str, &str); 4] {
+    [
+        ("title", &entry.title),
+        ("author", &entry.author),
+        ("type", &entry.file_type),
+        ("size", &entry.size),
+    ]
+}
+
+/// Update the metadata of links from a corrected result CSV, recording each change in the audit log
+async fn apply_metadata(driver: &WebDriver, csv: &Path, config: &Config) -> Result<()> {
+    let entries: Vec<ReportEntry> = report::load_results(&[csv.to_path_buf()])?
+        .into_iter()
+        .filter(|e| e.content_type == "ExternalLink" && !e.url.is_empty())
+        .collect();
+
+    if entries.is_empty() {
+        println!("No ExternalLink rows in {}.", csv.display());
+        return Ok(());
+    }
+
+    // Links are told apart by their node and URL in the job log
+    let stem = csv.file_stem().unwrap_or_default().to_string_lossy();
+    let job = format!("metadata-{stem}");
+    let mut log = JobLog::open(&joblog::path(&config.state.jobs_dir, &job))?;
+    let keys: Vec<String> = entries
+        .iter()
+        .map(|entry| format!("{} {}", entry.source_node, entry.url))
+        .collect();
+    let remaining = keys.iter().filter(|key| !log.is_applied(key)).count();
+    if remaining < entries.len() {
+        println!(
+            "Resuming an interrupted run at link {} of {}, {} links were already done",
+            log.cursor(keys.iter().map(String::as_str)) + 1,
+            entries.len(),
+            entries.len() - remaining
+        );
+    }
+    if !ask_yes_no(&format!("Update the metadata of {remaining} links in the CMS?")) {
+        return Ok(());
+    }
+
+    let mut updated = 0;
+    let mut failed = 0;
+    for (i, (entry, key)) in entries.iter().zip(&keys).enumerate() {
+        if log.is_applied(key) {
+            continue;
+        }
+        println!("[{}/{}] {} in {}...", i + 1, entries.len(), entry.url, entry.source_node);
+        match update_link_metadata(driver, entry, &config.state.audit_log).await {
+            Ok(changed) => {
+                if changed == 0 {
+                    println!("  Already up to date");
+                } else {
+                    println!("  ✓ Updated {changed} fields");
+                    updated += 1;
+                }
+                record_applied(&mut log, key);
+            }
+            Err(e) => {
+                eprintln!("  ✗ Could not update metadata: {e:#}");
+                failed += 1;
+            }
+        }
+    }
+    if failed == 0 {
+        log.finish()?;
+    }
+
+    println!("Updated {updated} links, {failed} failed.");
+    println!("The changes are in your workspace; review and publish them in Neos.");
+    Ok(())
+}
+
+/// Set the properties of one link that differ from the CSV. Empty cells leave the property
+/// unchanged. Returns the number of changed properties.
+async fn update_link_metadata(driver: &WebDriver, entry: &ReportEntry, audit_log: &Path) -> Result<usize> {
+    let wanted: Vec<(&str, &str)> = metadata_values(entry)
+        .into_iter()
+        .filter(|(_, value)| !value.is_empty())
+        .collect();
+    let properties: Vec<&str> = wanted.iter().map(|(property, _)| *property).collect();
+
+    let current = select_external_link(driver, &entry.source_node, &entry.url, &properties).await?;
+    let changes: Vec<(&str, AuditRecord)> = wanted
+        .iter()
+        .zip(&current)
+        .filter(|((_, new), old)| new != old)
+        .map(|((property, new), old)| {
+            let action = format!("set_{property}");
+            (*property, AuditRecord::new(&entry.source_node, &action, old, new))
+        })
+        .collect();
+    if changes.is_empty() {
+        return Ok(0);
+    }
+
+    let result = set_link_metadata(driver, &entry.url, &changes).await;
+    for (_, change) in &changes {
+        let record = match &result {
+            Ok(()) => change.clone(),
+            Err(e) => change.clone().failed(e),
+        };
+        audit::append(audit_log, &record)?;
+    }
+    result.map(|()| changes.len())
+}
+
+async fn set_link_metadata(driver: &WebDriver, url: &str, changes: &[(&str, AuditRecord)]) -> Result<()> {
+    for (property, change) in changes {
+        InspectorField::find(driver, property).await?.set(&change.new_value).await?;
+    }
+    apply_inspector(driver).await?;
+
+    // Check that the content now shows the new values
+    let properties: Vec<&str> = changes.iter().map(|(property, _)| *property).collect();
+    let values = read_external_link(driver, url, &properties)
+        .await?
+        .context("The link is no longer on the page after applying")?;
+    for ((property, change), value) in changes.iter().zip(values) {
+        if value != change.new_value {
+            anyhow::bail!("The {property} shows '{value}' instead of '{}'", change.new_value);
+        }
+    }
+    Ok(())
+}
+
+/// The tags of the `[tagging]` tags file by question ID, failing if it has problems.
+fn load_tags(tagging: &TaggingConfig) -> Result<HashMap<String, String>> {
+    let path = &tagging.tags_path;
+    TagsFile::read(path, tagging.duplicates)?
+        .into_tags()
+        .with_context(|| format!("Invalid tags file {}", path.display()))
+}
+
+/// `tags lint`: list the problems of the tags files with their line numbers.
+fn lint_tags(path: Option<&Path>, tagging: &TaggingConfig) -> Result<()> {
+    let path = path.unwrap_or(&tagging.tags_path);
+    let file = TagsFile::read(path, tagging.duplicates)?;
+    for (file, encoding) in &file.files {
+        if *encoding == Encoding::Utf8 {
+            println!("✓ {}", file.display());
+        } else {
+            println!("⚠ {} is saved as {encoding}, not UTF-8", file.display());
+        }
+    }
+    for issue in &file.issues {
+        let marker = if issue.error { "✗" } else { "⚠" };
+        println!("{marker} {issue}");
+    }
+    let errors = file.errors();
+    if errors > 0 {
+        anyhow::bail!("{errors} problems keep {} from being used", path.display());
+    }
+    println!("✓ {} questions with tags in {}", file.tags.len(), path.display());
+    Ok(())
+}
+
+/// Example function to add tags.
+async fn add_tags(clear: bool, driver: &WebDriver, cancel: &CancellationToken) -> Result<()> {
+    let tagging = TAGGING.get().cloned().unwrap_or_default();
+    let tags = load_tags(&tagging)?;
+    let question_ids = QuestionIds::new(&tagging.question_id_pattern)?;
+    let mut unparsed = Vec::new();
+    let content = &Selectors::get().content;
+    let iframe = selectors::find_first(&driver.handle, "content.frame", &content.frame).await?;
+    iframe.clone().enter_frame().await?;
+
+    let content_collection =
+        selectors::find_first(&driver.handle, "content.collection", &content.collection).await?;
+    let questions =
+        selectors::probe_all_first(&content_collection, "content.question_title", &content.question_title)
+            .await?;
+
+    for question in questions {
+        // Stop between questions, so no tag field is left half edited
+        if cancel.is_cancelled() {
+            println!("⚠ Cancelled, the remaining questions keep their tags");
+            break;
+        }
+        question.scroll_into_view().await?;
+        let title = text::normalize(&question.text().await?);
+        let Some(id) = question_ids.find(&title) else {
+            unparsed.push(title);
+            continue;
+        };
+        // Questions missing from the tags file are left alone, not cleared
+        let value = match tags.get(id) {
+            _ if clear => "",
+            Some(value) => value.as_str(),
+            None => {
+                eprintln!("Error: key {id} not found! Skipping...");
+                continue;
+            }
+        };
+
+        question.click().await?;
+        driver.enter_default_frame().await?;
+
+        let field = InspectorField::find(driver, "Tags").await?;
+        field
+            .set(value)
+            .await
+            .with_context(|| format!("Could not change the tags of question {id}"))?;
+        if field.apply().await? {
+            println!("{id} -> {value}");
+        } else {
+            println!("{id} already has these tags");
+        }
+        iframe.clone().enter_frame().await?;
+        support::sleep(tagging.apply_delay()).await;
+    }
+    driver.enter_default_frame().await?;
+
+    if !unparsed.is_empty() {
+        println!(
+            "⚠ {} question titles have no ID matching [tagging] question_id_pattern and were \
+             left unchanged:",
+            unparsed.len()
+        );
+        for title in &unparsed {
+            println!("    {title}");
+        }
+    }
+    Ok(())
+}
+
+/// Find the title of question `question` in the content of the open node
+async fn find_question(driver: &WebDriver, question: &str) -> Result<Option<WebElement>> {
+    let tagging = TAGGING.get().cloned().unwrap_or_default();
+    let question_ids = QuestionIds::new(&tagging.question_id_pattern)?;
+    let candidates = &Selectors::get().content.question_title;
+    let titles = selectors::find_all_first(&driver.handle, "content.question_title", candidates).await?;
+    for title in titles {
+        let text = text::normalize(&title.text().await?);
+        if question_ids.find(&text) == Some(text::normalize(question).as_str()) {
+            return Ok(Some(title));
+        }
+    }
+    Ok(None)
+}
+
+/// Open the page of a node given by treeitem ID or backend URL
+async fn open_node(spider: &Spider, node: &str) -> Result<()> {
+    let driver = &spider.driver;
+    match NodeRef::parse(node).context("Expected a treeitem ID or a backend URL")? {
+        NodeRef::TreeItem(id) => {
+            // Expand the ancestors known from the file tree, so the node does not have to be visible
+            if spider.file_tree.nodes.contains_key(&id) {
+                spider.find_treeitem(&id).await?;
+            }
+            retry_with_relogin(driver, || async {
+                find_and_click_folder(driver, &id).await
+            }, 3).await?;
+            require_page_load(driver).await?;
+        }
+        node => {
+            open_node_ref(driver, &node).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Open a node and add the tags from the tags file to its questions, or remove them if `clear`
+async fn tag_node(spider: &Spider, node: &str, clear: bool) -> Result<()> {
+    open_node(spider, node).await?;
+    println!("Tagging the questions of {node}");
+    recorded_add_tags(clear, &spider.driver, &spider.cancel).await
+}
+
+/// Tag a node and every page below it, or clear their tags if `clear`. The pages to visit, the
+/// expected duration and the tags to add are shown first, and nothing is changed until that is
+/// confirmed, or `yes` is given
+async fn tag_subtree(
+    spider: &Spider,
+    config: &Config,
+    node: &str,
+    clear: bool,
+    yes: bool,
+) -> Result<()> {
+    let NodeRef::TreeItem(root) =
+        NodeRef::parse(node).context("Expected a treeitem ID or a backend URL")?
+    else {
+        anyhow::bail!("--subtree needs a treeitem ID, the file tree does not know backend URLs");
+    };
+    let mut pages = vec![root.clone()];
+    pages.extend(spider.file_tree.descendants(&root));
+
+    let job = format!("{}-{root}", if clear { "clear-tags" } else { "tag" });
+    let mut log = JobLog::open(&joblog::path(&config.state.jobs_dir, &job))?;
+    let remaining: Vec<String> =
+        pages.iter().filter(|page| !log.is_applied(page)).cloned().collect();
+    if remaining.len() < pages.len() {
+        println!(
+            "Resuming an interrupted run at page {} of {}, {} pages were already done",
+            log.cursor(pages.iter().map(String::as_str)) + 1,
+            pages.len(),
+            pages.len() - remaining.len()
+        );
+    }
+
+    let tagging = &config.tagging;
+    let tags = load_tags(tagging)?;
+    let history = TimingHistory::load(&config.bulk.out_dir);
+    let page_time = history.estimate(&remaining).total();
+    let preview = TagPreview::new(remaining.len(), &tags, clear, page_time, tagging.apply_delay());
+    print_tag_preview(&preview, &config.bulk.out_dir);
+
+    if !yes {
+        if !io::stdin().is_terminal() {
+            anyhow::bail!("No terminal to confirm on, pass --yes to apply the changes");
+        }
+        if !ask_yes_no("Apply these changes?") {
+            println!("Nothing changed.");
+            return Ok(());
+        }
+    }
+
+    let mut failed = 0;
+    for (index, page) in pages.iter().enumerate() {
+        if log.is_applied(page) {
+            continue;
+        }
+        if spider.cancel.is_cancelled() {
+            println!("⚠ Cancelled, run again to continue at page {}", index + 1);
+            return Ok(());
+        }
+        print!("[{}/{}] ", index + 1, pages.len());
+        match tag_node(spider, page, clear).await {
+            // A page cancelled halfway is not finished, so it is tagged again on resume
+            Ok(()) if spider.cancel.is_cancelled() => {}
+            Ok(()) => record_applied(&mut log, page),
+            Err(e) => {
+                println!("  ✗ {page}: {e:#}");
+                failed += 1;
+            }
+        }
+    }
+    if failed > 0 {
+        anyhow::bail!(
+            "{failed} of {} pages could not be tagged, run again to retry them",
+            pages.len()
+        );
+    }
+    log.finish()?;
+    println!("✓ Tagged the questions of {} pages", pages.len());
+    Ok(())
+}
+
+/// Print what a tag apply job is about to change
+fn print_tag_preview(preview: &TagPreview, history_dir: &Path) {
+    /// Tags listed by name, the rest are only counted
+    const SHOWN_TAGS: usize = 20;
+
+    println!("Pages to visit:     {}", preview.pages);
+    match preview.questions {
+        Some(questions) => println!(
+            "Questions to tag:   up to {questions} (those in the tags file found on the pages)"
+        ),
+        None => println!("Questions to clear: all questions on these pages"),
+    }
+    match preview.duration {
+        Some(duration) => println!("Estimated duration: ~{}", format_eta(duration)),
+        None => println!(
+            "Estimated duration: unknown, no timings of earlier runs in {}",
+            history_dir.display()
+        ),
+    }
+    if !preview.by_tag.is_empty() {
+        println!("Questions per tag:");
+        for (tag, count) in preview.by_tag.iter().take(SHOWN_TAGS) {
+            println!("  {count:>5}  {tag}");
+        }
+        if preview.by_tag.len() > SHOWN_TAGS {
+            println!("  ... and {} more tags", preview.by_tag.len() - SHOWN_TAGS);
+        }
+    }
+}
+
+/// Replace the tags of one question and check the value the inspector shows afterwards
+async fn set_question_tags(
+    spider: &Spider,
+    config: &Config,
+    node: &str,
+    question: &str,
+    tags: &str,
+) -> Result<()> {
+    let driver = &spider.driver;
+    open_node(spider, node).await?;
+
+    let in_iframe = enter_content_frame(driver).await?;
+    let title = find_question(driver, question)
+        .await?
+        .with_context(|| format!("Question {question} is not on this page"))?;
+    title.scroll_into_view().await?;
+    title.click().await?;
+    if in_iframe {
+        driver.enter_default_frame().await?;
+    }
+
+    let field = InspectorField::find(driver, "Tags").await?;
+    let old = field.read().await?;
+
+    let record = AuditRecord::new(node, "set_tags", &old, tags);
+    let result = async {
+        field.set(tags).await?;
+        apply_inspector(driver).await?;
+        field.verify(tags, |shown, tags| tag_list(shown) == tag_list(tags)).await
+    }
+    .await;
+
+    let record = match &result {
+        Ok(()) => record,
+        Err(e) => record.failed(e),
+    };
+    audit::append(&config.state.audit_log, &record)?;
+    result?;
+
+    println!("{question}: {old} -> {tags}");
+    Ok(())
+}
+
+/// `add_tags`, recorded as a session action
+async fn recorded_add_tags(clear: bool, driver: &WebDriver, cancel: &CancellationToken) -> Result<()> {
+    let started = Instant::now();
+    let result = add_tags(clear, driver, cancel).await;
+    record_action(driver, Action::AddTags { clear }, started, &result, |_| "tags applied".to_string()).await;
+    result
+}
+
+fn read_line() -> String {
+    let mut input = String::new();
+    print!("> ");
+    io::stdout().flush().unwrap();
+    io::stdin().read_line(&mut input).unwrap();
+    input.trim().to_string()
+}
+
+fn ask_yes_no(question: &str) -> bool {
+    loop {
+        println!("{question} (y/n)");
+        let input = read_line().to_lowercase();
+        match input.as_str() {
+            "y" | "yes" => return true,
+            "n" | "no" => return false,
+            _ => println!("Please enter 'y' or 'n'"),
+        }
+    }
+}
+
+/// Ask for the folder and options of a bulk extraction, then run it
+async fn bulk_extract_content(spider: &Spider, config: &Config) -> Result<()> {
+    let all_sites = !config.sites.is_empty()
+        && ask_yes_no(&format!("Crawl all {} configured sites?", config.sites.len()));
+    let folder = if all_sites {
+        None
+    } else {
+        let default = config.bulk.folder.as_deref().unwrap_or(DEFAULT_FOLDER);
+        println!("Enter the treeitem ID to start extraction from:");
+        let mut target_folder_id = read_line();
+        if target_folder_id.is_empty() {
+            println!("No folder ID provided. Using default: {default}");
+            target_folder_id = default.to_string();
+        }
+        Some(target_folder_id)
+    };
+
+    let options = BulkOptions {
+        validate_urls: ask_yes_no("Do you want to validate URLs? (This runs concurrently with the extraction)"),
+        crawl_dimensions: ask_crawl_dimensions(&config.dimensions),
+        ..bulk_options(config)
+    };
+    run_bulk_extract(spider, config, folder.as_deref(), options).await
+}
+
+/// Bulk options from the [bulk] settings, for runs without prompts
+fn bulk_options(config: &Config) -> BulkOptions {
+    BulkOptions {
+        site: String::new(),
+        validate_urls: config.bulk.validate_urls,
+        crawl_dimensions: config.bulk.all_dimensions && config.dimensions.is_matrix(),
+        max_depth: config.bulk.max_depth,
+        out_dir: config.bulk.out_dir.clone(),
+        sessions: config.bulk.sessions,
+        resume: false,
+        estimate_only: false,
+    }
+}
+
+/// Extract `folder`, or every configured site if no folder is given, or the default folder if
+/// there are no sites either
+async fn run_bulk_extract(
+    spider: &Spider,
+    config: &Config,
+    folder: Option<&str>,
+    mut options: BulkOptions,
+) -> Result<()> {
+    let (driver, cancel) = (&spider.driver, &spider.cancel);
+    println!("\n=== Bulk Content Extraction ===");
+    // Folders may have been collapsed by hand since the last run
+    spider::forget_expanded();
+
+    println!("Checking the CMS session...");
+    check_session(driver, timeouts().element).await?;
+
+    if folder.is_none() && !config.sites.is_empty() {
+        for site in &config.sites {
+            if cancel.is_cancelled() {
+                println!("⚠ Cancelled, skipping site {}", site.name);
+                continue;
+            }
+            println!("\n=== Site: {} ===", site.name);
+            let root = match open_site(driver, site).await {
+                Ok(root) => root,
+                Err(e) => {
+                    eprintln!("✗ Could not open site {}: {e}", site.name);
+                    continue;
+                }
+            };
+            options.site = site.name.clone();
+            let Err(e) = do_bulk_extract(spider, config, &root, &options).await else {
+                continue;
+            };
+            notify::post(&config.notify, &notify::failure(&site.name, &e)).await;
+            // The other sites share the session, so there is no point in continuing
+            if e.is::<ReloginStorm>() {
+                return Err(e);
+            }
+            eprintln!("✗ Extraction of site {} failed: {e}", site.name);
+        }
+        return Ok(());
+    }
+
+    // Without configured sites, the backend's host names the site
+    options.site = match driver.current_url().await {
+        Ok(url) => url.host_str().unwrap_or_default().to_string(),
+        Err(_) => String::new(),
+    };
+    let folder = folder.unwrap_or(DEFAULT_FOLDER);
+    let result = do_bulk_extract(spider, config, folder, &options).await;
+    if let Err(e) = &result {
+        notify::post(&config.notify, &notify::failure(folder, e)).await;
+    }
+    result
+}
+
+/// Record a finished node in the checkpoint. A failed write only means the node is extracted
+/// again when the run is resumed.
+fn save_checkpoint(checkpoint: &mut Checkpoint, record: &NodeRecord) {
+    if let Err(e) = checkpoint.record(record) {
+        eprintln!("⚠ Could not update the checkpoint: {e:#}");
+    }
+}
+
+/// Record an applied item in the job log. A failed write only means the item is applied again
+/// when the job is resumed.
+fn record_applied(log: &mut JobLog, item: &str) {
+    if let Err(e) = log.record(item) {
+        eprintln!("⚠ Could not update the job log: {e:#}");
+    }
+}
+
+/// Capabilities for another browser session, headless if the first one is
+fn session_capabilities(config: &SpiderConfig) -> Result<Capabilities> {
+    spider::capabilities(config, HEADLESS.get().copied().unwrap_or(false))
+}
+
+/// Extract the pages of `items` in `options.sessions` extra browser sessions, feeding the entries
+/// into the checking stage like the serial loop. Shortcuts are left out; dimension variants are
+/// only crawled serially. Each session reads a page like the serial loop, with the same relogin
+/// handling, overload backoff and stuck-page recovery.
+/// Returns the number of entries, of nodes with entries and of failed nodes.
+async fn extract_in_sessions(
+    config: &Config,
+    run_tree: &FileTree,
+    items: &[TreeItem],
+    options: &BulkOptions,
+    entry_tx: &mpsc::Sender<ContentEntry>,
+    checkpoint: &mut Checkpoint,
+    cancel: &CancellationToken,
+) -> Result<(usize, usize, usize)> {
+    println!("Opening {} browser sessions...", options.sessions);
+    let mut pool = DriverPool::connect(
+        options.sessions,
+        &config.spider.webdriver_url,
+        session_capabilities(&config.spider)?,
+        &config.spider.login_url,
+        run_tree,
+    )
+    .await?;
+    pool.set_timeouts(timeouts());
+    pool.set_extraction(&config.extraction);
+    let logins = pool.spiders.iter().map(|spider| login_and_wait(&spider.driver, config));
+    if let Err(e) = futures::future::try_join_all(logins).await {
+        let _ = pool.quit().await;
+        return Err(e.context("Could not log in the extra browser sessions"));
+    }
+    println!("✓ {} sessions logged in", pool.spiders.len());
+
+    // Ctrl+C stops every session after its current node
+    let pool_cancel = pool.cancel.clone();
+    let run_cancel = cancel.clone();
+    let forward = tokio::spawn(async move {
+        run_cancel.cancelled().await;
+        pool_cancel.cancel();
+    });
+
+    let nodes: VecDeque<(usize, &TreeItem)> = items
+        .iter()
+        .filter(|item| !item.is_shortcut(&config.tree.shortcut_icons))
+        .enumerate()
+        .collect();
+    let total = nodes.len();
+    // Each session takes the next node once it is done with its current one
+    let queue = Mutex::new(nodes);
+    let checkpoint = Mutex::new(checkpoint);
+    let sessions = pool.spiders.iter().map(|spider| {
+        extract_in_session(spider, &queue, total, config, options, entry_tx, &checkpoint)
+    });
+    let tallies = futures::future::join_all(sessions).await;
+    forward.abort();
+    pool.quit().await?;
+
+    let (mut found, mut extracted, mut failed) = (0, 0, 0);
+    for tally in tallies {
+        let (session_found, session_extracted, session_failed) = tally?;
+        found += session_found;
+        extracted += session_extracted;
+        failed += session_failed;
+    }
+    Ok((found, extracted, failed))
+}
+
+/// Extract nodes from `queue` in the session of `spider` until it is empty, with the relogin
+/// handling, overload backoff and stuck-page recovery of the serial extraction. Returns the
+/// entries found and the nodes extracted and failed.
+async fn extract_in_session(
+    spider: &Spider,
+    queue: &Mutex<VecDeque<(usize, &TreeItem)>>,
+    total: usize,
+    config: &Config,
+    options: &BulkOptions,
+    entry_tx: &mpsc::Sender<ContentEntry>,
+    checkpoint: &Mutex<&mut Checkpoint>,
+) -> Result<(usize, usize, usize)> {
+    let driver = &spider.driver;
+    let dimension = config.dimensions.values.first().cloned().unwrap_or_default();
+    let mut backoff = config.backoff.to_backoff();
+    let mut dom_errors = 0;
+    let (mut found, mut extracted, mut failed) = (0, 0, 0);
+    loop {
+        if spider.cancel.is_cancelled() || backoff::relogin_storm().is_some() {
+            break;
+        }
+        let Some((index, item)) = queue.lock().unwrap().pop_front() else {
+            break;
+        };
+        println!(
+            "\n=== Processing item {} of {total}: {} (ID: {}) ===",
+            index + 1,
+            item.label,
+            item.id
+        );
+        heartbeat::progress(&item.id, index);
+        if relogin_before_node(driver, &item.id).await {
+            spider.forget_expanded();
+        }
+
+        let node_start = Instant::now();
+        let mut result = read_node_in_session(spider, &item.id, &config.recovery).await;
+        if result.as_ref().is_err_and(is_dom_drift) {
+            dom_errors += 1;
+        } else {
+            dom_errors = 0;
+        }
+        let max_dom_errors = config.recovery.max_dom_errors;
+        if max_dom_errors > 0 && dom_errors >= max_dom_errors {
+            println!("⚠ {dom_errors} nodes in a row failed on missing or stale elements, reloading the backend...");
+            dom_errors = 0;
+            match reload_backend(driver).await {
+                Ok(()) => {
+                    spider.forget_expanded();
+                    if let Err(e) = &result {
+                        record_selector_retry(e);
+                    }
+                    result = read_node_in_session(spider, &item.id, &config.recovery).await;
+                }
+                Err(e) => eprintln!("✗ Could not reload the backend: {e}"),
+            }
+        }
+
+        let count = result.as_ref().map_or(0, |entries| entries.len());
+        match result {
+            Ok(entries) => {
+                let mut record = NodeRecord {
+                    node: item.id.clone(),
+                    ..NodeRecord::default()
+                };
+                for mut entry in entries {
+                    entry.url_valid = "Pending".to_string();
+                    entry.visibility = item.visibility().to_string();
+                    entry.site = options.site.clone();
+                    entry.dimension = dimension.clone();
+                    record.entries.push(ReportEntry::from(&entry));
+                    if entry_tx.send(entry).await.is_err() {
+                        anyhow::bail!("Validation stage stopped unexpectedly");
+                    }
+                }
+                save_checkpoint(&mut checkpoint.lock().unwrap(), &record);
+                if count > 0 {
+                    println!("✓ Found {count} entries in item {}", item.id);
+                    extracted += 1;
+                } else {
+                    println!("⚠ No content found in item {}", item.id);
+                }
+                found += count;
+            }
+            Err(e) => {
+                eprintln!("✗ Failed to extract from item {}: {e:#}", item.id);
+                failed += 1;
+            }
+        }
+        metrics::record_node(&item.id, node_start.elapsed(), count);
+
+        pace_next_node(driver, &mut backoff, node_start.elapsed(), &config.backoff).await;
+    }
+    Ok((found, extracted, failed))
+}
+
+/// Open `node_id` in the session of `spider` and read its entries like the serial extraction,
+/// reloading the page like `wait_for_node_load` when the loading overlay gets stuck
+async fn read_node_in_session(
+    spider: &Spider,
+    node_id: &str,
+    recovery: &RecoveryConfig,
+) -> Result<Vec<ContentEntry>> {
+    spider.click_treeitem(node_id).await?;
+    wait_for_node_load(&spider.driver, recovery, || async {
+        reload_backend(&spider.driver).await?;
+        spider.forget_expanded();
+        spider.click_treeitem(node_id).await
+    })
+    .await?;
+    extract_loaded_page(&spider.driver, node_id).await
+}
+
+/// Deal with a relogin dialog, or renew the session if it is about to expire, before the next
+/// node. Returns whether the backend was reloaded, which collapses the document tree
+async fn relogin_before_node(driver: &WebDriver, node_id: &str) -> bool {
+    if auth().detect_expired(driver).await {
+        println!("Relogin dialog detected before processing item {node_id}");
+        match handle_relogin_dialog(driver).await {
+            Ok(true) => println!("Relogin successful, continuing..."),
+            Ok(false) => println!("Relogin failed, but continuing..."),
+            Err(e) => println!("Error handling relogin: {e}, continuing..."),
+        }
+        return false;
+    }
+    match renew_session_if_due(driver).await {
+        Ok(renewed) => renewed,
+        Err(e) => {
+            println!("⚠ Could not start a new session: {e:#}, continuing...");
+            true
+        }
+    }
+}
+
+/// The error that stopped the validation stage once it no longer accepts entries
+async fn validation_stopped(checker: tokio::task::JoinHandle<Result<usize>>) -> anyhow::Error {
+    match checker.await {
+        Ok(Err(e)) => e.context("Validation stage stopped"),
+        Ok(Ok(_)) => anyhow::anyhow!("Validation stage stopped unexpectedly"),
+        Err(e) => anyhow::Error::new(e).context("Validation task panicked"),
+    }
+}
+
+/// Open the configured output sinks for the results of `job`, writing files to `dir`
+fn open_sinks(config: &Config, dir: &Path, job: &str) -> Result<MultiSink> {
+    let mut sink = sink::from_config(&config.output, dir)?;
+    sink.open(job)?;
+    Ok(sink)
+}
+
+/// Open a node copied from the browser and read its tree item
+async fn open_node_ref(driver: &WebDriver, node: &NodeRef) -> Result<TreeItem> {
+    match node {
+        NodeRef::TreeItem(id) => read_tree_item(driver, id).await,
+        NodeRef::BackendUrl(url) => {
+            driver.goto(url).await?;
+            spider::forget_expanded();
+            require_page_load(driver).await?;
+            let tree = &Selectors::get().tree;
+            let focused = selectors::find_first(&driver.handle, "tree.focused", &tree.focused)
+                .await
+                .context("The node of the copied URL is not selected in the document tree")?;
+            TreeItem::from_element(&focused).await
+        }
+    }
+}
+
+/// Open a single node and extract its entries, without its descendants
+async fn extract_single_node(
+    driver: &WebDriver,
+    config: &Config,
+    node: &NodeRef,
+) -> Result<(TreeItem, Vec<ContentEntry>)> {
+    let item = open_node_ref(driver, node).await?;
+    println!("Extracting {} ({})", item.label, item.id);
+
+    let mut entries = recorded_extract(driver, &item.id, &config.recovery).await?;
+    let site = match driver.current_url().await {
+        Ok(url) => url.host_str().unwrap_or_default().to_string(),
+        Err(_) => String::new(),
+    };
+    let dimension = config.dimensions.values.first().cloned().unwrap_or_default();
+    for entry in &mut entries {
+        entry.visibility = item.visibility().to_string();
+        entry.site = site.clone();
+        entry.dimension = dimension.clone();
+    }
+    Ok((item, entries))
+}
 
-        let tag_textbox = driver
-            .query(By::Css("#__neos__editor__property---Tags"))
-            .first()
-            .await?;
+/// Extract and check a single node, then print its entries as a table or JSON
+async fn extract_one(driver: &WebDriver, config: &Config, node: &str, json: bool) -> Result<()> {
+    let node = NodeRef::parse(node).context("Expected a treeitem ID or a backend URL")?;
+    let (item, mut entries) = extract_single_node(driver, config, &node).await?;
 
-        driver
-            .action_chain()
-            .click_element(&tag_textbox)
-            .key_down(thirtyfour::Key::Control)
-            .send_keys("a")
-            .key_up(thirtyfour::Key::Control)
-            .send_keys(thirtyfour::Key::Backspace)
-            .perform()
-            .await?;
+    // Check the node's URLs at once, the entries then read their status from the validator
+    let validator = Validator::new(&config.checks);
+    validator
+        .checks(entries.iter().map(|entry| entry.url.as_str()))
+        .for_each(|_| async {})
+        .await;
+    let current_year = Local::now().year();
+    for entry in &mut entries {
+        let max_age = config.checks.max_content_age_years;
+        check_entry(entry, Some(&validator), current_year, max_age).await;
+    }
 
-        if !clear {
-            if let Some(val) = tags.get(id) {
-                tag_textbox.send_keys(val).await?;
-            } else {
-                eprintln!("Error: key {id} not found! Skipping...");
-                iframe.clone().enter_frame().await?;
-                continue;
-            }
+    if porcelain::is_enabled() {
+        for entry in &entries {
+            porcelain::emit(&ReportEntry::from(entry))?;
         }
+    } else if json {
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+    } else {
+        println!("\n=== {} ({}, {}) ===", item.label, item.id, item.visibility());
+        print_entries_table(&entries);
+    }
+    Ok(())
+}
 
-        let apply_button = driver
-            .query(By::Css("#neos-Inspector-Apply"))
-            .first()
-            .await?;
-        apply_button.click().await?;
+/// Print entries as an aligned table, shortening long titles and URLs
+fn print_entries_table(entries: &[ContentEntry]) {
+    if entries.is_empty() {
+        println!("No entries found.");
+        return;
+    }
 
-        println!("{id} -> {value}");
-        iframe.clone().enter_frame().await?;
-        support::sleep(Duration::new(1, 0)).await;
+    let rows: Vec<[String; 5]> = entries
+        .iter()
+        .map(|entry| {
+            let checks: Vec<&str> = [&entry.visual_check, &entry.title_check, &entry.age_flag]
+                .into_iter()
+                .map(String::as_str)
+                .filter(|check| !check.is_empty())
+                .collect();
+            [
+                entry.content_type.clone(),
+                shorten(&entry.title, 40),
+                shorten(&entry.url, 60),
+                entry.url_valid.clone(),
+                checks.join(", "),
+            ]
+        })
+        .collect();
+
+    let header = ["Type", "Title", "URL", "URL Valid", "Checks"];
+    let mut widths = header.map(|h| h.chars().count());
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
     }
-    driver.enter_default_frame().await?;
-    Ok(())
+
+    let line = |cells: [&str; 5]| {
+        let padded: Vec<String> = cells
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{cell:<width$}"))
+            .collect();
+        println!("{}", padded.join("  ").trim_end());
+    };
+    line(header);
+    let rule = widths.map(|width| "─".repeat(width));
+    line(rule.each_ref().map(String::as_str));
+    for row in &rows {
+        line(row.each_ref().map(String::as_str));
+    }
+    println!("{} entries", entries.len());
 }
 
-fn read_line() -> String {
-    let mut input = String::new();
-    print!("> ");
-    io::stdout().flush().unwrap();
-    io::stdin().read_line(&mut input).unwrap();
-    input.trim().to_string()
+/// Cut `text` to at most `max` characters, marking the cut with an ellipsis
+fn shorten(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    let cut: String = text.chars().take(max - 1).collect();
+    format!("{cut}…")
 }
 
-fn ask_yes_no(question: &str) -> bool {
-    loop {
-        println!("{question} (y/n)");
-        let input = read_line().to_lowercase();
-        match input.as_str() {
-            "y" | "yes" => return true,
-            "n" | "no" => return false,
-            _ => println!("Please enter 'y' or 'n'"),
+/// Extract only the node on the clipboard, checking and saving its entries like a bulk run
+async fn extract_from_clipboard(
+    driver: &WebDriver,
+    config: &Config,
+    cancel: &CancellationToken,
+) -> Result<()> {
+    let node = clipboard::read_node_ref()?;
+    let (item, entries) = extract_single_node(driver, config, &node).await?;
+
+    fs::create_dir_all("./embedded_content")
+        .context("Failed to create embedded_content directory")?;
+    let started_at = Local::now();
+    let output = open_sinks(config, Path::new("./embedded_content"), &item.id)?;
+
+    // A single node has few entries, so its URLs are always validated
+    let (entry_tx, entry_rx) = mpsc::channel(100);
+    let (checked_tx, checked_rx) = mpsc::channel(100);
+    let checker = tokio::spawn(check_entries(
+        entry_rx,
+        checked_tx,
+        true,
+        config.checks.clone(),
+        cancel.clone(),
+    ));
+    let writer = tokio::spawn(write_entries(checked_rx, output, config.output.clone()));
+
+    for entry in entries {
+        if entry_tx.send(entry).await.is_err() {
+            return Err(validation_stopped(checker).await);
         }
     }
+    drop(entry_tx);
+    let outdated = checker.await.context("Validation task panicked")??;
+    let (written, mut output) = writer.await.context("Writer task panicked")??;
+    output.write_summary(&RunSummary {
+        job: item.id.clone(),
+        started_at,
+        finished_at: Local::now(),
+        entries: written,
+        outdated,
+        relogin_storm: backoff::relogin_storm(),
+    })?;
+    output.close()?;
+
+    println!("Entries flagged as outdated: {outdated}");
+    println!("{written} entries saved to: {}", output.location());
+    Ok(())
 }
 
-async fn bulk_extract_content(driver: &WebDriver) -> Result<()> {
-    println!("\n=== Bulk Content Extraction ===");
+/// Choices made once for a bulk extraction run
+struct BulkOptions {
+    /// Recorded in the Site column of every entry
+    site: String,
+    validate_urls: bool,
+    /// Also extract the other configured dimension variants of every node
+    crawl_dimensions: bool,
+    /// Levels below the target folder to traverse
+    max_depth: usize,
+    /// Directory the results and reports are written to
+    out_dir: PathBuf,
+    /// Browser sessions extracting the descendants in parallel
+    sessions: usize,
+    /// Skip the nodes finished by an earlier run, as recorded in its checkpoint
+    resume: bool,
+    /// Stop after printing the expected duration, without extracting anything
+    estimate_only: bool,
+}
 
-    println!("Enter the treeitem ID to start extraction from:");
-    let target_folder_id = read_line();
+fn ask_crawl_dimensions(dimensions: &DimensionsConfig) -> bool {
+    dimensions.is_matrix()
+        && ask_yes_no(&format!(
+            "Extract all dimension variants ({})?",
+            dimensions.values.join(", ")
+        ))
+}
+
+/// Switch the backend to a configured site and return the treeitem ID to start from
+async fn open_site(driver: &WebDriver, site: &SiteConfig) -> Result<String> {
+    if let Some(domain) = &site.domain {
+        println!("Opening the backend of {domain}...");
+        driver.get(format!("https://{domain}/neos/content")).await?;
+        spider::forget_expanded();
+
+        // Each domain has its own session, so we may land on the login page
+        let (tree, login_form) = (&Selectors::get().tree, &Selectors::get().login);
+        wait::until(timeouts().page_load, || async {
+            wait::is_present(&driver.handle, &tree.root).await
+                || wait::is_present(&driver.handle, &login_form.username).await
+        })
+        .await;
+        if selectors::probe_first(&driver.handle, "login.username", &login_form.username).await.is_ok() {
+            login(driver).await?;
+            wait::wait_for_element(&driver.handle, "tree.root", &tree.root, timeouts().page_load).await?;
+        }
+        // The tree items are waited for below
+        if !wait_for_page_load(driver, timeouts().page_load).await? {
+            println!("  ⚠ The backend of {domain} was still loading");
+        }
+    }
 
-    if target_folder_id.is_empty() {
-        println!("No folder ID provided. Using default: treeitem-c6643bf0-label");
-        let target_folder_id = "treeitem-c6643bf0-label";
-        return do_bulk_extract(driver, target_folder_id).await;
+    if let Some(root) = &site.root {
+        return Ok(root.clone());
+    }
+    if site.domain.is_none() {
+        anyhow::bail!("Site {} needs a root node or a domain", site.name);
     }
 
-    do_bulk_extract(driver, &target_folder_id).await
+    // The first tree item is the root node of the site shown in the backend
+    let tree = &Selectors::get().tree;
+    let items = selectors::find_all_first(&driver.handle, "tree.child_item", &tree.child_item).await?;
+    let root = items.first().context("The document tree is empty")?;
+    let root = TreeItem::from_element(root).await?;
+    println!("Using site root node: {} ({})", root.label, root.id);
+    Ok(root.id)
 }
 
-async fn do_bulk_extract(driver: &WebDriver, target_folder_id: &str) -> Result<()> {
-    let validate_urls = ask_yes_no("Do you want to validate URLs? (This will be done concurrently at the end)");
+async fn do_bulk_extract(
+    spider: &Spider,
+    config: &Config,
+    target_folder_id: &str,
+    options: &BulkOptions,
+) -> Result<()> {
+    let (driver, cancel) = (&spider.driver, &spider.cancel);
+    let validate_urls = options.validate_urls;
+    let site = options.site.as_str();
+    let base_dimension = config.dimensions.values.first().cloned().unwrap_or_default();
 
     println!("Starting bulk extraction from folder: {target_folder_id}");
+    let started_at = Local::now();
 
     println!("Checking if target folder exists on current page...");
 
@@ -1098,73 +4202,228 @@ async fn do_bulk_extract(driver: &WebDriver, target_folder_id: &str) -> Result<(
         .unwrap_or_else(|_| "Unknown".to_string());
     println!("Current page title: {}", page_title);
 
-    // Wait a bit to ensure page is fully loaded
     println!("Waiting for page to load completely...");
-    support::sleep(Duration::from_secs(2)).await;
+    wait_for_backend(driver).await?;
 
     // Navigate to the target folder and expand it
-    expand_folder_if_needed(driver, target_folder_id).await?;
+    let started = Instant::now();
+    let expanded = expand_folder_if_needed(driver, target_folder_id).await;
+    let action = Action::ExpandFolder {
+        folder_id: target_folder_id.to_string(),
+    };
+    record_action(driver, action, started, &expanded, |_| "expanded".to_string()).await;
+    expanded?;
 
     // Get all descendants (children, grandchildren, etc.) of the target folder
-    let max_traversal_depth = 5;
+    let max_traversal_depth = options.max_depth;
     println!("Starting recursive traversal with max depth: {max_traversal_depth}");
-    let child_ids = get_all_descendants(driver, target_folder_id, max_traversal_depth, 0).await?;
+    // Where each node sits below the target folder, to find it again after a page reload
+    let mut run_tree = FileTree::new(target_folder_id.to_string());
+    let descendants =
+        get_all_descendants(spider, &mut run_tree, target_folder_id, max_traversal_depth).await?;
     println!(
         "Found {} total items to process (including all descendants)",
-        child_ids.len()
+        descendants.len()
     );
 
-    // Create embedded_content directory if it doesn't exist
-    fs::create_dir_all("./embedded_content")
-        .context("Failed to create embedded_content directory")?;
+    // Earlier runs' timings tell how long this one takes, before committing to it
+    let history = TimingHistory::load(&options.out_dir);
+    let mut nodes: Vec<&str> = descendants.iter().map(|item| item.id.as_str()).collect();
+    nodes.push(target_folder_id);
+    let parallel = if options.crawl_dimensions { 1 } else { options.sessions.max(1) };
+    print_estimate(&history.estimate(&nodes), &options.out_dir, parallel);
+    if options.estimate_only {
+        return Ok(());
+    }
+
+    // The target folder itself is processed last
+    let _heartbeat = heartbeat::spawn(&config.heartbeat, target_folder_id, descendants.len() + 1);
+
+    // Create the results directory if it doesn't exist
+    fs::create_dir_all(&options.out_dir).with_context(|| {
+        format!("Failed to create results directory {}", options.out_dir.display())
+    })?;
+
+    // Results are named after the target folder
+    let mut output_config = config.output.clone();
+    if config.bulk.html_report && !output_config.sinks.contains(&SinkKind::Html) {
+        output_config.sinks.push(SinkKind::Html);
+    }
+    let mut output = sink::from_config(&output_config, &options.out_dir)?;
+    output.open(target_folder_id)?;
+    println!("Results will be saved to: {}", output.location());
+
+    if !validate_urls {
+        println!("URL validation skipped by user");
+    }
 
-    // Create CSV writer with entry ID as filename
-    let output_file = format!("./embedded_content/{target_folder_id}.csv");
-    println!("CSV will be saved to: {output_file}");
-    let mut csv_writer = Writer::from_path(&output_file).context("Failed to create CSV file")?;
-
-    // Write CSV header
-    csv_writer
-        .write_record([
-            "Source Node",
-            "Breadcrumb Path",
-            "Content Type",
-            "URL",
-            "Title",
-            "Author",
-            "File Type",
-            "Size",
-            "URL Valid",
-        ])
-        .context("Failed to write CSV header")?;
-
-    let mut all_entries = Vec::new();
+    // Browser -> checks/validation -> CSV writer, each stage running as its own task
+    let (entry_tx, entry_rx) = mpsc::channel(100);
+    let (checked_tx, checked_rx) = mpsc::channel(100);
+    let checker = tokio::spawn(check_entries(
+        entry_rx,
+        checked_tx,
+        validate_urls,
+        config.checks.clone(),
+        cancel.clone(),
+    ));
+    let writer = tokio::spawn(write_entries(checked_rx, output, config.output.clone()));
+
+    let mut backoff = config.backoff.to_backoff();
+    let mut shortcuts = Vec::new();
+    let mut coverage = Vec::new();
+    let mut total_entries = 0;
     let mut successful = 0;
     let mut failed = 0;
+    let mut dom_errors = 0;
+
+    // Nodes an interrupted earlier run finished are restored from its checkpoint instead of
+    // being extracted again
+    let checkpoint_path = checkpoint::path(&options.out_dir, target_folder_id);
+    let mut done = HashSet::new();
+    if options.resume {
+        for record in checkpoint::load(&checkpoint_path)? {
+            total_entries += record.entries.len();
+            if !record.entries.is_empty() {
+                successful += 1;
+            }
+            coverage.extend(record.coverage);
+            for entry in record.entries {
+                if entry_tx.send(ContentEntry::from(entry)).await.is_err() {
+                    return Err(validation_stopped(checker).await);
+                }
+            }
+            done.insert(record.node);
+        }
+        println!(
+            "Resuming: {} nodes and {total_entries} entries restored from {}",
+            done.len(),
+            checkpoint_path.display()
+        );
+    }
+    let mut checkpoint = Checkpoint::open(&checkpoint_path, options.resume)?;
+    let pending: Vec<TreeItem> =
+        descendants.iter().filter(|item| !done.contains(&item.id)).cloned().collect();
+
+    // Extra sessions extract the pages in parallel; shortcuts are still resolved below, in the
+    // session that traversed the tree
+    let mut serial = pending.clone();
+    if options.sessions > 1 && options.crawl_dimensions {
+        println!("⚠ Dimension variants are only crawled in a single session, extracting serially");
+    } else if options.sessions > 1 {
+        let result = extract_in_sessions(
+            config,
+            &run_tree,
+            &pending,
+            options,
+            &entry_tx,
+            &mut checkpoint,
+            cancel,
+        )
+        .await;
+        if entry_tx.is_closed() {
+            return Err(validation_stopped(checker).await);
+        }
+        let (found, extracted, failures) = result?;
+        total_entries += found;
+        successful += extracted;
+        failed += failures;
+        serial.retain(|item| item.is_shortcut(&config.tree.shortcut_icons));
+    }
 
-    for (index, child_id) in child_ids.iter().enumerate() {
+    let serial_ids: Vec<&str> = serial.iter().map(|item| item.id.as_str()).collect();
+    let estimate = history.estimate(&serial_ids);
+    let serial_started = Instant::now();
+    for (index, item) in serial.iter().enumerate() {
+        let child_id = &item.id;
         println!(
-            "\n=== Processing item {} of {} (ID: {}) ===",
+            "\n=== Processing item {} of {}: {} (ID: {}) ===",
             index + 1,
-            child_ids.len(),
+            serial.len(),
+            item.label,
             child_id
         );
+        if index > 0 {
+            if let Some(left) = estimate.remaining(index, serial_started.elapsed()) {
+                println!("Estimated time left: ~{}", format_eta(left));
+            }
+        }
 
         // Check for relogin dialog before processing each item
-        if is_relogin_dialog_present(driver).await {
-            println!("Relogin dialog detected before processing item {child_id}");
-            match handle_relogin_dialog(driver).await {
-                Ok(true) => println!("Relogin successful, continuing..."),
-                Ok(false) => println!("Relogin failed, but continuing..."),
-                Err(e) => println!("Error handling relogin: {e}, continuing..."),
+        relogin_before_node(driver, child_id).await;
+
+        // Stop crawling on Ctrl+C or once the relogin breaker tripped; the results so far are
+        // still saved
+        if cancel.is_cancelled() {
+            break;
+        }
+        if let Some(storm) = backoff::relogin_storm() {
+            eprintln!("✗ Stopping the extraction: {storm}");
+            break;
+        }
+
+        heartbeat::progress(child_id, index);
+        let node_start = Instant::now();
+
+        // Shortcuts only redirect to other nodes, so record the target instead of extracting
+        if item.is_shortcut(&config.tree.shortcut_icons) {
+            match resolve_shortcut(driver, item).await {
+                Ok(shortcut) => {
+                    println!("↪ Shortcut {} -> {}", item.label, shortcut.target_url);
+                    shortcuts.push(shortcut);
+                }
+                Err(e) => eprintln!("✗ Could not resolve shortcut {child_id}: {e}"),
+            }
+            metrics::record_node(child_id, node_start.elapsed(), 0);
+            pace_next_node(driver, &mut backoff, node_start.elapsed(), &config.backoff).await;
+            continue;
+        }
+
+        let mut result = recorded_extract(driver, child_id, &config.recovery).await;
+        if result.as_ref().is_err_and(is_dom_drift) {
+            dom_errors += 1;
+        } else {
+            dom_errors = 0;
+        }
+
+        // Several nodes in a row failing on missing elements means the DOM drifted; reload and retry
+        let max_dom_errors = config.recovery.max_dom_errors;
+        if max_dom_errors > 0 && dom_errors >= max_dom_errors {
+            println!("⚠ {dom_errors} nodes in a row failed on missing or stale elements, reloading the backend...");
+            dom_errors = 0;
+            match restore_tree_path(driver, &run_tree, child_id).await {
+                Ok(()) => {
+                    println!("✓ Tree expanded again, retrying item {child_id}");
+                    if let Err(e) = &result {
+                        record_selector_retry(e);
+                    }
+                    result = recorded_extract(driver, child_id, &config.recovery).await;
+                }
+                Err(e) => eprintln!("✗ Could not expand the tree again after the reload: {e}"),
             }
         }
 
-        match extract_content_from_page(driver, child_id).await {
+        let found = result.as_ref().map_or(0, |entries| entries.len());
+        let extracted = result.is_ok();
+        let mut record = NodeRecord {
+            node: child_id.clone(),
+            ..NodeRecord::default()
+        };
+
+        match result {
             Ok(entries) => {
                 if !entries.is_empty() {
                     println!("✓ Found {} entries in item {}", entries.len(), child_id);
-                    all_entries.extend(entries);
+                    total_entries += entries.len();
+                    for mut entry in entries {
+                        entry.visibility = item.visibility().to_string();
+                        entry.site = site.to_string();
+                        entry.dimension = base_dimension.clone();
+                        record.entries.push(ReportEntry::from(&entry));
+                        if entry_tx.send(entry).await.is_err() {
+                            return Err(validation_stopped(checker).await);
+                        }
+                    }
                     successful += 1;
                 } else {
                     println!("⚠ No content found in item {child_id}");
@@ -1176,100 +4435,664 @@ async fn do_bulk_extract(driver: &WebDriver, target_folder_id: &str) -> Result<(
             }
         }
 
-        // Reduced sleep delay from 1500ms to 800ms
-        support::sleep(Duration::from_millis(800)).await;
+        let mut variant_count = 0;
+        if options.crawl_dimensions && extracted {
+            match extract_variants(driver, child_id, &item.label, &config.dimensions).await {
+                Ok((variants, found_in)) => {
+                    record.coverage.extend(found_in.iter().cloned());
+                    coverage.extend(found_in);
+                    variant_count = variants.len();
+                    total_entries += variants.len();
+                    for mut entry in variants {
+                        entry.visibility = item.visibility().to_string();
+                        entry.site = site.to_string();
+                        record.entries.push(ReportEntry::from(&entry));
+                        if entry_tx.send(entry).await.is_err() {
+                            return Err(validation_stopped(checker).await);
+                        }
+                    }
+                }
+                Err(e) => eprintln!("✗ Could not extract dimension variants of {child_id}: {e}"),
+            }
+        }
+        metrics::record_node(child_id, node_start.elapsed(), found + variant_count);
+        if extracted {
+            save_checkpoint(&mut checkpoint, &record);
+        }
+
+        pace_next_node(driver, &mut backoff, node_start.elapsed(), &config.backoff).await;
     }
 
-    // Also extract from the target folder itself
-    println!("\nProcessing target folder: {target_folder_id}");
-    match extract_content_from_page(driver, target_folder_id).await {
-        Ok(entries) => {
-            if !entries.is_empty() {
-                println!("Found {} entries in target folder", entries.len());
-                all_entries.extend(entries);
-                successful += 1;
+    // Also extract from the target folder itself, unless the run was cancelled
+    if cancel.is_cancelled() {
+        println!("\n⚠ Cancelled, skipping the remaining items and the target folder");
+    } else if done.contains(target_folder_id) {
+        println!("\nTarget folder {target_folder_id} restored from the checkpoint");
+    } else {
+        println!("\nProcessing target folder: {target_folder_id}");
+        let target_item = read_tree_item(driver, target_folder_id).await.ok();
+        heartbeat::progress(target_folder_id, descendants.len());
+        let node_start = Instant::now();
+        let result = recorded_extract(driver, target_folder_id, &config.recovery).await;
+        let found = result.as_ref().map_or(0, |entries| entries.len());
+        let extracted = result.is_ok();
+        let mut record = NodeRecord {
+            node: target_folder_id.to_string(),
+            ..NodeRecord::default()
+        };
+
+        match result {
+            Ok(entries) => {
+                if !entries.is_empty() {
+                    println!("Found {} entries in target folder", entries.len());
+                    total_entries += entries.len();
+                    for mut entry in entries {
+                        entry.visibility = target_item.as_ref().map_or("Unknown", TreeItem::visibility).to_string();
+                        entry.site = site.to_string();
+                        entry.dimension = base_dimension.clone();
+                        record.entries.push(ReportEntry::from(&entry));
+                        if entry_tx.send(entry).await.is_err() {
+                            return Err(validation_stopped(checker).await);
+                        }
+                    }
+                    successful += 1;
+                }
+            }
+            Err(e) => {
+                eprintln!("Failed to extract from target folder {target_folder_id}: {e}");
+                failed += 1;
             }
         }
-        Err(e) => {
-            eprintln!("Failed to extract from target folder {target_folder_id}: {e}");
-            failed += 1;
+
+        let mut variant_count = 0;
+        if options.crawl_dimensions && extracted {
+            let label = target_item.as_ref().map_or(target_folder_id, |item| item.label.as_str());
+            match extract_variants(driver, target_folder_id, label, &config.dimensions).await {
+                Ok((variants, found_in)) => {
+                    record.coverage.extend(found_in.iter().cloned());
+                    coverage.extend(found_in);
+                    variant_count = variants.len();
+                    total_entries += variants.len();
+                    for mut entry in variants {
+                        entry.visibility = target_item.as_ref().map_or("Unknown", TreeItem::visibility).to_string();
+                        entry.site = site.to_string();
+                        record.entries.push(ReportEntry::from(&entry));
+                        if entry_tx.send(entry).await.is_err() {
+                            return Err(validation_stopped(checker).await);
+                        }
+                    }
+                }
+                Err(e) => eprintln!("✗ Could not extract dimension variants of {target_folder_id}: {e}"),
+            }
+        }
+        metrics::record_node(target_folder_id, node_start.elapsed(), found + variant_count);
+        if extracted {
+            save_checkpoint(&mut checkpoint, &record);
         }
     }
 
     println!("\n=== Content extraction complete! ===");
-    println!("Total entries found: {}", all_entries.len());
+    println!("Total entries found: {total_entries}");
     println!("Successfully processed pages: {successful}");
     println!("Failed pages: {failed}");
+    let drift = selectors::drift_suspected();
+    if !drift.is_empty() {
+        let containers: usize = drift.values().sum();
+        println!(
+            "⚠ Selector drift suspected on {} nodes ({containers} content containers without any \
+             expected field); the Neos templates may have changed",
+            drift.len()
+        );
+    }
+    let storm = backoff::relogin_storm();
+    if let Some(storm) = &storm {
+        println!(
+            "⚠ Aborted at {} by the relogin circuit breaker: {storm}",
+            storm.at.format("%H:%M:%S")
+        );
+    }
 
-    // Validate URLs concurrently if requested
-    if validate_urls && !all_entries.is_empty() {
-        println!("\n=== Starting concurrent URL validation ===");
-        validate_urls_concurrent(&mut all_entries, 15).await;
-    } else if !validate_urls {
-        // Mark all as skipped
-        for entry in &mut all_entries {
-            entry.url_valid = "Skipped".to_string();
+    // Closing the channel lets the remaining stages drain and finish
+    drop(entry_tx);
+    if validate_urls {
+        println!("\nWaiting for remaining URL validations...");
+    }
+    let outdated = checker.await.context("Validation task panicked")??;
+    let (written, mut output) = writer.await.context("Writer task panicked")??;
+    let summary = RunSummary {
+        job: target_folder_id.to_string(),
+        started_at,
+        finished_at: Local::now(),
+        entries: written,
+        outdated,
+        relogin_storm: storm.clone(),
+    };
+    output.write_summary(&summary)?;
+    output.close()?;
+    // An aborted run is reported as a failure by the caller
+    if storm.is_none() {
+        let message = notify::run_summary(&summary, failed, cancel.is_cancelled());
+        notify::post(&config.notify, &message).await;
+    }
+    if let Some(path) = &config.calendar.path {
+        if let Err(e) = calendar::write(&config.calendar, &config.output.sqlite_path, path) {
+            println!("⚠ Could not update the calendar: {e:#}");
         }
-        println!("URL validation skipped by user");
     }
 
-    // Write all entries to CSV
-    println!("\nWriting results to CSV...");
-    for entry in &all_entries {
-        csv_writer
-            .write_record([
-                &entry.source_node,
-                &entry.breadcrumb_path,
-                &entry.content_type,
-                &entry.url,
-                &entry.title,
-                &entry.author,
-                &entry.file_type,
-                &entry.size,
-                &entry.url_valid,
-            ])
-            .context("Failed to write CSV record")?;
+    println!("Entries flagged as outdated: {outdated}");
+    if cancel.is_cancelled() || storm.is_some() {
+        println!(
+            "Progress saved to {}; run `extract --resume` to continue",
+            checkpoint_path.display()
+        );
+    } else {
+        checkpoint.finish()?;
+    }
+
+    if !coverage.is_empty() {
+        let coverage_file = options.out_dir.join(format!("{target_folder_id}-translations.csv"));
+        write_coverage(&coverage_file, &coverage)?;
+        for (dimension, missing) in dimensions::missing_per_dimension(&coverage) {
+            println!("Nodes missing a {dimension} variant: {missing}");
+        }
+        println!("Translation coverage saved to: {}", coverage_file.display());
+    }
+
+    if !shortcuts.is_empty() {
+        let shortcut_file = options.out_dir.join(format!("{target_folder_id}-shortcuts.csv"));
+        write_shortcuts(&shortcut_file, &shortcuts)?;
+        println!(
+            "{} shortcut nodes skipped, targets saved to: {}",
+            shortcuts.len(),
+            shortcut_file.display()
+        );
     }
 
-    csv_writer.flush().context("Failed to flush CSV writer")?;
+    let timing = TimingReport::collect();
+    timing.print();
+    selectors::print_match_report();
+    selectors::print_flakiness_leaderboard(10);
+    let timing_file = options.out_dir.join(format!("{target_folder_id}-timing.json"));
+    timing.to_json_file(&timing_file)?;
 
     println!("\n=== Bulk extraction complete! ===");
-    println!("CSV output saved to: {output_file}");
+    println!("{written} entries saved to: {}", output.location());
+    println!("Timing report saved to: {}", timing_file.display());
 
-    Ok(())
+    match storm {
+        None if cancel.is_cancelled() => {
+            heartbeat::finish("cancelled");
+            Ok(())
+        }
+        Some(storm) => {
+            heartbeat::finish("aborted");
+            Err(storm.into())
+        }
+        None => {
+            heartbeat::finish("finished");
+            Ok(())
+        }
+    }
+}
+
+/// Cancel `token` on Ctrl+C, so running work stops at the next node or question and keeps its
+/// results. A second Ctrl+C quits right away.
+fn cancel_on_ctrl_c(token: CancellationToken) {
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_err() {
+            return;
+        }
+        println!("\n⚠ Stopping after the current step, press Ctrl+C again to quit immediately");
+        token.cancel();
+        if tokio::signal::ctrl_c().await.is_ok() {
+            std::process::exit(130);
+        }
+    });
+}
+
+/// Cancel `token` once `duration` has passed, wrapping the run up like Ctrl+C does
+fn cancel_after(token: CancellationToken, duration: Duration) {
+    tokio::spawn(async move {
+        tokio::time::sleep(duration).await;
+        if token.is_cancelled() {
+            return;
+        }
+        println!(
+            "\n⚠ Maximum run duration of {} reached, stopping after the current node",
+            format_duration(duration)
+        );
+        token.cancel();
+    });
+}
+
+/// Print the expected duration of a run, spread over `parallel` sessions
+fn print_estimate(estimate: &Estimate, history_dir: &Path, parallel: usize) {
+    match estimate.total() {
+        Some(total) => println!(
+            "Estimated duration: ~{} ({} of the nodes were timed in earlier runs)",
+            format_eta(total / parallel as u32),
+            estimate.known()
+        ),
+        None => println!(
+            "No timings of earlier runs in {}, the time left is estimated once the first nodes \
+             are done",
+            history_dir.display()
+        ),
+    }
+}
+
+/// A remaining time rounded down to minutes, e.g. `2h15m`
+fn format_eta(duration: Duration) -> String {
+    if duration.as_secs() < 60 {
+        return "<1m".to_string();
+    }
+    format_duration(Duration::from_secs(duration.as_secs() / 60 * 60))
+}
+
+/// Parse a duration like `2h`, `90m`, `45s` or `1h30m`
+fn parse_duration(text: &str) -> Result<Duration, String> {
+    let mut total = 0;
+    let mut number = String::new();
+    for c in text.trim().chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let unit = match c {
+            'h' => 3600,
+            'm' => 60,
+            's' => 1,
+            _ => return Err(format!("unknown unit '{c}', use h, m or s (e.g. 1h30m)")),
+        };
+        let value: u64 = number
+            .parse()
+            .map_err(|_| format!("expected a number before '{c}' (e.g. 1h30m)"))?;
+        total += value * unit;
+        number.clear();
+    }
+    if !number.is_empty() {
+        return Err(format!("'{number}' has no unit, use h, m or s (e.g. {number}m)"));
+    }
+    if total == 0 {
+        return Err("the duration must be longer than 0s".to_string());
+    }
+    Ok(Duration::from_secs(total))
+}
+
+/// `duration` in the form `parse_duration` reads, e.g. `1h30m`
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    let parts = [(secs / 3600, "h"), (secs / 60 % 60, "m"), (secs % 60, "s")];
+    parts
+        .iter()
+        .filter(|(value, _)| *value > 0)
+        .map(|(value, unit)| format!("{value}{unit}"))
+        .collect()
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let filetree = FileTree::from_json_file(PathBuf::from("resources/tree.json"))
+    let cli = Cli::parse();
+    match &cli.command {
+        Some(Command::Completions { shell }) => {
+            print_completions(*shell);
+            return Ok(());
+        }
+        // Runs before loading the configuration, so an invalid file is reported like any problem
+        Some(Command::Config {
+            command: ConfigCommand::Check,
+        }) => return check_config(&cli.config).await,
+        _ => {}
+    }
+    let mut config = Config::load(&cli.config).context("Could not load configuration")?;
+    if let Some(login_url) = &cli.login_url {
+        config.spider.login_url = login_url.clone();
+    }
+    if let Some(webdriver_url) = &cli.webdriver_url {
+        config.spider.webdriver_url = webdriver_url.clone();
+    }
+    if let Some(browser) = cli.browser {
+        config.spider.browser = browser;
+    }
+    if let Some(tags) = &cli.tags {
+        config.tagging.tags_path = tags.clone();
+    }
+    if !cli.format.is_empty() {
+        config.output.sinks = cli.format.clone();
+    }
+    if !cli.types.is_empty() {
+        config.extraction.types = cli.types.clone();
+    }
+    if let Some(secs) = cli.page_load_timeout {
+        config.timeouts.page_load_secs = secs;
+    }
+    if let Some(secs) = cli.element_timeout {
+        config.timeouts.element_secs = secs;
+    }
+    if let Some(secs) = cli.url_timeout {
+        config.checks.url_timeout_secs = secs;
+    }
+    // --porcelain adds a stdout sink of JSON lines, unless one is configured already
+    if cli.porcelain && !config.output.sinks.contains(&SinkKind::Stdout) {
+        config.output.sinks.push(SinkKind::Stdout);
+        config.output.stdout_format = StdoutFormat::Jsonl;
+    }
+    if cli.porcelain || config.output.sinks.contains(&SinkKind::Stdout) {
+        porcelain::enable()?;
+    }
+    let _ = EXTRACTION.set(config.extraction.clone());
+    let _ = TAGGING.set(config.tagging.clone());
+    let _ = TIMEOUTS.set(config.timeouts());
+    selectors::set_element_timeout(config.timeouts().element);
+    let form_login = NeosFormLogin::new(get_credentials, &config.spider.login_url)
+        .with_form_wait(config.timeouts().element);
+    let _ = AUTH.set(Box::new(form_login));
+    session::install(&config.session);
+
+    match &cli.command {
+        Some(command) => {
+            if let Some(operation) = command_operation(command) {
+                config.permissions.check(operation)?;
+            }
+        }
+        None if cli.from_clipboard || !io::stdin().is_terminal() => {
+            config.permissions.check(Operation::Extract)?
+        }
+        // The interactive menu checks each key
+        None => {}
+    }
+
+    // Reports only need the result files, not a browser
+    match &cli.command {
+        Some(Command::Report { command }) => return run_report(command, &config).await,
+        Some(Command::Merge { inputs, out }) => return run_merge(inputs, out),
+        Some(Command::Validate { inputs, out }) => return run_validate(inputs, out, &config).await,
+        Some(Command::Snapshot { command }) => return run_snapshot(command, &config),
+        Some(Command::Config {
+            command: ConfigCommand::Show,
+        }) => return show_config(&config, &cli),
+        Some(Command::Cache {
+            command: CacheCommand::Serve { listen },
+        }) => {
+            let path = config.checks.cache_path.as_deref();
+            let token = cacheserver::token(&config.checks.cache_token_env)?;
+            return cacheserver::serve(listen, path, config.checks.cache_max_age(), token).await;
+        }
+        Some(Command::Review {
+            command: ReviewCommand::ApplyReplacements { apply: true },
+        }) => {}
+        Some(Command::Review {
+            command: ReviewCommand::ApplyReplacements { apply: false },
+        }) => return list_replacements(&config),
+        Some(Command::Review {
+            command: ReviewCommand::Import { csv },
+        }) => return import_review(csv, &config),
+        Some(Command::Review {
+            command: ReviewCommand::Status { inputs },
+        }) => return review_status(inputs, &config),
+        Some(Command::Tags {
+            command: TagsCommand::Lint { path },
+        }) => return lint_tags(path.as_deref(), &config.tagging),
+        Some(Command::Fixtures {
+            command: FixturesCommand::Bench(args),
+        }) => return run_bench(args, &config).await,
+        _ => {}
+    }
+
+    // Only one job at a time may change content in the CMS
+    let _lock = match cli.command.as_ref().and_then(command_operation) {
+        Some(operation) if is_write(operation) => Some(lock_cms(&config, operation, cli.force)?),
+        _ => None,
+    };
+
+    if env_credentials()?.is_some() {
+        println!("Using credentials from {USERNAME_ENV} and {PASSWORD_ENV}");
+    } else if let Some(credentials) = secrets::fetch_credentials(&config.secrets, config.timeouts().validation).await? {
+        println!("Using credentials from the secrets backend");
+        let _ = BACKEND_CREDENTIALS.set(credentials);
+    }
+
+    let filetree = FileTree::from_json_file(TREEPATH)
         .context("Could not create filetree from json")?;
 
-    // Check for headless mode via environment variable
-    let headless = std::env::var("HEADLESS")
-        .unwrap_or_else(|_| "false".to_string())
-        .to_lowercase()
-        == "true";
-
-    let caps = if headless {
-        println!("Running in headless mode");
-        let mut caps = DesiredCapabilities::firefox();
-        caps.set_headless()?;
-        caps
+    // Check for headless mode via --headless or the environment variable
+    let headless = cli.headless
+        || std::env::var("HEADLESS")
+            .unwrap_or_else(|_| "false".to_string())
+            .to_lowercase()
+            == "true";
+
+    let _ = HEADLESS.set(headless);
+    if headless {
+        println!("Running {:?} in headless mode", config.spider.browser);
     } else {
-        println!("Running in normal (visible) mode. Set HEADLESS=true environment variable to run headless.");
-        DesiredCapabilities::firefox()
-    };
+        println!(
+            "Running {:?} in normal (visible) mode. Pass --headless or set HEADLESS=true to run \
+             headless.",
+            config.spider.browser
+        );
+    }
+    let caps = spider::capabilities(&config.spider, headless)?;
 
-    let spider = Spider::new(caps, URL, filetree).await?;
+    // Start geckodriver unless one is running already; it is stopped when main returns
+    let managed_driver = driver::ensure(&config.spider, &config.driver).await?;
+    if let Some(managed_driver) = &managed_driver {
+        println!("Started geckodriver on {}", managed_driver.url());
+        config.spider.webdriver_url = managed_driver.url().to_string();
+    }
 
-    // Log in.
-    login(&spider.driver).await?;
+    let mut spider = Spider::connect(
+        &config.spider.webdriver_url,
+        caps,
+        &config.spider.login_url,
+        filetree,
+    )
+    .await?;
+    spider.timeouts = timeouts();
+    spider.extraction = config.extraction.clone();
+    backoff::install_relogin_breaker(config.backoff.to_relogin_breaker());
+
+    // Pick the selector profile matching the Neos version shown on the login page.
+    let login_page = spider.driver.source().await.unwrap_or_default();
+    let neos_version = selectors::detect_neos_version(&login_page);
+    Selectors::resolve(SELECTORPATH, neos_version.as_deref())
+        .context("Could not load selectors")?
+        .install();
+
+    match &cli.command {
+        Some(Command::Replay {
+            session,
+            offline,
+            apply_tags,
+        }) => {
+            if !offline {
+                login_and_wait(&spider.driver, &config).await?;
+            }
+            let options = ReplayOptions {
+                offline: *offline,
+                apply_tags: *apply_tags,
+            };
+            cancel_on_ctrl_c(spider.cancel.clone());
+            replay_session(&spider.driver, session, &options, &config.recovery, &spider.cancel)
+                .await?;
+            spider.driver.quit().await?;
+            return Ok(());
+        }
+        Some(Command::Fixtures {
+            command: FixturesCommand::Capture { nodes, out },
+        }) => {
+            login_and_wait(&spider.driver, &config).await?;
+            capture_fixtures(&spider.driver, nodes, out, neos_version.as_deref()).await?;
+            spider.driver.quit().await?;
+            return Ok(());
+        }
+        Some(Command::Review {
+            command: ReviewCommand::ApplyReplacements { .. },
+        }) => {
+            login_and_wait(&spider.driver, &config).await?;
+            apply_replacements(&spider.driver, &config).await?;
+            spider.driver.quit().await?;
+            return Ok(());
+        }
+        Some(Command::ExtractOne { node, json }) => {
+            login_and_wait(&spider.driver, &config).await?;
+            extract_one(&spider.driver, &config, node, *json).await?;
+            spider.driver.quit().await?;
+            return Ok(());
+        }
+        Some(Command::Tags {
+            command: TagsCommand::Set { question, tags, node },
+        }) => {
+            login_and_wait(&spider.driver, &config).await?;
+            set_question_tags(&spider, &config, node, question, tags).await?;
+            spider.driver.quit().await?;
+            return Ok(());
+        }
+        Some(Command::Links {
+            command: LinksCommand::ApplyMetadata { csv },
+        }) => {
+            login_and_wait(&spider.driver, &config).await?;
+            apply_metadata(&spider.driver, csv, &config).await?;
+            spider.driver.quit().await?;
+            return Ok(());
+        }
+        Some(
+            Command::Extract { .. }
+            | Command::Tag { .. }
+            | Command::ClearTags { .. }
+            | Command::Validate { .. }
+            | Command::Report { .. }
+            | Command::Merge { .. }
+            | Command::Snapshot { .. }
+            | Command::Review { .. }
+            | Command::Config { .. }
+            | Command::Cache { .. }
+            | Command::Tags {
+                command: TagsCommand::Lint { .. },
+            }
+            | Command::Fixtures {
+                command: FixturesCommand::Bench(_),
+            }
+            | Command::Completions { .. },
+        )
+        | None => {}
+    }
+
+    match archive::apply_retention(&config.recorder.dir, &config.archive) {
+        Ok(summary) if summary.compressed > 0 || summary.deleted > 0 => println!(
+            "Archived recordings: {} snapshots compressed, {} old sessions deleted",
+            summary.compressed, summary.deleted
+        ),
+        Ok(_) => {}
+        Err(e) => println!("⚠ Could not archive old recordings: {e:#}"),
+    }
+    match snapshot::apply_retention(&config.snapshots.dir, &config.archive) {
+        Ok(summary) if summary.compressed > 0 || summary.deleted > 0 => println!(
+            "Archived the snapshot store: {} objects compressed, {} old runs deleted",
+            summary.compressed, summary.deleted
+        ),
+        Ok(_) => {}
+        Err(e) => println!("⚠ Could not archive old snapshots: {e:#}"),
+    }
+    let mut keep = vec![
+        config.output.sqlite_path.as_path(),
+        config.state.path.as_path(),
+        config.heartbeat.path.as_path(),
+    ];
+    keep.extend(config.checks.cache_path.as_deref());
+    match archive::apply_results_retention(&config.bulk.out_dir, &keep, &config.archive) {
+        Ok(summary) if summary.deleted > 0 => {
+            println!("Deleted {} old result files", summary.deleted)
+        }
+        Ok(_) => {}
+        Err(e) => println!("⚠ Could not delete old results: {e:#}"),
+    }
+    if let Some(path) = recorder::start(&config.recorder, config.archive.compression)? {
+        println!("Recording this session to {}", path.display());
+    }
+    if let Some(run) = snapshot::start(&config.snapshots, config.archive.compression)? {
+        println!("Storing node snapshots as run {run}");
+    }
 
-    if !headless {
+    // Log in.
+    let started = Instant::now();
+    let logged_in = login(&spider.driver).await;
+    record_action(&spider.driver, Action::Login, started, &logged_in, |_| "logged in".to_string()).await;
+    logged_in?;
+
+    // Nobody can log in by hand in headless mode, so only wait for the document tree and fail
+    // right away if the login did not work
+    if headless {
+        check_session(&spider.driver, config.spider.login_wait() + timeouts().page_load).await?;
+        println!("✓ Logged in");
+    } else {
         println!("Login attempted. Please manually navigate to the CMS and log in if needed.");
+        println!(
+            "Waiting {} seconds for you to complete login and navigation...",
+            config.spider.login_wait_secs
+        );
+        support::sleep(config.spider.login_wait()).await;
+    }
+
+    cancel_on_ctrl_c(spider.cancel.clone());
+
+    if cli.from_clipboard {
+        extract_from_clipboard(&spider.driver, &config, &spider.cancel).await?;
+        spider.driver.quit().await?;
+        return Ok(());
+    }
+
+    // Unattended runs of the interactive actions, e.g. from cron or CI
+    let ran = match &cli.command {
+        Some(Command::Extract {
+            folder,
+            depth,
+            out,
+            no_validate,
+            all_dimensions,
+            resume,
+            max_duration,
+            estimate,
+        }) => {
+            if let Some(max_duration) = max_duration {
+                cancel_after(spider.cancel.clone(), *max_duration);
+            }
+            let mut options = bulk_options(&config);
+            options.resume = *resume;
+            options.estimate_only = *estimate;
+            if let Some(depth) = depth {
+                options.max_depth = *depth;
+            }
+            if let Some(out) = out {
+                options.out_dir = out.clone();
+            }
+            options.validate_urls &= !no_validate;
+            options.crawl_dimensions |= *all_dimensions && config.dimensions.is_matrix();
+            let folder = folder.as_deref().or(config.bulk.folder.as_deref());
+            Some(run_bulk_extract(&spider, &config, folder, options).await)
+        }
+        // Without a terminal (e.g. in Docker) nobody can answer the menu, so run a bulk extraction
+        None if !io::stdin().is_terminal() => {
+            println!("No terminal attached, running a bulk extraction with the [bulk] settings");
+            let folder = config.bulk.folder.as_deref();
+            let options = bulk_options(&config);
+            Some(run_bulk_extract(&spider, &config, folder, options).await)
+        }
+        Some(Command::Tag { node, subtree: true, yes }) => {
+            Some(tag_subtree(&spider, &config, node, false, *yes).await)
+        }
+        Some(Command::ClearTags { node, subtree: true, yes }) => {
+            Some(tag_subtree(&spider, &config, node, true, *yes).await)
+        }
+        Some(Command::Tag { node, .. }) => Some(tag_node(&spider, node, false).await),
+        Some(Command::ClearTags { node, .. }) => Some(tag_node(&spider, node, true).await),
+        _ => None,
+    };
+    if let Some(result) = ran {
+        spider.driver.quit().await?;
+        return result;
     }
-    println!("Waiting 10 seconds for you to complete login and navigation...");
-    support::sleep(Duration::from_secs(10)).await;
 
     let welcome_message = r#"
     Welcome to the tag spider. You can do the following actions by pressing:
@@ -1280,16 +5103,29 @@ async fn main() -> Result<()> {
     d -> bulk extract and validate dynamic content from folders
     "#;
 
-    loop {
+    // After Ctrl+C stopped an action, quit instead of offering the next one
+    while !spider.cancel.is_cancelled() {
         println!("{welcome_message}");
         if let Event::Key(event) = crossterm::event::read().unwrap() {
             match event.code {
                 KeyCode::Char('q') => break,
-                KeyCode::Char('a') => add_tags(false, &spider.driver).await?,
-                KeyCode::Char('c') => add_tags(true, &spider.driver).await?,
-                KeyCode::Char('d') => {
-                    bulk_extract_content(&spider.driver).await?;
+                KeyCode::Char('a' | 'c') => {
+                    let lock = config
+                        .permissions
+                        .check(Operation::WriteTags)
+                        .and_then(|()| lock_cms(&config, Operation::WriteTags, cli.force));
+                    match lock {
+                        Ok(_lock) => {
+                            let clear = event.code == KeyCode::Char('c');
+                            recorded_add_tags(clear, &spider.driver, &spider.cancel).await?
+                        }
+                        Err(e) => eprintln!("✗ {e}"),
+                    }
                 }
+                KeyCode::Char('d') => match config.permissions.check(Operation::Extract) {
+                    Ok(()) => bulk_extract_content(&spider, &config).await?,
+                    Err(e) => eprintln!("✗ {e}"),
+                },
                 _ => {}
             }
         }
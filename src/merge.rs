@@ -0,0 +1,71 @@
+use crate::report::{self, ReportEntry};
+use anyhow::{Context, Result};
+use csv::Writer;
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+/// Counts from merging the results of several partial runs.
+#[derive(Debug, Default)]
+pub struct MergeSummary {
+    pub files: usize,
+    pub rows: usize,
+    /// Rows replaced by the same finding from a more recent file.
+    pub duplicates: usize,
+    /// Duplicates whose URL validation status differed; the more recent one was kept.
+    pub conflicts: usize,
+}
+
+/// Merge result CSVs into one set with a single row per node, URL and dimension, sorted by
+/// node and URL. Files are applied oldest first (by modification time), so each finding keeps
+/// the row of the most recent run.
+pub fn merge_results(paths: &[PathBuf]) -> Result<(Vec<ReportEntry>, MergeSummary)> {
+    let mut files = Vec::new();
+    for path in paths {
+        let modified = fs::metadata(path)
+            .and_then(|meta| meta.modified())
+            .unwrap_or(SystemTime::UNIX_EPOCH);
+        files.push((modified, path));
+    }
+    files.sort();
+
+    let mut summary = MergeSummary {
+        files: files.len(),
+        ..MergeSummary::default()
+    };
+    let mut merged: BTreeMap<(String, String, String), ReportEntry> = BTreeMap::new();
+    for (_, path) in files {
+        for entry in report::load_results(std::slice::from_ref(path))? {
+            summary.rows += 1;
+            let key = (
+                entry.source_node.clone(),
+                entry.url.clone(),
+                entry.dimension.clone(),
+            );
+            let status = entry.url_valid.clone();
+            if let Some(previous) = merged.insert(key, entry) {
+                summary.duplicates += 1;
+                if previous.url_valid != status {
+                    summary.conflicts += 1;
+                }
+            }
+        }
+    }
+
+    Ok((merged.into_values().collect(), summary))
+}
+
+/// Write entries as a result CSV with the usual columns.
+pub fn write_results<P: AsRef<Path>>(path: P, entries: &[ReportEntry]) -> Result<()> {
+    let path = path.as_ref();
+    let mut writer = Writer::from_path(path)
+        .with_context(|| format!("Could not create {}", path.display()))?;
+    for entry in entries {
+        writer.serialize(entry).context("Could not write merged row")?;
+    }
+    writer.flush().context("Could not write merged results")?;
+    Ok(())
+}
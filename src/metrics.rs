@@ -0,0 +1,328 @@
+use crate::backoff::{self, ReloginStorm};
+use anyhow::{Context, Result};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    future::Future,
+    path::Path,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// A selector whose average latency exceeds this is reported as slow.
+const SLOW_THRESHOLD: Duration = Duration::from_secs(2);
+/// Number of samples needed before a selector counts as consistently slow.
+const SLOW_MIN_SAMPLES: usize = 3;
+
+static METRICS: Lazy<Mutex<DriverMetrics>> = Lazy::new(|| Mutex::new(DriverMetrics::default()));
+
+/// Latency statistics for one WebDriver command class against one selector.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct CommandStats {
+    pub command: String,
+    pub selector: String,
+    pub count: usize,
+    pub total_ms: f64,
+    pub max_ms: f64,
+}
+
+impl CommandStats {
+    pub fn avg_ms(&self) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            self.total_ms / self.count as f64
+        }
+    }
+
+    fn is_slow(&self) -> bool {
+        self.count >= SLOW_MIN_SAMPLES && self.avg_ms() > SLOW_THRESHOLD.as_secs_f64() * 1000.0
+    }
+}
+
+/// How long the extraction of a single node took.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NodeTiming {
+    pub node_id: String,
+    pub duration_ms: f64,
+    pub entries: usize,
+}
+
+#[derive(Debug, Default)]
+struct DriverMetrics {
+    commands: HashMap<(String, String), CommandStats>,
+    nodes: Vec<NodeTiming>,
+    warned: HashSet<(String, String)>,
+}
+
+/// Await a WebDriver command and record its latency under `command` and `selector`.
+pub async fn timed<F, T>(command: &str, selector: &str, fut: F) -> T
+where
+    F: Future<Output = T>,
+{
+    let start = Instant::now();
+    let result = fut.await;
+    record_command(command, selector, start.elapsed());
+    result
+}
+
+/// Record the latency of one WebDriver command, warning once per selector that is consistently slow.
+pub fn record_command(command: &str, selector: &str, elapsed: Duration) {
+    let mut metrics = METRICS.lock().unwrap();
+    let key = (command.to_string(), selector.to_string());
+
+    let stats = metrics
+        .commands
+        .entry(key.clone())
+        .or_insert_with(|| CommandStats {
+            command: command.to_string(),
+            selector: selector.to_string(),
+            ..Default::default()
+        });
+    let ms = elapsed.as_secs_f64() * 1000.0;
+    stats.count += 1;
+    stats.total_ms += ms;
+    stats.max_ms = stats.max_ms.max(ms);
+
+    if stats.is_slow() {
+        let avg = stats.avg_ms();
+        if metrics.warned.insert(key) {
+            println!(
+                "⚠ Slow selector: {command} '{selector}' averages {:.1}s - check for a virtualized tree or wrong frame",
+                avg / 1000.0
+            );
+        }
+    }
+}
+
+/// Record how long a node took to extract.
+pub fn record_node(node_id: &str, elapsed: Duration, entries: usize) {
+    METRICS.lock().unwrap().nodes.push(NodeTiming {
+        node_id: node_id.to_string(),
+        duration_ms: elapsed.as_secs_f64() * 1000.0,
+        entries,
+    });
+}
+
+/// Snapshot of all timings collected during a run.
+#[derive(Debug, Clone, Serialize)]
+pub struct TimingReport {
+    pub nodes: Vec<NodeTiming>,
+    pub commands: Vec<CommandStats>,
+    pub slow_selectors: Vec<String>,
+    /// The relogin circuit breaker event that aborted the run, if it tripped.
+    pub relogin_storm: Option<ReloginStorm>,
+}
+
+impl TimingReport {
+    /// Collect the current metrics, with the slowest commands first.
+    pub fn collect() -> Self {
+        let metrics = METRICS.lock().unwrap();
+        let mut commands: Vec<CommandStats> = metrics.commands.values().cloned().collect();
+        commands.sort_by(|a, b| b.total_ms.total_cmp(&a.total_ms));
+
+        let slow_selectors = commands
+            .iter()
+            .filter(|c| c.is_slow())
+            .map(|c| format!("{} {}", c.command, c.selector))
+            .collect();
+
+        Self {
+            nodes: metrics.nodes.clone(),
+            commands,
+            slow_selectors,
+            relogin_storm: backoff::relogin_storm(),
+        }
+    }
+
+    /// Print a short summary of node and command timings.
+    pub fn print(&self) {
+        println!("\n=== Timing report ===");
+
+        let total_ms: f64 = self.nodes.iter().map(|n| n.duration_ms).sum();
+        if !self.nodes.is_empty() {
+            println!(
+                "Nodes: {} in {:.1}s (avg {:.1}s per node)",
+                self.nodes.len(),
+                total_ms / 1000.0,
+                total_ms / 1000.0 / self.nodes.len() as f64
+            );
+        }
+
+        let mut slowest = self.nodes.clone();
+        slowest.sort_by(|a, b| b.duration_ms.total_cmp(&a.duration_ms));
+        for node in slowest.iter().take(5) {
+            println!(
+                "  {:>7.1}s  {} ({} entries)",
+                node.duration_ms / 1000.0,
+                node.node_id,
+                node.entries
+            );
+        }
+
+        println!("Driver commands (by total time):");
+        for stats in self.commands.iter().take(10) {
+            println!(
+                "  {:>5}x  avg {:>7.0}ms  max {:>7.0}ms  {} '{}'",
+                stats.count,
+                stats.avg_ms(),
+                stats.max_ms,
+                stats.command,
+                stats.selector
+            );
+        }
+
+        if !self.slow_selectors.is_empty() {
+            println!("Consistently slow selectors:");
+            for selector in &self.slow_selectors {
+                println!("  ⚠ {selector}");
+            }
+        }
+
+        if let Some(storm) = &self.relogin_storm {
+            println!("⚠ Aborted at {}: {storm}", storm.at.format("%H:%M:%S"));
+        }
+    }
+
+    pub fn to_json_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(path, json).context("Could not write timing report")?;
+        Ok(())
+    }
+}
+
+/// The node timings of a saved `TimingReport`.
+#[derive(Deserialize)]
+struct SavedTimings {
+    nodes: Vec<NodeTiming>,
+}
+
+/// How long nodes took in earlier runs, from the timing reports in a results directory.
+#[derive(Debug, Clone, Default)]
+pub struct TimingHistory {
+    nodes: HashMap<String, Duration>,
+}
+
+impl TimingHistory {
+    /// Read the `*-timing.json` reports in `dir`. Newer reports win for nodes timed more than
+    /// once; unreadable reports are skipped.
+    pub fn load<P: AsRef<Path>>(dir: P) -> Self {
+        let mut reports: Vec<_> = fs::read_dir(dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.to_string_lossy().ends_with("-timing.json"))
+            .filter_map(|path| Some((fs::metadata(&path).ok()?.modified().ok()?, path)))
+            .collect();
+        reports.sort();
+
+        let mut nodes = HashMap::new();
+        for (_, path) in reports {
+            let Ok(json) = fs::read_to_string(&path) else {
+                continue;
+            };
+            let Ok(saved) = serde_json::from_str::<SavedTimings>(&json) else {
+                continue;
+            };
+            for node in saved.nodes {
+                nodes.insert(node.node_id, Duration::from_secs_f64(node.duration_ms / 1000.0));
+            }
+        }
+        Self { nodes }
+    }
+
+    /// Expected durations of `nodes`, in order. Nodes without a recorded timing are expected to
+    /// take the median of the history.
+    pub fn estimate<S: AsRef<str>>(&self, nodes: &[S]) -> Estimate {
+        let mut known: Vec<Duration> = self.nodes.values().copied().collect();
+        known.sort();
+        Estimate {
+            per_node: nodes
+                .iter()
+                .map(|node| self.nodes.get(node.as_ref()).copied())
+                .collect(),
+            fallback: known.get(known.len() / 2).copied(),
+        }
+    }
+}
+
+/// Expected duration of a run, refined with the actual pace as it goes.
+#[derive(Debug, Clone)]
+pub struct Estimate {
+    /// Recorded duration of each node, if it was timed before.
+    per_node: Vec<Option<Duration>>,
+    /// Expected duration of nodes that were never timed.
+    fallback: Option<Duration>,
+}
+
+impl Estimate {
+    /// Nodes with a recorded timing.
+    pub fn known(&self) -> usize {
+        self.per_node.iter().flatten().count()
+    }
+
+    /// Expected duration of the whole run, or `None` without any history.
+    pub fn total(&self) -> Option<Duration> {
+        let fallback = self.fallback?;
+        Some(self.per_node.iter().map(|node| node.unwrap_or(fallback)).sum())
+    }
+
+    /// Expected time left after the first `done` nodes took `elapsed`. The expectation is
+    /// scaled by how fast this run is compared to the history; without history the average pace
+    /// of this run is used. `None` before anything can be said.
+    pub fn remaining(&self, done: usize, elapsed: Duration) -> Option<Duration> {
+        let done = done.min(self.per_node.len());
+        let pace = (done > 0).then(|| elapsed / done as u32);
+        let fallback = self.fallback.or(pace)?;
+        let expected = |nodes: &[Option<Duration>]| -> Duration {
+            nodes.iter().map(|node| node.unwrap_or(fallback)).sum()
+        };
+
+        let (finished, left) = self.per_node.split_at(done);
+        let left = expected(left);
+        let predicted = expected(finished);
+        if done == 0 || predicted.is_zero() {
+            return Some(left);
+        }
+        Some(left.mul_f64(elapsed.as_secs_f64() / predicted.as_secs_f64()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEC: Duration = Duration::from_secs(1);
+
+    fn estimate(per_node: &[Option<u64>], fallback: Option<u64>) -> Estimate {
+        Estimate {
+            per_node: per_node.iter().map(|secs| secs.map(Duration::from_secs)).collect(),
+            fallback: fallback.map(Duration::from_secs),
+        }
+    }
+
+    #[test]
+    fn remaining_follows_the_history_at_this_run_s_pace() {
+        let estimate = estimate(&[Some(10), Some(20), None, Some(30)], Some(20));
+        assert_eq!(estimate.known(), 3);
+        assert_eq!(estimate.total(), Some(80 * SEC));
+        assert_eq!(estimate.remaining(0, Duration::ZERO), Some(80 * SEC));
+        // The first two nodes took 30s as expected, then twice as long
+        assert_eq!(estimate.remaining(2, 30 * SEC), Some(50 * SEC));
+        assert_eq!(estimate.remaining(2, 60 * SEC), Some(100 * SEC));
+        assert_eq!(estimate.remaining(4, 90 * SEC), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn remaining_uses_this_run_s_pace_without_history() {
+        let estimate = estimate(&[None, None, None, None], None);
+        assert_eq!(estimate.total(), None);
+        assert_eq!(estimate.remaining(0, Duration::ZERO), None);
+        assert_eq!(estimate.remaining(1, 12 * SEC), Some(36 * SEC));
+        // More nodes done than planned counts as all of them
+        assert_eq!(estimate.remaining(9, 12 * SEC), Some(Duration::ZERO));
+    }
+}
@@ -0,0 +1,70 @@
+use crate::config::{MatrixConfig, NotifyConfig};
+use crate::sink::RunSummary;
+use anyhow::{Context, Result};
+use reqwest::Url;
+use serde_json::json;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Distinguishes the messages sent within one millisecond.
+static SENT: AtomicU64 = AtomicU64::new(0);
+
+/// Post `message` to every configured chat room. Notifications are best effort: a failure is
+/// reported but never ends the run.
+pub async fn post(config: &NotifyConfig, message: &str) {
+    if config.matrix.homeserver.is_some() {
+        if let Err(e) = post_matrix(&config.matrix, message).await {
+            eprintln!("⚠ Could not post to Matrix: {e:#}");
+        }
+    }
+}
+
+/// Message announcing the end of a run.
+pub fn run_summary(summary: &RunSummary, failed_pages: usize, cancelled: bool) -> String {
+    let outcome = if cancelled { "cancelled" } else { "finished" };
+    let minutes = (summary.finished_at - summary.started_at).num_minutes();
+    format!(
+        "tag-spider: extraction of {} {outcome} after {minutes} min: {} entries, {} outdated, \
+         {failed_pages} failed pages",
+        summary.job, summary.entries, summary.outdated
+    )
+}
+
+/// Message announcing a run that stopped on an error.
+pub fn failure(job: &str, error: &anyhow::Error) -> String {
+    format!("tag-spider: ✗ extraction of {job} failed: {error:#}")
+}
+
+/// Send `message` as a notice to the configured room through the client-server API.
+async fn post_matrix(config: &MatrixConfig, message: &str) -> Result<()> {
+    let homeserver = config.homeserver.as_deref().unwrap_or_default();
+    let token = std::env::var(&config.token_env).with_context(|| {
+        format!("Set {} to the access token of the Matrix account", config.token_env)
+    })?;
+
+    // The transaction ID only has to be unique per access token
+    let txn = format!(
+        "tag-spider-{}-{}",
+        chrono::Local::now().timestamp_millis(),
+        SENT.fetch_add(1, Ordering::Relaxed)
+    );
+    let mut url = Url::parse(homeserver).context("[notify.matrix] homeserver is not a valid URL")?;
+    url.path_segments_mut()
+        .map_err(|_| anyhow::anyhow!("[notify.matrix] homeserver cannot have a path"))?
+        .pop_if_empty()
+        .extend(["_matrix", "client", "v3", "rooms", config.room.as_str()])
+        .extend(["send", "m.room.message", txn.as_str()]);
+
+    // Notices are the message type meant for bots; clients don't answer them
+    let payload = json!({ "msgtype": "m.notice", "body": message });
+    reqwest::Client::new()
+        .put(url)
+        .bearer_auth(token)
+        .header("Content-Type", "application/json")
+        .body(payload.to_string())
+        .send()
+        .await
+        .context("Could not reach the Matrix homeserver")?
+        .error_for_status()
+        .context("The Matrix homeserver refused the message")?;
+    Ok(())
+}
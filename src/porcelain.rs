@@ -0,0 +1,61 @@
+use anyhow::{Context, Result};
+use once_cell::sync::OnceCell;
+use serde::Serialize;
+use std::{
+    io::{self, Write},
+    sync::Mutex,
+};
+
+/// The original stdout, which only carries data rows once porcelain mode is on.
+static DATA: OnceCell<Mutex<Box<dyn Write + Send>>> = OnceCell::new();
+
+/// Switch to porcelain mode: everything printed from now on goes to stderr, and stdout only
+/// receives the rows passed to `emit` or `write`.
+#[cfg(unix)]
+pub fn enable() -> Result<()> {
+    use std::{fs::File, os::fd::AsFd};
+
+    io::stdout().flush()?;
+    let stdout = io::stdout()
+        .as_fd()
+        .try_clone_to_owned()
+        .context("Could not duplicate stdout")?;
+    // SAFETY: dup2 only replaces a file descriptor and touches no Rust memory
+    if unsafe { libc::dup2(libc::STDERR_FILENO, libc::STDOUT_FILENO) } == -1 {
+        return Err(io::Error::last_os_error()).context("Could not redirect stdout to stderr");
+    }
+    let _ = DATA.set(Mutex::new(Box::new(File::from(stdout))));
+    Ok(())
+}
+
+/// Switch to porcelain mode. Without a way to redirect stdout here, the rows passed to `emit`
+/// or `write` share stdout with the progress output.
+#[cfg(not(unix))]
+pub fn enable() -> Result<()> {
+    let _ = DATA.set(Mutex::new(Box::new(io::stdout())));
+    Ok(())
+}
+
+/// Whether data rows go to stdout.
+pub fn is_enabled() -> bool {
+    DATA.get().is_some()
+}
+
+/// Write `row` to stdout as one JSON line in porcelain mode, and do nothing otherwise.
+pub fn emit<T: Serialize>(row: &T) -> Result<()> {
+    let mut line = serde_json::to_vec(row)?;
+    line.push(b'\n');
+    write(&line)
+}
+
+/// Write already formatted rows to stdout in porcelain mode, and do nothing otherwise.
+pub fn write(rows: &[u8]) -> Result<()> {
+    let Some(data) = DATA.get() else {
+        return Ok(());
+    };
+
+    data.lock()
+        .unwrap()
+        .write_all(rows)
+        .context("Could not write row to stdout")
+}
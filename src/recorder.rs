@@ -0,0 +1,179 @@
+use crate::archive;
+use crate::config::{Compression, RecorderConfig};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt,
+    fs::{self, File},
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::Duration,
+};
+
+static RECORDER: Lazy<Mutex<Option<Recorder>>> = Lazy::new(|| Mutex::new(None));
+
+/// A high-level action performed against the CMS.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum Action {
+    Login,
+    Relogin,
+    ExpandFolder { folder_id: String },
+    GetChildren { folder_id: String },
+    ExtractNode { node_id: String },
+    AddTags { clear: bool },
+}
+
+impl Action {
+    /// Short name used for snapshot file names.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Action::Login => "login",
+            Action::Relogin => "relogin",
+            Action::ExpandFolder { .. } => "expand_folder",
+            Action::GetChildren { .. } => "get_children",
+            Action::ExtractNode { .. } => "extract_node",
+            Action::AddTags { .. } => "add_tags",
+        }
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Action::Login => write!(f, "login"),
+            Action::Relogin => write!(f, "relogin"),
+            Action::ExpandFolder { folder_id } => write!(f, "expand folder {folder_id}"),
+            Action::GetChildren { folder_id } => write!(f, "get children of {folder_id}"),
+            Action::ExtractNode { node_id } => write!(f, "extract node {node_id}"),
+            Action::AddTags { clear } => write!(f, "add tags (clear: {clear})"),
+        }
+    }
+}
+
+/// One line of a recorded session.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedAction {
+    pub seq: usize,
+    pub at: DateTime<Local>,
+    #[serde(flatten)]
+    pub action: Action,
+    pub duration_ms: f64,
+    pub ok: bool,
+    /// Short description of the result, or the error message.
+    pub outcome: String,
+    /// Page source saved after the action, if snapshots are enabled. Archiving may have
+    /// compressed it since, see `archive::locate`.
+    pub snapshot: Option<PathBuf>,
+}
+
+struct Recorder {
+    file: File,
+    snapshot_dir: Option<PathBuf>,
+    compression: Compression,
+    seq: usize,
+}
+
+/// Start recording this session into a new file in the configured directory, saving page
+/// snapshots with `compression`. Returns the path of the session file, or `None` if recording
+/// is disabled.
+pub fn start(config: &RecorderConfig, compression: Compression) -> Result<Option<PathBuf>> {
+    if !config.enabled {
+        return Ok(None);
+    }
+
+    fs::create_dir_all(&config.dir).context("Could not create recordings directory")?;
+    let name = format!("session-{}", Local::now().format("%Y%m%d-%H%M%S"));
+    let path = config.dir.join(format!("{name}.jsonl"));
+    let file = File::create(&path).context("Could not create session recording")?;
+
+    let snapshot_dir = if config.snapshots {
+        let dir = config.dir.join(&name);
+        fs::create_dir_all(&dir).context("Could not create snapshot directory")?;
+        Some(dir)
+    } else {
+        None
+    };
+
+    *RECORDER.lock().unwrap() = Some(Recorder {
+        file,
+        snapshot_dir,
+        compression,
+        seq: 0,
+    });
+    Ok(Some(path))
+}
+
+/// Whether the running recorder wants a page snapshot after each action.
+pub fn wants_snapshot() -> bool {
+    RECORDER
+        .lock()
+        .unwrap()
+        .as_ref()
+        .is_some_and(|r| r.snapshot_dir.is_some())
+}
+
+/// Append an action and its outcome to the session file. Does nothing if no recorder is running.
+pub fn record(
+    action: Action,
+    elapsed: Duration,
+    outcome: std::result::Result<String, String>,
+    page_source: Option<&str>,
+) -> Result<()> {
+    let mut guard = RECORDER.lock().unwrap();
+    let Some(recorder) = guard.as_mut() else {
+        return Ok(());
+    };
+    recorder.seq += 1;
+
+    let snapshot = match (&recorder.snapshot_dir, page_source) {
+        (Some(dir), Some(source)) => {
+            let path = dir.join(format!("{:04}-{}.html", recorder.seq, action.kind()));
+            let path = archive::write(&path, source.as_bytes(), recorder.compression)
+                .context("Could not write page snapshot")?;
+            Some(path)
+        }
+        _ => None,
+    };
+
+    let (ok, outcome) = match outcome {
+        Ok(summary) => (true, summary),
+        Err(error) => (false, error),
+    };
+    let line = RecordedAction {
+        seq: recorder.seq,
+        at: Local::now(),
+        action,
+        duration_ms: elapsed.as_secs_f64() * 1000.0,
+        ok,
+        outcome,
+        snapshot,
+    };
+
+    // One line per action, written immediately so a crash still leaves the full trail
+    let json = serde_json::to_string(&line)?;
+    writeln!(recorder.file, "{json}").context("Could not write session recording")?;
+    Ok(())
+}
+
+/// Read a recorded session back.
+pub fn load_session<P: AsRef<Path>>(path: P) -> Result<Vec<RecordedAction>> {
+    let path = path.as_ref();
+    let file = File::open(path)
+        .with_context(|| format!("Could not open session recording {}", path.display()))?;
+
+    let mut actions = Vec::new();
+    for (number, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let action = serde_json::from_str(&line)
+            .with_context(|| format!("Invalid action on line {} of {}", number + 1, path.display()))?;
+        actions.push(action);
+    }
+    Ok(actions)
+}
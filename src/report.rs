@@ -0,0 +1,534 @@
+use crate::sink::RunSummary;
+use crate::state::State;
+use crate::text;
+use anyhow::{Context, Result};
+use csv::Reader;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fmt::Write as _,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Column headers of an extraction result, in the order of `ReportEntry::values`.
+pub const COLUMNS: [&str; 22] = [
+    "Source Node",
+    "Breadcrumb Path",
+    "Content Type",
+    "URL",
+    "Title",
+    "Author",
+    "File Type",
+    "Size",
+    "URL Valid",
+    "Visual Check",
+    "Title Check",
+    "Age Flag",
+    "Visibility",
+    "Site",
+    "Dimension",
+    "Container Index",
+    "Fusion Path",
+    "Backend URL",
+    "URL Method",
+    "Final URL",
+    "Redirect Hops",
+    "Wayback URL",
+];
+
+/// One row of an extraction result CSV. Columns added in later versions are optional,
+/// so older result files can still be read.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReportEntry {
+    #[serde(rename = "Source Node")]
+    pub source_node: String,
+    #[serde(rename = "Breadcrumb Path")]
+    pub breadcrumb_path: String,
+    #[serde(rename = "Content Type")]
+    pub content_type: String,
+    #[serde(rename = "URL")]
+    pub url: String,
+    #[serde(rename = "Title")]
+    pub title: String,
+    #[serde(rename = "Author")]
+    pub author: String,
+    #[serde(rename = "File Type")]
+    pub file_type: String,
+    #[serde(rename = "Size")]
+    pub size: String,
+    #[serde(rename = "URL Valid")]
+    pub url_valid: String,
+    #[serde(rename = "Visual Check")]
+    pub visual_check: String,
+    #[serde(rename = "Title Check")]
+    pub title_check: String,
+    #[serde(rename = "Age Flag")]
+    pub age_flag: String,
+    #[serde(rename = "Visibility")]
+    pub visibility: String,
+    #[serde(rename = "Site")]
+    pub site: String,
+    #[serde(rename = "Dimension")]
+    pub dimension: String,
+    #[serde(rename = "Container Index")]
+    pub container_index: String,
+    #[serde(rename = "Fusion Path")]
+    pub fusion_path: String,
+    #[serde(rename = "Backend URL")]
+    pub backend_url: String,
+    /// Request method that answered the URL check, "HEAD" or "GET".
+    #[serde(rename = "URL Method")]
+    pub url_method: String,
+    /// Where the URL redirects to, empty if it does not.
+    #[serde(rename = "Final URL")]
+    pub final_url: String,
+    #[serde(rename = "Redirect Hops")]
+    pub redirect_hops: String,
+    /// Archived copy of a dead URL, empty if there is none.
+    #[serde(rename = "Wayback URL")]
+    pub wayback_url: String,
+}
+
+impl ReportEntry {
+    /// The fields in `COLUMNS` order.
+    pub fn values(&self) -> [&str; 22] {
+        [
+            &self.source_node,
+            &self.breadcrumb_path,
+            &self.content_type,
+            &self.url,
+            &self.title,
+            &self.author,
+            &self.file_type,
+            &self.size,
+            &self.url_valid,
+            &self.visual_check,
+            &self.title_check,
+            &self.age_flag,
+            &self.visibility,
+            &self.site,
+            &self.dimension,
+            &self.container_index,
+            &self.fusion_path,
+            &self.backend_url,
+            &self.url_method,
+            &self.final_url,
+            &self.redirect_hops,
+            &self.wayback_url,
+        ]
+    }
+
+    /// Normalize the scraped text fields with [`text::normalize`], so invisible characters in
+    /// older results do not make them differ from new ones.
+    pub fn normalize(&mut self) {
+        for field in [
+            &mut self.breadcrumb_path,
+            &mut self.title,
+            &mut self.author,
+            &mut self.file_type,
+            &mut self.size,
+        ] {
+            *field = text::normalize(field);
+        }
+        self.url = text::normalize_url(&self.url);
+    }
+
+    /// Whether the link check found the URL broken, or the YouTube video gone or private.
+    pub fn has_broken_url(&self) -> bool {
+        self.url_valid.starts_with("Error")
+            || matches!(self.url_valid.as_str(), "Invalid" | "Deleted" | "Private")
+    }
+
+    /// Whether any check flagged this entry.
+    pub fn has_problem(&self) -> bool {
+        self.has_broken_url()
+            || self.visual_check.starts_with("Broken")
+            || self.title_check == "Mismatch"
+            || !self.age_flag.is_empty()
+    }
+
+    /// The breadcrumb segment at `level`, or the deepest one if the path is shorter.
+    pub fn chapter(&self, level: usize) -> &str {
+        let segments: Vec<&str> = self.breadcrumb_path.split(" > ").collect();
+        segments
+            .get(level)
+            .or(segments.last())
+            .copied()
+            .unwrap_or("Unknown")
+    }
+}
+
+/// Sort entries by breadcrumb path, node, then content container, so runs over the same content
+/// write their rows in the same order. Entries outside a container (tutorials) come last. The
+/// sort is stable, so the entries of a container stay in page order.
+pub fn sort_entries(entries: &mut [ReportEntry]) {
+    let container = |entry: &ReportEntry| entry.container_index.parse().unwrap_or(usize::MAX);
+    entries.sort_by(|a, b| {
+        (&a.breadcrumb_path, &a.source_node, container(a))
+            .cmp(&(&b.breadcrumb_path, &b.source_node, container(b)))
+    });
+}
+
+/// All extraction result CSVs in `dir` (skipping the shortcut and translation side files).
+pub fn find_result_files<P: AsRef<Path>>(dir: P) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir.as_ref()).context("Could not read the results directory")? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "csv") && is_result_file(&path)? {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn is_result_file(path: &Path) -> Result<bool> {
+    let mut reader = Reader::from_path(path)?;
+    Ok(reader.headers()?.get(0) == Some("Source Node"))
+}
+
+/// Read the entries of several result CSVs.
+pub fn load_results(paths: &[PathBuf]) -> Result<Vec<ReportEntry>> {
+    let mut entries = Vec::new();
+    for path in paths {
+        let mut reader = Reader::from_path(path)
+            .with_context(|| format!("Could not open results {}", path.display()))?;
+        for row in reader.deserialize() {
+            let mut entry: ReportEntry =
+                row.with_context(|| format!("Invalid row in {}", path.display()))?;
+            entry.normalize();
+            entries.push(entry);
+        }
+    }
+    Ok(entries)
+}
+
+/// Write a static HTML site with an index, one page per chapter and a client-side
+/// search over titles and URLs. Findings reviewers chose to ignore are left out.
+/// Returns the number of chapter pages.
+pub fn write_site<P: AsRef<Path>>(
+    entries: &[ReportEntry],
+    state: &State,
+    out: P,
+    chapter_level: usize,
+) -> Result<usize> {
+    let out = out.as_ref();
+    fs::create_dir_all(out).context("Could not create the report directory")?;
+
+    let mut chapters: BTreeMap<&str, Vec<&ReportEntry>> = BTreeMap::new();
+    for entry in entries.iter().filter(|e| !state.is_ignored(e)) {
+        chapters.entry(entry.chapter(chapter_level)).or_default().push(entry);
+    }
+
+    let mut index = String::new();
+    let mut search = Vec::new();
+    if !state.reviews.is_empty() {
+        index.push_str("<table id=\"progress\">\n<tr><th colspan=\"2\">Review progress</th></tr>\n");
+        for (label, count) in state.progress(entries).rows() {
+            let _ = writeln!(index, "<tr><td>{label}</td><td>{count}</td></tr>");
+        }
+        index.push_str("</table>\n");
+    }
+    index.push_str("<input id=\"search\" type=\"search\" placeholder=\"Search titles and URLs\" autofocus>\n");
+    index.push_str("<ul id=\"results\"></ul>\n<table id=\"chapters\">\n");
+    index.push_str("<tr><th>Chapter</th><th>Entries</th><th>Problems</th></tr>\n");
+
+    for (number, (chapter, chapter_entries)) in chapters.iter().enumerate() {
+        let file = format!("chapter-{:03}.html", number + 1);
+        let problems = chapter_entries.iter().filter(|e| e.has_problem()).count();
+        let _ = writeln!(
+            index,
+            "<tr><td><a href=\"{file}\">{}</a></td><td>{}</td><td>{problems}</td></tr>",
+            escape(chapter),
+            chapter_entries.len()
+        );
+
+        for entry in chapter_entries {
+            search.push(serde_json::json!({
+                "title": entry.title,
+                "url": entry.url,
+                "chapter": chapter,
+                "page": file,
+            }));
+        }
+
+        fs::write(out.join(&file), chapter_page(chapter, chapter_entries, state))
+            .context("Could not write chapter page")?;
+    }
+    index.push_str("</table>\n");
+
+    let title = format!("Content report ({} entries)", entries.len());
+    fs::write(out.join("index.html"), page(&title, &index, true))
+        .context("Could not write report index")?;
+
+    // A script instead of JSON, so the search also works when the site is opened from disk
+    let search_index = format!("const SEARCH_INDEX = {};\n", serde_json::to_string(&search)?);
+    fs::write(out.join("search-index.js"), search_index).context("Could not write search index")?;
+    fs::write(out.join("search.js"), SEARCH_JS).context("Could not write search script")?;
+    fs::write(out.join("style.css"), STYLE_CSS).context("Could not write stylesheet")?;
+
+    Ok(chapters.len())
+}
+
+fn chapter_page(chapter: &str, entries: &[&ReportEntry], state: &State) -> String {
+    let mut body = String::from("<p><a href=\"index.html\">← All chapters</a></p>\n<table>\n");
+    body.push_str(
+        "<tr><th>Title</th><th>Type</th><th>Path</th><th>URL</th><th>Checks</th>\
+         <th>Visibility</th><th>Site</th><th>Dimension</th></tr>\n",
+    );
+
+    for entry in entries {
+        let mut checks: Vec<String> = [
+            &entry.url_valid,
+            &entry.visual_check,
+            &entry.title_check,
+            &entry.age_flag,
+        ]
+        .into_iter()
+        .filter(|check| !check.is_empty())
+        .cloned()
+        .collect();
+        checks.extend(state.note(entry));
+        let class = if entry.has_problem() { " class=\"problem\"" } else { "" };
+
+        let url = url_link(&entry.url);
+        let path = backend_link(entry);
+
+        let _ = writeln!(
+            body,
+            "<tr{class}><td>{}</td><td>{}</td><td>{path}</td><td>{url}</td>\
+             <td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            escape(&entry.title),
+            escape(&entry.content_type),
+            escape(&checks.join(", ")),
+            escape(&entry.visibility),
+            escape(&entry.site),
+            escape(&entry.dimension),
+        );
+    }
+    body.push_str("</table>\n");
+
+    page(chapter, &body, false)
+}
+
+/// A single HTML file with the totals of a run and a table of entries per node, with broken
+/// links in red. Styles are inlined, so the file can be sent around on its own.
+pub fn run_report(entries: &[ReportEntry], summary: Option<&RunSummary>) -> String {
+    let mut nodes: BTreeMap<(&str, &str), Vec<&ReportEntry>> = BTreeMap::new();
+    for entry in entries {
+        nodes
+            .entry((&entry.breadcrumb_path, &entry.source_node))
+            .or_default()
+            .push(entry);
+    }
+    let broken = entries.iter().filter(|entry| entry.has_broken_url()).count();
+    let problems = entries.iter().filter(|entry| entry.has_problem()).count();
+
+    let mut body = String::from("<table id=\"summary\">\n");
+    let mut rows = Vec::new();
+    if let Some(summary) = summary {
+        let minutes = (summary.finished_at - summary.started_at).num_minutes();
+        rows.extend([
+            ("Job", summary.job.clone()),
+            ("Started", summary.started_at.format("%Y-%m-%d %H:%M").to_string()),
+            ("Finished", summary.finished_at.format("%Y-%m-%d %H:%M").to_string()),
+            ("Duration", format!("{minutes} min")),
+            ("Outdated", summary.outdated.to_string()),
+        ]);
+        if let Some(storm) = &summary.relogin_storm {
+            rows.push(("Aborted", storm.to_string()));
+        }
+    }
+    rows.extend([
+        ("Nodes", nodes.len().to_string()),
+        ("Entries", entries.len().to_string()),
+        ("Broken links", broken.to_string()),
+        ("Entries with problems", problems.to_string()),
+    ]);
+    for (label, value) in rows {
+        let _ = writeln!(body, "<tr><th>{label}</th><td>{}</td></tr>", escape(&value));
+    }
+    body.push_str("</table>\n");
+
+    for ((_, node), node_entries) in &nodes {
+        let heading = backend_link(node_entries[0]);
+        let _ = writeln!(body, "<h2 id=\"{}\">{heading}</h2>", escape(node));
+        body.push_str(
+            "<table>\n<tr><th>Type</th><th>Title</th><th>URL</th><th>URL Valid</th>\
+             <th>Checks</th></tr>\n",
+        );
+        for entry in node_entries {
+            let class = if entry.has_broken_url() {
+                " class=\"broken\""
+            } else if entry.has_problem() {
+                " class=\"problem\""
+            } else {
+                ""
+            };
+            let checks: Vec<&str> = [&entry.visual_check, &entry.title_check, &entry.age_flag]
+                .into_iter()
+                .filter(|check| !check.is_empty())
+                .map(String::as_str)
+                .collect();
+            let _ = writeln!(
+                body,
+                "<tr{class}><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                escape(&entry.content_type),
+                escape(&entry.title),
+                url_link(&entry.url),
+                escape(&entry.url_valid),
+                escape(&checks.join(", ")),
+            );
+        }
+        body.push_str("</table>\n");
+    }
+
+    let title = match summary {
+        Some(summary) => format!("Extraction report: {}", summary.job),
+        None => "Extraction report".to_string(),
+    };
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>{title}</title>\n<style>\n{STYLE_CSS}{RUN_REPORT_CSS}</style>\n</head>\n\
+         <body>\n<h1>{title}</h1>\n{body}</body>\n</html>\n",
+        title = escape(&title)
+    )
+}
+
+/// Markdown list of the entries with broken URLs, with a table per breadcrumb path, to paste
+/// into an issue tracker. Empty paths are listed as "Unknown".
+pub fn broken_links_markdown(entries: &[ReportEntry]) -> String {
+    let mut paths: BTreeMap<&str, Vec<&ReportEntry>> = BTreeMap::new();
+    for entry in entries.iter().filter(|entry| entry.has_broken_url()) {
+        let path = match entry.breadcrumb_path.as_str() {
+            "" => "Unknown",
+            path => path,
+        };
+        paths.entry(path).or_default().push(entry);
+    }
+
+    let broken: usize = paths.values().map(Vec::len).sum();
+    let mut markdown =
+        format!("# Broken links\n\n{broken} broken links on {} pages.\n", paths.len());
+    for (path, path_entries) in &paths {
+        let _ = write!(markdown, "\n## {}\n\n", markdown_cell(path));
+        if let Some(backend_url) = path_entries
+            .iter()
+            .map(|entry| &entry.backend_url)
+            .find(|url| !url.is_empty())
+        {
+            let _ = write!(markdown, "[Open in the backend]({backend_url})\n\n");
+        }
+        markdown.push_str(
+            "| Title | URL | Status | Type | Archived copy |\n|---|---|---|---|---|\n",
+        );
+        for entry in path_entries {
+            let _ = writeln!(
+                markdown,
+                "| {} | {} | {} | {} | {} |",
+                markdown_cell(&entry.title),
+                markdown_cell(&entry.url),
+                markdown_cell(&entry.url_valid),
+                markdown_cell(&entry.content_type),
+                markdown_cell(&entry.wayback_url),
+            );
+        }
+    }
+    markdown
+}
+
+/// Text that cannot break out of a Markdown table cell.
+pub fn markdown_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+/// `url` as a link if it is a web URL; anything else is shown as text
+fn url_link(url: &str) -> String {
+    if url.starts_with("http://") || url.starts_with("https://") {
+        format!("<a href=\"{0}\">{0}</a>", escape(url))
+    } else {
+        escape(url)
+    }
+}
+
+/// The breadcrumb path of `entry`, linking to its node in the backend where the finding can be
+/// fixed
+fn backend_link(entry: &ReportEntry) -> String {
+    if entry.backend_url.is_empty() {
+        escape(&entry.breadcrumb_path)
+    } else {
+        format!(
+            "<a href=\"{}\">{}</a>",
+            escape(&entry.backend_url),
+            escape(&entry.breadcrumb_path)
+        )
+    }
+}
+
+fn page(title: &str, body: &str, with_search: bool) -> String {
+    let scripts = if with_search {
+        "<script src=\"search-index.js\"></script>\n<script src=\"search.js\"></script>\n"
+    } else {
+        ""
+    };
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>{title}</title>\n<link rel=\"stylesheet\" href=\"style.css\">\n</head>\n\
+         <body>\n<h1>{title}</h1>\n{body}{scripts}</body>\n</html>\n",
+        title = escape(title)
+    )
+}
+
+/// Escape text for use in HTML content and attribute values.
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+const SEARCH_JS: &str = r#"const input = document.getElementById("search");
+const results = document.getElementById("results");
+
+input.addEventListener("input", () => {
+  const query = input.value.trim().toLowerCase();
+  results.replaceChildren();
+  if (query.length < 2) return;
+
+  const matches = SEARCH_INDEX.filter(
+    (e) => e.title.toLowerCase().includes(query) || e.url.toLowerCase().includes(query)
+  );
+  for (const match of matches.slice(0, 100)) {
+    const item = document.createElement("li");
+    const link = document.createElement("a");
+    link.href = match.page;
+    link.textContent = match.title || match.url;
+    item.append(link, ` (${match.chapter}) `, match.url);
+    results.append(item);
+  }
+});
+"#;
+
+const STYLE_CSS: &str = "body { font-family: sans-serif; margin: 2em; }
+table { border-collapse: collapse; width: 100%; }
+th, td { border: 1px solid #ccc; padding: 0.3em 0.5em; text-align: left; vertical-align: top; }
+tr.problem { background: #fdecea; }
+#search { width: 100%; padding: 0.5em; margin-bottom: 1em; font-size: 1em; }
+td a { word-break: break-all; }
+";
+
+const RUN_REPORT_CSS: &str = "#summary { width: auto; margin-bottom: 2em; }
+h2 { font-size: 1.1em; margin-top: 2em; }
+tr.broken { background: #f8d7da; color: #842029; }
+tr.broken a { color: #842029; font-weight: bold; }
+";
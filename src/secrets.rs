@@ -0,0 +1,46 @@
+use crate::config::SecretsConfig;
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::time::Duration;
+
+/// Fetch the CMS credentials from the configured secrets backend.
+/// Returns `None` if no backend is configured. Fails if the backend does not answer within
+/// `timeout`.
+pub async fn fetch_credentials(
+    config: &SecretsConfig,
+    timeout: Duration,
+) -> Result<Option<(String, String)>> {
+    let Some(url) = &config.url else {
+        return Ok(None);
+    };
+    let token = std::env::var(&config.token_env)
+        .with_context(|| format!("Set {} to the token for the secrets backend", config.token_env))?;
+
+    let response = reqwest::Client::builder()
+        .timeout(timeout)
+        .build()
+        .context("Could not create the secrets backend client")?
+        .get(url)
+        .header(config.token_header.as_str(), token)
+        .send()
+        .await
+        .context("Could not reach the secrets backend")?
+        .error_for_status()
+        .context("The secrets backend refused the request")?;
+    let body = response.text().await.context("Could not read the secret")?;
+    let secret: Value =
+        serde_json::from_str(&body).context("The secrets backend did not return JSON")?;
+
+    let username = field(&secret, &config.username_field)?;
+    let password = field(&secret, &config.password_field)?;
+    Ok(Some((username, password)))
+}
+
+/// The string at a dotted `path` (e.g. `data.data.username`) in a JSON value.
+fn field(value: &Value, path: &str) -> Result<String> {
+    path.split('.')
+        .try_fold(value, |value, key| value.get(key))
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .with_context(|| format!("The secret has no string field '{path}'"))
+}
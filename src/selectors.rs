@@ -0,0 +1,726 @@
+use anyhow::{Context, Result};
+use once_cell::sync::{Lazy, OnceCell};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fs, path::Path, sync::Mutex, time::Duration};
+use thirtyfour::{prelude::ElementQueryable, support, By, WebElement};
+use tokio::time::Instant;
+
+static SELECTORS: OnceCell<Selectors> = OnceCell::new();
+
+/// How long [`find_first`] and [`find_all_first`] wait for a match, from `[timeouts]`.
+static ELEMENT_TIMEOUT: OnceCell<Duration> = OnceCell::new();
+
+/// Pause between lookups while waiting for a match.
+const POLL: Duration = Duration::from_millis(200);
+
+/// Selector profiles bundled with the crate, keyed by Neos major version. Only profiles verified
+/// against a running backend belong here; other versions get the defaults with a warning.
+static PROFILES: &[(u32, &str)] = &[(8, include_str!("../resources/selectors/neos-8.toml"))];
+
+/// Text directly preceding the Neos version in the login page or backend source.
+const VERSION_MARKERS: &[&str] = &[
+    "name=\"neos-version\" content=\"",
+    "data-neos-version=\"",
+    "\"neosVersion\":\"",
+    "Neos CMS ",
+];
+
+/// How often each candidate of each logical selector matched, keyed by (name, candidate index).
+static MATCHES: Lazy<Mutex<BTreeMap<(String, usize), MatchCount>>> =
+    Lazy::new(|| Mutex::new(BTreeMap::new()));
+
+/// Lookups retried after a logical selector matched nothing, counted per selector name.
+static RETRIES: Lazy<Mutex<BTreeMap<String, usize>>> = Lazy::new(|| Mutex::new(BTreeMap::new()));
+
+/// Content containers without any of their expected fields, counted per node ID.
+static EMPTY_CONTAINERS: Lazy<Mutex<BTreeMap<String, usize>>> =
+    Lazy::new(|| Mutex::new(BTreeMap::new()));
+
+/// CSS selectors used to drive the Neos backend, loaded from `selectors.toml`.
+///
+/// Each logical element has an ordered list of candidate selectors which are
+/// tried in turn, so one file can cover several Neos UI versions.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Selectors {
+    pub login: LoginForm,
+    pub tree: TreeSelectors,
+    pub content: ContentSelectors,
+    pub dimensions: DimensionSelectors,
+    pub inspector: InspectorSelectors,
+    pub backend: BackendSelectors,
+}
+
+/// Parts of the backend UI outside the tree, content and inspector.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BackendSelectors {
+    /// Error flash messages and the server error screen, signs of an overloaded CMS.
+    pub error_screen: Vec<String>,
+}
+
+impl Default for BackendSelectors {
+    fn default() -> Self {
+        Self {
+            error_screen: vec![
+                "[class*='flashMessage--error']".to_string(),
+                "[class*='errorScreen']".to_string(),
+                "#neos-ErrorScreen".to_string(),
+            ],
+        }
+    }
+}
+
+/// Property editors in the Neos inspector.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InspectorSelectors {
+    /// Editor of a node property; `{property}` is replaced by the property name.
+    pub field: Vec<String>,
+    pub apply: Vec<String>,
+}
+
+impl Default for InspectorSelectors {
+    fn default() -> Self {
+        Self {
+            field: vec![
+                "#__neos__editor__property---{property}".to_string(),
+                "input[id$='property---{property}']".to_string(),
+            ],
+            apply: vec!["#neos-Inspector-Apply".to_string()],
+        }
+    }
+}
+
+/// The content dimension switcher in the top bar. `{value}` is replaced by the dimension value.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DimensionSelectors {
+    pub switcher: Vec<String>,
+    pub option: Vec<String>,
+    /// Confirmation button of switchers that apply a selection explicitly.
+    pub apply: Vec<String>,
+}
+
+impl Default for DimensionSelectors {
+    fn default() -> Self {
+        Self {
+            switcher: vec![
+                "button[class*='dimensionSwitcher']".to_string(),
+                "[class*='dimensionSwitcher'] button".to_string(),
+            ],
+            option: vec![
+                "[class*='dimensionSwitcher'] [data-value='{value}']".to_string(),
+                "[class*='dimensionSwitcher'] [title='{value}']".to_string(),
+            ],
+            apply: vec!["[class*='dimensionSwitcher'] button[class*='apply']".to_string()],
+        }
+    }
+}
+
+/// Elements of the node content shown in the content iframe.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ContentSelectors {
+    /// The link to the target on the placeholder page of a shortcut node.
+    pub shortcut_target: Vec<String>,
+    /// The iframe showing the node content in the backend.
+    pub frame: Vec<String>,
+    /// The content collection holding the elements of a node page.
+    pub collection: Vec<String>,
+    /// Editable question titles on question-answer pages.
+    pub question_title: Vec<String>,
+    /// Dynamic content containers holding ExternalLinks and YouTube elements.
+    pub container: Vec<String>,
+    pub external_link: Vec<String>,
+    pub youtube: Vec<String>,
+    pub tutorial: Vec<String>,
+    /// Fields of an ExternalLinks element.
+    pub url: Vec<String>,
+    pub title: Vec<String>,
+    pub author: Vec<String>,
+    #[serde(rename = "type")]
+    pub file_type: Vec<String>,
+    pub size: Vec<String>,
+    /// The embedded player of a YouTube element or tutorial.
+    pub player: Vec<String>,
+    /// The video URL of a tutorial.
+    pub video_url: Vec<String>,
+    /// Links of the breadcrumb above the node page.
+    pub breadcrumb: Vec<String>,
+}
+
+impl ContentSelectors {
+    /// The candidates of the ExternalLinks field shown in the inspector as `property`
+    /// (`url`, `title`, `author`, `type` or `size`).
+    pub fn link_field(&self, property: &str) -> Option<&[String]> {
+        match property {
+            "url" => Some(&self.url),
+            "title" => Some(&self.title),
+            "author" => Some(&self.author),
+            "type" => Some(&self.file_type),
+            "size" => Some(&self.size),
+            _ => None,
+        }
+    }
+}
+
+impl Default for ContentSelectors {
+    fn default() -> Self {
+        Self {
+            shortcut_target: vec![
+                ".neos-shortcut a[href]".to_string(),
+                "[class*='shortcut'] a[href]".to_string(),
+            ],
+            frame: vec![
+                "iframe[name='neos-content-main']".to_string(),
+                "iframe[name*='neos-content']".to_string(),
+            ],
+            collection: vec![
+                "html body.neos-backend div.container div.neos-contentcollection".to_string(),
+                ".neos-contentcollection".to_string(),
+            ],
+            question_title: vec![
+                "p.neos-inline-editable.questionTitle".to_string(),
+                "[class*='questionTitle']".to_string(),
+            ],
+            container: vec![".dynamicContent.dynamic-content-container-1".to_string()],
+            external_link: vec!["div[data-__neos-fusion-path*='ExternalLinks']".to_string()],
+            youtube: vec!["div[data-__neos-fusion-path*='YouTube']".to_string()],
+            tutorial: vec![
+                "article[data-__neos-fusion-path*='Plan2net.Schrack4students:Tutorials']".to_string(),
+            ],
+            url: vec!["p[property='typo3:url']".to_string()],
+            title: vec!["p[property='typo3:title']".to_string()],
+            author: vec!["p[property='typo3:author']".to_string()],
+            file_type: vec!["p[property='typo3:type']".to_string()],
+            size: vec!["p[property='typo3:size']".to_string()],
+            player: vec!["iframe, video".to_string()],
+            video_url: vec!["div[property='typo3:videoUrl']".to_string()],
+            breadcrumb: vec![
+                ".neos-breadcrumb a".to_string(),
+                ".breadcrumb a".to_string(),
+                "[class*='breadcrumb'] a".to_string(),
+            ],
+        }
+    }
+}
+
+/// Elements of the Neos document tree. `{id}` is replaced by the tree item ID.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TreeSelectors {
+    /// The tree container itself.
+    pub root: Vec<String>,
+    pub treeitem: Vec<String>,
+    pub header: Vec<String>,
+    pub chevron: Vec<String>,
+    /// Class name fragments on the header marking a hidden node.
+    pub hidden_class: Vec<String>,
+    /// Class name fragments on the header marking a node with unpublished changes.
+    pub unpublished_class: Vec<String>,
+    /// Node type icon, looked up inside the header.
+    pub icon: Vec<String>,
+    pub contents: Vec<String>,
+    pub child_item: Vec<String>,
+    pub loading_indicator: Vec<String>,
+    /// The tree item of the node currently shown in the content area.
+    pub focused: Vec<String>,
+}
+
+impl Default for TreeSelectors {
+    fn default() -> Self {
+        Self {
+            root: vec!["[role='tree']".to_string()],
+            treeitem: vec![
+                "div[aria-labelledby='{id}']".to_string(),
+                "[role='treeitem'][aria-labelledby='{id}']".to_string(),
+            ],
+            header: vec![
+                ".node__header__labelWrapper___dJ7OH".to_string(),
+                "[class*='node__header__labelWrapper']".to_string(),
+            ],
+            chevron: vec![
+                "a.node__header__chevron___zXVME.reset__reset___2e25U".to_string(),
+                "a.node__header__chevron___zXVME".to_string(),
+                "a[class*='node__header__chevron']".to_string(),
+            ],
+            hidden_class: vec!["labelWrapper--isHidden".to_string()],
+            unpublished_class: vec!["labelWrapper--isDirty".to_string()],
+            icon: vec![
+                "[class*='node__header__iconWrapper'] [data-icon]".to_string(),
+                "[class*='node__header__iconWrapper'] i".to_string(),
+                "i[class*='fa-']".to_string(),
+            ],
+            contents: vec![
+                "div.node__contents___GgwYX".to_string(),
+                "div[class*='node__contents']".to_string(),
+            ],
+            child_item: vec!["div[role='treeitem']".to_string()],
+            loading_indicator: vec![
+                ".style__loadingIndicator__container___1yhsy".to_string(),
+                "[class*='loadingIndicator__container']".to_string(),
+            ],
+            focused: vec![
+                "[role='treeitem'][aria-selected='true']".to_string(),
+                "[role='treeitem']:has(> [class*='isFocused'])".to_string(),
+            ],
+        }
+    }
+}
+
+/// The username/password form shared by the login page and the relogin dialog.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LoginForm {
+    /// The relogin dialog shown when the session expires.
+    pub relogin_dialog: Vec<String>,
+    pub username: Vec<String>,
+    pub password: Vec<String>,
+    pub submit: Vec<String>,
+}
+
+impl Default for LoginForm {
+    fn default() -> Self {
+        Self {
+            relogin_dialog: vec!["#neos-ReloginDialog".to_string()],
+            username: vec![
+                "#username".to_string(),
+                "input[name$='[username]']".to_string(),
+            ],
+            password: vec![
+                "#password".to_string(),
+                "input[name$='[password]']".to_string(),
+            ],
+            submit: vec![
+                ".neos-login-btn".to_string(),
+                "button[class*='loginButton']".to_string(),
+                "button[type='submit']".to_string(),
+            ],
+        }
+    }
+}
+
+impl Selectors {
+    /// Load selectors from `path`, falling back to the built-in defaults if it does not exist.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let data = fs::read_to_string(path)
+            .with_context(|| format!("Could not read selector file {}", path.display()))?;
+        toml::from_str(&data).with_context(|| format!("Invalid selector file {}", path.display()))
+    }
+
+    /// The bundled profile for a Neos major version, if there is one.
+    pub fn profile(major: u32) -> Result<Option<Self>> {
+        let Some((_, data)) = PROFILES.iter().find(|(version, _)| *version == major) else {
+            return Ok(None);
+        };
+        let profile = toml::from_str(data)
+            .with_context(|| format!("Invalid bundled selector profile for Neos {major}"))?;
+        Ok(Some(profile))
+    }
+
+    /// Neos major versions with a bundled profile.
+    pub fn profile_versions() -> impl Iterator<Item = u32> {
+        PROFILES.iter().map(|(version, _)| *version)
+    }
+
+    /// Pick the selectors for this run: a selector file at `path` wins, then the bundled
+    /// profile for the detected Neos `version`, then the built-in defaults.
+    pub fn resolve<P: AsRef<Path>>(path: P, version: Option<&str>) -> Result<Self> {
+        let path = path.as_ref();
+        if path.exists() {
+            println!("Using selectors from {}", path.display());
+            return Self::load(path);
+        }
+
+        let Some(version) = version else {
+            println!("⚠ Could not detect the Neos version, using the default selectors");
+            return Ok(Self::default());
+        };
+
+        let profile = match major_version(version) {
+            Some(major) => Self::profile(major)?,
+            None => None,
+        };
+        match profile {
+            Some(profile) => {
+                println!("Detected Neos {version}, using the bundled selector profile");
+                Ok(profile)
+            }
+            None => {
+                let supported: Vec<String> =
+                    Self::profile_versions().map(|major| major.to_string()).collect();
+                println!(
+                    "⚠ Neos {version} is not supported, only Neos {} has verified selectors; \
+                     using the default selectors, which may not match",
+                    supported.join(", ")
+                );
+                Ok(Self::default())
+            }
+        }
+    }
+
+    /// Install the selectors used by the rest of the program. Only the first call has an effect.
+    pub fn install(self) {
+        let _ = SELECTORS.set(self);
+    }
+
+    /// The installed selectors, or the defaults if none were installed.
+    pub fn get() -> &'static Selectors {
+        SELECTORS.get_or_init(Selectors::default)
+    }
+}
+
+/// Find the Neos version in the source of the login page or the backend.
+pub fn detect_neos_version(html: &str) -> Option<String> {
+    VERSION_MARKERS.iter().find_map(|marker| {
+        html.match_indices(marker)
+            .find_map(|(pos, _)| parse_version(&html[pos + marker.len()..]))
+    })
+}
+
+fn parse_version(text: &str) -> Option<String> {
+    let version: String = text
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || *c == '.')
+        .collect();
+    let version = version.trim_end_matches('.');
+    major_version(version).map(|_| version.to_string())
+}
+
+/// The major component of a version string like `8.3.5`.
+pub fn major_version(version: &str) -> Option<u32> {
+    version.split('.').next()?.parse().ok()
+}
+
+/// Substitute `{id}` in every candidate.
+pub fn with_id(candidates: &[String], id: &str) -> Vec<String> {
+    candidates.iter().map(|c| c.replace("{id}", id)).collect()
+}
+
+/// Substitute `{value}` in every candidate.
+pub fn with_value(candidates: &[String], value: &str) -> Vec<String> {
+    candidates.iter().map(|c| c.replace("{value}", value)).collect()
+}
+
+/// Substitute `{property}` in every candidate.
+pub fn with_property(candidates: &[String], property: &str) -> Vec<String> {
+    candidates.iter().map(|c| c.replace("{property}", property)).collect()
+}
+
+/// Raised when none of the candidates of a logical selector matched, usually because the
+/// element is gone or the DOM changed under us.
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("None of the selectors for {name} matched: {candidates}")]
+pub struct NoMatch {
+    pub name: String,
+    pub candidates: String,
+}
+
+/// Set how long [`find_first`] and [`find_all_first`] wait for a match. Only the first call has
+/// an effect; until then they wait 10 seconds.
+pub fn set_element_timeout(timeout: Duration) {
+    let _ = ELEMENT_TIMEOUT.set(timeout);
+}
+
+fn element_timeout() -> Duration {
+    ELEMENT_TIMEOUT.get().copied().unwrap_or(Duration::from_secs(10))
+}
+
+/// Try each candidate selector in order and return the first matching element, trying them
+/// again until one matches or the element timeout has passed.
+/// `name` identifies the logical selector in the match report.
+pub async fn find_first<R: ElementQueryable>(
+    root: &R,
+    name: &str,
+    candidates: &[String],
+) -> Result<WebElement> {
+    let deadline = Instant::now() + element_timeout();
+    while Instant::now() < deadline {
+        if let Some(element) = first_match(root, name, candidates).await {
+            return Ok(element);
+        }
+        support::sleep(POLL).await;
+    }
+    require_first(root, name, candidates).await
+}
+
+/// Like [`find_first`], but look only once, for elements that are often legitimately missing.
+/// Finding nothing is not counted as a miss in the match report.
+pub async fn probe_first<R: ElementQueryable>(
+    root: &R,
+    name: &str,
+    candidates: &[String],
+) -> Result<WebElement> {
+    match first_match(root, name, candidates).await {
+        Some(element) => Ok(element),
+        None => Err(no_match(name, candidates)),
+    }
+}
+
+/// Like [`probe_first`], but count finding nothing as a miss, for the last look of a lookup
+/// whose element is required.
+pub async fn require_first<R: ElementQueryable>(
+    root: &R,
+    name: &str,
+    candidates: &[String],
+) -> Result<WebElement> {
+    match first_match(root, name, candidates).await {
+        Some(element) => Ok(element),
+        None => {
+            record_miss(name);
+            Err(no_match(name, candidates))
+        }
+    }
+}
+
+fn no_match(name: &str, candidates: &[String]) -> anyhow::Error {
+    NoMatch {
+        name: name.to_string(),
+        candidates: candidates.join(" | "),
+    }
+    .into()
+}
+
+/// Return all elements matched by the first candidate that matches anything, trying the
+/// candidates again until one matches or the element timeout has passed.
+/// An empty list means no candidate matched.
+pub async fn find_all_first<R: ElementQueryable>(
+    root: &R,
+    name: &str,
+    candidates: &[String],
+) -> Result<Vec<WebElement>> {
+    let deadline = Instant::now() + element_timeout();
+    while Instant::now() < deadline {
+        let elements = probe_all_first(root, name, candidates).await?;
+        if !elements.is_empty() {
+            return Ok(elements);
+        }
+        support::sleep(POLL).await;
+    }
+    let elements = probe_all_first(root, name, candidates).await?;
+    if elements.is_empty() {
+        record_miss(name);
+    }
+    Ok(elements)
+}
+
+/// Like [`find_all_first`], but look only once, for elements that are often legitimately
+/// missing. Finding nothing is not counted as a miss in the match report.
+pub async fn probe_all_first<R: ElementQueryable>(
+    root: &R,
+    name: &str,
+    candidates: &[String],
+) -> Result<Vec<WebElement>> {
+    for (index, candidate) in candidates.iter().enumerate() {
+        let elements = root
+            .query(By::Css(candidate))
+            .nowait()
+            .all_from_selector()
+            .await?;
+        if !elements.is_empty() {
+            record_match(name, index, candidate);
+            return Ok(elements);
+        }
+    }
+
+    Ok(Vec::new())
+}
+
+/// The first element matched by one of `candidates`, recorded in the match report.
+async fn first_match<R: ElementQueryable>(
+    root: &R,
+    name: &str,
+    candidates: &[String],
+) -> Option<WebElement> {
+    for (index, candidate) in candidates.iter().enumerate() {
+        if let Ok(element) = root.query(By::Css(candidate)).nowait().first().await {
+            record_match(name, index, candidate);
+            return Some(element);
+        }
+    }
+    None
+}
+
+/// Number of times a candidate matched.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct MatchCount {
+    pub candidate: String,
+    pub count: usize,
+}
+
+fn record_match(name: &str, index: usize, candidate: &str) {
+    let mut matches = MATCHES.lock().unwrap();
+    let entry = matches
+        .entry((name.to_string(), index))
+        .or_insert_with(|| MatchCount {
+            candidate: candidate.to_string(),
+            count: 0,
+        });
+    entry.count += 1;
+}
+
+fn record_miss(name: &str) {
+    let mut matches = MATCHES.lock().unwrap();
+    let entry = matches
+        .entry((name.to_string(), usize::MAX))
+        .or_insert_with(|| MatchCount {
+            candidate: "<no match>".to_string(),
+            count: 0,
+        });
+    entry.count += 1;
+}
+
+/// Record that an operation was retried because the logical selector `name` matched nothing.
+pub fn record_retry(name: &str) {
+    *RETRIES.lock().unwrap().entry(name.to_string()).or_default() += 1;
+}
+
+/// How unreliable a logical selector was during a run.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct Flakiness {
+    pub name: String,
+    pub lookups: usize,
+    /// Lookups matched by a later candidate than the first.
+    pub fallbacks: usize,
+    /// Required lookups no candidate matched.
+    pub misses: usize,
+    /// Operations retried after a miss.
+    pub retries: usize,
+}
+
+impl Flakiness {
+    /// Fallbacks, misses and retries together.
+    pub fn problems(&self) -> usize {
+        self.fallbacks + self.misses + self.retries
+    }
+}
+
+/// Logical selectors that needed a fallback candidate, missed or caused a retry during this
+/// run, the most troublesome first.
+pub fn flakiness() -> Vec<Flakiness> {
+    let mut stats: BTreeMap<String, Flakiness> = BTreeMap::new();
+    for ((name, index), found) in MATCHES.lock().unwrap().iter() {
+        let stat = stats.entry(name.clone()).or_insert_with(|| Flakiness {
+            name: name.clone(),
+            ..Flakiness::default()
+        });
+        stat.lookups += found.count;
+        match *index {
+            0 => {}
+            usize::MAX => stat.misses += found.count,
+            _ => stat.fallbacks += found.count,
+        }
+    }
+    for (name, retries) in RETRIES.lock().unwrap().iter() {
+        stats
+            .entry(name.clone())
+            .or_insert_with(|| Flakiness {
+                name: name.clone(),
+                ..Flakiness::default()
+            })
+            .retries += retries;
+    }
+
+    let mut flaky: Vec<Flakiness> = stats.into_values().filter(|stat| stat.problems() > 0).collect();
+    flaky.sort_by(|a, b| b.problems().cmp(&a.problems()).then_with(|| a.name.cmp(&b.name)));
+    flaky
+}
+
+/// Print the selectors that most often needed retries or fallbacks, as the next candidates to
+/// move to stabler attributes.
+pub fn print_flakiness_leaderboard(top: usize) {
+    let flaky = flakiness();
+    if flaky.is_empty() {
+        return;
+    }
+
+    println!("\n=== Flaky selectors ===");
+    for (rank, stat) in flaky.iter().take(top).enumerate() {
+        println!(
+            "{:>2}. {:<24} {:>5} problems in {:>6} lookups  (fallbacks {}, misses {}, retries {})",
+            rank + 1,
+            stat.name,
+            stat.problems(),
+            stat.lookups,
+            stat.fallbacks,
+            stat.misses,
+            stat.retries
+        );
+    }
+}
+
+/// Record that a `kind` content container on `node_id` was found, but none of its expected
+/// fields. This is the signature of a changed Neos template rather than of missing content.
+pub fn record_empty_container(node_id: &str, kind: &str) {
+    println!("      ⚠ Selector drift suspected: {kind} container without any of its expected fields");
+    *EMPTY_CONTAINERS
+        .lock()
+        .unwrap()
+        .entry(node_id.to_string())
+        .or_default() += 1;
+}
+
+/// Nodes with content containers lacking all expected fields, with the number of such containers.
+pub fn drift_suspected() -> BTreeMap<String, usize> {
+    EMPTY_CONTAINERS.lock().unwrap().clone()
+}
+
+/// Print which candidate matched for each logical selector during this run.
+/// Matches by anything but the first candidate indicate a changed Neos UI.
+pub fn print_match_report() {
+    let matches = MATCHES.lock().unwrap();
+    if matches.is_empty() {
+        return;
+    }
+
+    println!("\n=== Selector match report ===");
+    for ((name, index), found) in matches.iter() {
+        let marker = match *index {
+            0 => " ",
+            usize::MAX => "✗",
+            _ => "⚠",
+        };
+        let position = if *index == usize::MAX {
+            "-".to_string()
+        } else {
+            format!("#{}", index + 1)
+        };
+        println!(
+            "{marker} {name:<24} {position:>3} {:>6}x  {}",
+            found.count, found.candidate
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_the_version_from_its_markers() {
+        let meta = r#"<meta name="neos-version" content="8.3.7">"#;
+        assert_eq!(detect_neos_version(meta).as_deref(), Some("8.3.7"));
+        let config = r#"{"neosVersion":"8.2.","user":"x"}"#;
+        assert_eq!(detect_neos_version(config).as_deref(), Some("8.2"));
+        assert_eq!(detect_neos_version("Powered by Neos CMS 8.3").as_deref(), Some("8.3"));
+    }
+
+    #[test]
+    fn ignores_neos_mentioned_in_passing() {
+        assert_eq!(detect_neos_version("<p>Neos 2024 conference, 12 talks</p>"), None);
+        assert_eq!(detect_neos_version("<title>Login</title>"), None);
+    }
+
+    #[test]
+    fn only_verified_profiles_are_bundled() {
+        assert_eq!(Selectors::profile_versions().collect::<Vec<_>>(), [8]);
+        assert!(Selectors::profile(8).unwrap().is_some());
+        assert!(Selectors::profile(9).unwrap().is_none());
+    }
+}
@@ -0,0 +1,178 @@
+use crate::config::SessionConfig;
+use once_cell::sync::Lazy;
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+static CLOCKS: Lazy<Mutex<Clocks>> = Lazy::new(|| Mutex::new(Clocks::default()));
+
+/// One clock per browser session, keyed by the WebDriver session ID, so the logins of parallel
+/// sessions do not restart each other's clock.
+#[derive(Debug, Default)]
+struct Clocks {
+    /// Settings every new clock starts from.
+    template: SessionClock,
+    sessions: HashMap<String, SessionClock>,
+}
+
+impl Clocks {
+    fn get(&mut self, session: &str) -> &mut SessionClock {
+        let template = &self.template;
+        self.sessions
+            .entry(session.to_string())
+            .or_insert_with(|| template.clone())
+    }
+}
+
+/// Predicts when the backend session expires from the lifetimes seen so far, so the spider can
+/// log in again between two nodes instead of being interrupted by the relogin dialog mid-click.
+#[derive(Debug, Clone, Default)]
+pub struct SessionClock {
+    /// When the current session was started by a login or relogin.
+    started: Option<Instant>,
+    /// Lifetimes observed from a login to the relogin dialog, sorted.
+    observed: Vec<Duration>,
+    /// Lifetime from the configuration, used until one was observed.
+    configured: Option<Duration>,
+    margin: Duration,
+    enabled: bool,
+}
+
+impl SessionClock {
+    pub fn new(config: &SessionConfig) -> Self {
+        Self {
+            started: None,
+            observed: Vec::new(),
+            configured: config.lifetime(),
+            margin: config.margin(),
+            enabled: config.proactive_reauth,
+        }
+    }
+
+    /// A session started at `now`.
+    pub fn on_login(&mut self, now: Instant) {
+        self.started = Some(now);
+    }
+
+    /// The session expired at `now`. Returns the lifetime it had.
+    pub fn on_expired(&mut self, now: Instant) -> Option<Duration> {
+        let lifetime = now.duration_since(self.started.take()?);
+        // A session that ended within the margin was cut short for another reason and would
+        // make every later session renew right after its login
+        if lifetime > self.margin {
+            let index = self.observed.partition_point(|observed| *observed < lifetime);
+            self.observed.insert(index, lifetime);
+        }
+        Some(lifetime)
+    }
+
+    /// How long a session is expected to last, if known: the (lower) median of the observed
+    /// lifetimes, so one session ending early does not shorten all later ones.
+    pub fn expected_lifetime(&self) -> Option<Duration> {
+        let median = self.observed.get(self.observed.len().saturating_sub(1) / 2);
+        median.copied().or(self.configured)
+    }
+
+    /// Whether the current session is within the margin of its expected expiry at `now`.
+    pub fn reauth_due(&self, now: Instant) -> bool {
+        let (Some(started), Some(lifetime)) = (self.started, self.expected_lifetime()) else {
+            return false;
+        };
+        self.enabled && now.duration_since(started) + self.margin >= lifetime
+    }
+}
+
+/// Use the settings of `config` for the sessions of this run.
+pub fn install(config: &SessionConfig) {
+    *CLOCKS.lock().unwrap() = Clocks {
+        template: SessionClock::new(config),
+        sessions: HashMap::new(),
+    };
+}
+
+/// Register a successful login or relogin of `session`.
+pub fn note_login(session: &str) {
+    CLOCKS.lock().unwrap().get(session).on_login(Instant::now());
+}
+
+/// Register the relogin dialog, i.e. the end of the current backend session of `session`.
+/// Returns its lifetime.
+pub fn note_expired(session: &str) -> Option<Duration> {
+    CLOCKS.lock().unwrap().get(session).on_expired(Instant::now())
+}
+
+/// Whether `session` should be renewed before the next node.
+pub fn reauth_due(session: &str) -> bool {
+    CLOCKS.lock().unwrap().get(session).reauth_due(Instant::now())
+}
+
+/// How long a backend session of `session` is expected to last, if known.
+pub fn expected_lifetime(session: &str) -> Option<Duration> {
+    CLOCKS.lock().unwrap().get(session).expected_lifetime()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MIN: Duration = Duration::from_secs(60);
+
+    fn clock(lifetime_mins: u64) -> SessionClock {
+        SessionClock::new(&SessionConfig {
+            proactive_reauth: true,
+            lifetime_mins,
+            margin_secs: 120,
+        })
+    }
+
+    #[test]
+    fn uses_the_configured_lifetime_until_one_was_observed() {
+        let mut clock = clock(30);
+        let start = Instant::now();
+        assert_eq!(clock.expected_lifetime(), Some(30 * MIN));
+        assert!(!clock.reauth_due(start));
+
+        clock.on_login(start);
+        assert!(!clock.reauth_due(start + 27 * MIN));
+        assert!(clock.reauth_due(start + 28 * MIN));
+        assert_eq!(clock.on_expired(start + 20 * MIN), Some(20 * MIN));
+        assert_eq!(clock.expected_lifetime(), Some(20 * MIN));
+    }
+
+    #[test]
+    fn expects_the_lower_median_of_observed_lifetimes() {
+        let mut clock = clock(0);
+        let start = Instant::now();
+        assert_eq!(clock.expected_lifetime(), None);
+        for minutes in [24, 10, 25] {
+            clock.on_login(start);
+            clock.on_expired(start + minutes * MIN);
+        }
+        assert_eq!(clock.expected_lifetime(), Some(24 * MIN));
+    }
+
+    #[test]
+    fn ignores_sessions_cut_short_within_the_margin() {
+        let mut clock = clock(0);
+        let start = Instant::now();
+        clock.on_login(start);
+        assert_eq!(clock.on_expired(start + MIN), Some(MIN));
+        assert_eq!(clock.expected_lifetime(), None);
+        // No login, no lifetime
+        assert_eq!(clock.on_expired(start + 2 * MIN), None);
+    }
+
+    #[test]
+    fn never_due_when_disabled() {
+        let mut clock = SessionClock::new(&SessionConfig {
+            proactive_reauth: false,
+            lifetime_mins: 30,
+            margin_secs: 120,
+        });
+        let start = Instant::now();
+        clock.on_login(start);
+        assert!(!clock.reauth_due(start + 60 * MIN));
+    }
+}
@@ -0,0 +1,991 @@
+use crate::backoff::ReloginStorm;
+use crate::config::{OutputConfig, SinkKind, StdoutFormat};
+use crate::porcelain;
+use crate::report::{self, ReportEntry, COLUMNS};
+use crate::spill::EntryBuffer;
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Local};
+use csv::Writer;
+use rusqlite::{params, Connection};
+use rust_xlsxwriter::{
+    Color, ConditionalFormatText, ConditionalFormatTextRule, Format, Workbook, Worksheet, XlsxError,
+};
+use serde::Serialize;
+use std::{
+    collections::{btree_map::Entry, BTreeMap, HashMap},
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+/// Totals of a finished run, handed to every sink before it is closed.
+#[derive(Debug, Clone, Serialize)]
+pub struct RunSummary {
+    /// What the run worked on, e.g. the start folder of a bulk extraction.
+    pub job: String,
+    pub started_at: DateTime<Local>,
+    pub finished_at: DateTime<Local>,
+    pub entries: usize,
+    pub outdated: usize,
+    /// The relogin circuit breaker event that aborted the run, if it tripped.
+    pub relogin_storm: Option<ReloginStorm>,
+}
+
+/// A destination for the checked entries of a run.
+pub trait OutputSink: Send {
+    /// Create the destination for the results of `job`.
+    fn open(&mut self, job: &str) -> Result<()>;
+    /// Append one checked entry.
+    fn write_entry(&mut self, entry: &ReportEntry) -> Result<()>;
+    /// Make the entries written so far durable; called according to the `[output]` flush policy.
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+    /// Record the totals of the run after the last entry.
+    fn write_summary(&mut self, summary: &RunSummary) -> Result<()>;
+    /// Finish writing. Nothing is written to the sink afterwards.
+    fn close(&mut self) -> Result<()>;
+    /// Where the entries end up, for progress messages.
+    fn location(&self) -> String;
+}
+
+/// Create the sinks listed in `config`, writing their files to `dir`.
+pub fn from_config(config: &OutputConfig, dir: &Path) -> Result<MultiSink> {
+    if config.sinks.is_empty() {
+        bail!("No output sinks configured in [output] sinks");
+    }
+    let sinks = config
+        .sinks
+        .iter()
+        .map(|kind| -> Result<Box<dyn OutputSink>> {
+            Ok(match kind {
+                SinkKind::Csv => Box::new(CsvSink::new(dir, config.fsync)),
+                SinkKind::Jsonl => Box::new(JsonlSink::new(dir, config.fsync)),
+                SinkKind::Json => Box::new(JsonSink::new(dir, config.memory_limit())),
+                SinkKind::Sqlite => Box::new(SqliteSink::new(&config.sqlite_path)),
+                SinkKind::Xlsx => Box::new(XlsxSink::new(dir, config.memory_limit())),
+                SinkKind::Html => Box::new(HtmlSink::new(dir, config.memory_limit())),
+                #[cfg(feature = "parquet")]
+                SinkKind::Parquet => Box::new(ParquetSink::new(dir)),
+                #[cfg(not(feature = "parquet"))]
+                SinkKind::Parquet => {
+                    bail!("The parquet sink needs a build with `--features parquet`")
+                }
+                SinkKind::Stdout => Box::new(StdoutSink::new(config.stdout_format)),
+            })
+        })
+        .collect::<Result<_>>()?;
+    Ok(MultiSink { sinks })
+}
+
+/// Writes every entry to several sinks at once, e.g. CSV for editors plus SQLite for history.
+pub struct MultiSink {
+    sinks: Vec<Box<dyn OutputSink>>,
+}
+
+impl OutputSink for MultiSink {
+    fn open(&mut self, job: &str) -> Result<()> {
+        self.sinks.iter_mut().try_for_each(|sink| sink.open(job))
+    }
+
+    fn write_entry(&mut self, entry: &ReportEntry) -> Result<()> {
+        self.sinks.iter_mut().try_for_each(|sink| sink.write_entry(entry))
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.sinks.iter_mut().try_for_each(|sink| sink.flush())
+    }
+
+    fn write_summary(&mut self, summary: &RunSummary) -> Result<()> {
+        self.sinks.iter_mut().try_for_each(|sink| sink.write_summary(summary))
+    }
+
+    fn close(&mut self) -> Result<()> {
+        self.sinks.iter_mut().try_for_each(|sink| sink.close())
+    }
+
+    fn location(&self) -> String {
+        let locations: Vec<String> = self.sinks.iter().map(|sink| sink.location()).collect();
+        locations.join(", ")
+    }
+}
+
+/// `{job}.csv` with the usual result columns, readable by `report` and `merge`.
+pub struct CsvSink {
+    dir: PathBuf,
+    fsync: bool,
+    path: Option<PathBuf>,
+    writer: Option<Writer<File>>,
+}
+
+impl CsvSink {
+    pub fn new(dir: &Path, fsync: bool) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+            fsync,
+            path: None,
+            writer: None,
+        }
+    }
+
+    fn writer(&mut self) -> Result<&mut Writer<File>> {
+        self.writer.as_mut().context("CSV sink is not open")
+    }
+}
+
+impl OutputSink for CsvSink {
+    fn open(&mut self, job: &str) -> Result<()> {
+        let path = self.dir.join(format!("{job}.csv"));
+        let mut writer = Writer::from_path(&path).context("Failed to create CSV file")?;
+        writer.write_record(COLUMNS).context("Failed to write CSV header")?;
+        self.path = Some(path);
+        self.writer = Some(writer);
+        Ok(())
+    }
+
+    fn write_entry(&mut self, entry: &ReportEntry) -> Result<()> {
+        self.writer()?
+            .write_record(entry.values())
+            .context("Failed to write CSV record")
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        let fsync = self.fsync;
+        let writer = self.writer()?;
+        writer.flush().context("Failed to flush CSV writer")?;
+        if fsync {
+            writer.get_ref().sync_data().context("Failed to fsync CSV file")?;
+        }
+        Ok(())
+    }
+
+    /// Result CSVs hold only entries; the totals are in the run's console summary.
+    fn write_summary(&mut self, _summary: &RunSummary) -> Result<()> {
+        Ok(())
+    }
+
+    fn close(&mut self) -> Result<()> {
+        if self.writer.is_some() {
+            self.flush()?;
+            self.writer = None;
+        }
+        Ok(())
+    }
+
+    fn location(&self) -> String {
+        location(&self.path, &self.dir, "csv")
+    }
+}
+
+/// `{job}.jsonl` with one JSON object per entry, keyed by the CSV column names.
+pub struct JsonlSink {
+    dir: PathBuf,
+    fsync: bool,
+    path: Option<PathBuf>,
+    writer: Option<BufWriter<File>>,
+}
+
+impl JsonlSink {
+    pub fn new(dir: &Path, fsync: bool) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+            fsync,
+            path: None,
+            writer: None,
+        }
+    }
+
+    fn writer(&mut self) -> Result<&mut BufWriter<File>> {
+        self.writer.as_mut().context("JSONL sink is not open")
+    }
+}
+
+impl OutputSink for JsonlSink {
+    fn open(&mut self, job: &str) -> Result<()> {
+        let path = self.dir.join(format!("{job}.jsonl"));
+        let file = File::create(&path).context("Failed to create JSONL file")?;
+        self.path = Some(path);
+        self.writer = Some(BufWriter::new(file));
+        Ok(())
+    }
+
+    fn write_entry(&mut self, entry: &ReportEntry) -> Result<()> {
+        let line = serde_json::to_string(entry)?;
+        writeln!(self.writer()?, "{line}").context("Failed to write JSONL entry")
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        let fsync = self.fsync;
+        let writer = self.writer()?;
+        writer.flush().context("Failed to flush JSONL writer")?;
+        if fsync {
+            writer.get_ref().sync_data().context("Failed to fsync JSONL file")?;
+        }
+        Ok(())
+    }
+
+    /// Every line is an entry, so consumers need not tell records apart.
+    fn write_summary(&mut self, _summary: &RunSummary) -> Result<()> {
+        Ok(())
+    }
+
+    fn close(&mut self) -> Result<()> {
+        if self.writer.is_some() {
+            self.flush()?;
+            self.writer = None;
+        }
+        Ok(())
+    }
+
+    fn location(&self) -> String {
+        location(&self.path, &self.dir, "jsonl")
+    }
+}
+
+/// `{job}.json`: one pretty-printed document with the run summary and the entries, keyed by the
+/// CSV column names. Like a workbook it is only valid as a whole, so it is written on close.
+pub struct JsonSink {
+    dir: PathBuf,
+    path: Option<PathBuf>,
+    entries: EntryBuffer,
+    summary: Option<RunSummary>,
+}
+
+/// Contents of a `JsonSink` file.
+#[derive(Serialize)]
+struct JsonDocument<'a> {
+    summary: Option<&'a RunSummary>,
+    entries: &'a EntryBuffer,
+}
+
+impl JsonSink {
+    pub fn new(dir: &Path, memory_limit: usize) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+            path: None,
+            entries: EntryBuffer::new(memory_limit),
+            summary: None,
+        }
+    }
+}
+
+impl OutputSink for JsonSink {
+    fn open(&mut self, job: &str) -> Result<()> {
+        self.path = Some(self.dir.join(format!("{job}.json")));
+        self.entries.clear();
+        self.summary = None;
+        Ok(())
+    }
+
+    fn write_entry(&mut self, entry: &ReportEntry) -> Result<()> {
+        self.entries.push(entry.clone())
+    }
+
+    fn write_summary(&mut self, summary: &RunSummary) -> Result<()> {
+        self.summary = Some(summary.clone());
+        Ok(())
+    }
+
+    fn close(&mut self) -> Result<()> {
+        let Some(path) = self.path.take() else {
+            return Ok(());
+        };
+        let document = JsonDocument {
+            summary: self.summary.as_ref(),
+            entries: &self.entries,
+        };
+        let file = File::create(&path)
+            .with_context(|| format!("Could not create {}", path.display()))?;
+        let mut writer = BufWriter::new(file);
+        serde_json::to_writer_pretty(&mut writer, &document)
+            .with_context(|| format!("Could not write {}", path.display()))?;
+        writer.flush().with_context(|| format!("Could not write {}", path.display()))?;
+        self.entries.clear();
+        Ok(())
+    }
+
+    fn location(&self) -> String {
+        location(&self.path, &self.dir, "json")
+    }
+}
+
+/// `{job}.html`: a self-contained report with the run totals and the entries per node, for
+/// sharing with people who don't work with CSV. Written on close.
+pub struct HtmlSink {
+    dir: PathBuf,
+    path: Option<PathBuf>,
+    entries: EntryBuffer,
+    summary: Option<RunSummary>,
+}
+
+impl HtmlSink {
+    pub fn new(dir: &Path, memory_limit: usize) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+            path: None,
+            entries: EntryBuffer::new(memory_limit),
+            summary: None,
+        }
+    }
+}
+
+impl OutputSink for HtmlSink {
+    fn open(&mut self, job: &str) -> Result<()> {
+        self.path = Some(self.dir.join(format!("{job}.html")));
+        self.entries.clear();
+        self.summary = None;
+        Ok(())
+    }
+
+    fn write_entry(&mut self, entry: &ReportEntry) -> Result<()> {
+        self.entries.push(entry.clone())
+    }
+
+    fn write_summary(&mut self, summary: &RunSummary) -> Result<()> {
+        self.summary = Some(summary.clone());
+        Ok(())
+    }
+
+    fn close(&mut self) -> Result<()> {
+        let Some(path) = self.path.take() else {
+            return Ok(());
+        };
+        // The report groups the entries by node, so spilled ones are read back for it
+        let html = report::run_report(&self.entries.to_vec()?, self.summary.as_ref());
+        fs::write(&path, html).with_context(|| format!("Could not write {}", path.display()))?;
+        self.entries.clear();
+        Ok(())
+    }
+
+    fn location(&self) -> String {
+        location(&self.path, &self.dir, "html")
+    }
+}
+
+/// Streams entries to stdout as they arrive, for piping into other tools. Writes nothing
+/// unless porcelain mode moved the progress output to stderr.
+pub struct StdoutSink {
+    format: StdoutFormat,
+}
+
+impl StdoutSink {
+    pub fn new(format: StdoutFormat) -> Self {
+        Self { format }
+    }
+}
+
+impl OutputSink for StdoutSink {
+    fn open(&mut self, _job: &str) -> Result<()> {
+        match self.format {
+            StdoutFormat::Csv => porcelain::write(&csv_row(&COLUMNS)?),
+            StdoutFormat::Jsonl => Ok(()),
+        }
+    }
+
+    fn write_entry(&mut self, entry: &ReportEntry) -> Result<()> {
+        match self.format {
+            StdoutFormat::Csv => porcelain::write(&csv_row(&entry.values())?),
+            StdoutFormat::Jsonl => porcelain::emit(entry),
+        }
+    }
+
+    /// The summary is printed to stderr with the rest of the progress output.
+    fn write_summary(&mut self, _summary: &RunSummary) -> Result<()> {
+        Ok(())
+    }
+
+    fn close(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn location(&self) -> String {
+        "stdout".to_string()
+    }
+}
+
+/// One CSV line with the quoting of the result files.
+fn csv_row(fields: &[&str]) -> Result<Vec<u8>> {
+    let mut writer = Writer::from_writer(Vec::new());
+    writer.write_record(fields)?;
+    writer.into_inner().map_err(|e| e.into_error().into())
+}
+
+const SQLITE_SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS runs (
+    id INTEGER PRIMARY KEY,
+    job TEXT NOT NULL,
+    started_at TEXT NOT NULL,
+    finished_at TEXT,
+    entries INTEGER,
+    outdated INTEGER,
+    aborted TEXT
+);
+CREATE TABLE IF NOT EXISTS nodes (
+    id INTEGER PRIMARY KEY,
+    source_node TEXT NOT NULL,
+    dimension TEXT NOT NULL,
+    breadcrumb_path TEXT NOT NULL,
+    site TEXT NOT NULL,
+    backend_url TEXT NOT NULL,
+    last_run_id INTEGER REFERENCES runs(id),
+    UNIQUE (source_node, dimension)
+);
+CREATE TABLE IF NOT EXISTS links (
+    id INTEGER PRIMARY KEY,
+    url TEXT NOT NULL UNIQUE,
+    first_run_id INTEGER REFERENCES runs(id),
+    last_run_id INTEGER REFERENCES runs(id)
+);
+CREATE TABLE IF NOT EXISTS validations (
+    run_id INTEGER NOT NULL REFERENCES runs(id),
+    node_id INTEGER NOT NULL REFERENCES nodes(id),
+    link_id INTEGER NOT NULL REFERENCES links(id),
+    content_type TEXT NOT NULL,
+    title TEXT NOT NULL,
+    author TEXT NOT NULL,
+    file_type TEXT NOT NULL,
+    size TEXT NOT NULL,
+    url_valid TEXT NOT NULL,
+    visual_check TEXT NOT NULL,
+    title_check TEXT NOT NULL,
+    age_flag TEXT NOT NULL,
+    visibility TEXT NOT NULL,
+    container_index TEXT NOT NULL,
+    fusion_path TEXT NOT NULL,
+    url_method TEXT NOT NULL DEFAULT '',
+    final_url TEXT NOT NULL DEFAULT '',
+    redirect_hops TEXT NOT NULL DEFAULT '',
+    wayback_url TEXT NOT NULL DEFAULT ''
+);
+CREATE INDEX IF NOT EXISTS validations_by_run ON validations (run_id);
+CREATE INDEX IF NOT EXISTS validations_by_node ON validations (node_id);
+CREATE INDEX IF NOT EXISTS validations_by_link ON validations (link_id);
+CREATE INDEX IF NOT EXISTS validations_by_status ON validations (url_valid);
+";
+
+/// Validation columns added after the tables were normalized.
+const SQLITE_ADDED_VALIDATION_COLUMNS: [&str; 4] =
+    ["url_method", "final_url", "redirect_hops", "wayback_url"];
+
+/// The entries of each run as in the result CSV. Recreated on open, so it shows added columns.
+const SQLITE_VIEW: &str = "
+DROP VIEW IF EXISTS run_entries;
+CREATE VIEW run_entries AS
+    SELECT validations.run_id, nodes.source_node, nodes.breadcrumb_path, validations.content_type,
+        links.url, validations.title, validations.author, validations.file_type, validations.size,
+        validations.url_valid, validations.visual_check, validations.title_check,
+        validations.age_flag, validations.visibility, nodes.site, nodes.dimension,
+        validations.container_index, validations.fusion_path, nodes.backend_url,
+        validations.url_method, validations.final_url, validations.redirect_hops,
+        validations.wayback_url
+    FROM validations
+    JOIN nodes ON nodes.id = validations.node_id
+    JOIN links ON links.id = validations.link_id;
+";
+
+/// Columns added to the flat `entries` table of the first schema before it was normalized.
+const SQLITE_ADDED_COLUMNS: [&str; 3] = ["container_index", "fusion_path", "backend_url"];
+
+/// Moves the rows of the flat `entries` table of the first schema into the normalized tables.
+/// Nodes keep the breadcrumb path of their latest run.
+const SQLITE_MIGRATION: &str = "
+BEGIN;
+INSERT OR IGNORE INTO nodes (source_node, dimension, breadcrumb_path, site, backend_url, last_run_id)
+    SELECT source_node, dimension, breadcrumb_path, site, backend_url, MAX(run_id)
+    FROM entries GROUP BY source_node, dimension;
+INSERT OR IGNORE INTO links (url, first_run_id, last_run_id)
+    SELECT url, MIN(run_id), MAX(run_id) FROM entries GROUP BY url;
+INSERT INTO validations (run_id, node_id, link_id, content_type, title, author, file_type, size,
+        url_valid, visual_check, title_check, age_flag, visibility, container_index, fusion_path)
+    SELECT entries.run_id, nodes.id, links.id, entries.content_type, entries.title,
+        entries.author, entries.file_type, entries.size, entries.url_valid, entries.visual_check,
+        entries.title_check, entries.age_flag, entries.visibility, entries.container_index,
+        entries.fusion_path
+    FROM entries
+    JOIN nodes ON nodes.source_node = entries.source_node AND nodes.dimension = entries.dimension
+    JOIN links ON links.url = entries.url;
+DROP TABLE entries;
+COMMIT;
+";
+
+/// Appends the entries of every run to one database, so findings can be compared over time.
+/// Nodes and links are stored once and updated by each run that sees them again; every run adds
+/// its validations of them. Entries are committed on each flush.
+pub struct SqliteSink {
+    path: PathBuf,
+    connection: Option<Connection>,
+    run_id: i64,
+    /// IDs of the node variants and links already written in this run.
+    node_ids: HashMap<(String, String), i64>,
+    link_ids: HashMap<String, i64>,
+}
+
+impl SqliteSink {
+    pub fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            connection: None,
+            run_id: 0,
+            node_ids: HashMap::new(),
+            link_ids: HashMap::new(),
+        }
+    }
+
+    fn connection(&mut self) -> Result<&mut Connection> {
+        self.connection.as_mut().context("SQLite sink is not open")
+    }
+}
+
+/// Move the flat `entries` table of a database created by the first schema into the
+/// normalized tables.
+fn migrate_entries(connection: &Connection) -> Result<()> {
+    let legacy: i64 = connection.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'entries'",
+        [],
+        |row| row.get(0),
+    )?;
+    if legacy == 0 {
+        return Ok(());
+    }
+    // Databases created before these columns existed; the error only says they already do
+    for column in SQLITE_ADDED_COLUMNS {
+        let _ = connection.execute_batch(&format!(
+            "ALTER TABLE entries ADD COLUMN {column} TEXT NOT NULL DEFAULT ''"
+        ));
+    }
+    if let Err(e) = connection.execute_batch(SQLITE_MIGRATION) {
+        let _ = connection.execute_batch("ROLLBACK");
+        return Err(e).context("Could not migrate the SQLite entries to the normalized tables");
+    }
+    Ok(())
+}
+
+/// Insert or refresh the node variant of `entry`, returning its ID.
+fn upsert_node(connection: &Connection, run_id: i64, entry: &ReportEntry) -> Result<i64> {
+    let id = connection
+        .query_row(
+            "INSERT INTO nodes (source_node, dimension, breadcrumb_path, site, backend_url, \
+             last_run_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6) \
+             ON CONFLICT (source_node, dimension) DO UPDATE SET \
+             breadcrumb_path = excluded.breadcrumb_path, site = excluded.site, \
+             backend_url = excluded.backend_url, last_run_id = excluded.last_run_id \
+             RETURNING id",
+            params![
+                entry.source_node,
+                entry.dimension,
+                entry.breadcrumb_path,
+                entry.site,
+                entry.backend_url,
+                run_id
+            ],
+            |row| row.get(0),
+        )
+        .context("Failed to write SQLite node")?;
+    Ok(id)
+}
+
+/// Insert or refresh the link `url`, returning its ID.
+fn upsert_link(connection: &Connection, run_id: i64, url: &str) -> Result<i64> {
+    let id = connection
+        .query_row(
+            "INSERT INTO links (url, first_run_id, last_run_id) VALUES (?1, ?2, ?2) \
+             ON CONFLICT (url) DO UPDATE SET last_run_id = excluded.last_run_id \
+             RETURNING id",
+            params![url, run_id],
+            |row| row.get(0),
+        )
+        .context("Failed to write SQLite link")?;
+    Ok(id)
+}
+
+impl OutputSink for SqliteSink {
+    fn open(&mut self, job: &str) -> Result<()> {
+        if let Some(dir) = self.path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir).context("Could not create the SQLite directory")?;
+        }
+        let connection = Connection::open(&self.path)
+            .with_context(|| format!("Could not open {}", self.path.display()))?;
+        connection
+            .execute_batch(SQLITE_SCHEMA)
+            .context("Could not create the SQLite tables")?;
+        migrate_entries(&connection)?;
+        // Databases created before these columns existed; the error only says they already do
+        let _ = connection.execute_batch("ALTER TABLE runs ADD COLUMN aborted TEXT");
+        for column in SQLITE_ADDED_VALIDATION_COLUMNS {
+            let _ = connection.execute_batch(&format!(
+                "ALTER TABLE validations ADD COLUMN {column} TEXT NOT NULL DEFAULT ''"
+            ));
+        }
+        connection
+            .execute_batch(SQLITE_VIEW)
+            .context("Could not create the SQLite views")?;
+        connection
+            .execute(
+                "INSERT INTO runs (job, started_at) VALUES (?1, ?2)",
+                params![job, Local::now().to_rfc3339()],
+            )
+            .context("Could not record the run")?;
+        self.run_id = connection.last_insert_rowid();
+        self.node_ids.clear();
+        self.link_ids.clear();
+        connection.execute_batch("BEGIN")?;
+        self.connection = Some(connection);
+        Ok(())
+    }
+
+    fn write_entry(&mut self, entry: &ReportEntry) -> Result<()> {
+        let run_id = self.run_id;
+        let connection = self.connection.as_ref().context("SQLite sink is not open")?;
+
+        let key = (entry.source_node.clone(), entry.dimension.clone());
+        let node_id = match self.node_ids.get(&key) {
+            Some(&id) => id,
+            None => {
+                let id = upsert_node(connection, run_id, entry)?;
+                self.node_ids.insert(key, id);
+                id
+            }
+        };
+        let link_id = match self.link_ids.get(&entry.url) {
+            Some(&id) => id,
+            None => {
+                let id = upsert_link(connection, run_id, &entry.url)?;
+                self.link_ids.insert(entry.url.clone(), id);
+                id
+            }
+        };
+
+        connection
+            .execute(
+                "INSERT INTO validations (run_id, node_id, link_id, content_type, title, author, \
+                 file_type, size, url_valid, visual_check, title_check, age_flag, visibility, \
+                 container_index, fusion_path, url_method, final_url, redirect_hops, wayback_url) \
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, \
+                 ?17, ?18, ?19)",
+                params![
+                    run_id,
+                    node_id,
+                    link_id,
+                    entry.content_type,
+                    entry.title,
+                    entry.author,
+                    entry.file_type,
+                    entry.size,
+                    entry.url_valid,
+                    entry.visual_check,
+                    entry.title_check,
+                    entry.age_flag,
+                    entry.visibility,
+                    entry.container_index,
+                    entry.fusion_path,
+                    entry.url_method,
+                    entry.final_url,
+                    entry.redirect_hops,
+                    entry.wayback_url
+                ],
+            )
+            .context("Failed to write SQLite entry")?;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.connection()?
+            .execute_batch("COMMIT; BEGIN")
+            .context("Failed to commit SQLite entries")
+    }
+
+    fn write_summary(&mut self, summary: &RunSummary) -> Result<()> {
+        let run_id = self.run_id;
+        self.connection()?
+            .execute(
+                "UPDATE runs SET started_at = ?1, finished_at = ?2, entries = ?3, outdated = ?4, \
+                 aborted = ?5 WHERE id = ?6",
+                params![
+                    summary.started_at.to_rfc3339(),
+                    summary.finished_at.to_rfc3339(),
+                    summary.entries as i64,
+                    summary.outdated as i64,
+                    summary.relogin_storm.as_ref().map(ToString::to_string),
+                    run_id
+                ],
+            )
+            .context("Failed to write the SQLite run summary")?;
+        Ok(())
+    }
+
+    fn close(&mut self) -> Result<()> {
+        if let Some(connection) = self.connection.take() {
+            connection
+                .execute_batch("COMMIT")
+                .context("Failed to commit SQLite entries")?;
+        }
+        Ok(())
+    }
+
+    fn location(&self) -> String {
+        self.path.display().to_string()
+    }
+}
+
+/// `{job}.xlsx` with a sheet of entries per content type and a "Summary" sheet. A workbook can
+/// only be saved as a whole, so entries are buffered and written on close.
+pub struct XlsxSink {
+    dir: PathBuf,
+    path: Option<PathBuf>,
+    entries: EntryBuffer,
+    summary: Option<RunSummary>,
+}
+
+impl XlsxSink {
+    pub fn new(dir: &Path, memory_limit: usize) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+            path: None,
+            entries: EntryBuffer::new(memory_limit),
+            summary: None,
+        }
+    }
+}
+
+impl OutputSink for XlsxSink {
+    fn open(&mut self, job: &str) -> Result<()> {
+        self.path = Some(self.dir.join(format!("{job}.xlsx")));
+        self.entries.clear();
+        self.summary = None;
+        Ok(())
+    }
+
+    fn write_entry(&mut self, entry: &ReportEntry) -> Result<()> {
+        self.entries.push(entry.clone())
+    }
+
+    fn write_summary(&mut self, summary: &RunSummary) -> Result<()> {
+        self.summary = Some(summary.clone());
+        Ok(())
+    }
+
+    fn close(&mut self) -> Result<()> {
+        let Some(path) = self.path.take() else {
+            return Ok(());
+        };
+
+        // Rows go straight into their sheet, so spilled entries are read back one at a time
+        let mut by_type: BTreeMap<String, (Worksheet, u32)> = BTreeMap::new();
+        self.entries.for_each(|entry| {
+            let (sheet, rows) = match by_type.entry(entry.content_type.clone()) {
+                Entry::Occupied(sheet) => sheet.into_mut(),
+                Entry::Vacant(slot) => slot.insert((entries_sheet(&entry.content_type)?, 0)),
+            };
+            *rows += 1;
+            for (col, value) in entry.values().iter().enumerate() {
+                sheet.write_string(*rows, col as u16, *value)?;
+            }
+            Ok(())
+        })?;
+
+        let mut workbook = Workbook::new();
+        if by_type.is_empty() {
+            by_type.insert(String::new(), (entries_sheet("Entries")?, 0));
+        }
+        for (mut sheet, rows) in by_type.into_values() {
+            highlight_status(&mut sheet, rows)?;
+            workbook.push_worksheet(sheet);
+        }
+        if let Some(summary) = &self.summary {
+            let mut sheet = Worksheet::new();
+            sheet.set_name("Summary")?;
+            let mut rows = vec![
+                ("Job", summary.job.clone()),
+                ("Started", summary.started_at.format("%Y-%m-%d %H:%M").to_string()),
+                ("Finished", summary.finished_at.format("%Y-%m-%d %H:%M").to_string()),
+                ("Entries", summary.entries.to_string()),
+                ("Outdated", summary.outdated.to_string()),
+            ];
+            if let Some(storm) = &summary.relogin_storm {
+                rows.push(("Aborted", storm.to_string()));
+            }
+            for (row, (label, value)) in rows.into_iter().enumerate() {
+                sheet.write_string(row as u32, 0, label)?;
+                sheet.write_string(row as u32, 1, value)?;
+            }
+            workbook.push_worksheet(sheet);
+        }
+        workbook
+            .save(&path)
+            .with_context(|| format!("Could not save {}", path.display()))?;
+        self.entries.clear();
+        Ok(())
+    }
+
+    fn location(&self) -> String {
+        location(&self.path, &self.dir, "xlsx")
+    }
+}
+
+/// Rows buffered by `ParquetSink` per row group. Parquet files are only readable once closed,
+/// so smaller groups would only make the file slower to scan.
+#[cfg(feature = "parquet")]
+const PARQUET_ROW_GROUP: usize = 8192;
+
+/// `{job}.parquet` with one string column per CSV column, named like the SQLite columns
+/// (`url_valid`), for loading large crawls into DuckDB or Spark.
+#[cfg(feature = "parquet")]
+pub struct ParquetSink {
+    dir: PathBuf,
+    path: Option<PathBuf>,
+    schema: arrow_schema::SchemaRef,
+    writer: Option<parquet::arrow::ArrowWriter<File>>,
+    pending: Vec<ReportEntry>,
+}
+
+#[cfg(feature = "parquet")]
+impl ParquetSink {
+    pub fn new(dir: &Path) -> Self {
+        use arrow_schema::{DataType, Field, Schema};
+
+        let fields: Vec<Field> = COLUMNS
+            .iter()
+            .map(|title| Field::new(title.to_lowercase().replace(' ', "_"), DataType::Utf8, false))
+            .collect();
+        Self {
+            dir: dir.to_path_buf(),
+            path: None,
+            schema: std::sync::Arc::new(Schema::new(fields)),
+            writer: None,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Write the buffered entries as one row group.
+    fn write_pending(&mut self) -> Result<()> {
+        use arrow_array::{ArrayRef, RecordBatch, StringArray};
+
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        let columns: Vec<ArrayRef> = (0..COLUMNS.len())
+            .map(|col| {
+                let values = self.pending.iter().map(|entry| entry.values()[col]);
+                std::sync::Arc::new(StringArray::from_iter_values(values)) as ArrayRef
+            })
+            .collect();
+        let batch = RecordBatch::try_new(self.schema.clone(), columns)?;
+        let writer = self.writer.as_mut().context("Parquet sink is not open")?;
+        writer.write(&batch).context("Failed to write Parquet row group")?;
+        writer.flush().context("Failed to write Parquet row group")?;
+        self.pending.clear();
+        Ok(())
+    }
+}
+
+#[cfg(feature = "parquet")]
+impl OutputSink for ParquetSink {
+    fn open(&mut self, job: &str) -> Result<()> {
+        use parquet::{
+            arrow::ArrowWriter,
+            basic::{Compression, ZstdLevel},
+            file::properties::WriterProperties,
+        };
+
+        let path = self.dir.join(format!("{job}.parquet"));
+        let file = File::create(&path)
+            .with_context(|| format!("Could not create {}", path.display()))?;
+        let properties = WriterProperties::builder()
+            .set_compression(Compression::ZSTD(ZstdLevel::default()))
+            .build();
+        self.writer = Some(ArrowWriter::try_new(file, self.schema.clone(), Some(properties))?);
+        self.path = Some(path);
+        self.pending.clear();
+        Ok(())
+    }
+
+    fn write_entry(&mut self, entry: &ReportEntry) -> Result<()> {
+        self.pending.push(entry.clone());
+        if self.pending.len() >= PARQUET_ROW_GROUP {
+            self.write_pending()?;
+        }
+        Ok(())
+    }
+
+    fn write_summary(&mut self, _summary: &RunSummary) -> Result<()> {
+        Ok(())
+    }
+
+    fn close(&mut self) -> Result<()> {
+        if self.writer.is_none() {
+            return Ok(());
+        }
+        self.write_pending()?;
+        if let Some(writer) = self.writer.take() {
+            writer.close().context("Failed to finish Parquet file")?;
+        }
+        Ok(())
+    }
+
+    fn location(&self) -> String {
+        location(&self.path, &self.dir, "parquet")
+    }
+}
+
+/// Sheet named after `name` with a frozen header row, for the entries to be written below.
+fn entries_sheet(name: &str) -> Result<Worksheet, XlsxError> {
+    let mut sheet = Worksheet::new();
+    sheet.set_name(sheet_name(name))?;
+    let header = Format::new().set_bold();
+    for (col, title) in COLUMNS.iter().enumerate() {
+        sheet.write_string_with_format(0, col as u16, *title, &header)?;
+    }
+    sheet.set_freeze_panes(1, 0)?;
+    Ok(sheet)
+}
+
+/// Highlight the URL status of the first `rows` entries of an entries sheet: green for valid,
+/// yellow for redirects and red for broken links.
+fn highlight_status(sheet: &mut Worksheet, rows: u32) -> Result<(), XlsxError> {
+    let Some(status) = COLUMNS.iter().position(|title| *title == "URL Valid") else {
+        return Ok(());
+    };
+    let status = status as u16;
+    let last_row = rows.max(1);
+    let highlights = [
+        ("Valid", 0x006100, 0xC6EFCE),
+        ("Available", 0x006100, 0xC6EFCE),
+        ("Redirect", 0x9C5700, 0xFFEB9C),
+        ("Error", 0x9C0006, 0xFFC7CE),
+        ("Invalid", 0x9C0006, 0xFFC7CE),
+        ("Deleted", 0x9C0006, 0xFFC7CE),
+        ("Private", 0x9C0006, 0xFFC7CE),
+    ];
+    for (prefix, font, background) in highlights {
+        let format = Format::new()
+            .set_font_color(Color::RGB(font))
+            .set_background_color(Color::RGB(background));
+        let rule = ConditionalFormatText::new()
+            .set_rule(ConditionalFormatTextRule::BeginsWith(prefix.to_string()))
+            .set_format(format);
+        sheet.add_conditional_format(1, status, last_row, status, &rule)?;
+    }
+    Ok(())
+}
+
+/// `name` as a valid sheet name: at most 31 characters, none of `[]:*?/\`.
+fn sheet_name(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| if "[]:*?/\\".contains(c) { '_' } else { c })
+        .take(31)
+        .collect();
+    if name.is_empty() {
+        "Entries".to_string()
+    } else {
+        name
+    }
+}
+
+/// The opened file, or the pattern it will be created from.
+fn location(path: &Option<PathBuf>, dir: &Path, extension: &str) -> String {
+    match path {
+        Some(path) => path.display().to_string(),
+        None => format!("{}/{{job}}.{extension}", dir.display()),
+    }
+}
@@ -0,0 +1,220 @@
+use crate::archive::{self, RetentionSummary};
+use crate::config::{ArchiveConfig, Compression, SnapshotConfig, SnapshotFormat};
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Local};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use similar::TextDiff;
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs::{self, File},
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+static STORE: Lazy<Mutex<Option<Store>>> = Lazy::new(|| Mutex::new(None));
+
+/// One line of a run's index: which content a node had in that run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexEntry {
+    pub node: String,
+    /// SHA-256 of the content, naming its object in the store.
+    pub hash: String,
+    pub at: DateTime<Local>,
+}
+
+struct Store {
+    dir: PathBuf,
+    format: SnapshotFormat,
+    compression: Compression,
+    index: File,
+}
+
+/// Start storing node snapshots for a run named after the current time, compressing new
+/// objects with `compression`. Returns the run ID, or `None` if snapshots are disabled.
+pub fn start(config: &SnapshotConfig, compression: Compression) -> Result<Option<String>> {
+    if !config.enabled {
+        return Ok(None);
+    }
+
+    let runs = config.dir.join("runs");
+    fs::create_dir_all(&runs).context("Could not create the snapshot store")?;
+    let run = Local::now().format("%Y%m%d-%H%M%S").to_string();
+    let index = File::create(runs.join(format!("{run}.jsonl")))
+        .context("Could not create the snapshot index")?;
+
+    *STORE.lock().unwrap() = Some(Store {
+        dir: config.dir.clone(),
+        format: config.format,
+        compression,
+        index,
+    });
+    Ok(Some(run))
+}
+
+/// What the running store keeps of a node page, or `None` if no store is running.
+pub fn wanted() -> Option<SnapshotFormat> {
+    STORE.lock().unwrap().as_ref().map(|store| store.format)
+}
+
+/// Store `content` as the snapshot of `node` in the current run. Content that is already in the
+/// store, e.g. from an unchanged node in an earlier run, is only referenced again.
+pub fn save(node: &str, content: &str) -> Result<()> {
+    let mut guard = STORE.lock().unwrap();
+    let Some(store) = guard.as_mut() else {
+        return Ok(());
+    };
+
+    let hash = format!("{:x}", Sha256::digest(content.as_bytes()));
+    let path = object_path(&store.dir, &hash);
+    if archive::locate(&path).is_none() {
+        fs::create_dir_all(path.parent().unwrap_or(&store.dir))
+            .context("Could not create the snapshot object directory")?;
+        archive::write(&path, content.as_bytes(), store.compression)?;
+    }
+
+    let entry = IndexEntry {
+        node: node.to_string(),
+        hash,
+        at: Local::now(),
+    };
+    let json = serde_json::to_string(&entry)?;
+    writeln!(store.index, "{json}").context("Could not write the snapshot index")?;
+    Ok(())
+}
+
+/// Runs that stored a snapshot of `node`, oldest first, with the hash of its content.
+pub fn runs_with(dir: &Path, node: &str) -> Result<Vec<(String, String)>> {
+    let mut runs = Vec::new();
+    let Ok(files) = fs::read_dir(dir.join("runs")) else {
+        return Ok(runs);
+    };
+    for file in files {
+        let path = file?.path();
+        let Some(run) = path.file_stem().and_then(|stem| stem.to_str()) else {
+            continue;
+        };
+        if let Some(hash) = load_index(&path)?.remove(node) {
+            runs.push((run.to_string(), hash));
+        }
+    }
+    runs.sort();
+    Ok(runs)
+}
+
+/// The snapshot of `node` stored in `run`.
+pub fn load(dir: &Path, run: &str, node: &str) -> Result<String> {
+    let index = dir.join("runs").join(format!("{run}.jsonl"));
+    if !index.exists() {
+        bail!("No snapshot run {run} in {}", dir.display());
+    }
+    let Some(hash) = load_index(&index)?.remove(node) else {
+        bail!("Run {run} has no snapshot of {node}");
+    };
+    let path = object_path(dir, &hash);
+    let path = archive::locate(&path)
+        .with_context(|| format!("Snapshot object {hash} is missing from the store"))?;
+    String::from_utf8(archive::read(&path)?).context("Snapshot is not valid UTF-8")
+}
+
+/// Unified diff of the snapshots of `node` from `run_a` to `run_b`. Empty if nothing changed.
+pub fn diff(dir: &Path, node: &str, run_a: &str, run_b: &str) -> Result<String> {
+    let old = load(dir, run_a, node)?;
+    let new = load(dir, run_b, node)?;
+    if old == new {
+        return Ok(String::new());
+    }
+    Ok(TextDiff::from_lines(&old, &new)
+        .unified_diff()
+        .context_radius(3)
+        .header(&format!("{node} ({run_a})"), &format!("{node} ({run_b})"))
+        .to_string())
+}
+
+/// Apply the retention policy to the store in `dir`: runs older than `delete_after_days` are
+/// deleted together with the objects no remaining run refers to, and objects not written for
+/// `compress_after_days` are compressed.
+pub fn apply_retention(dir: &Path, config: &ArchiveConfig) -> Result<RetentionSummary> {
+    let mut summary = RetentionSummary::default();
+    let runs = dir.join("runs");
+    if !runs.is_dir() {
+        return Ok(summary);
+    }
+
+    if config.delete_after_days > 0 {
+        for file in fs::read_dir(&runs).context("Could not read the snapshot runs")? {
+            let path = file?.path();
+            let expired = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(archive::timestamp_age)
+                .is_some_and(|age| age > config.delete_after());
+            if expired {
+                fs::remove_file(&path)
+                    .with_context(|| format!("Could not delete {}", path.display()))?;
+                summary.deleted += 1;
+            }
+        }
+    }
+    let compress = config.compression != Compression::None && config.compress_after_days > 0;
+    if summary.deleted == 0 && !compress {
+        return Ok(summary);
+    }
+
+    let mut referenced = HashSet::new();
+    for file in fs::read_dir(&runs).context("Could not read the snapshot runs")? {
+        referenced.extend(load_index(&file?.path())?.into_values());
+    }
+    let Ok(prefixes) = fs::read_dir(dir.join("objects")) else {
+        return Ok(summary);
+    };
+    for prefix in prefixes {
+        let prefix = prefix?.path();
+        let Some(first) = prefix.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        for object in fs::read_dir(&prefix)? {
+            let path = object?.path();
+            let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+            // Compressed objects carry the extension of their format after the hash
+            let hash = format!("{first}{}", name.split('.').next().unwrap_or(name));
+            if summary.deleted > 0 && !referenced.contains(&hash) {
+                fs::remove_file(&path)
+                    .with_context(|| format!("Could not delete {}", path.display()))?;
+            } else if compress
+                && path.extension().is_none()
+                && archive::modified_age(&path).is_some_and(|age| age > config.compress_after())
+            {
+                archive::compress_file(&path, config.compression)?;
+                summary.compressed += 1;
+            }
+        }
+    }
+    Ok(summary)
+}
+
+/// Node to content hash of one run. A node extracted several times keeps its last snapshot.
+fn load_index(path: &Path) -> Result<BTreeMap<String, String>> {
+    let file = File::open(path)
+        .with_context(|| format!("Could not open snapshot index {}", path.display()))?;
+    let mut nodes = BTreeMap::new();
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        // A run that crashed mid-write can leave half a line at the end
+        if let Ok(entry) = serde_json::from_str::<IndexEntry>(&line) {
+            nodes.insert(entry.node, entry.hash);
+        }
+    }
+    Ok(nodes)
+}
+
+fn object_path(dir: &Path, hash: &str) -> PathBuf {
+    dir.join("objects").join(&hash[..2]).join(&hash[2..])
+}
@@ -1,14 +1,214 @@
-use std::time::Duration;
-
-use crate::{lexer::Lexer, tree::FileTree};
-use anyhow::{bail, Context, Result};
+use std::{
+    collections::{HashSet, VecDeque},
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{self, Poll},
+    time::Duration,
+};
+
+use crate::{
+    config::{Browser, ContentType, ExtractionConfig, SpiderConfig},
+    dimensions,
+    lexer::Lexer,
+    report::ReportEntry,
+    selectors::{self, Selectors},
+    tree::FileTree,
+    treeitem::TreeItem,
+    wait::{self, Timeouts},
+};
+use anyhow::{Context, Result};
 use async_recursion::async_recursion;
-use thirtyfour::{prelude::*, support, WebDriver};
+use futures::{
+    future::{BoxFuture, Future},
+    stream::{self, Stream},
+    FutureExt,
+};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use thirtyfour::{prelude::*, WebDriver};
 use tokio::{fs, time::Instant};
+use tokio_util::sync::CancellationToken;
+
+/// Window size of headless Chrome and Edge, which otherwise lay out the backend for 800x600.
+const HEADLESS_WINDOW_SIZE: &str = "--window-size=1920,1080";
+
+/// Capabilities starting the `[spider] browser`, without a window if `headless`, with the
+/// `[spider.capabilities]` added. An extra capability that is an object, like
+/// `goog:chromeOptions`, is merged into the browser's own, so the headless setup is kept.
+pub fn capabilities(config: &SpiderConfig, headless: bool) -> Result<Capabilities> {
+    let mut caps = browser_capabilities(config.browser, headless)?;
+    for (key, value) in &config.capabilities {
+        match (caps.get_mut(key), value) {
+            (Some(serde_json::Value::Object(own)), serde_json::Value::Object(extra)) => {
+                own.extend(extra.clone());
+            }
+            _ => {
+                caps.insert(key.clone(), value.clone());
+            }
+        }
+    }
+    Ok(caps)
+}
+
+fn browser_capabilities(browser: Browser, headless: bool) -> Result<Capabilities> {
+    Ok(match browser {
+        Browser::Firefox => {
+            let mut caps = DesiredCapabilities::firefox();
+            if headless {
+                caps.set_headless()?;
+            }
+            caps.into()
+        }
+        Browser::Chrome => {
+            let mut caps = DesiredCapabilities::chrome();
+            if headless {
+                caps.set_headless()?;
+                caps.add_arg(HEADLESS_WINDOW_SIZE)?;
+            }
+            caps.into()
+        }
+        Browser::Edge => {
+            let mut caps = DesiredCapabilities::edge();
+            if headless {
+                caps.set_headless()?;
+                caps.add_arg(HEADLESS_WINDOW_SIZE)?;
+            }
+            caps.into()
+        }
+    })
+}
+
+/// Reads the ExternalLinks, YouTube and Tutorial entries of a loaded node page in one call.
+pub static CONTENT_SCRIPT: &str = include_str!("../resources/scripts/content-entries.js");
+
+/// Folders expanded in this browser session, so expanding them again needs no DOM queries.
+static EXPANDED: Lazy<Mutex<HashSet<String>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+/// Whether `id` was already expanded in this session.
+pub fn is_expanded(id: &str) -> bool {
+    EXPANDED.lock().unwrap().contains(id)
+}
+
+/// Remember that `id` is expanded.
+pub fn mark_expanded(id: &str) {
+    EXPANDED.lock().unwrap().insert(id.to_string());
+}
+
+/// Forget all expanded folders, e.g. after a page reload collapsed the tree.
+pub fn forget_expanded() {
+    EXPANDED.lock().unwrap().clear();
+}
+
+/// Breadcrumb of the loaded node page, joined with ` > `, or the page title if there is none.
+pub async fn breadcrumb_path(driver: &WebDriver) -> Result<String> {
+    let candidates = &Selectors::get().content.breadcrumb;
+    let breadcrumbs =
+        selectors::probe_all_first(&driver.handle, "content.breadcrumb", candidates).await?;
+
+    if !breadcrumbs.is_empty() {
+        let mut path_parts = Vec::new();
+        for breadcrumb in breadcrumbs {
+            if let Ok(text) = breadcrumb.text().await {
+                let trimmed = text.trim();
+                if !trimmed.is_empty() {
+                    path_parts.push(trimmed.to_string());
+                }
+            }
+        }
+        if !path_parts.is_empty() {
+            return Ok(path_parts.join(" > "));
+        }
+    }
+
+    if let Ok(title) = driver.title().await {
+        return Ok(title);
+    }
+
+    Ok("Unknown Path".to_string())
+}
+
+/// An entry as returned by `CONTENT_SCRIPT`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ScriptEntry {
+    pub kind: String,
+    pub url: String,
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub author: String,
+    #[serde(default)]
+    pub file_type: String,
+    #[serde(default)]
+    pub size: String,
+    /// 1-based index of the dynamic container, `None` for tutorials.
+    #[serde(default)]
+    pub container_index: Option<usize>,
+    #[serde(default)]
+    pub fusion_path: String,
+    /// Marker of the embedded player, for the visual check.
+    pub player: Option<String>,
+    /// Whether any of the expected fields was found in the container.
+    pub matched: bool,
+}
+
+/// Progress of `Spider::extract_stream` and `DriverPool::extract_stream`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ExtractEvent {
+    /// Extraction of a node began; `index` is its position in the run, from 1 up to `total`.
+    NodeStarted {
+        node_id: String,
+        index: usize,
+        total: usize,
+    },
+    EntryFound {
+        node_id: String,
+        entry: Box<ReportEntry>,
+    },
+    NodeFinished {
+        node_id: String,
+        entries: usize,
+        elapsed: Duration,
+    },
+    /// The node could not be extracted. The stream goes on with the next node.
+    Error { node_id: String, message: String },
+    /// `Spider::cancel` was cancelled; the stream ends without extracting the remaining nodes.
+    Cancelled { remaining: usize },
+}
+
+/// Where `Spider::extract_stream` is in its list of nodes.
+struct StreamState<I> {
+    nodes: I,
+    /// Announced with `NodeStarted`, extracted on the next poll.
+    current: Option<String>,
+    pending: VecDeque<ExtractEvent>,
+}
+
+/// The nodes of a `DriverPool` run, each with its position in the run. Every session takes the
+/// next node once it is done with its current one, so a slow part of the tree does not hold up
+/// the sessions that are done with theirs.
+struct NodeQueue(Arc<Mutex<VecDeque<(usize, String)>>>);
+
+impl Iterator for NodeQueue {
+    type Item = (usize, String);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.lock().unwrap().pop_front()
+    }
+}
 
 pub struct Spider {
     pub driver: WebDriver,
     pub file_tree: FileTree,
+    /// Cancelling it stops long-running work at the next node, keeping what was done so far.
+    pub cancel: CancellationToken,
+    /// How long to wait for pages and folders, `[timeouts]` by default.
+    pub timeouts: Timeouts,
+    /// Which region and content types `extract_entries` reads, `[extraction]` by default.
+    pub extraction: ExtractionConfig,
+    /// Folders this session expanded, for sessions of a `DriverPool`. `None` shares the
+    /// module-wide set (see `is_expanded`) with the driver-level helpers of a single session.
+    expanded: Option<Mutex<HashSet<String>>>,
 }
 
 impl Spider {
@@ -16,12 +216,56 @@ impl Spider {
     where
         C: Into<Capabilities>,
     {
-        let driver = WebDriver::new("http://localhost:4444", capabilities)
+        Self::connect("http://localhost:4444", capabilities, url, file_tree).await
+    }
+
+    /// Like `new`, with the browser controlled by the WebDriver server at `webdriver_url`.
+    pub async fn connect<C>(
+        webdriver_url: &str,
+        capabilities: C,
+        url: &str,
+        file_tree: FileTree,
+    ) -> Result<Self>
+    where
+        C: Into<Capabilities>,
+    {
+        let driver = WebDriver::new(webdriver_url, capabilities)
             .await
             .context("Failed to create WebDriver")?;
         driver.get(url).await.context("Failed to navigate to URL")?;
 
-        Ok(Self { driver, file_tree })
+        Ok(Self {
+            driver,
+            file_tree,
+            cancel: CancellationToken::new(),
+            timeouts: Timeouts::default(),
+            extraction: ExtractionConfig::default(),
+            expanded: None,
+        })
+    }
+
+    fn is_expanded(&self, id: &str) -> bool {
+        match &self.expanded {
+            Some(expanded) => expanded.lock().unwrap().contains(id),
+            None => is_expanded(id),
+        }
+    }
+
+    fn mark_expanded(&self, id: &str) {
+        match &self.expanded {
+            Some(expanded) => {
+                expanded.lock().unwrap().insert(id.to_string());
+            }
+            None => mark_expanded(id),
+        }
+    }
+
+    /// Forget the folders this session expanded, e.g. after a page reload collapsed the tree.
+    pub fn forget_expanded(&self) {
+        match &self.expanded {
+            Some(expanded) => expanded.lock().unwrap().clear(),
+            None => forget_expanded(),
+        }
     }
 
     #[async_recursion]
@@ -31,7 +275,8 @@ impl Spider {
             id
         ))?;
 
-        if let Some(parent_id) = &current_node.parent {
+        let mut toggled = false;
+        if let Some(parent_id) = current_node.parent.as_ref().filter(|id| !self.is_expanded(id)) {
             // Check if already expanded before toggling
             let expanded = self
                 .find_treeitem(parent_id)
@@ -41,27 +286,73 @@ impl Spider {
 
             if expanded != Some("true".to_string()) {
                 self.click_treeitem_toggle(parent_id).await?;
+                toggled = true;
             }
+            self.mark_expanded(parent_id);
         }
 
-        // Now attempt to find the current node
-        let selector = format!("div[aria-labelledby='{}']", id);
+        // Now attempt to find the current node, giving a just expanded tree time to render it
+        let candidates = selectors::with_id(&Selectors::get().tree.treeitem, id);
+        let timeout = if toggled { self.timeouts.expand } else { Duration::ZERO };
+        let treeitem = wait::wait_for_element(&self.driver.handle, "tree.treeitem", &candidates, timeout)
+            .await
+            .with_context(|| format!("Could not find treeitem {id}"))?;
+        treeitem.scroll_into_view().await?;
+        Ok(treeitem)
+    }
 
-        match self.driver.find(By::Css(&selector)).await {
-            Ok(treeitem) => {
-                treeitem.scroll_into_view().await.expect("We found the treeitem already there is no reasont that we cant scroll to its location!");
-                Ok(treeitem)
-            }
-            Err(_) => bail!("Something went wrong!"),
+    /// The tree item `id`: found through the file tree if the spider knows the node, otherwise
+    /// waited for in the rendered tree, e.g. below a folder a [`Traversal`] just expanded.
+    pub async fn locate_treeitem(&self, id: &str) -> Result<WebElement> {
+        if self.file_tree.nodes.contains_key(id) {
+            return self.find_treeitem(id).await;
         }
+        let candidates = selectors::with_id(&Selectors::get().tree.treeitem, id);
+        let treeitem = wait::wait_for_element(
+            &self.driver.handle,
+            "tree.treeitem",
+            &candidates,
+            self.timeouts.element,
+        )
+        .await
+        .with_context(|| format!("Could not find treeitem {id}"))?;
+        treeitem.scroll_into_view().await?;
+        Ok(treeitem)
     }
 
-    pub async fn click_treeitem(&self, id: &str) -> Result<()> {
-        let treeitem = self.find_treeitem(id).await?;
-        let treeitem_header = treeitem
-            .find(By::ClassName("node__header__labelWrapper___dJ7OH"))
+    /// Expand the folder `id` if it is collapsed and read its children.
+    pub async fn expanded_children(&self, id: &str) -> Result<Vec<TreeItem>> {
+        let folder = self.locate_treeitem(id).await?;
+        let tree = &Selectors::get().tree;
+        if folder.attr("aria-expanded").await?.as_deref() != Some("true") {
+            // Without a chevron the node cannot have children
+            let Ok(chevron) = selectors::probe_first(&folder, "tree.chevron", &tree.chevron).await
+            else {
+                return Ok(Vec::new());
+            };
+            chevron
+                .click()
+                .await
+                .context("Could not click the toggle button!")?;
+            wait::wait_for_element(
+                &folder,
+                "tree.contents",
+                &tree.contents,
+                self.timeouts.expand,
+            )
             .await
-            .context("Could not find treeitem header!")?;
+            .context("The folder did not show its children")?;
+        }
+        self.mark_expanded(id);
+        TreeItem::read_children(&self.driver, &folder).await
+    }
+
+    pub async fn click_treeitem(&self, id: &str) -> Result<()> {
+        let treeitem = self.locate_treeitem(id).await?;
+        let treeitem_header =
+            selectors::find_first(&treeitem, "tree.header", &Selectors::get().tree.header)
+                .await
+                .context("Could not find treeitem header!")?;
         treeitem_header
             .click()
             .await
@@ -72,12 +363,10 @@ impl Spider {
 
     pub async fn click_treeitem_toggle(&self, id: &str) -> Result<()> {
         let treeitem = self.find_treeitem(id).await?;
-        let treeitem_toggle = treeitem
-            .find(By::Css(
-                "a.node__header__chevron___zXVME.reset__reset___2e25U",
-            ))
-            .await
-            .context("Could not find toggle button in this element!")?;
+        let treeitem_toggle =
+            selectors::find_first(&treeitem, "tree.chevron", &Selectors::get().tree.chevron)
+                .await
+                .context("Could not find toggle button in this element!")?;
 
         treeitem_toggle
             .click()
@@ -88,45 +377,189 @@ impl Spider {
     }
 
     async fn wait_content_load(&self, timeout: Duration) -> Result<()> {
-        let start = Instant::now();
+        // Past the timeout the page is read as it is
+        let loading = &Selectors::get().tree.loading_indicator;
+        wait::wait_until_gone(&self.driver.handle, loading, timeout).await;
+        Ok(())
+    }
 
-        loop {
-            let loading_bars = self
-                .driver
-                .find_all(By::Css(".style__loadingIndicator__container___1yhsy"))
-                .await?;
+    /// Extract every node below `folder` in the file tree, reporting progress as a stream of
+    /// events so GUI or server wrappers can show it live. URLs are not validated.
+    ///
+    /// Cancelling `self.cancel` ends the stream after the node being extracted.
+    pub fn extract_stream<'a>(&'a self, folder: &str) -> impl Stream<Item = ExtractEvent> + 'a {
+        let nodes = self.file_tree.descendants(folder);
+        let total = nodes.len();
+        self.stream_nodes(nodes.into_iter().enumerate(), total)
+    }
 
-            if loading_bars.is_empty() {
-                return Ok(());
+    /// Extract `nodes`, each with its position among the `total` nodes of the run.
+    fn stream_nodes<'a, I>(
+        &'a self,
+        nodes: I,
+        total: usize,
+    ) -> impl Stream<Item = ExtractEvent> + 'a
+    where
+        I: Iterator<Item = (usize, String)> + 'a,
+    {
+        let state = StreamState {
+            nodes,
+            current: None,
+            pending: VecDeque::new(),
+        };
+
+        stream::unfold(state, move |mut state| async move {
+            loop {
+                if let Some(event) = state.pending.pop_front() {
+                    return Some((event, state));
+                }
+
+                if let Some(node_id) = state.current.take() {
+                    let started = Instant::now();
+                    match self.extract_entries(&node_id).await {
+                        Ok(entries) => {
+                            let count = entries.len();
+                            state.pending.extend(entries.into_iter().map(|entry| {
+                                ExtractEvent::EntryFound {
+                                    node_id: node_id.clone(),
+                                    entry: Box::new(entry),
+                                }
+                            }));
+                            state.pending.push_back(ExtractEvent::NodeFinished {
+                                node_id,
+                                entries: count,
+                                elapsed: started.elapsed(),
+                            });
+                        }
+                        Err(e) => state.pending.push_back(ExtractEvent::Error {
+                            node_id,
+                            message: format!("{e:#}"),
+                        }),
+                    }
+                    continue;
+                }
+
+                if self.cancel.is_cancelled() {
+                    let remaining = state.nodes.by_ref().count();
+                    return (remaining > 0)
+                        .then_some((ExtractEvent::Cancelled { remaining }, state));
+                }
+
+                let (index, node_id) = state.nodes.next()?;
+                state.current = Some(node_id.clone());
+                return Some((
+                    ExtractEvent::NodeStarted {
+                        node_id,
+                        index: index + 1,
+                        total,
+                    },
+                    state,
+                ));
             }
+        })
+    }
 
-            if start.elapsed() > timeout {
-                return Ok(());
+    /// Open the node `id` and read the entries of the configured types with `CONTENT_SCRIPT`,
+    /// from the content iframe if the page is rendered there.
+    pub async fn extract_entries(&self, id: &str) -> Result<Vec<ReportEntry>> {
+        let region = self.region_for(id).await;
+        self.click_treeitem(id).await?;
+        self.wait_content_load(self.timeouts.page_load).await?;
+
+        self.read_entries(id, region.as_deref(), &self.extraction.types).await
+    }
+
+    /// The `[extraction]` region of node `id`, by its tree icon if regions differ by node type.
+    async fn region_for(&self, id: &str) -> Option<String> {
+        let icon = if self.extraction.regions.is_empty() {
+            None
+        } else {
+            match self.locate_treeitem(id).await {
+                Ok(element) => TreeItem::from_element(&element).await.ok().and_then(|item| item.icon),
+                Err(_) => None,
             }
+        };
+        self.extraction.region_for(icon.as_deref()).map(str::to_string)
+    }
 
-            support::sleep(Duration::from_millis(500)).await;
+    /// Read the entries of `types` in `region` (the whole page if `None`) of the loaded page of
+    /// node `id` with `CONTENT_SCRIPT`, from the content iframe if the page is rendered there.
+    /// Containers without any of their expected fields are recorded as suspected selector drift.
+    pub async fn read_entries(
+        &self,
+        id: &str,
+        region: Option<&str>,
+        types: &[ContentType],
+    ) -> Result<Vec<ReportEntry>> {
+        let backend_url = dimensions::backend_link(self.driver.current_url().await?.as_str());
+        let breadcrumb_path = breadcrumb_path(&self.driver)
+            .await
+            .unwrap_or_else(|_| "Unknown Path".to_string());
+        let mut found = self.read_script_entries(region, types).await?;
+        if found.is_empty() && self.driver.enter_frame(0).await.is_ok() {
+            let in_frame = self.read_script_entries(region, types).await;
+            self.driver.enter_default_frame().await?;
+            found = in_frame?;
+        }
+
+        for found in found.iter().filter(|found| !found.matched) {
+            let kind = if found.kind == "ExternalLink" { "ExternalLinks" } else { found.kind.as_str() };
+            selectors::record_empty_container(id, kind);
         }
+        Ok(found
+            .into_iter()
+            .map(|found| ReportEntry {
+                source_node: id.to_string(),
+                breadcrumb_path: breadcrumb_path.clone(),
+                content_type: found.kind,
+                url: found.url,
+                title: found.title,
+                author: found.author,
+                file_type: found.file_type,
+                size: found.size,
+                container_index: found.container_index.map(|i| i.to_string()).unwrap_or_default(),
+                fusion_path: found.fusion_path,
+                backend_url: backend_url.clone().unwrap_or_default(),
+                ..ReportEntry::default()
+            })
+            .collect())
+    }
+
+    async fn read_script_entries(
+        &self,
+        region: Option<&str>,
+        types: &[ContentType],
+    ) -> Result<Vec<ScriptEntry>> {
+        let types: Vec<&str> = types.iter().map(|kind| kind.name()).collect();
+        self.driver
+            .execute(
+                CONTENT_SCRIPT,
+                vec![
+                    serde_json::json!(region),
+                    serde_json::json!(Selectors::get().content),
+                    serde_json::json!(types),
+                ],
+            )
+            .await
+            .context("Could not run the content script")?
+            .convert()
+            .context("Unexpected result from the content script")
     }
 
     pub async fn extract_content(&self, id: &str) -> Result<()> {
         self.click_treeitem(id).await?;
-
-        support::sleep(Duration::from_secs(2)).await;
-
-        self.wait_content_load(Duration::from_secs(30)).await?;
+        self.wait_content_load(self.timeouts.page_load).await?;
 
         self.driver
             .enter_frame(0)
             .await
             .context("Could not enter main content iFrame!")?;
 
-        let content_collection = self
-            .driver
-            .find(By::Css(
-                "html body.neos-backend div.container div.neos-contentcollection",
-            ))
-            .await
-            .context("Could not find neos-contentcollection!")?;
+        let collection = &Selectors::get().content.collection;
+        let content_collection =
+            selectors::find_first(&self.driver.handle, "content.collection", collection)
+                .await
+                .context("Could not find neos-contentcollection!")?;
 
         let elements = content_collection
             .query(By::Css(":scope > div"))
@@ -137,8 +570,6 @@ impl Spider {
         for element in elements {
             element.scroll_into_view().await?;
 
-            support::sleep(Duration::from_millis(500)).await;
-
             for field in element.find_all(By::Css("p, ul")).await? {
                 let txt = field.text().await?;
                 full_text.push_str(&txt);
@@ -164,3 +595,219 @@ impl Spider {
         Ok(())
     }
 }
+
+/// Walks the document tree below a node in the live backend, expanding folders as it goes, and
+/// yields every node it visits as a [`Stream`] of [`TreeItem`]s, depth first with the children
+/// in tree order. Library users can run their own processing per node on it instead of the
+/// built-in extraction:
+///
+/// ```ignore
+/// let mut nodes = Traversal::new(&spider, "treeitem-c6643bf0-label").with_depth(3);
+/// while let Some(item) = nodes.next().await {
+///     println!("{} {}", item.id, item.label);
+/// }
+/// ```
+///
+/// A folder is only expanded when the stream is polled past it, so the node just yielded can be
+/// opened or edited first. Folders whose children cannot be read are skipped and listed by
+/// [`Traversal::failures`]. Cancelling `Spider::cancel` ends the stream.
+pub struct Traversal<'a> {
+    spider: &'a Spider,
+    root: String,
+    max_depth: Option<usize>,
+    children: Option<ChildrenReader<'a>>,
+    failures: Arc<Mutex<Vec<(String, String)>>>,
+    /// The visited nodes below their parents.
+    tree: Arc<Mutex<FileTree>>,
+    walk: Option<Pin<Box<dyn Stream<Item = TreeItem> + Send + 'a>>>,
+}
+
+/// Expands a folder and reads its children, for [`Traversal::with_children`].
+type ChildrenReader<'a> =
+    Arc<dyn Fn(String) -> BoxFuture<'a, Result<Vec<TreeItem>>> + Send + Sync + 'a>;
+
+/// Where a [`Traversal`] is in the tree.
+struct WalkState {
+    /// Nodes still to visit with their depth below the root, the next one last.
+    stack: Vec<(TreeItem, usize)>,
+    /// The folder to expand on the next poll, with its depth.
+    expand: Option<(String, usize)>,
+}
+
+impl<'a> Traversal<'a> {
+    /// Visit every node below `root`, the treeitem ID of a node shown in the tree or known to
+    /// the spider's file tree. The root itself is not yielded.
+    pub fn new(spider: &'a Spider, root: &str) -> Self {
+        Self {
+            spider,
+            root: root.to_string(),
+            max_depth: None,
+            children: None,
+            failures: Arc::new(Mutex::new(Vec::new())),
+            tree: Arc::new(Mutex::new(FileTree::new(root.to_string()))),
+            walk: None,
+        }
+    }
+
+    /// Visit only `depth` levels below the root; 1 visits just its children, 0 nothing.
+    pub fn with_depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    /// Expand folders and read their children with `read` instead of
+    /// [`Spider::expanded_children`], e.g. to record or log each folder.
+    pub fn with_children<F, Fut>(mut self, read: F) -> Self
+    where
+        F: Fn(String) -> Fut + Send + Sync + 'a,
+        Fut: Future<Output = Result<Vec<TreeItem>>> + Send + 'a,
+    {
+        self.children = Some(Arc::new(move |id| read(id).boxed()));
+        self
+    }
+
+    /// Folders whose children could not be read so far, with the reason.
+    pub fn failures(&self) -> Vec<(String, String)> {
+        self.failures.lock().unwrap().clone()
+    }
+
+    /// The nodes visited so far below their parents, rooted at the traversal's root.
+    pub fn file_tree(&self) -> FileTree {
+        self.tree.lock().unwrap().clone()
+    }
+
+    fn walk(&self) -> impl Stream<Item = TreeItem> + Send + 'a {
+        let spider = self.spider;
+        let max_depth = self.max_depth;
+        let children = self.children.clone().unwrap_or_else(|| {
+            Arc::new(move |id: String| async move { spider.expanded_children(&id).await }.boxed())
+        });
+        let failures = self.failures.clone();
+        let tree = self.tree.clone();
+        let state = WalkState {
+            stack: Vec::new(),
+            expand: max_depth
+                .is_none_or(|max| max > 0)
+                .then(|| (self.root.clone(), 0)),
+        };
+
+        stream::unfold(state, move |mut state| {
+            let (children, failures, tree) = (children.clone(), failures.clone(), tree.clone());
+            async move {
+                if let Some((id, depth)) = state.expand.take() {
+                    match children(id.clone()).await {
+                        Ok(children) => {
+                            let mut tree = tree.lock().unwrap();
+                            for child in &children {
+                                tree.insert_child(&id, &child.id);
+                            }
+                            // Reversed, so the first child is visited next
+                            state
+                                .stack
+                                .extend(children.into_iter().rev().map(|child| (child, depth + 1)));
+                        }
+                        Err(e) => failures.lock().unwrap().push((id, format!("{e:#}"))),
+                    }
+                }
+                if spider.cancel.is_cancelled() {
+                    return None;
+                }
+
+                let (item, depth) = state.stack.pop()?;
+                if item.expandable && max_depth.is_none_or(|max| depth < max) {
+                    state.expand = Some((item.id.clone(), depth));
+                }
+                Some((item, state))
+            }
+        })
+    }
+}
+
+impl Stream for Traversal<'_> {
+    type Item = TreeItem;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Option<TreeItem>> {
+        let this = self.get_mut();
+        if this.walk.is_none() {
+            this.walk = Some(Box::pin(this.walk()));
+        }
+        this.walk
+            .as_mut()
+            .map_or(Poll::Ready(None), |walk| walk.as_mut().poll_next(cx))
+    }
+}
+
+/// Several browser sessions on the same backend, extracting the nodes of one run in parallel.
+/// Most of a node's time is spent waiting for the page to load, so the sessions barely compete.
+pub struct DriverPool {
+    pub spiders: Vec<Spider>,
+    /// Cancelling it cancels every session.
+    pub cancel: CancellationToken,
+}
+
+impl DriverPool {
+    /// Open `size` browser sessions at `url` with `Spider::connect`, all knowing `file_tree`.
+    /// The sessions still have to log in.
+    pub async fn connect<C>(
+        size: usize,
+        webdriver_url: &str,
+        capabilities: C,
+        url: &str,
+        file_tree: &FileTree,
+    ) -> Result<Self>
+    where
+        C: Into<Capabilities> + Clone,
+    {
+        let sessions = (0..size.max(1)).map(|_| {
+            Spider::connect(webdriver_url, capabilities.clone(), url, file_tree.clone())
+        });
+        let mut spiders = futures::future::try_join_all(sessions).await?;
+
+        let cancel = CancellationToken::new();
+        for spider in &mut spiders {
+            spider.cancel = cancel.child_token();
+            spider.expanded = Some(Mutex::new(HashSet::new()));
+        }
+        Ok(Self { spiders, cancel })
+    }
+
+    /// Use `timeouts` in every session.
+    pub fn set_timeouts(&mut self, timeouts: Timeouts) {
+        for spider in &mut self.spiders {
+            spider.timeouts = timeouts;
+        }
+    }
+
+    /// Read the region and content types of `extraction` in every session.
+    pub fn set_extraction(&mut self, extraction: &ExtractionConfig) {
+        for spider in &mut self.spiders {
+            spider.extraction = extraction.clone();
+        }
+    }
+
+    /// Like `Spider::extract_stream`, with the nodes below `folder` shared among the sessions.
+    pub fn extract_stream<'a>(&'a self, folder: &str) -> impl Stream<Item = ExtractEvent> + 'a {
+        let nodes = self.spiders[0].file_tree.descendants(folder);
+        self.extract_nodes(nodes)
+    }
+
+    /// Extract `nodes` from a queue shared by the sessions, each taking the next node once it is
+    /// done with its current one. The events of all sessions are merged as they arrive; the
+    /// first session that is cancelled reports the nodes left in the queue as `Cancelled`.
+    pub fn extract_nodes<'a>(&'a self, nodes: Vec<String>) -> impl Stream<Item = ExtractEvent> + 'a {
+        let total = nodes.len();
+        let queue = Arc::new(Mutex::new(nodes.into_iter().enumerate().collect()));
+        let streams = self.spiders.iter().map(|spider| {
+            Box::pin(spider.stream_nodes(NodeQueue(Arc::clone(&queue)), total))
+        });
+        stream::select_all(streams.collect::<Vec<_>>())
+    }
+
+    /// End every browser session.
+    pub async fn quit(self) -> Result<()> {
+        for spider in self.spiders {
+            spider.driver.quit().await?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,236 @@
+use crate::report::{self, ReportEntry};
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection};
+use serde::{
+    ser::{Error as _, SerializeSeq},
+    Serialize, Serializer,
+};
+use std::{
+    env, fs, mem,
+    path::PathBuf,
+    process,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// Approximate bytes all entry buffers of the process hold in memory.
+static BUFFERED: AtomicUsize = AtomicUsize::new(0);
+
+/// Numbers the spill files, since a run can have several buffers at once.
+static SPILLS: AtomicUsize = AtomicUsize::new(0);
+
+/// Schema of a spill file. It is deleted after the run, so it skips the journal and syncing.
+const SCHEMA: &str = "
+PRAGMA journal_mode = OFF;
+PRAGMA synchronous = OFF;
+CREATE TABLE entries (
+    seq INTEGER PRIMARY KEY,
+    breadcrumb_path TEXT NOT NULL,
+    source_node TEXT NOT NULL,
+    container INTEGER,
+    entry TEXT NOT NULL
+);
+";
+
+/// Entries a sink keeps until it can write them as a whole. They stay in memory while all
+/// buffers together hold less than the limit, and move to a temporary SQLite file beyond it, so
+/// a large run does not run a small machine out of memory.
+pub struct EntryBuffer {
+    limit: usize,
+    entries: Vec<ReportEntry>,
+    bytes: usize,
+    sorted: bool,
+    spill: Option<Spill>,
+}
+
+/// The temporary SQLite file of a buffer that went over the limit.
+struct Spill {
+    connection: Connection,
+    path: PathBuf,
+    len: usize,
+}
+
+impl Spill {
+    fn create() -> Result<Self> {
+        let name = format!(
+            "tag-spider-spill-{}-{}.sqlite",
+            process::id(),
+            SPILLS.fetch_add(1, Ordering::Relaxed)
+        );
+        let path = env::temp_dir().join(name);
+        let _ = fs::remove_file(&path);
+        let connection = Connection::open(&path)
+            .with_context(|| format!("Could not create spill file {}", path.display()))?;
+        connection
+            .execute_batch(SCHEMA)
+            .context("Could not create the spill file schema")?;
+        Ok(Self {
+            connection,
+            path,
+            len: 0,
+        })
+    }
+
+    fn insert(&mut self, entry: &ReportEntry) -> Result<()> {
+        self.connection
+            .execute(
+                "INSERT INTO entries (breadcrumb_path, source_node, container, entry) \
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    entry.breadcrumb_path,
+                    entry.source_node,
+                    entry.container_index.parse::<i64>().ok(),
+                    serde_json::to_string(entry)?
+                ],
+            )
+            .context("Could not write to the spill file")?;
+        self.len += 1;
+        Ok(())
+    }
+}
+
+impl EntryBuffer {
+    /// A buffer that spills to disk once all buffers together hold more than `limit` bytes.
+    /// With a limit of 0 it never spills.
+    pub fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            entries: Vec::new(),
+            bytes: 0,
+            sorted: false,
+            spill: None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len() + self.spill.as_ref().map_or(0, |spill| spill.len)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn push(&mut self, entry: ReportEntry) -> Result<()> {
+        if let Some(spill) = &mut self.spill {
+            return spill.insert(&entry);
+        }
+        let size = approximate_size(&entry);
+        self.entries.push(entry);
+        self.bytes += size;
+        let buffered = BUFFERED.fetch_add(size, Ordering::Relaxed) + size;
+        if self.limit > 0 && buffered > self.limit {
+            self.spill()?;
+        }
+        Ok(())
+    }
+
+    /// Move the entries in memory to a new spill file, where all further entries go as well.
+    fn spill(&mut self) -> Result<()> {
+        let mut spill = Spill::create()?;
+        println!(
+            "⚠ Buffered entries are over the [output] memory limit, moving {} of them to {}",
+            self.entries.len(),
+            spill.path.display()
+        );
+        spill.connection.execute_batch("BEGIN")?;
+        for entry in &self.entries {
+            spill.insert(entry)?;
+        }
+        spill.connection.execute_batch("COMMIT")?;
+        self.release();
+        self.spill = Some(spill);
+        Ok(())
+    }
+
+    /// Order the entries like [`report::sort_entries`] once all of them were pushed. Spilled
+    /// entries are sorted as they are read back.
+    pub fn sort(&mut self) {
+        report::sort_entries(&mut self.entries);
+        self.sorted = true;
+    }
+
+    /// Hand every entry to `f`, in the order they were pushed unless [`Self::sort`] was called.
+    /// Spilled entries are read back one at a time.
+    pub fn for_each(&self, mut f: impl FnMut(&ReportEntry) -> Result<()>) -> Result<()> {
+        let Some(spill) = &self.spill else {
+            return self.entries.iter().try_for_each(f);
+        };
+        let order = if self.sorted {
+            // Entries outside a container have none and come last, like in the sort in memory
+            "breadcrumb_path, source_node, container NULLS LAST, seq"
+        } else {
+            "seq"
+        };
+        let mut statement = spill
+            .connection
+            .prepare(&format!("SELECT entry FROM entries ORDER BY {order}"))
+            .context("Could not read the spill file")?;
+        let rows = statement.query_map(params![], |row| row.get::<_, String>(0))?;
+        for json in rows {
+            let json = json.context("Could not read the spill file")?;
+            let entry: ReportEntry =
+                serde_json::from_str(&json).context("Invalid entry in the spill file")?;
+            f(&entry)?;
+        }
+        Ok(())
+    }
+
+    /// All entries at once, for output that needs them together.
+    pub fn to_vec(&self) -> Result<Vec<ReportEntry>> {
+        let mut entries = Vec::with_capacity(self.len());
+        self.for_each(|entry| {
+            entries.push(entry.clone());
+            Ok(())
+        })?;
+        Ok(entries)
+    }
+
+    /// Drop all entries and delete the spill file, if there is one.
+    pub fn clear(&mut self) {
+        self.release();
+        self.sorted = false;
+        if let Some(Spill {
+            connection, path, ..
+        }) = self.spill.take()
+        {
+            let _ = connection.close();
+            let _ = fs::remove_file(path);
+        }
+    }
+
+    /// Free the entries in memory and stop counting them.
+    fn release(&mut self) {
+        self.entries = Vec::new();
+        BUFFERED.fetch_sub(mem::take(&mut self.bytes), Ordering::Relaxed);
+    }
+}
+
+impl Drop for EntryBuffer {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+/// Written as a sequence of entries without reading spilled ones back all at once.
+impl Serialize for EntryBuffer {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.len()))?;
+        let mut failed = None;
+        let read = self.for_each(|entry| {
+            seq.serialize_element(entry).map_err(|e| {
+                let message = anyhow::anyhow!("{e}");
+                failed = Some(e);
+                message
+            })
+        });
+        if let Some(e) = failed {
+            return Err(e);
+        }
+        read.map_err(|e| S::Error::custom(format!("{e:#}")))?;
+        seq.end()
+    }
+}
+
+/// Rough heap and inline size of an entry, which is mostly its strings.
+fn approximate_size(entry: &ReportEntry) -> usize {
+    mem::size_of::<ReportEntry>() + entry.values().iter().map(|value| value.len()).sum::<usize>()
+}
@@ -0,0 +1,269 @@
+use crate::report::ReportEntry;
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Local};
+use csv::Reader;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fs, path::Path};
+
+/// What an editor decided about a finding.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "decision", rename_all = "snake_case")]
+pub enum Decision {
+    /// The problem was fixed in the CMS.
+    Fixed,
+    /// The finding is acknowledged and should not be reported again.
+    Ignore,
+    /// The link should be replaced by `url`.
+    Replace { url: String },
+}
+
+impl Decision {
+    /// Parse a reviewer's note: `fixed`, `ignore` or `replace with <url>`.
+    /// An empty cell means no decision.
+    pub fn parse(text: &str) -> Result<Option<Self>> {
+        let text = text.trim();
+        let lower = text.to_ascii_lowercase();
+        match lower.as_str() {
+            "" => Ok(None),
+            "fixed" => Ok(Some(Decision::Fixed)),
+            "ignore" | "ignored" => Ok(Some(Decision::Ignore)),
+            _ if lower.starts_with("replace with ") => {
+                let url = text["replace with ".len()..].trim();
+                if url.is_empty() {
+                    bail!("'replace with' needs a URL");
+                }
+                Ok(Some(Decision::Replace {
+                    url: url.to_string(),
+                }))
+            }
+            _ => bail!("Unknown decision '{text}' (expected fixed, ignore or replace with <url>)"),
+        }
+    }
+}
+
+/// A decision about one finding, keyed by node and URL in the state file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Review {
+    #[serde(flatten)]
+    pub decision: Decision,
+    pub source_node: String,
+    pub url: String,
+    pub title: String,
+    pub decided_at: DateTime<Local>,
+    /// When a replacement was carried out in the CMS.
+    #[serde(default)]
+    pub applied_at: Option<DateTime<Local>>,
+}
+
+/// Persistent state across runs, stored as JSON.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct State {
+    pub reviews: BTreeMap<String, Review>,
+}
+
+/// How far the review decisions have been worked through.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct Progress {
+    /// Findings nobody has decided on yet.
+    pub open: usize,
+    /// Marked fixed and no longer flagged in the results.
+    pub fixed: usize,
+    /// Marked fixed but still flagged in the results.
+    pub fixed_still_failing: usize,
+    pub ignored: usize,
+    pub replace_pending: usize,
+    /// Replacements already carried out in the CMS.
+    pub replaced: usize,
+}
+
+/// Result of importing a reviewed CSV.
+#[derive(Debug, Default)]
+pub struct ImportSummary {
+    pub imported: usize,
+    pub errors: Vec<String>,
+}
+
+impl State {
+    /// Load the state from `path`, starting empty if the file does not exist.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let data = fs::read_to_string(path)
+            .with_context(|| format!("Could not read state file {}", path.display()))?;
+        serde_json::from_str(&data).with_context(|| format!("Invalid state file {}", path.display()))
+    }
+
+    /// Save the state, replacing the file only once the new version is fully written.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_string_pretty(self)?).context("Could not write state file")?;
+        fs::rename(&tmp, path).context("Could not replace state file")?;
+        Ok(())
+    }
+
+    fn key(source_node: &str, url: &str) -> String {
+        format!("{source_node} {url}")
+    }
+
+    pub fn review_for(&self, entry: &ReportEntry) -> Option<&Review> {
+        self.reviews.get(&Self::key(&entry.source_node, &entry.url))
+    }
+
+    /// Import the decisions from a reviewed result CSV with an added `Decision` column.
+    /// Later imports overwrite earlier decisions for the same finding.
+    pub fn import_csv<P: AsRef<Path>>(&mut self, path: P) -> Result<ImportSummary> {
+        let path = path.as_ref();
+        let mut reader = Reader::from_path(path)
+            .with_context(|| format!("Could not open reviewed CSV {}", path.display()))?;
+        let headers = reader.headers()?.clone();
+        let column = headers
+            .iter()
+            .position(|h| h.trim().eq_ignore_ascii_case("decision"))
+            .context("The reviewed CSV has no Decision column")?;
+
+        let mut summary = ImportSummary::default();
+        for (row, record) in reader.records().enumerate() {
+            // Row numbers as shown in a spreadsheet, counting the header
+            let line = row + 2;
+            let record = record?;
+            let decision = match Decision::parse(record.get(column).unwrap_or_default()) {
+                Ok(Some(decision)) => decision,
+                Ok(None) => continue,
+                Err(e) => {
+                    summary.errors.push(format!("row {line}: {e}"));
+                    continue;
+                }
+            };
+            let entry: ReportEntry = match record.deserialize(Some(&headers)) {
+                Ok(entry) => entry,
+                Err(e) => {
+                    summary.errors.push(format!("row {line}: {e}"));
+                    continue;
+                }
+            };
+
+            self.reviews.insert(
+                Self::key(&entry.source_node, &entry.url),
+                Review {
+                    decision,
+                    source_node: entry.source_node,
+                    url: entry.url,
+                    title: entry.title,
+                    decided_at: Local::now(),
+                    applied_at: None,
+                },
+            );
+            summary.imported += 1;
+        }
+
+        Ok(summary)
+    }
+
+    /// Whether reviewers acknowledged the finding, so reports leave it out.
+    pub fn is_ignored(&self, entry: &ReportEntry) -> bool {
+        matches!(
+            self.review_for(entry).map(|r| &r.decision),
+            Some(Decision::Ignore)
+        )
+    }
+
+    /// Short note on the review state of a flagged entry, for reports.
+    pub fn note(&self, entry: &ReportEntry) -> Option<String> {
+        let review = self.review_for(entry)?;
+        match &review.decision {
+            Decision::Fixed if entry.has_problem() => Some("Marked fixed, still failing".to_string()),
+            Decision::Replace { url } if review.applied_at.is_none() => {
+                Some(format!("Replace with {url}"))
+            }
+            Decision::Replace { url } => Some(format!("Replaced with {url}")),
+            _ => None,
+        }
+    }
+
+    /// Fix progress of the flagged entries in `entries`.
+    pub fn progress(&self, entries: &[ReportEntry]) -> Progress {
+        let mut progress = Progress::default();
+        for entry in entries {
+            let Some(review) = self.review_for(entry) else {
+                if entry.has_problem() {
+                    progress.open += 1;
+                }
+                continue;
+            };
+            match review.decision {
+                Decision::Fixed if entry.has_problem() => progress.fixed_still_failing += 1,
+                Decision::Fixed => progress.fixed += 1,
+                Decision::Ignore => progress.ignored += 1,
+                Decision::Replace { .. } if review.applied_at.is_some() => progress.replaced += 1,
+                Decision::Replace { .. } => progress.replace_pending += 1,
+            }
+        }
+        progress
+    }
+
+    /// Replacements decided on but not carried out yet.
+    pub fn pending_replacements(&self) -> Vec<(&String, &Review)> {
+        self.reviews
+            .iter()
+            .filter(|(_, r)| {
+                matches!(r.decision, Decision::Replace { .. }) && r.applied_at.is_none()
+            })
+            .collect()
+    }
+
+    /// Record that the replacement for `key` was carried out.
+    pub fn mark_applied(&mut self, key: &str) {
+        if let Some(review) = self.reviews.get_mut(key) {
+            review.applied_at = Some(Local::now());
+        }
+    }
+}
+impl Progress {
+    pub fn rows(&self) -> [(&'static str, usize); 6] {
+        [
+            ("Open findings", self.open),
+            ("Fixed", self.fixed),
+            ("Marked fixed, still failing", self.fixed_still_failing),
+            ("Ignored", self.ignored),
+            ("Replacement pending", self.replace_pending),
+            ("Replaced", self.replaced),
+        ]
+    }
+
+    pub fn print(&self) {
+        for (label, count) in self.rows() {
+            println!("  {label:<28} {count}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_reviewer_notes() {
+        assert_eq!(Decision::parse("").unwrap(), None);
+        assert_eq!(Decision::parse("  Fixed ").unwrap(), Some(Decision::Fixed));
+        assert_eq!(Decision::parse("ignored").unwrap(), Some(Decision::Ignore));
+        assert_eq!(
+            Decision::parse("Replace with https://example.com/New ").unwrap(),
+            Some(Decision::Replace {
+                url: "https://example.com/New".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_notes() {
+        assert!(Decision::parse("replace with ").is_err());
+        assert!(Decision::parse("replace").is_err());
+        let error = Decision::parse("later").unwrap_err().to_string();
+        assert!(error.contains("'later'"), "{error}");
+    }
+}
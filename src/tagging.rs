@@ -0,0 +1,381 @@
+use crate::config::DuplicatePolicy;
+use crate::text::{self, Encoding};
+use anyhow::{bail, Context, Result};
+use csv::ReaderBuilder;
+use regex::Regex;
+use std::{
+    collections::HashMap,
+    fmt, fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// A problem on one line of a tags file.
+#[derive(Debug, Clone)]
+pub struct TagsIssue {
+    pub file: PathBuf,
+    pub line: u64,
+    /// Whether the tags are refused because of it, rather than read with a warning.
+    pub error: bool,
+    pub message: String,
+}
+
+impl fmt::Display for TagsIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.file.display(), self.line, self.message)
+    }
+}
+
+/// The tags CSVs: question IDs in the first column and their comma-separated tags in the
+/// second, after a header line. Several files, e.g. one per chapter, are merged into one set.
+#[derive(Debug, Clone)]
+pub struct TagsFile {
+    /// Tags by question ID, both normalized with [`text::normalize`].
+    pub tags: HashMap<String, String>,
+    /// The files read, in the order they were merged, with the encoding each was saved in.
+    pub files: Vec<(PathBuf, Encoding)>,
+    pub issues: Vec<TagsIssue>,
+    /// Where each question's tags were read from.
+    sources: HashMap<String, (PathBuf, u64)>,
+}
+
+impl TagsFile {
+    /// Read the tags files `pattern` names (see [`expand`]) in whichever encoding each was saved
+    /// in. Problems are collected per line instead of stopping at the first one; a question ID
+    /// in several places is handled by `duplicates`, unless the tags are the same. Only a
+    /// missing or unreadable file is an error.
+    pub fn read(pattern: &Path, duplicates: DuplicatePolicy) -> Result<Self> {
+        let mut file = Self {
+            tags: HashMap::new(),
+            files: Vec::new(),
+            issues: Vec::new(),
+            sources: HashMap::new(),
+        };
+        for path in expand(pattern)? {
+            let bytes =
+                fs::read(&path).with_context(|| format!("Could not read {}", path.display()))?;
+            let (content, encoding) = text::decode(&bytes);
+            file.merge(&path, &content, duplicates);
+            file.files.push((path, encoding));
+        }
+        Ok(file)
+    }
+
+    fn merge(&mut self, path: &Path, content: &str, duplicates: DuplicatePolicy) {
+        let mut reader = ReaderBuilder::new()
+            .flexible(true)
+            .from_reader(content.as_bytes());
+        for record in reader.records() {
+            let record = match record {
+                Ok(record) => record,
+                Err(e) => {
+                    let line = e.position().map_or(0, |position| position.line());
+                    self.issue(path, line, true, format!("not valid CSV ({e})"));
+                    continue;
+                }
+            };
+            let line = record.position().map_or(0, |position| position.line());
+            let id = text::normalize(record.get(0).unwrap_or_default());
+            if id.is_empty() {
+                self.issue(path, line, true, "no question ID".to_string());
+                continue;
+            }
+            let value = match record.len() {
+                1 => {
+                    self.issue(
+                        path,
+                        line,
+                        true,
+                        format!("question {id} has no tags column"),
+                    );
+                    continue;
+                }
+                2 => text::normalize(&record[1]),
+                columns => {
+                    // An editor forgot the quotes around the tags
+                    let message =
+                        format!("{columns} columns, the tags of question {id} need quotes");
+                    self.issue(path, line, false, message);
+                    let tags: Vec<String> = record.iter().skip(1).flat_map(tag_list).collect();
+                    tags.join(", ")
+                }
+            };
+            if tag_list(&value).is_empty() {
+                let message = format!("question {id} has no tags, applying them removes its tags");
+                self.issue(path, line, false, message);
+            }
+
+            if let Some((first_path, first_line)) = self.sources.get(&id) {
+                let place = if first_path == path {
+                    format!("on line {first_line}")
+                } else {
+                    format!("in {}:{first_line}", first_path.display())
+                };
+                if same_tags(&self.tags[&id], &value) {
+                    let message = format!("question {id} is listed {place} already, same tags");
+                    self.issue(path, line, false, message);
+                    continue;
+                }
+                let message = format!("question {id} is listed {place} already with other tags");
+                match duplicates {
+                    DuplicatePolicy::Error => {
+                        self.issue(path, line, true, message);
+                        continue;
+                    }
+                    DuplicatePolicy::First => {
+                        self.issue(path, line, false, format!("{message}, ignored"));
+                        continue;
+                    }
+                    DuplicatePolicy::Last => {
+                        self.issue(path, line, false, format!("{message}, replaced"));
+                    }
+                }
+            }
+            self.sources.insert(id.clone(), (path.to_path_buf(), line));
+            self.tags.insert(id, value);
+        }
+    }
+
+    fn issue(&mut self, file: &Path, line: u64, error: bool, message: String) {
+        self.issues.push(TagsIssue {
+            file: file.to_path_buf(),
+            line,
+            error,
+            message,
+        });
+    }
+
+    /// Number of problems that keep the tags from being used.
+    pub fn errors(&self) -> usize {
+        self.issues.iter().filter(|issue| issue.error).count()
+    }
+
+    /// The tags by question ID, or an error naming the first problems if there are any.
+    pub fn into_tags(self) -> Result<HashMap<String, String>> {
+        let errors: Vec<String> = self
+            .issues
+            .iter()
+            .filter(|issue| issue.error)
+            .map(ToString::to_string)
+            .collect();
+        if errors.is_empty() {
+            return Ok(self.tags);
+        }
+        let shown = errors
+            .iter()
+            .take(3)
+            .cloned()
+            .collect::<Vec<_>>()
+            .join("; ");
+        let more = if errors.len() > 3 { "; ..." } else { "" };
+        bail!(
+            "{} problems, run `tags lint` for all of them: {shown}{more}",
+            errors.len()
+        )
+    }
+}
+
+/// Whether two tag strings name the same tags, in any order.
+fn same_tags(a: &str, b: &str) -> bool {
+    let (mut a, mut b) = (tag_list(a), tag_list(b));
+    a.sort();
+    b.sort();
+    a == b
+}
+
+/// The files `pattern` names: the path itself, or if its file name has `*` or `?` wildcards,
+/// the matching files in its directory in name order, e.g. `resources/tags/*.csv`. Only the
+/// file name is matched: wildcards in the directories are taken literally and `**` does not
+/// recurse.
+pub fn expand(pattern: &Path) -> Result<Vec<PathBuf>> {
+    let Some(name) = pattern.file_name().and_then(|name| name.to_str()) else {
+        return Ok(vec![pattern.to_path_buf()]);
+    };
+    if !name.contains(['*', '?']) {
+        return Ok(vec![pattern.to_path_buf()]);
+    }
+    let dir = pattern
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let wildcard: Vec<char> = name.chars().collect();
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir).with_context(|| format!("Could not read {}", dir.display()))? {
+        let path = entry?.path();
+        let matched = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| wildcard_match(&wildcard, &name.chars().collect::<Vec<_>>()));
+        if matched && path.is_file() {
+            files.push(path);
+        }
+    }
+    if files.is_empty() {
+        bail!("No tags files match {}", pattern.display());
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Whether `name` matches `pattern`, in which `*` stands for any characters and `?` for one.
+fn wildcard_match(pattern: &[char], name: &[char]) -> bool {
+    match (pattern.split_first(), name.split_first()) {
+        (None, _) => name.is_empty(),
+        (Some(('*', rest)), _) => {
+            wildcard_match(rest, name) || (!name.is_empty() && wildcard_match(pattern, &name[1..]))
+        }
+        (Some(_), None) => false,
+        (Some(('?', rest)), Some((_, name_rest))) => wildcard_match(rest, name_rest),
+        (Some((c, rest)), Some((n, name_rest))) => c == n && wildcard_match(rest, name_rest),
+    }
+}
+
+/// What a tag apply job over a subtree is expected to do, shown before anything is changed.
+#[derive(Debug, Clone)]
+pub struct TagPreview {
+    /// Pages that are opened, the subtree's root included.
+    pub pages: usize,
+    /// Questions with tags in the tags file, or `None` if the tags of all questions on the pages
+    /// are removed.
+    pub questions: Option<usize>,
+    /// Expected duration, or `None` without timings of earlier runs.
+    pub duration: Option<Duration>,
+    /// Number of questions each tag is added to, most used first.
+    pub by_tag: Vec<(String, usize)>,
+}
+
+impl TagPreview {
+    /// Preview of tagging `pages` pages with `tags` (question ID to tags), or clearing their
+    /// tags. `page_time` is the expected time to open all pages, `apply_delay` the pause after
+    /// each question.
+    pub fn new(
+        pages: usize,
+        tags: &HashMap<String, String>,
+        clear: bool,
+        page_time: Option<Duration>,
+        apply_delay: Duration,
+    ) -> Self {
+        if clear {
+            return Self {
+                pages,
+                questions: None,
+                duration: page_time,
+                by_tag: Vec::new(),
+            };
+        }
+
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for tag in tags.values().flat_map(|tags| tag_list(tags)) {
+            *counts.entry(tag).or_default() += 1;
+        }
+        let mut by_tag: Vec<(String, usize)> = counts.into_iter().collect();
+        by_tag.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+        Self {
+            pages,
+            questions: Some(tags.len()),
+            duration: page_time.map(|time| time + apply_delay * tags.len() as u32),
+            by_tag,
+        }
+    }
+}
+
+/// Finds the question ID in a question's title with the configured pattern.
+#[derive(Debug, Clone)]
+pub struct QuestionIds {
+    pattern: Regex,
+}
+
+impl QuestionIds {
+    /// Compile `pattern`, which needs a capture group named `id`.
+    pub fn new(pattern: &str) -> Result<Self> {
+        let pattern =
+            Regex::new(pattern).context("[tagging] question_id_pattern is not a valid regex")?;
+        if !pattern.capture_names().flatten().any(|name| name == "id") {
+            bail!("[tagging] question_id_pattern has no (?P<id>...) group");
+        }
+        Ok(Self { pattern })
+    }
+
+    /// The question ID in `title`, or `None` if the title does not match or the ID is empty.
+    pub fn find<'a>(&self, title: &'a str) -> Option<&'a str> {
+        let id = self.pattern.captures(title)?.name("id")?.as_str().trim();
+        (!id.is_empty()).then_some(id)
+    }
+}
+
+/// Tags of a Tags field, normalized with [`text::normalize`] and without empty items
+pub fn tag_list(tags: &str) -> Vec<String> {
+    tags.split(',').map(text::normalize).filter(|tag| !tag.is_empty()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, name: &str) -> bool {
+        let chars = |s: &str| s.chars().collect::<Vec<_>>();
+        wildcard_match(&chars(pattern), &chars(name))
+    }
+
+    fn merged(files: &[(&str, &str)], duplicates: DuplicatePolicy) -> TagsFile {
+        let mut tags = TagsFile {
+            tags: HashMap::new(),
+            files: Vec::new(),
+            issues: Vec::new(),
+            sources: HashMap::new(),
+        };
+        for (path, content) in files {
+            tags.merge(Path::new(path), content, duplicates);
+        }
+        tags
+    }
+
+    #[test]
+    fn wildcards_match_file_names() {
+        assert!(matches("*.csv", "chapter-1.csv"));
+        assert!(matches("chapter-?.csv", "chapter-1.csv"));
+        assert!(!matches("chapter-?.csv", "chapter-10.csv"));
+        assert!(matches("*", ""));
+        assert!(!matches("*.csv", "chapter.csv.bak"));
+        assert!(matches("tags", "tags"));
+    }
+
+    #[test]
+    fn merge_reads_ids_and_tags() {
+        let tags = merged(
+            &[("a.csv", "id,tags\nq1,\"Strom, Spannung\"\nq2,Widerstand\n")],
+            DuplicatePolicy::Error,
+        );
+        assert_eq!(tags.tags["q1"], "Strom, Spannung");
+        assert_eq!(tags.tags["q2"], "Widerstand");
+        assert!(tags.issues.is_empty());
+    }
+
+    #[test]
+    fn merge_reports_broken_lines() {
+        let tags = merged(
+            &[("a.csv", "id,tags\n,Strom\nq1\nq2,Strom,Spannung\nq3,\n")],
+            DuplicatePolicy::Error,
+        );
+        // No ID and no tags column are errors, missing quotes and empty tags only warnings
+        assert_eq!(tags.errors(), 2);
+        assert_eq!(tags.issues.len(), 4);
+        assert_eq!(tags.tags["q2"], "Strom, Spannung");
+        assert_eq!(tags.tags["q3"], "");
+    }
+
+    #[test]
+    fn merge_applies_the_duplicate_policy() {
+        let files = [("a.csv", "id,tags\nq1,Strom\n"), ("b.csv", "id,tags\nq1,Spannung\n")];
+        assert_eq!(merged(&files, DuplicatePolicy::First).tags["q1"], "Strom");
+        assert_eq!(merged(&files, DuplicatePolicy::Last).tags["q1"], "Spannung");
+        let refused = merged(&files, DuplicatePolicy::Error);
+        assert_eq!(refused.errors(), 1);
+        assert!(refused.into_tags().is_err());
+
+        // The same tags in another order are no conflict
+        let same = [("a.csv", "id,tags\nq1,\"a, b\"\n"), ("b.csv", "id,tags\nq1,\"b, a\"\n")];
+        assert_eq!(merged(&same, DuplicatePolicy::Error).errors(), 0);
+    }
+}
@@ -0,0 +1,128 @@
+use std::fmt;
+use unicode_normalization::UnicodeNormalization;
+
+/// Characters that render as nothing, but make otherwise equal text differ: the soft hyphen,
+/// zero-width space, word joiner, byte order mark and Mongolian vowel separator.
+const INVISIBLE: [char; 5] = ['\u{00AD}', '\u{200B}', '\u{2060}', '\u{FEFF}', '\u{180E}'];
+
+/// `text` in Unicode NFC without invisible characters. Every kind of space, non-breaking ones
+/// and line breaks included, becomes one plain space, and the ends are trimmed.
+pub fn normalize(text: &str) -> String {
+    let mut normalized = String::with_capacity(text.len());
+    for c in text.nfc().filter(|c| !INVISIBLE.contains(c)) {
+        if !c.is_whitespace() {
+            normalized.push(c);
+        } else if !normalized.is_empty() && !normalized.ends_with(' ') {
+            normalized.push(' ');
+        }
+    }
+    normalized.truncate(normalized.trim_end().len());
+    normalized
+}
+
+/// `url` in Unicode NFC without invisible characters and surrounding spaces. Spaces inside are
+/// kept, so a URL broken by one still fails validation.
+pub fn normalize_url(url: &str) -> String {
+    url.nfc()
+        .filter(|c| !INVISIBLE.contains(c))
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+/// Encodings a text file can be read in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Windows1252,
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Encoding::Utf8 => "UTF-8",
+            Encoding::Utf16Le => "UTF-16LE",
+            Encoding::Utf16Be => "UTF-16BE",
+            Encoding::Windows1252 => "Windows-1252",
+        })
+    }
+}
+
+/// Characters of the bytes 0x80 to 0x9F in Windows-1252. Bytes it leaves undefined keep their
+/// Latin-1 control character.
+const WINDOWS_1252: [char; 32] = [
+    '€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{8D}', 'Ž', '\u{8F}',
+    '\u{90}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{9D}', 'ž', 'Ÿ',
+];
+
+/// `bytes` as text, with the encoding they were read in: UTF-8 or UTF-16 as marked by a byte
+/// order mark, else UTF-8 if valid, else Windows-1252, the superset of Latin-1 that Excel saves
+/// CSV files in on Windows. A byte order mark is dropped.
+pub fn decode(bytes: &[u8]) -> (String, Encoding) {
+    let utf16 = |rest: &[u8], unit: fn([u8; 2]) -> u16| {
+        let units = rest.chunks_exact(2).map(|pair| unit([pair[0], pair[1]]));
+        char::decode_utf16(units)
+            .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+            .collect()
+    };
+    if let Some(rest) = bytes.strip_prefix(b"\xEF\xBB\xBF") {
+        return (String::from_utf8_lossy(rest).into_owned(), Encoding::Utf8);
+    }
+    if let Some(rest) = bytes.strip_prefix(b"\xFF\xFE") {
+        return (utf16(rest, u16::from_le_bytes), Encoding::Utf16Le);
+    }
+    if let Some(rest) = bytes.strip_prefix(b"\xFE\xFF") {
+        return (utf16(rest, u16::from_be_bytes), Encoding::Utf16Be);
+    }
+    if let Ok(text) = std::str::from_utf8(bytes) {
+        return (text.to_string(), Encoding::Utf8);
+    }
+    let text = bytes
+        .iter()
+        .map(|&byte| match byte {
+            0x80..=0x9F => WINDOWS_1252[usize::from(byte - 0x80)],
+            _ => char::from(byte),
+        })
+        .collect();
+    (text, Encoding::Windows1252)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_unifies_spaces_and_drops_invisible_characters() {
+        assert_eq!(normalize("  Schalt\u{00AD}pläne\u{00A0}\u{00A0}lesen\n"), "Schaltpläne lesen");
+        assert_eq!(normalize("Wider\u{200B}stand \t und\r\nStrom"), "Widerstand und Strom");
+        assert_eq!(normalize(" \u{FEFF} "), "");
+    }
+
+    #[test]
+    fn normalize_composes_to_nfc() {
+        assert_eq!(normalize("Bru\u{0308}cke"), "Brücke");
+        assert_eq!(normalize("Brücke"), normalize("Bru\u{0308}cke"));
+    }
+
+    #[test]
+    fn normalize_url_keeps_inner_spaces() {
+        assert_eq!(
+            normalize_url(" https://example.com/a\u{200B} b.pdf\u{00A0}"),
+            "https://example.com/a b.pdf"
+        );
+    }
+
+    #[test]
+    fn decode_detects_the_encoding() {
+        assert_eq!(decode("Größe".as_bytes()), ("Größe".to_string(), Encoding::Utf8));
+        assert_eq!(decode(b"\xEF\xBB\xBFid"), ("id".to_string(), Encoding::Utf8));
+        assert_eq!(decode(b"\xFF\xFEi\0d\0"), ("id".to_string(), Encoding::Utf16Le));
+        assert_eq!(decode(b"\xFE\xFF\0i\0d"), ("id".to_string(), Encoding::Utf16Be));
+        assert_eq!(
+            decode(b"Gr\xF6\xDFe \x80 \x93x\x94"),
+            ("Größe € “x”".to_string(), Encoding::Windows1252)
+        );
+    }
+}
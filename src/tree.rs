@@ -116,6 +116,52 @@ impl FileTree {
         }
     }
 
+    /// Record `id` as a child of `parent_id`, adding the parent if it is not known yet.
+    pub fn insert_child(&mut self, parent_id: &str, id: &str) {
+        self.nodes
+            .entry(parent_id.to_string())
+            .or_insert_with(|| FileNode::new(parent_id.to_string(), None, HashSet::new()))
+            .children
+            .insert(id.to_string());
+        self.nodes
+            .entry(id.to_string())
+            .or_insert_with(|| FileNode::new(id.to_string(), None, HashSet::new()))
+            .parent = Some(parent_id.to_string());
+    }
+
+    /// IDs of all nodes below `id`, depth first with siblings in ID order.
+    pub fn descendants(&self, id: &str) -> Vec<String> {
+        let mut descendants = Vec::new();
+        let mut stack = vec![id.to_string()];
+        while let Some(current) = stack.pop() {
+            let Some(node) = self.nodes.get(&current) else {
+                continue;
+            };
+            let mut children: Vec<&String> = node.children.iter().collect();
+            children.sort();
+            // Reversed, so the first child is visited next
+            for child in children.into_iter().rev() {
+                stack.push(child.clone());
+            }
+            if current != id {
+                descendants.push(current);
+            }
+        }
+        descendants
+    }
+
+    /// IDs of the ancestors of `id`, outermost first.
+    pub fn ancestors(&self, id: &str) -> Vec<String> {
+        let mut ancestors = Vec::new();
+        let mut current = self.nodes.get(id);
+        while let Some(parent_id) = current.and_then(|node| node.parent.as_ref()) {
+            ancestors.push(parent_id.clone());
+            current = self.nodes.get(parent_id);
+        }
+        ancestors.reverse();
+        ancestors
+    }
+
     pub fn from_json_file<P: AsRef<Path>>(path: P) -> Result<Self> {
         let data = fs::read_to_string(path)?;
         let mut tree: FileTree =
@@ -0,0 +1,167 @@
+use crate::selectors::{self, Selectors};
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use thirtyfour::{WebDriver, WebElement};
+
+static CHILDREN_SCRIPT: &str = include_str!("../resources/scripts/tree-children.js");
+
+/// A node of the Neos document tree as rendered in the backend.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TreeItem {
+    /// The `aria-labelledby` ID, e.g. `treeitem-c6643bf0-label`.
+    pub id: String,
+    pub label: String,
+    pub level: Option<u32>,
+    /// Whether the node has a chevron, i.e. can have children.
+    pub expandable: bool,
+    pub expanded: bool,
+    /// Name of the node type icon (e.g. `file`, `folder`, `link`), if one is shown.
+    pub icon: Option<String>,
+    /// The node is hidden and not shown on the live site.
+    pub hidden: bool,
+    /// The node has changes (or is new) that are not published yet.
+    pub unpublished: bool,
+}
+
+impl TreeItem {
+    /// Read a tree item from its `role='treeitem'` element.
+    pub async fn from_element(element: &WebElement) -> Result<Self> {
+        let tree = &Selectors::get().tree;
+
+        let id = element
+            .attr("aria-labelledby")
+            .await?
+            .context("Tree item has no aria-labelledby ID")?;
+        let level = element
+            .attr("aria-level")
+            .await?
+            .and_then(|level| level.parse().ok());
+        let expanded = element.attr("aria-expanded").await?.as_deref() == Some("true");
+
+        let header = selectors::probe_first(element, "tree.header", &tree.header).await.ok();
+        let label = match &header {
+            Some(header) => header.text().await?.trim().to_string(),
+            None => String::new(),
+        };
+        let expandable = selectors::probe_first(element, "tree.chevron", &tree.chevron)
+            .await
+            .is_ok();
+
+        let header_classes = match &header {
+            Some(header) => header.attr("class").await?.unwrap_or_default(),
+            None => String::new(),
+        };
+        let hidden = has_class_fragment(&header_classes, &tree.hidden_class);
+        let unpublished = has_class_fragment(&header_classes, &tree.unpublished_class);
+
+        let icon = match &header {
+            Some(header) => match selectors::probe_first(header, "tree.icon", &tree.icon).await {
+                Ok(icon) => {
+                    let classes = icon.attr("class").await?.unwrap_or_default();
+                    icon_name(icon.attr("data-icon").await?, &classes)
+                }
+                Err(_) => None,
+            },
+            None => None,
+        };
+
+        Ok(Self {
+            id,
+            label,
+            level,
+            expandable,
+            expanded,
+            icon,
+            hidden,
+            unpublished,
+        })
+    }
+
+    /// Read the child tree items below the `parent` tree item with a single script call,
+    /// instead of several WebDriver round trips per item as `from_element` needs. Fails if the
+    /// script returned something else or items without an ID.
+    pub async fn read_children(driver: &WebDriver, parent: &WebElement) -> Result<Vec<Self>> {
+        let tree = &Selectors::get().tree;
+        let result = driver
+            .execute(CHILDREN_SCRIPT, vec![parent.to_json()?, serde_json::to_value(tree)?])
+            .await
+            .context("Could not run the tree item script")?;
+        let raw: Vec<RawTreeItem> = result
+            .convert()
+            .context("Unexpected result from the tree item script")?;
+        if raw.iter().any(|item| item.id.is_none()) {
+            bail!("The tree item script returned items without an ID");
+        }
+
+        Ok(raw
+            .into_iter()
+            .filter_map(|item| {
+                Some(Self {
+                    id: item.id?,
+                    label: item.label,
+                    level: item.level.and_then(|level| level.parse().ok()),
+                    expandable: item.expandable,
+                    expanded: item.expanded,
+                    icon: icon_name(item.icon_data, &item.icon_classes),
+                    hidden: has_class_fragment(&item.header_classes, &tree.hidden_class),
+                    unpublished: has_class_fragment(&item.header_classes, &tree.unpublished_class),
+                })
+            })
+            .collect())
+    }
+
+    /// Publication state for audit reports: `Live`, `Hidden`, `Draft` or `Hidden draft`.
+    pub fn visibility(&self) -> &'static str {
+        match (self.hidden, self.unpublished) {
+            (false, false) => "Live",
+            (true, false) => "Hidden",
+            (false, true) => "Draft",
+            (true, true) => "Hidden draft",
+        }
+    }
+
+    /// Whether the node type icon marks a shortcut node, which redirects to another node.
+    pub fn is_shortcut(&self, shortcut_icons: &[String]) -> bool {
+        self.icon
+            .as_ref()
+            .is_some_and(|icon| shortcut_icons.iter().any(|s| s == icon))
+    }
+}
+
+/// Whether any class contains one of the fragments as a whole word, so `isHidden`
+/// matches `labelWrapper--isHidden___3ZwHD` but not `labelWrapper--isHiddenInIndex`.
+fn has_class_fragment(classes: &str, fragments: &[String]) -> bool {
+    classes.split_whitespace().any(|class| {
+        fragments.iter().any(|fragment| {
+            class.match_indices(fragment.as_str()).any(|(pos, _)| {
+                !class[pos + fragment.len()..].starts_with(|c: char| c.is_ascii_alphabetic())
+            })
+        })
+    })
+}
+
+/// The icon name from `data-icon` (SVG icons) or the first `fa-*` class (font icons).
+fn icon_name(data_icon: Option<String>, classes: &str) -> Option<String> {
+    if data_icon.is_some() {
+        return data_icon;
+    }
+
+    classes
+        .split_whitespace()
+        .filter_map(|class| class.strip_prefix("fa-"))
+        .find(|name| !matches!(*name, "fw" | "lg" | "sm" | "xs"))
+        .map(str::to_string)
+}
+
+/// A tree item as returned by the tree item script, before interpreting classes and icons.
+#[derive(Debug, Deserialize)]
+struct RawTreeItem {
+    id: Option<String>,
+    level: Option<String>,
+    expanded: bool,
+    expandable: bool,
+    label: String,
+    header_classes: String,
+    icon_data: Option<String>,
+    icon_classes: String,
+}
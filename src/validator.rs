@@ -0,0 +1,441 @@
+use crate::cacheserver;
+use crate::checks;
+use crate::config::ChecksConfig;
+use crate::wayback;
+use crate::youtube::{self, Availability};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use futures::{stream, Stream, StreamExt};
+use reqwest::{
+    header::{CONTENT_TYPE, LOCATION, RANGE},
+    redirect::Policy,
+    Response, StatusCode, Url,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::sync::{OnceCell, Semaphore};
+
+/// Bytes of a page read to find its `<title>`.
+const TITLE_BYTES: usize = 64 * 1024;
+
+/// Result of checking one URL.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UrlCheck {
+    /// "Valid", "Redirect", "Error <code>" or "Invalid", and "N/A" for an empty URL. YouTube
+    /// videos are "Available", "Private" or "Deleted".
+    pub status: String,
+    /// Request method whose answer gave the status, "HEAD" or "GET", or "oEmbed" for YouTube
+    /// videos; empty for an empty URL.
+    pub method: String,
+    /// Where the redirects led, empty if the URL answered itself.
+    pub final_url: String,
+    /// Redirects followed to reach `final_url`.
+    pub redirect_hops: usize,
+    /// Closest archived copy of a dead URL in the Wayback Machine, empty if there is none or
+    /// the URL works.
+    pub wayback_url: String,
+}
+
+/// A check with the time it was made, as kept in the cache file or on the cache server.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Cached {
+    #[serde(flatten)]
+    pub check: UrlCheck,
+    pub checked_at: DateTime<Local>,
+}
+
+impl Cached {
+    /// Whether the check is younger than `max_age`. Checks from the future count as fresh.
+    pub fn is_fresh(&self, max_age: Duration) -> bool {
+        let age = (Local::now() - self.checked_at).to_std();
+        age.map_or(true, |age| age < max_age)
+    }
+}
+
+/// Where the checks of reachable URLs are kept between runs.
+enum CacheStore {
+    File(PathBuf),
+    /// A cache server shared by the team and its token, see [`cacheserver::serve`].
+    Server { url: String, token: String },
+}
+
+/// Checks URLs over one shared HTTP client, with at most `validation_concurrency` requests in
+/// flight across all callers. Each distinct URL is only requested once, so entries linking the
+/// same page share its status. URLs are told apart by [`cache_key`].
+pub struct Validator {
+    client: reqwest::Client,
+    permits: Semaphore,
+    concurrency: usize,
+    max_redirects: usize,
+    wayback: bool,
+    checks: Mutex<HashMap<String, Arc<OnceCell<Cached>>>>,
+    /// Page titles by [`cache_key`], `None` for pages without one.
+    titles: Mutex<HashMap<String, Arc<OnceCell<Option<String>>>>>,
+    /// Where the reachable URLs are kept between runs, if enabled.
+    store: Option<CacheStore>,
+}
+
+impl Validator {
+    pub fn new(config: &ChecksConfig) -> Self {
+        let concurrency = config.validation_concurrency.max(1);
+        Self {
+            // Redirects are followed by hand, to record where they lead
+            client: reqwest::Client::builder()
+                .timeout(config.url_timeout())
+                .redirect(Policy::none())
+                .build()
+                .unwrap_or_else(|_| reqwest::Client::new()),
+            permits: Semaphore::new(concurrency),
+            concurrency,
+            max_redirects: config.max_redirects,
+            wayback: config.wayback,
+            checks: Mutex::new(HashMap::new()),
+            titles: Mutex::new(HashMap::new()),
+            store: None,
+        }
+    }
+
+    /// A validator that starts from the checks younger than `cache_max_age_hours` on the
+    /// `[checks] cache_url` server, or else in the `cache_path` file, and keeps its own there on
+    /// [`Validator::save`]. Without either this is [`Validator::new`]. An unreadable cache file
+    /// is ignored, and an unreachable server or a missing token reported and then ignored.
+    pub async fn with_cache(config: &ChecksConfig) -> Self {
+        let mut validator = Self::new(config);
+        let (store, cached) = match (&config.cache_url, &config.cache_path) {
+            (Some(url), _) => {
+                let token = match cacheserver::token(&config.cache_token_env) {
+                    Ok(token) => token,
+                    Err(e) => {
+                        println!("⚠ {e:#}");
+                        return validator;
+                    }
+                };
+                let cached = cacheserver::fetch(&validator.client, url, &token).await;
+                let cached = cached.unwrap_or_else(|e| {
+                    println!("⚠ {e:#}");
+                    HashMap::new()
+                });
+                let store = CacheStore::Server {
+                    url: url.clone(),
+                    token,
+                };
+                (store, cached)
+            }
+            (None, Some(path)) => {
+                let cached: HashMap<String, Cached> = fs::read_to_string(path)
+                    .ok()
+                    .and_then(|json| serde_json::from_str(&json).ok())
+                    .unwrap_or_default();
+                (CacheStore::File(path.clone()), cached)
+            }
+            (None, None) => return validator,
+        };
+        let max_age = config.cache_max_age();
+        validator.checks = Mutex::new(
+            cached
+                .into_iter()
+                .filter(|(_, cached)| cached.is_fresh(max_age))
+                .map(|(url, cached)| (url, Arc::new(OnceCell::new_with(Some(cached)))))
+                .collect(),
+        );
+        validator.store = Some(store);
+        validator
+    }
+
+    /// Keep the reachable URLs in the cache file or on the cache server, if there is one. Broken
+    /// ones are left out, so the next run checks them again.
+    pub async fn save(&self) -> Result<()> {
+        let Some(store) = &self.store else {
+            return Ok(());
+        };
+        let cached: HashMap<String, Cached> = self
+            .checks
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|(url, cell)| Some((url.clone(), cell.get()?.clone())))
+            .filter(|(_, cached)| {
+                matches!(cached.check.status.as_str(), "Valid" | "Redirect" | "Available")
+            })
+            .collect();
+        let path = match store {
+            CacheStore::Server { url, token } => {
+                return cacheserver::push(&self.client, url, token, &cached).await
+            }
+            CacheStore::File(path) => path,
+        };
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir).context("Could not create the validation cache directory")?;
+        }
+        fs::write(path, serde_json::to_string(&cached)?)
+            .with_context(|| format!("Could not write the validation cache {}", path.display()))
+    }
+
+    /// Check `url`. A URL that is already being checked is waited for instead of requested
+    /// again.
+    pub async fn check(&self, url: &str) -> UrlCheck {
+        if url.is_empty() {
+            return UrlCheck {
+                status: "N/A".to_string(),
+                ..UrlCheck::default()
+            };
+        }
+        let key = cache_key(url);
+        let cell = self.checks.lock().unwrap().entry(key.clone()).or_default().clone();
+        let cached = cell
+            .get_or_init(|| async {
+                Cached {
+                    check: self.request(&key).await,
+                    checked_at: Local::now(),
+                }
+            })
+            .await;
+        cached.check.clone()
+    }
+
+    /// Check the distinct URLs among `urls` concurrently, yielding each with its result as soon
+    /// as it is known. Later calls to [`Validator::check`] answer from these results.
+    pub fn checks<'a>(
+        &'a self,
+        urls: impl IntoIterator<Item = &'a str>,
+    ) -> impl Stream<Item = (String, UrlCheck)> + 'a {
+        let distinct: HashSet<&str> = urls.into_iter().collect();
+        stream::iter(distinct)
+            .map(move |url| async move { (url.to_string(), self.check(url).await) })
+            .buffer_unordered(self.concurrency)
+    }
+
+    /// Check `url` with [`Validator::follow`], and for a dead URL look up an archived copy if
+    /// `[checks] wayback` is on.
+    async fn request(&self, url: &str) -> UrlCheck {
+        // The semaphore is never closed
+        let _permit = self.permits.acquire().await.ok();
+        let mut check = self.follow(url).await;
+        if self.wayback && wayback::is_dead(&check.status) {
+            check.wayback_url = wayback::closest(&self.client, url).await.unwrap_or_default();
+        }
+        check
+    }
+
+    /// Follow `url` through up to `max_redirects` redirects. A URL still redirecting after that
+    /// is reported as "Redirect". YouTube videos are looked up with oEmbed instead, as their
+    /// pages answer even when the video is gone.
+    async fn follow(&self, url: &str) -> UrlCheck {
+        if let Some(id) = youtube::video_id(url) {
+            match youtube::availability(&self.client, &id).await {
+                Availability::Unknown => {}
+                availability => {
+                    return UrlCheck {
+                        status: availability.as_str().to_string(),
+                        method: "oEmbed".to_string(),
+                        ..UrlCheck::default()
+                    }
+                }
+            }
+        }
+        let mut current = url.to_string();
+        let mut redirect_hops = 0;
+        loop {
+            let (status, method, next) = match self.ask(&current).await {
+                Some((response, method)) => {
+                    let (status, next) = (status(&response), location(&response));
+                    // The GET read the start of the page, so its title needs no request of its own
+                    if method == "GET" && status == "Valid" {
+                        let title = read_title(response).await;
+                        let _ = self.title_cell(&current).set(title);
+                    }
+                    (status, method, next)
+                }
+                None => ("Invalid".to_string(), "HEAD", None),
+            };
+            match next {
+                Some(next) if status == "Redirect" && redirect_hops < self.max_redirects => {
+                    current = next;
+                    redirect_hops += 1;
+                }
+                _ => {
+                    return UrlCheck {
+                        status,
+                        method: method.to_string(),
+                        final_url: if redirect_hops > 0 { current } else { String::new() },
+                        redirect_hops,
+                        ..UrlCheck::default()
+                    }
+                }
+            }
+        }
+    }
+
+    /// The `<title>` of the page at `url`, read from its first [`TITLE_BYTES`]. Each distinct
+    /// page is requested once, and not at all if the GET of its check already read it.
+    pub async fn page_title(&self, url: &str) -> Option<String> {
+        let key = cache_key(url);
+        let cell = self.title_cell(&key);
+        let title = cell
+            .get_or_init(|| async {
+                // The semaphore is never closed
+                let _permit = self.permits.acquire().await.ok();
+                let response = self.client.get(&key).header(RANGE, title_range()).send().await;
+                read_title(response.ok().filter(|response| response.status().is_success())?).await
+            })
+            .await;
+        title.clone()
+    }
+
+    fn title_cell(&self, url: &str) -> Arc<OnceCell<Option<String>>> {
+        let mut titles = self.titles.lock().unwrap();
+        titles.entry(cache_key(url)).or_default().clone()
+    }
+
+    /// Ask with HEAD first. Many servers answer HEAD with 403 or 405, or drop the connection,
+    /// while serving the page, so an error status or a failed HEAD is checked again with a GET
+    /// of the start of the page before it counts. `None` if neither request gets an answer.
+    async fn ask(&self, url: &str) -> Option<(Response, &'static str)> {
+        let head = match self.client.head(url).send().await {
+            Ok(head) if !head.status().is_client_error() && !head.status().is_server_error() => {
+                return Some((head, "HEAD"))
+            }
+            Ok(head) => Some(head),
+            Err(_) => None,
+        };
+        match self.client.get(url).header(RANGE, title_range()).send().await {
+            Ok(response) => Some((response, "GET")),
+            Err(_) => head.map(|head| (head, "HEAD")),
+        }
+    }
+}
+
+/// `url` with what does not change the page it names normalized away: the fragment, the case of
+/// the scheme and host, a default port and surrounding spaces. URLs that do not parse are kept.
+pub fn cache_key(url: &str) -> String {
+    match Url::parse(url.trim()) {
+        Ok(mut url) => {
+            url.set_fragment(None);
+            url.to_string()
+        }
+        Err(_) => url.trim().to_string(),
+    }
+}
+
+/// Range header asking for the bytes a page title is read from.
+fn title_range() -> String {
+    format!("bytes=0-{}", TITLE_BYTES - 1)
+}
+
+/// The `<title>` in the first [`TITLE_BYTES`] of an HTML response.
+async fn read_title(mut response: Response) -> Option<String> {
+    let html = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_none_or(|value| value.contains("html"));
+    if !html {
+        return None;
+    }
+    let mut body = Vec::new();
+    while body.len() < TITLE_BYTES {
+        match response.chunk().await {
+            Ok(Some(chunk)) => body.extend_from_slice(&chunk),
+            _ => break,
+        }
+    }
+    checks::extract_html_title(&String::from_utf8_lossy(&body))
+}
+
+/// Absolute target of a redirect response.
+fn location(response: &Response) -> Option<String> {
+    let location = response.headers().get(LOCATION)?.to_str().ok()?;
+    Some(response.url().join(location).ok()?.to_string())
+}
+
+fn status(response: &Response) -> String {
+    // The file exists, but is empty, so it has no first byte to send
+    if response.status().is_success() || response.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+        "Valid".to_string()
+    } else if response.status().is_redirection() {
+        "Redirect".to_string()
+    } else {
+        format!("Error {}", response.status().as_u16())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    #[test]
+    fn cache_key_ignores_what_does_not_change_the_page() {
+        assert_eq!(
+            cache_key(" HTTPS://Example.COM:443/Path?q=1#section "),
+            "https://example.com/Path?q=1"
+        );
+        assert_eq!(cache_key("http://example.com"), "http://example.com/");
+        assert_ne!(cache_key("https://example.com/a"), cache_key("https://example.com/A"));
+    }
+
+    #[test]
+    fn cache_key_keeps_urls_that_do_not_parse() {
+        assert_eq!(cache_key("  www.example.com/page "), "www.example.com/page");
+    }
+
+    /// A server that drops HEAD requests and answers GET with a small page.
+    async fn serve_get_only() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/page", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut request = [0; 1024];
+                let read = stream.read(&mut request).await.unwrap_or(0);
+                if request[..read].starts_with(b"GET") {
+                    let page = "<html><head><title>Schaltpläne</title></head></html>";
+                    let response = format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: {}\r\n\
+                         Connection: close\r\n\r\n{page}",
+                        page.len()
+                    );
+                    let _ = stream.write_all(response.as_bytes()).await;
+                }
+            }
+        });
+        url
+    }
+
+    /// A validator that does not look up dead URLs in the Wayback Machine.
+    fn offline_validator() -> Validator {
+        Validator::new(&ChecksConfig {
+            wayback: false,
+            ..ChecksConfig::default()
+        })
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_get_when_head_fails() {
+        let url = serve_get_only().await;
+        let validator = offline_validator();
+        let check = validator.check(&url).await;
+        assert_eq!(check.status, "Valid");
+        assert_eq!(check.method, "GET");
+        // The GET of the check already read the title
+        assert_eq!(validator.page_title(&url).await.as_deref(), Some("Schaltpläne"));
+    }
+
+    #[tokio::test]
+    async fn unreachable_urls_are_invalid() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        drop(listener);
+        let validator = offline_validator();
+        assert_eq!(validator.check(&url).await.status, "Invalid");
+    }
+}
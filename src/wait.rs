@@ -0,0 +1,118 @@
+use crate::selectors;
+use anyhow::{bail, Result};
+use std::{future::Future, time::Duration};
+use thirtyfour::{prelude::ElementQueryable, support, By, WebElement};
+use tokio::time::Instant;
+
+/// Pause between checks while waiting.
+const POLL: Duration = Duration::from_millis(200);
+
+/// How long the spider waits for the backend before giving up or moving on, from the
+/// `[timeouts]` settings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timeouts {
+    /// For a node page, the document tree or the backend to finish loading.
+    pub page_load: Duration,
+    /// For a single element to appear or go away, e.g. the login form.
+    pub element: Duration,
+    /// For the children of a just expanded folder to show up.
+    pub expand: Duration,
+    /// For a linked server to answer before its URL counts as invalid.
+    pub validation: Duration,
+    /// Pause before retrying a failed operation, or giving the backend time to react.
+    pub retry: Duration,
+}
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        Self {
+            page_load: Duration::from_secs(30),
+            element: Duration::from_secs(10),
+            expand: Duration::from_secs(5),
+            validation: Duration::from_secs(10),
+            retry: Duration::from_secs(2),
+        }
+    }
+}
+
+/// Check `condition` every [`POLL`] until it holds or `timeout` has passed. Returns whether it
+/// held.
+pub async fn until<F, Fut>(timeout: Duration, mut condition: F) -> bool
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = bool>,
+{
+    let deadline = Instant::now() + timeout;
+    loop {
+        if condition().await {
+            return true;
+        }
+        if Instant::now() >= deadline {
+            return false;
+        }
+        support::sleep(POLL).await;
+    }
+}
+
+/// Wait up to `timeout` for an element matching one of `candidates` below `root`. `name`
+/// identifies the logical selector in the match report, which counts the wait as one lookup.
+/// Fails like [`selectors::require_first`] if no element appears.
+pub async fn wait_for_element<R: ElementQueryable>(
+    root: &R,
+    name: &str,
+    candidates: &[String],
+    timeout: Duration,
+) -> Result<WebElement> {
+    until(timeout, || async move { !matching(root, candidates).await.is_empty() }).await;
+    selectors::require_first(root, name, candidates).await
+}
+
+/// Wait up to `timeout` for an element matching one of `candidates` below `root` to be shown
+/// and enabled.
+pub async fn wait_for_clickable<R: ElementQueryable>(
+    root: &R,
+    name: &str,
+    candidates: &[String],
+    timeout: Duration,
+) -> Result<WebElement> {
+    let clickable = until(timeout, || async move {
+        match matching(root, candidates).await.first() {
+            Some(element) => element.is_clickable().await.unwrap_or(false),
+            None => false,
+        }
+    })
+    .await;
+    let element = selectors::require_first(root, name, candidates).await?;
+    if !clickable {
+        bail!("{name} was not clickable within {}s", timeout.as_secs());
+    }
+    Ok(element)
+}
+
+/// Whether anything below `root` matches one of `candidates` right now.
+pub async fn is_present<R: ElementQueryable>(root: &R, candidates: &[String]) -> bool {
+    !matching(root, candidates).await.is_empty()
+}
+
+/// Wait up to `timeout` until nothing below `root` matches `candidates`, e.g. a loading
+/// indicator or a dialog. Returns whether it was gone in time.
+pub async fn wait_until_gone<R: ElementQueryable>(
+    root: &R,
+    candidates: &[String],
+    timeout: Duration,
+) -> bool {
+    until(timeout, || async move { matching(root, candidates).await.is_empty() }).await
+}
+
+/// The elements matched by the first of `candidates` that matches anything. Not counted in the
+/// match report, which would otherwise count every poll.
+async fn matching<R: ElementQueryable>(root: &R, candidates: &[String]) -> Vec<WebElement> {
+    for candidate in candidates {
+        if let Ok(elements) = root.query(By::Css(candidate)).nowait().all_from_selector().await {
+            if !elements.is_empty() {
+                return elements;
+            }
+        }
+    }
+    Vec::new()
+}
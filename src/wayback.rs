@@ -0,0 +1,25 @@
+use reqwest::{Client, Url};
+use serde_json::Value;
+
+/// The Internet Archive's availability API, which names the closest archived copy of a URL.
+const AVAILABILITY_URL: &str = "https://archive.org/wayback/available";
+
+/// Whether the status of a URL check means the page is gone, so an archived copy is worth
+/// suggesting.
+pub fn is_dead(status: &str) -> bool {
+    matches!(status, "Invalid" | "Error 404" | "Error 410")
+}
+
+/// The closest archived copy of `url` in the Wayback Machine, if there is one. Failures to ask
+/// count as no copy.
+pub async fn closest(client: &Client, url: &str) -> Option<String> {
+    let mut api = Url::parse(AVAILABILITY_URL).ok()?;
+    api.query_pairs_mut().append_pair("url", url);
+    let response = client.get(api).send().await.ok()?.error_for_status().ok()?;
+    let body: Value = serde_json::from_str(&response.text().await.ok()?).ok()?;
+    let closest = body.get("archived_snapshots")?.get("closest")?;
+    if closest.get("available").and_then(Value::as_bool) != Some(true) {
+        return None;
+    }
+    Some(closest.get("url")?.as_str()?.replacen("http://", "https://", 1))
+}
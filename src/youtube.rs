@@ -0,0 +1,67 @@
+use reqwest::{Client, StatusCode, Url};
+
+/// YouTube's oEmbed endpoint, which knows whether a video can still be watched.
+const OEMBED_URL: &str = "https://www.youtube.com/oembed";
+
+/// Whether a YouTube video can be watched. Embed URLs answer HEAD requests for deleted videos
+/// too, so only oEmbed tells.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Availability {
+    Available,
+    /// Private, or its owner does not allow embedding it.
+    Private,
+    Deleted,
+    /// oEmbed could not be asked or gave an unexpected answer.
+    Unknown,
+}
+
+impl Availability {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Availability::Available => "Available",
+            Availability::Private => "Private",
+            Availability::Deleted => "Deleted",
+            Availability::Unknown => "Unknown",
+        }
+    }
+}
+
+/// The video ID of a YouTube watch, embed, shorts or youtu.be URL.
+pub fn video_id(url: &str) -> Option<String> {
+    let url = Url::parse(url.trim()).ok()?;
+    let host = url.host_str()?;
+    let host = host.strip_prefix("www.").or(host.strip_prefix("m.")).unwrap_or(host);
+    let mut segments = url.path_segments()?;
+    let id = match host {
+        "youtu.be" => segments.next()?.to_string(),
+        "youtube.com" | "youtube-nocookie.com" => match segments.next()? {
+            "watch" => url.query_pairs().find(|(key, _)| key == "v")?.1.into_owned(),
+            "embed" | "shorts" | "live" | "v" => segments.next()?.to_string(),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    let valid = id.len() == 11
+        && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    valid.then_some(id)
+}
+
+/// Ask oEmbed about the video `id`. YouTube answers 401 or 403 for private videos and those
+/// that must not be embedded, and 404 or 400 for deleted ones.
+pub async fn availability(client: &Client, id: &str) -> Availability {
+    let Ok(mut url) = Url::parse(OEMBED_URL) else {
+        return Availability::Unknown;
+    };
+    url.query_pairs_mut()
+        .append_pair("url", &format!("https://www.youtube.com/watch?v={id}"))
+        .append_pair("format", "json");
+    let Ok(response) = client.get(url).send().await else {
+        return Availability::Unknown;
+    };
+    match response.status() {
+        status if status.is_success() => Availability::Available,
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Availability::Private,
+        StatusCode::NOT_FOUND | StatusCode::BAD_REQUEST => Availability::Deleted,
+        _ => Availability::Unknown,
+    }
+}
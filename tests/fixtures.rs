@@ -0,0 +1,195 @@
+//! Reads the captured pages in `tests/fixtures` offline and compares the entries with those the
+//! extractors found when each page was captured, with the bundled selector profile of its Neos
+//! version and with the built-in defaults.
+
+use serde_json::Value;
+use std::{collections::BTreeSet, fs, path::PathBuf};
+use tag_spider_rs::config::ContentType;
+use tag_spider_rs::fixtures::{self, FixtureManifest};
+use tag_spider_rs::html::Document;
+use tag_spider_rs::selectors::{self, Selectors};
+use tag_spider_rs::spider::ScriptEntry;
+
+const ALL_TYPES: [ContentType; 3] = [
+    ContentType::ExternalLink,
+    ContentType::YouTube,
+    ContentType::Tutorial,
+];
+
+struct Fixture {
+    dir: PathBuf,
+    manifest: FixtureManifest,
+    page: Document,
+    tree: Document,
+    expected: Vec<Value>,
+}
+
+fn fixtures() -> Vec<Fixture> {
+    let root = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let mut dirs: Vec<PathBuf> = fs::read_dir(&root)
+        .unwrap()
+        .map(|dir| dir.unwrap().path())
+        .filter(|dir| dir.join("manifest.json").is_file())
+        .collect();
+    dirs.sort();
+    assert!(!dirs.is_empty(), "no fixtures in {}", root.display());
+
+    dirs.into_iter()
+        .map(|dir| {
+            let read = |name: &str| fs::read_to_string(dir.join(name)).unwrap();
+            Fixture {
+                manifest: FixtureManifest::from_json_file(dir.join("manifest.json")).unwrap(),
+                page: Document::parse(&read("page.html")),
+                tree: Document::parse(&read("tree.html")),
+                expected: serde_json::from_str(&read("expected.json")).unwrap(),
+                dir,
+            }
+        })
+        .collect()
+}
+
+/// The selectors a fixture was captured with.
+fn profile(fixture: &Fixture) -> Selectors {
+    let major = fixture
+        .manifest
+        .neos_version
+        .as_deref()
+        .and_then(selectors::major_version);
+    major
+        .and_then(|major| Selectors::profile(major).unwrap())
+        .unwrap_or_default()
+}
+
+/// The entries the extractors keep: links need a URL or title, videos a URL.
+fn kept(found: Vec<ScriptEntry>) -> Vec<ScriptEntry> {
+    found
+        .into_iter()
+        .filter(|entry| {
+            !entry.url.is_empty() || (entry.kind == "ExternalLink" && !entry.title.is_empty())
+        })
+        .collect()
+}
+
+fn assert_same_entries(fixture: &Fixture, found: &[ScriptEntry], selectors: &str) {
+    let dir = fixture.dir.display();
+    assert_eq!(
+        found.len(),
+        fixture.expected.len(),
+        "{dir} with the {selectors}: {found:#?}"
+    );
+    for (found, expected) in found.iter().zip(&fixture.expected) {
+        let field = |name: &str| expected[name].as_str().unwrap_or_default();
+        assert_eq!(found.kind, field("content_type"), "{dir}");
+        assert_eq!(found.url, field("url"), "{dir}");
+        assert_eq!(found.fusion_path, field("fusion_path"), "{dir}");
+        let container = found.container_index.map(|i| i.to_string()).unwrap_or_default();
+        assert_eq!(container, field("container_index"), "{dir}");
+        if found.kind == "ExternalLink" {
+            assert_eq!(found.title, field("title"), "{dir}");
+            assert_eq!(found.author, field("author"), "{dir}");
+            assert_eq!(found.file_type, field("file_type"), "{dir}");
+            assert_eq!(found.size, field("size"), "{dir}");
+        } else {
+            assert!(found.player.is_some(), "{dir}: {} without a player", found.url);
+        }
+    }
+}
+
+#[test]
+fn pages_give_the_captured_entries() {
+    for fixture in fixtures() {
+        let selectors = profile(&fixture);
+        let found = fixtures::page_entries(&fixture.page, None, &selectors.content, &ALL_TYPES);
+        assert_same_entries(&fixture, &kept(found), "bundled profile");
+        assert_eq!(fixture.manifest.entries, fixture.expected.len());
+    }
+}
+
+#[test]
+fn default_selectors_read_the_pages_too() {
+    for fixture in fixtures() {
+        let content = &Selectors::default().content;
+        let found = fixtures::page_entries(&fixture.page, None, content, &ALL_TYPES);
+        assert_same_entries(&fixture, &kept(found), "default selectors");
+    }
+}
+
+#[test]
+fn pages_are_read_per_content_type() {
+    for fixture in fixtures() {
+        let content = &profile(&fixture).content;
+        for kind in ALL_TYPES {
+            let found = kept(fixtures::page_entries(&fixture.page, None, content, &[kind]));
+            let expected = fixture
+                .expected
+                .iter()
+                .filter(|entry| entry["content_type"] == kind.name())
+                .count();
+            assert_eq!(found.len(), expected, "{} {}", fixture.dir.display(), kind.name());
+            assert!(found.iter().all(|entry| entry.kind == kind.name()));
+        }
+    }
+}
+
+#[test]
+fn empty_containers_are_reported_unmatched() {
+    for fixture in fixtures() {
+        let content = &profile(&fixture).content;
+        let found = fixtures::page_entries(&fixture.page, None, content, &ALL_TYPES);
+        for entry in found.iter().filter(|entry| !entry.matched) {
+            assert!(entry.url.is_empty() && entry.title.is_empty(), "{entry:?}");
+        }
+    }
+}
+
+#[test]
+fn a_missing_region_falls_back_to_the_whole_page() {
+    for fixture in fixtures() {
+        let content = &profile(&fixture).content;
+        let whole = fixtures::page_entries(&fixture.page, None, content, &ALL_TYPES);
+        let missing =
+            fixtures::page_entries(&fixture.page, Some("#no-such-region"), content, &ALL_TYPES);
+        assert_eq!(whole.len(), missing.len());
+        let collection = content.collection.last().unwrap();
+        let region = fixtures::page_entries(&fixture.page, Some(collection), content, &ALL_TYPES);
+        assert_eq!(kept(region).len(), fixture.expected.len());
+    }
+}
+
+#[test]
+fn tree_selectors_find_the_captured_tree_items() {
+    for fixture in fixtures() {
+        let tree = &profile(&fixture).tree;
+        let ids: BTreeSet<String> = fixtures::tree_node_ids(&fixture.tree, &tree.child_item)
+            .into_iter()
+            .collect();
+        assert!(ids.contains(&fixture.manifest.node_id), "{}", fixture.dir.display());
+
+        for id in &ids {
+            let item = tree
+                .treeitem
+                .iter()
+                .find_map(|candidate| fixture.tree.select_first(&candidate.replace("{id}", id)));
+            let item = item.unwrap_or_else(|| panic!("no tree item for {id}"));
+            let header = tree.header.iter().find_map(|candidate| item.select_first(candidate));
+            assert!(header.is_some(), "no header for {id}");
+        }
+    }
+}
+
+#[test]
+fn bundled_profiles_parse_and_use_supported_content_selectors() {
+    let profiles = Selectors::profile_versions().map(|version| Selectors::profile(version).unwrap());
+    for selectors in profiles.map(Option::unwrap).chain([Selectors::default()]) {
+        let content = serde_json::to_value(&selectors.content).unwrap();
+        for (name, candidates) in content.as_object().unwrap() {
+            for candidate in candidates.as_array().unwrap() {
+                let candidate = candidate.as_str().unwrap();
+                assert!(
+                    tag_spider_rs::html::is_supported(candidate),
+                    "content.{name}: {candidate}"
+                );
+            }
+        }
+    }
+}